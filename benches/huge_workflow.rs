@@ -0,0 +1,102 @@
+//! How long linting a generated workflow with thousands of jobs takes, a
+//! segment at a time, and whether that grows with the file rather than
+//! faster. Run it with `cargo bench`; it fails if doubling the jobs more
+//! than triples the time, which is what a rule comparing every job with
+//! every other does.
+
+use std::time::{Duration, Instant};
+
+use serde_yaml::Value;
+use workflow_linter::eval::Stubs;
+use workflow_linter::lint::{self, Context};
+use workflow_linter::segment;
+use workflow_linter::spans::SpanMap;
+use workflow_linter::workflow::Workflow;
+
+/// A workflow of `jobs` jobs alike but for their names and a value or two.
+fn generate(jobs: usize) -> String {
+    let mut source =
+        String::from("name: generated\non: [push, pull_request]\npermissions: {}\njobs:\n");
+    for i in 0..jobs {
+        let job = [
+            format!("  job{}:", i),
+            "    runs-on: ubuntu-latest".to_string(),
+            format!("    needs: [job{}]", i.saturating_sub(1)),
+            "    steps:".to_string(),
+            "      - uses: actions/checkout@v4".to_string(),
+            format!("      - name: build part {}", i),
+            "        uses: ./.github/actions/build".to_string(),
+            format!("        with: {{ part: \"{}\" }}", i),
+            format!("      - name: upload part {}", i),
+            "        uses: actions/upload-artifact@v4".to_string(),
+            "        with:".to_string(),
+            format!("          name: part-{}", i),
+            format!("          path: out/{}", i),
+        ];
+        for line in &job {
+            source.push_str(line);
+            source.push('\n');
+        }
+    }
+    source.push_str("env:\n  GENERATED: \"true\"\n");
+    source
+}
+
+/// How long linting `source` takes, in segments of about `max_bytes`, the
+/// fastest of a few runs.
+fn time(source: &str, max_bytes: usize) -> Duration {
+    let rules = lint::builtin_rules();
+    let stubs = Stubs::default();
+    (0..3)
+        .map(|_| {
+            let started = Instant::now();
+            let segments = segment::split(source, max_bytes).expect("jobs to split");
+            for segment in segments {
+                let workflow = Workflow::parse_str(&segment.text).expect("a workflow");
+                let document: Value = serde_yaml::from_str(&segment.text).expect("YAML");
+                let spans = SpanMap::parse(&segment.text);
+                let cx = Context {
+                    path: None,
+                    source: &segment.text,
+                    workflow: &workflow,
+                    document: &document,
+                    spans: &spans,
+                    repo_root: None,
+                    stubs: &stubs,
+                    analyses: Default::default(),
+                    budget: Default::default(),
+                };
+                segment.unwrap(lint::lint(&cx, &rules));
+            }
+            started.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    // Whole, so rules see every job, as they do in files under the threshold
+    let small = generate(1_000);
+    let large = generate(2_000);
+    let small_time = time(&small, usize::MAX);
+    let large_time = time(&large, usize::MAX);
+    println!(
+        "huge_workflow: {} KiB in {:.2?}, {} KiB in {:.2?}",
+        small.len() / 1024,
+        small_time,
+        large.len() / 1024,
+        large_time
+    );
+    assert!(
+        large_time < small_time * 3,
+        "twice the jobs took {:.1}x as long",
+        large_time.as_secs_f64() / small_time.as_secs_f64()
+    );
+
+    let huge = generate(20_000);
+    println!(
+        "huge_workflow: {} KiB in segments in {:.2?}",
+        huge.len() / 1024,
+        time(&huge, segment::SEGMENT_BYTES)
+    );
+}
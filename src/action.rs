@@ -0,0 +1,117 @@
+//! Action metadata, the `action.yml` that defines what an action takes,
+//! gives back and runs.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Error;
+
+use crate::custom_types::scalar_map;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Input {
+    pub description: Option<String>,
+    pub required: Option<bool>,
+    pub default: Option<String>,
+    /// Shown as a warning when the input is used.
+    pub deprecation_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Output {
+    pub description: Option<String>,
+    /// Where the output comes from. Only composite actions set this, usually
+    /// to `${{ steps.<id>.outputs.<name> }}`.
+    pub value: Option<String>,
+}
+
+/// A step of a composite action. Unlike a workflow step, `shell` is required
+/// for `run` steps.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CompositeStep {
+    pub name: Option<String>,
+    pub id: Option<String>,
+    #[serde(rename = "if")]
+    pub run_if: Option<String>,
+    pub uses: Option<String>,
+    pub run: Option<String>,
+    pub shell: Option<String>,
+    pub working_directory: Option<String>,
+    #[serde(default, deserialize_with = "scalar_map")]
+    pub with: HashMap<String, String>,
+    #[serde(default, deserialize_with = "scalar_map")]
+    pub env: HashMap<String, String>,
+}
+
+/// How the action runs: `using` is `composite`, `docker` or a Node version
+/// like `node20`, and decides which of the other fields apply.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Runs {
+    pub using: String,
+
+    /// The steps of a composite action.
+    #[serde(default)]
+    pub steps: Vec<CompositeStep>,
+
+    /// The script a JavaScript action runs.
+    pub main: Option<String>,
+    pub pre: Option<String>,
+    pub post: Option<String>,
+
+    /// `Dockerfile`, a path to one, or `docker://image` for a Docker action.
+    pub image: Option<String>,
+    pub entrypoint: Option<String>,
+    /// Scripts run in the container before and after `entrypoint`.
+    pub pre_entrypoint: Option<String>,
+    pub post_entrypoint: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default, deserialize_with = "scalar_map")]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Action {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub inputs: HashMap<String, Input>,
+    #[serde(default)]
+    pub outputs: HashMap<String, Output>,
+    pub runs: Runs,
+}
+
+impl Action {
+    pub fn is_composite(&self) -> bool {
+        self.runs.using == "composite"
+    }
+}
+
+pub fn parse_str(input: &str) -> Result<Action, Error> {
+    serde_yaml::from_str(input)
+}
+
+/// The metadata file of the action in `dir`, which can be named
+/// `action.yml` or `action.yaml`.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    ["action.yml", "action.yaml"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The metadata file of the local action `uses: ./<path>` refers to, and
+/// what's in it.
+pub fn load_local(repo_root: &Path, path: &str) -> Option<(PathBuf, String)> {
+    let file = find(&repo_root.join(path.trim_start_matches("./")))?;
+    let source = fs::read_to_string(&file).ok()?;
+    Some((file, source))
+}
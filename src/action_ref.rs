@@ -0,0 +1,276 @@
+//! The different things a step's `uses` can point at.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed `uses:` value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ActionRef {
+    /// `owner/repo[/path]@ref`, an action in a public repository.
+    Repository {
+        owner: String,
+        repo: String,
+        /// Subdirectory of the repository containing the action.
+        path: Option<String>,
+        /// Branch, tag or commit SHA.
+        git_ref: String,
+    },
+    /// `./path/to/dir`, an action in the workflow's own repository.
+    Local { path: String },
+    /// `docker://[registry/]image[:tag][@digest]`, a public container image.
+    Docker {
+        image: String,
+        tag: Option<String>,
+        digest: Option<String>,
+    },
+}
+
+/// Why a `uses:` value couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionRefError {
+    Empty,
+    Whitespace,
+    Backslash,
+    Expression,
+    /// `owner/repo` without an `@ref`.
+    MissingRef,
+    /// Not `owner/repo`; a local path without `./` ends up here too.
+    MissingRepo,
+    InvalidName(String),
+    LocalWithRef,
+    EmptyImage,
+    InvalidImage(String),
+    InvalidTag(String),
+    InvalidDigest(String),
+}
+
+impl fmt::Display for ActionRefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionRefError::Empty => f.write_str("`uses` is empty"),
+            ActionRefError::Whitespace => f.write_str("`uses` can't contain whitespace"),
+            ActionRefError::Backslash => {
+                f.write_str("`uses` can't contain backslashes; use `/` even on Windows runners")
+            }
+            ActionRefError::Expression => f.write_str("`uses` can't contain expressions"),
+            ActionRefError::MissingRef => {
+                f.write_str("action is missing a version; add `@` and a tag, branch or commit SHA")
+            }
+            ActionRefError::MissingRepo => f.write_str(
+                "expected `owner/repo@ref`, `./path` for a local action, or `docker://image`",
+            ),
+            ActionRefError::InvalidName(name) => {
+                write!(
+                    f,
+                    "`{}` isn't a valid GitHub owner or repository name",
+                    name
+                )
+            }
+            ActionRefError::LocalWithRef => f.write_str(
+                "local actions always use the checked out version and can't have an `@ref`",
+            ),
+            ActionRefError::EmptyImage => f.write_str("`docker://` is missing an image"),
+            ActionRefError::InvalidImage(image) => write!(
+                f,
+                "`{}` isn't a valid image name; names must be lowercase",
+                image
+            ),
+            ActionRefError::InvalidTag(tag) => write!(f, "`{}` isn't a valid image tag", tag),
+            ActionRefError::InvalidDigest(digest) => write!(
+                f,
+                "`{}` isn't a valid image digest; expected `sha256:` and 64 hex digits",
+                digest
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ActionRefError {}
+
+impl ActionRef {
+    /// Whether the reference can't change under the workflow: a full commit
+    /// SHA, an image digest, or an action in the same repository.
+    pub fn is_pinned(&self) -> bool {
+        match self {
+            ActionRef::Repository { git_ref, .. } => {
+                git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+            }
+            ActionRef::Local { .. } => true,
+            ActionRef::Docker { digest, .. } => digest.is_some(),
+        }
+    }
+
+    /// The action without its version, like `actions/checkout` or
+    /// `docker://alpine`.
+    pub fn name(&self) -> String {
+        match self {
+            ActionRef::Repository {
+                owner, repo, path, ..
+            } => match path {
+                Some(path) => format!("{}/{}/{}", owner, repo, path),
+                None => format!("{}/{}", owner, repo),
+            },
+            ActionRef::Local { path } => path.clone(),
+            ActionRef::Docker { image, .. } => format!("docker://{}", image),
+        }
+    }
+}
+
+impl FromStr for ActionRef {
+    type Err = ActionRefError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ActionRefError::Empty);
+        }
+        if s.contains("${{") {
+            return Err(ActionRefError::Expression);
+        }
+        if s.contains(char::is_whitespace) {
+            return Err(ActionRefError::Whitespace);
+        }
+        if s.contains('\\') {
+            return Err(ActionRefError::Backslash);
+        }
+
+        if let Some(image) = s.strip_prefix("docker://") {
+            return parse_docker(image);
+        }
+        if s.starts_with("./") {
+            if s.contains('@') {
+                return Err(ActionRefError::LocalWithRef);
+            }
+            return Ok(ActionRef::Local {
+                path: s.to_string(),
+            });
+        }
+        parse_repository(s)
+    }
+}
+
+fn parse_repository(s: &str) -> Result<ActionRef, ActionRefError> {
+    // Probably a local path that's missing the leading `./`
+    if s.starts_with(['.', '/']) {
+        return Err(ActionRefError::MissingRepo);
+    }
+    let (name, git_ref) = match s.split_once('@') {
+        Some((_, "")) | None => return Err(ActionRefError::MissingRef),
+        Some((name, git_ref)) => (name, git_ref),
+    };
+
+    let mut parts = name.splitn(3, '/');
+    let owner = parts.next().unwrap_or_default();
+    let repo = parts.next().ok_or(ActionRefError::MissingRepo)?;
+    let path = parts.next();
+    for part in &[owner, repo] {
+        if !valid_github_name(part) {
+            return Err(ActionRefError::InvalidName(part.to_string()));
+        }
+    }
+    if path == Some("") {
+        return Err(ActionRefError::MissingRepo);
+    }
+
+    Ok(ActionRef::Repository {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        path: path.map(|p| p.trim_end_matches('/').to_string()),
+        git_ref: git_ref.to_string(),
+    })
+}
+
+fn parse_docker(s: &str) -> Result<ActionRef, ActionRefError> {
+    let (rest, digest) = match s.split_once('@') {
+        Some((rest, digest)) => (rest, Some(digest)),
+        None => (s, None),
+    };
+    // The port of a registry looks like a tag, so only look for the tag
+    // after the last `/`
+    let last = rest.rfind('/').map_or(0, |i| i + 1);
+    let (image, tag) = match rest[last..].find(':') {
+        Some(i) => (&rest[..last + i], Some(&rest[last + i + 1..])),
+        None => (rest, None),
+    };
+
+    if image.is_empty() {
+        return Err(ActionRefError::EmptyImage);
+    }
+    // The registry host may be mixed case, but repository paths can't be
+    let repository = match image.split_once('/') {
+        Some((host, path)) if host.contains(['.', ':']) || host == "localhost" => path,
+        _ => image,
+    };
+    let valid_image = repository.split('/').all(|component| {
+        !component.is_empty()
+            && component
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c))
+            && component.starts_with(|c: char| c.is_ascii_alphanumeric())
+    });
+    if !valid_image {
+        return Err(ActionRefError::InvalidImage(image.to_string()));
+    }
+
+    if let Some(tag) = tag {
+        let valid_tag = tag.len() <= 128
+            && tag.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+        if !valid_tag {
+            return Err(ActionRefError::InvalidTag(tag.to_string()));
+        }
+    }
+    if let Some(digest) = digest {
+        let valid_digest = digest
+            .strip_prefix("sha256:")
+            .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+        if !valid_digest {
+            return Err(ActionRefError::InvalidDigest(digest.to_string()));
+        }
+    }
+
+    Ok(ActionRef::Docker {
+        image: image.to_string(),
+        tag: tag.map(String::from),
+        digest: digest.map(String::from),
+    })
+}
+
+/// Owners and repositories are letters, digits, `-`, `_` and `.`.
+fn valid_github_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+impl fmt::Display for ActionRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionRef::Repository {
+                owner,
+                repo,
+                path,
+                git_ref,
+            } => {
+                write!(f, "{}/{}", owner, repo)?;
+                if let Some(path) = path {
+                    write!(f, "/{}", path)?;
+                }
+                write!(f, "@{}", git_ref)
+            }
+            ActionRef::Local { path } => f.write_str(path),
+            ActionRef::Docker { image, tag, digest } => {
+                write!(f, "docker://{}", image)?;
+                if let Some(tag) = tag {
+                    write!(f, ":{}", tag)?;
+                }
+                if let Some(digest) = digest {
+                    write!(f, "@{}", digest)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Checking that the third-party actions jobs trust with secrets can be
+//! verified, for repositories that want more assurance than a pin gives.
+//!
+//! An action is verifiable when the release it's used at is immutable, so
+//! its tag can't be moved, its assets can't be replaced and GitHub publishes
+//! an attestation for it, or when the commit it's at is signed by a key
+//! GitHub verified. Actions from `actions` and `github` are GitHub's own and
+//! aren't checked.
+
+use std::path::Path;
+
+use serde_yaml::Value;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::github::{Response, Scheduler};
+use crate::pins;
+use crate::review::{job_secrets, jobs, uses};
+use crate::spans::SpanMap;
+
+/// The rule name of every finding here.
+pub const RULE: &str = "unverified-action";
+
+/// Owners whose actions are GitHub's own.
+const FIRST_PARTY: &[&str] = &["actions", "github"];
+
+/// How an action at a ref can be verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verification {
+    /// Its release is immutable.
+    pub immutable_release: bool,
+    /// Its commit has a signature GitHub verified.
+    pub signed_commit: bool,
+}
+
+impl Verification {
+    pub fn is_verified(self) -> bool {
+        self.immutable_release || self.signed_commit
+    }
+}
+
+/// Look up how `owner/repo` at `git_ref`, a tag, branch or commit SHA, can
+/// be verified.
+pub fn verify(
+    scheduler: &mut Scheduler,
+    owner: &str,
+    repo: &str,
+    git_ref: &str,
+) -> Response<Verification> {
+    let base = format!("/repos/{}/{}", owner, repo);
+    let commit = match scheduler.get(&format!("{}/commits/{}", base, git_ref)) {
+        Response::Found(commit) => commit,
+        Response::NotFound => return Response::NotFound,
+        Response::Unavailable => return Response::Unavailable,
+    };
+    let signed_commit = commit["commit"]["verification"]["verified"]
+        .as_bool()
+        .unwrap_or(false);
+
+    // A pin is checked against the release of the tag it's at
+    let is_sha = git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit());
+    let tags = if is_sha {
+        match pins::resolve(scheduler, owner, repo, git_ref) {
+            Response::Found(commit) => commit.tags,
+            _ => Vec::new(),
+        }
+    } else {
+        vec![git_ref.to_string()]
+    };
+    let immutable_release = tags.iter().any(|tag| {
+        let release = scheduler.get(&format!("{}/releases/tags/{}", base, tag));
+        match release {
+            Response::Found(release) => release["immutable"].as_bool().unwrap_or(false),
+            _ => false,
+        }
+    });
+    Response::Found(Verification {
+        immutable_release,
+        signed_commit,
+    })
+}
+
+/// Whether `action` is a third party's, from a repository, which are the
+/// ones checked.
+fn is_third_party(action: &ActionRef) -> bool {
+    match action {
+        ActionRef::Repository { owner, .. } => {
+            !FIRST_PARTY.contains(&owner.to_lowercase().as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Whether a job gets secrets, from its own steps and settings, the
+/// workflow's `env`, or `secrets: inherit` to a reusable workflow.
+fn handles_secrets(workflow: &Value, job: &Value) -> bool {
+    if job.get("secrets").and_then(Value::as_str) == Some("inherit") {
+        return true;
+    }
+    let env = workflow.get("env").cloned().unwrap_or(Value::Null);
+    !job_secrets(job).is_empty() || !job_secrets(&env).is_empty()
+}
+
+/// The third-party actions jobs of `workflow` with secrets use, by job and
+/// node path, which are the ones [`check`] looks up.
+pub fn checked(workflow: &Value) -> Vec<(&str, String, ActionRef)> {
+    jobs(workflow)
+        .into_iter()
+        .filter(|(_, job)| handles_secrets(workflow, job))
+        .flat_map(|(job_id, job)| {
+            uses(job_id, job)
+                .into_iter()
+                .map(move |(node, action)| (job_id, node, action))
+        })
+        .filter(|(_, _, action)| is_third_party(action))
+        .collect()
+}
+
+/// The findings for the third-party actions of `workflow`, from `source` at
+/// `path`, that jobs with secrets use without a way to verify them.
+pub fn check(
+    scheduler: &mut Scheduler,
+    workflow: &Value,
+    source: &str,
+    path: &Path,
+) -> Vec<Diagnostic> {
+    let spans = SpanMap::parse(source);
+    let mut diagnostics = Vec::new();
+    let used = checked(workflow);
+    pins::resolve_repositories(scheduler, used.iter().map(|(_, _, action)| action));
+
+    for (job_id, node, action) in used {
+        let (owner, repo, git_ref) = match &action {
+            ActionRef::Repository {
+                owner,
+                repo,
+                git_ref,
+                ..
+            } => (owner, repo, git_ref),
+            _ => continue,
+        };
+        let verified = match verify(scheduler, owner, repo, git_ref) {
+            Response::Found(verification) => verification.is_verified(),
+            // A ref that doesn't exist fails the run anyway
+            Response::NotFound | Response::Unavailable => continue,
+        };
+        if verified {
+            continue;
+        }
+        let mut diagnostic = Diagnostic::new(
+            RULE,
+            Severity::Warning,
+            format!(
+                "job `{}` has secrets, and uses `{}` at {}, which isn't an immutable \
+                     release or a signed commit, so what runs can't be verified",
+                job_id,
+                action.name(),
+                git_ref
+            ),
+        )
+        .with_path(path)
+        .with_location(spans.locate(&node))
+        .with_message_id("unverifiable")
+        .with_param("action", action.name())
+        .with_suggestion(Suggestion::new(
+            "use a release the action published as immutable, or move the secrets to \
+                 a job that doesn't use it",
+        ));
+        diagnostic.node = Some(node);
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
@@ -0,0 +1,144 @@
+//! A badge and score summing up how a repository's workflows lint, for
+//! READMEs and dashboards.
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{Diagnostic, Severity};
+
+/// The text on the left of the badge.
+pub const LABEL: &str = "workflow lint";
+
+/// What linting a repository's workflows came to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub files: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    /// The security rules that are on.
+    pub security_rules: usize,
+    /// The security rules that are on and found nothing, in any file.
+    pub security_rules_passing: usize,
+    /// `security_rules_passing` as a percentage of `security_rules`, 100
+    /// if there are none.
+    pub security_pass_rate: f64,
+    /// passing, warnings or failing, as the badge says.
+    pub status: String,
+}
+
+impl Score {
+    /// Count what was found in `files` workflows, given the names of the
+    /// security rules that ran.
+    pub fn new(files: usize, diagnostics: &[Diagnostic], security_rules: &[&str]) -> Score {
+        let count = |severity| {
+            diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+        let passing = security_rules
+            .iter()
+            .filter(|&&rule| {
+                !diagnostics
+                    .iter()
+                    .any(|d| d.rule == rule && d.severity >= Severity::Warning)
+            })
+            .count();
+        let security_pass_rate = match security_rules.len() {
+            0 => 100.0,
+            n => (passing as f64 * 1000.0 / n as f64).round() / 10.0,
+        };
+        let status = match (errors, warnings) {
+            (0, 0) => "passing",
+            (0, _) => "warnings",
+            _ => "failing",
+        };
+        Score {
+            files,
+            errors,
+            warnings,
+            security_rules: security_rules.len(),
+            security_rules_passing: passing,
+            security_pass_rate,
+            status: status.to_string(),
+        }
+    }
+
+    /// The text on the right of the badge, like `2 errors, 1 warning`.
+    pub fn message(&self) -> String {
+        let plural = |n: usize, one: &str| match n {
+            1 => format!("1 {}", one),
+            n => format!("{} {}s", n, one),
+        };
+        match (self.errors, self.warnings) {
+            (0, 0) => "passing".to_string(),
+            (0, warnings) => plural(warnings, "warning"),
+            (errors, 0) => plural(errors, "error"),
+            (errors, warnings) => format!(
+                "{}, {}",
+                plural(errors, "error"),
+                plural(warnings, "warning")
+            ),
+        }
+    }
+
+    /// Green when nothing was found, yellow for only warnings, red for
+    /// errors.
+    pub fn color(&self) -> &'static str {
+        match (self.errors, self.warnings) {
+            (0, 0) => "#4c1",
+            (0, _) => "#dfb317",
+            _ => "#e05d44",
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// About how wide `text` is in 11px Verdana, with padding.
+fn width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+/// `score` as a flat badge, like the ones shields.io makes.
+pub fn svg(score: &Score) -> String {
+    let message = score.message();
+    let (left, right) = (width(LABEL), width(&message));
+    let total = left + right;
+    let (label, message) = (escape(LABEL), escape(&message));
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{total}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{total}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{left}" height="20" fill="#555"/>
+    <rect x="{left}" width="{right}" height="20" fill="{color}"/>
+    <rect width="{total}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+        total = total,
+        left = left,
+        right = right,
+        color = score.color(),
+        label = label,
+        message = message,
+        label_x = left / 2,
+        message_x = left + right / 2,
+    )
+}
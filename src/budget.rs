@@ -0,0 +1,123 @@
+//! How long a lint run may take, and a way to stop it early.
+//!
+//! Stopping is cooperative. The engine checks between rules and skips the
+//! rest once the run is cancelled or out of time, the API scheduler stops
+//! making requests and bounds each one by what's left, and rules that do a
+//! lot of work can check [`Budget::interrupted`] as they go.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Tells a run to stop, from anywhere that has a clone of it, like an
+/// editor that's been sent a newer version of the file.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a run stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stopped {
+    Cancelled,
+    OutOfTime,
+}
+
+impl Stopped {
+    pub fn reason(self) -> &'static str {
+        match self {
+            Stopped::Cancelled => "the run was cancelled",
+            Stopped::OutOfTime => "the run's time limit ran out",
+        }
+    }
+}
+
+/// The limits of one run. No limits and nothing to cancel by default.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    pub cancel: CancelToken,
+    /// When the whole run has to be done.
+    pub deadline: Option<Instant>,
+    /// How long each rule may take. A rule that takes longer has its
+    /// findings dropped, since it may have stopped part way.
+    pub rule_timeout: Option<Duration>,
+    /// When the rule running now has to be done.
+    rule_deadline: Cell<Option<Instant>>,
+}
+
+impl Budget {
+    /// Stop the run `timeout` from now.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    pub fn with_rule_timeout(mut self, timeout: Duration) -> Self {
+        self.rule_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Why the run should stop now, if it should.
+    pub fn stopped(&self) -> Option<Stopped> {
+        if self.cancel.is_cancelled() {
+            Some(Stopped::Cancelled)
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(Stopped::OutOfTime)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the run, or the rule running now, should stop.
+    pub fn interrupted(&self) -> bool {
+        self.stopped().is_some()
+            || self
+                .rule_deadline
+                .get()
+                .is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// How long until the earlier of the run's and the current rule's
+    /// deadlines, if there is one.
+    pub fn remaining(&self) -> Option<Duration> {
+        let deadline = match (self.deadline, self.rule_deadline.get()) {
+            (Some(run), Some(rule)) => Some(run.min(rule)),
+            (run, rule) => run.or(rule),
+        };
+        deadline.map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// Start timing a rule, returning when it started.
+    pub(crate) fn start_rule(&self) -> Instant {
+        let started = Instant::now();
+        self.rule_deadline
+            .set(self.rule_timeout.map(|timeout| started + timeout));
+        started
+    }
+
+    /// Stop timing the rule that started at `started`, returning how long
+    /// it took if that was over its limit.
+    pub(crate) fn finish_rule(&self, started: Instant) -> Option<Duration> {
+        self.rule_deadline.set(None);
+        let took = started.elapsed();
+        Some(took).filter(|&took| self.rule_timeout.is_some_and(|limit| took > limit))
+    }
+}
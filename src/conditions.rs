@@ -0,0 +1,243 @@
+//! A report of every `if:` in a workflow, for reviewers: what each condition
+//! depends on, whether it can be worked out ahead of the run, and which ones
+//! are too tangled to be sure of without running them.
+
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::diagnostics::Location;
+use crate::eval;
+use crate::expr::{BinaryOp, Expr};
+use crate::lint::{job_path, sorted_jobs, step_path, Context};
+use crate::spans::key_path;
+
+/// Conditions with more `&&`, `||` and `!` than this are called out as
+/// complex.
+pub const MAX_OPERATORS: usize = 4;
+
+/// Contexts that only get values while the workflow runs, so a stubs file
+/// can't stand in for them.
+const RUNTIME_CONTEXTS: &[&str] = &["job", "jobs", "needs", "runner", "steps"];
+
+/// Functions whose results depend on the run.
+const RUNTIME_FUNCTIONS: &[&str] = &["always", "cancelled", "failure", "hashfiles", "success"];
+
+/// What the evaluator made of a condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// The same for every scenario.
+    Always(bool),
+    /// Known in every scenario but not the same in all of them, so it
+    /// depends only on values from the workflow's events or the stubs.
+    Varies,
+    /// Depends on something that isn't known ahead of the run.
+    Unknown,
+    /// Not an expression the evaluator understands, like text with an
+    /// expression in it.
+    Unparsed,
+}
+
+/// One `if:` in the workflow.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    /// The node, like `jobs.build.steps[2].if`.
+    pub node: String,
+    pub location: Option<Location>,
+    pub condition: String,
+    /// The context properties and run-dependent functions it reads, like
+    /// `github.event_name` or `success()`.
+    pub depends_on: BTreeSet<String>,
+    /// The number of `&&`, `||` and `!` in it.
+    pub operators: usize,
+    pub decision: Decision,
+}
+
+impl Condition {
+    /// Whether it depends on something only a run can tell, so it can't be
+    /// checked any other way.
+    pub fn untestable(&self) -> bool {
+        match self.decision {
+            Decision::Unparsed => true,
+            Decision::Unknown => self.depends_on.iter().any(|d| is_runtime(d)),
+            _ => false,
+        }
+    }
+
+    pub fn complex(&self) -> bool {
+        self.operators > MAX_OPERATORS
+    }
+}
+
+fn is_runtime(dependency: &str) -> bool {
+    match dependency.strip_suffix("()") {
+        Some(function) => RUNTIME_FUNCTIONS.contains(&function.to_ascii_lowercase().as_str()),
+        None => {
+            let context = dependency.split('.').next().unwrap_or(dependency);
+            RUNTIME_CONTEXTS.contains(&context)
+        }
+    }
+}
+
+/// The property path `expr` reads, like `github.event.action`, if it's one.
+fn property_path(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Context(name) => Some(name.clone()),
+        Expr::Property(base, name) => Some(format!("{}.{}", property_path(base)?, name)),
+        Expr::Index(base, index) => {
+            let base = property_path(base)?;
+            Some(match &**index {
+                Expr::String(name) => format!("{}.{}", base, name),
+                _ => format!("{}.*", base),
+            })
+        }
+        Expr::Filter(base) => Some(format!("{}.*", property_path(base)?)),
+        _ => None,
+    }
+}
+
+fn dependencies(expr: &Expr, found: &mut BTreeSet<String>) {
+    if let Some(path) = property_path(expr) {
+        found.insert(path);
+        return;
+    }
+    match expr {
+        Expr::Call(name, args) => {
+            if RUNTIME_FUNCTIONS.contains(&name.to_ascii_lowercase().as_str()) {
+                found.insert(format!("{}()", name));
+            }
+            for arg in args {
+                dependencies(arg, found);
+            }
+        }
+        Expr::Property(base, _) | Expr::Filter(base) | Expr::Not(base) => dependencies(base, found),
+        Expr::Index(base, index) => {
+            dependencies(base, found);
+            dependencies(index, found);
+        }
+        Expr::Binary(left, _, right) => {
+            dependencies(left, found);
+            dependencies(right, found);
+        }
+        _ => {}
+    }
+}
+
+fn operators(expr: &Expr) -> usize {
+    match expr {
+        Expr::Not(operand) => 1 + operators(operand),
+        Expr::Binary(left, op, right) => {
+            let own = matches!(op, BinaryOp::And | BinaryOp::Or) as usize;
+            own + operators(left) + operators(right)
+        }
+        Expr::Property(base, _) | Expr::Filter(base) => operators(base),
+        Expr::Index(base, index) => operators(base) + operators(index),
+        Expr::Call(_, args) => args.iter().map(operators).sum(),
+        _ => 0,
+    }
+}
+
+fn decide(expr: &Expr, scenarios: &[Value]) -> Decision {
+    let mut outcomes = BTreeSet::new();
+    for scenario in scenarios {
+        match eval::truthy(expr, scenario) {
+            Some(outcome) => outcomes.insert(outcome),
+            None => return Decision::Unknown,
+        };
+    }
+    match outcomes.iter().collect::<Vec<_>>().as_slice() {
+        [outcome] => Decision::Always(**outcome),
+        _ => Decision::Varies,
+    }
+}
+
+fn condition(cx: &Context, scenarios: &[Value], node: String, condition: &str) -> Condition {
+    let location = cx.spans.locate(&node);
+    let mut report = Condition {
+        node,
+        location,
+        condition: condition.trim().to_string(),
+        depends_on: BTreeSet::new(),
+        operators: 0,
+        decision: Decision::Unparsed,
+    };
+    if let Some(expr) = eval::parse_condition(condition) {
+        dependencies(&expr, &mut report.depends_on);
+        report.operators = operators(&expr);
+        report.decision = decide(&expr, scenarios);
+    }
+    report
+}
+
+/// Every job and step condition in the workflow, in file order.
+pub fn report(cx: &Context) -> Vec<Condition> {
+    let scenarios = cx.scenarios();
+    let mut conditions = Vec::new();
+    for (job_id, job) in sorted_jobs(cx.workflow) {
+        if let Some(run_if) = &job.run_if {
+            let node = key_path(&job_path(job_id), "if");
+            conditions.push(condition(cx, &scenarios, node, run_if));
+        }
+        for (i, step) in job.steps.iter().enumerate() {
+            if let Some(run_if) = &step.run_if {
+                let node = key_path(&step_path(job_id, i), "if");
+                conditions.push(condition(cx, &scenarios, node, run_if));
+            }
+        }
+    }
+    conditions.sort_by_key(|c| c.location);
+    conditions
+}
+
+/// Print the report, with a summary line at the end. `file` is shown before
+/// each location.
+pub fn write_report(out: &mut impl Write, file: &str, conditions: &[Condition]) -> io::Result<()> {
+    for c in conditions {
+        match c.location {
+            Some(l) => writeln!(out, "{}:{}:{}: {}", file, l.line, l.column, c.node)?,
+            None => writeln!(out, "{}: {}", file, c.node)?,
+        }
+        writeln!(out, "    if: {}", c.condition)?;
+        if !c.depends_on.is_empty() {
+            let depends_on: Vec<_> = c.depends_on.iter().map(String::as_str).collect();
+            writeln!(out, "    depends on: {}", depends_on.join(", "))?;
+        }
+        let decision = match c.decision {
+            Decision::Always(true) => "always true",
+            Decision::Always(false) => "always false",
+            Decision::Varies => "depends on the event and known context values",
+            Decision::Unknown => "can't be decided before the run",
+            Decision::Unparsed => "not an expression the evaluator understands",
+        };
+        writeln!(out, "    evaluates: {}", decision)?;
+        if c.untestable() {
+            writeln!(
+                out,
+                "    ! untestable: only a run can tell which way it goes"
+            )?;
+        }
+        if c.complex() {
+            writeln!(
+                out,
+                "    ! complex: {} boolean operators; consider splitting it up or \
+                 naming parts of it in a step output",
+                c.operators
+            )?;
+        }
+    }
+    let untestable = conditions.iter().filter(|c| c.untestable()).count();
+    let complex = conditions.iter().filter(|c| c.complex()).count();
+    let decided = conditions
+        .iter()
+        .filter(|c| matches!(c.decision, Decision::Always(_) | Decision::Varies))
+        .count();
+    writeln!(
+        out,
+        "{} conditions: {} decidable ahead of the run, {} untestable, {} complex",
+        conditions.len(),
+        decided,
+        untestable,
+        complex
+    )
+}
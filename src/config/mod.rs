@@ -0,0 +1,525 @@
+//! The `.workflow-lint.toml` config file at the root of a repository.
+//!
+//! ```toml
+//! extends = "github:my-org/lint-config"
+//! enable = ["terraform"]
+//! disable = ["runner-cost"]
+//! retry-action = "nick-fields/retry"
+//! shellcheck = "/usr/local/bin/shellcheck"
+//! deprecated = [".github/workflows/legacy-*.yml"]
+//! ignore = [".github/workflows/generated-*.yml"]
+//!
+//! [severity]
+//! step-name = "info"
+//!
+//! [runners]
+//! approved = ["ubuntu-latest", "linux-16-core"]
+//! cost = { linux-16-core = 8 }
+//!
+//! [pinning]
+//! require = "sha"
+//! exempt = ["actions/*"]
+//!
+//! [step-name]
+//! min-run-lines = 3
+//! exempt = ["actions/checkout"]
+//! ```
+//!
+//! The config is found by looking in the directory of the first file being
+//! linted and each directory above it, up to the root of the checkout, so a
+//! directory of workflows can have its own.
+//!
+//! `extends` starts from another config, so a platform team can keep the
+//! settings every repository shares in one place. It can be:
+//!
+//! - an `https://` URL
+//! - `github:owner/repo`, read through the API so private repositories work,
+//!   with an optional path and ref: `github:owner/repo/ci/lint.toml@v2`. The
+//!   path defaults to `.workflow-lint.toml`.
+//! - a file, relative to the config that extends it
+//!
+//! Configs fetched from the network are cached, and the cached copy is used
+//! when the network isn't there, or always with `offline`. The settings of the
+//! extending config win; its `enable` and `disable` lists are added on top of
+//! those it extends.
+//!
+//! Every config is checked before it's used: unknown settings, rules and
+//! severities are errors, with the line they're on and a suggestion when
+//! they look like a misspelling.
+
+mod schema;
+mod toml;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::diagnostics::Severity;
+#[cfg(feature = "network")]
+use crate::github::Client;
+use crate::lint::{Pinning, Runners, StepNames};
+use crate::provenance::content_hash;
+
+pub use self::toml::TomlError;
+
+/// The config file, at the root of the checkout.
+pub const CONFIG_FILE: &str = ".workflow-lint.toml";
+
+/// How long a chain of `extends` can be, which also stops cycles.
+const MAX_EXTENDS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// The config it's in, when it's about one.
+    pub file: Option<String>,
+    /// 1-based.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn in_file(mut self, file: impl fmt::Display) -> ConfigError {
+        self.file = Some(file.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: ", file, line)?,
+            (Some(file), None) => write!(f, "{}: ", file)?,
+            (None, Some(line)) => write!(f, "line {}: ", line)?,
+            (None, None) => {}
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn error(message: impl Into<String>) -> ConfigError {
+    ConfigError {
+        file: None,
+        line: None,
+        message: message.into(),
+    }
+}
+
+/// Settings from a config file. Each is the same as the command line option
+/// of the same name, which wins over it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// The config this one starts from. Cleared once it's been merged in.
+    pub extends: Option<String>,
+    /// Rules and groups of rules to run that are off by default.
+    #[serde(default)]
+    pub enable: Vec<String>,
+    /// Rules and groups of rules not to run.
+    #[serde(default)]
+    pub disable: Vec<String>,
+    pub retry_action: Option<String>,
+    pub shellcheck: Option<String>,
+    pub target: Option<String>,
+    pub locale: Option<String>,
+    pub max_diagnostics_per_file: Option<usize>,
+    /// The severity to report each rule's findings at, instead of its own.
+    #[serde(default)]
+    pub severity: BTreeMap<String, Severity>,
+    /// The runners jobs can use and what they cost.
+    #[serde(default)]
+    pub runners: Runners,
+    /// Patterns for the paths of deprecated workflows, in the checkout.
+    #[serde(default)]
+    pub deprecated: Vec<String>,
+    /// Patterns for the paths of workflows not to lint, in the checkout.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// What actions have to be pinned to.
+    #[serde(default)]
+    pub pinning: Pinning,
+    /// Which steps can go without a name.
+    #[serde(default)]
+    pub step_name: StepNames,
+}
+
+impl Config {
+    /// Read and check a config. `rules` are the names of the rules and
+    /// groups it can refer to; with none, any name is accepted.
+    pub fn parse(text: &str, rules: &[&str]) -> Result<Config, Vec<ConfigError>> {
+        let (value, lines) = toml::parse(text).map_err(|e| {
+            vec![ConfigError {
+                file: None,
+                line: Some(e.line),
+                message: e.message,
+            }]
+        })?;
+        let errors = schema::check(&value, &lines, rules);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        serde_json::from_value(value).map_err(|e| vec![error(e.to_string())])
+    }
+
+    /// `local` on top of `self`.
+    pub fn merge(self, local: Config) -> Config {
+        let mut enable: Vec<String> = self
+            .enable
+            .into_iter()
+            .filter(|name| !local.disable.contains(name))
+            .collect();
+        let mut disable: Vec<String> = self
+            .disable
+            .into_iter()
+            .filter(|name| !local.enable.contains(name))
+            .collect();
+        for name in local.enable {
+            if !enable.contains(&name) {
+                enable.push(name);
+            }
+        }
+        for name in local.disable {
+            if !disable.contains(&name) {
+                disable.push(name);
+            }
+        }
+        let mut severity = self.severity;
+        severity.extend(local.severity);
+        let mut deprecated = self.deprecated;
+        for pattern in local.deprecated {
+            if !deprecated.contains(&pattern) {
+                deprecated.push(pattern);
+            }
+        }
+        let mut ignore = self.ignore;
+        for pattern in local.ignore {
+            if !ignore.contains(&pattern) {
+                ignore.push(pattern);
+            }
+        }
+        Config {
+            extends: None,
+            enable,
+            disable,
+            retry_action: local.retry_action.or(self.retry_action),
+            shellcheck: local.shellcheck.or(self.shellcheck),
+            target: local.target.or(self.target),
+            locale: local.locale.or(self.locale),
+            max_diagnostics_per_file: local
+                .max_diagnostics_per_file
+                .or(self.max_diagnostics_per_file),
+            severity,
+            runners: self.runners.merge(local.runners),
+            deprecated,
+            ignore,
+            pinning: self.pinning.merge(local.pinning),
+            step_name: self.step_name.merge(local.step_name),
+        }
+    }
+}
+
+/// The config for the file or directory at `start`: the first
+/// `.workflow-lint.toml` in it or a directory above it, stopping at the root
+/// of the checkout. Outside a checkout every directory up to the root of
+/// the filesystem is looked in.
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    let start = start.canonicalize().ok()?;
+    let root = crate::repo::find_root(&start);
+    let dir = match start.is_dir() {
+        true => start.as_path(),
+        false => start.parent()?,
+    };
+    for dir in dir.ancestors() {
+        let path = dir.join(CONFIG_FILE);
+        if path.is_file() {
+            return Some(path);
+        }
+        if Some(dir) == root.as_deref() {
+            break;
+        }
+    }
+    None
+}
+
+/// Where an `extends` points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Url(String),
+    GitHub {
+        owner: String,
+        repo: String,
+        path: String,
+        reference: Option<String>,
+    },
+    File(PathBuf),
+}
+
+impl Source {
+    /// Read an `extends` value, with files relative to `dir`.
+    pub fn parse(spec: &str, dir: &Path) -> Result<Source, ConfigError> {
+        if spec.starts_with("https://") || spec.starts_with("http://") {
+            return Ok(Source::Url(spec.to_string()));
+        }
+        let rest = match spec.strip_prefix("github:") {
+            Some(rest) => rest,
+            None => return Ok(Source::File(dir.join(spec))),
+        };
+        let (rest, reference) = match rest.split_once('@') {
+            Some((rest, reference)) => (rest, Some(reference.to_string())),
+            None => (rest, None),
+        };
+        let mut parts = rest.splitn(3, '/');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(owner), Some(repo), path) if !owner.is_empty() && !repo.is_empty() => {
+                Ok(Source::GitHub {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    path: path.unwrap_or(CONFIG_FILE).to_string(),
+                    reference,
+                })
+            }
+            _ => Err(error(format!(
+                "`{}` isn't a config to extend; expected github:owner/repo[/path][@ref]",
+                spec
+            ))),
+        }
+    }
+
+    /// Whether the config comes over the network, and so is cached.
+    fn is_remote(&self) -> bool {
+        !matches!(self, Source::File(_))
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Url(url) => f.write_str(url),
+            Source::GitHub {
+                owner,
+                repo,
+                path,
+                reference,
+            } => {
+                write!(f, "github:{}/{}/{}", owner, repo, path)?;
+                match reference {
+                    Some(reference) => write!(f, "@{}", reference),
+                    None => Ok(()),
+                }
+            }
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Where fetched configs are kept: `WORKFLOW_LINTER_CACHE_DIR`, or
+/// `workflow-linter` in the user's cache directory.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("WORKFLOW_LINTER_CACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("workflow-linter"))
+}
+
+/// Reads configs and what they extend.
+pub struct Loader {
+    #[cfg(feature = "network")]
+    client: Option<Client>,
+    /// Makes `client` the first time a remote config is fetched.
+    #[cfg(feature = "network")]
+    connect: Option<Box<dyn FnOnce() -> Option<Client>>>,
+    /// Use only cached copies of remote configs.
+    pub offline: bool,
+    pub cache_dir: Option<PathBuf>,
+    /// The names of the rules and groups configs can refer to. Names aren't
+    /// checked if it's empty.
+    pub rules: Vec<String>,
+    /// Problems that didn't stop the config loading, like a fetch that
+    /// failed and fell back to the cache.
+    pub warnings: Vec<String>,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Loader::new()
+    }
+}
+
+impl Loader {
+    /// A loader that can't fetch remote configs until it's given a client,
+    /// and uses the cache for them.
+    pub fn new() -> Loader {
+        Loader {
+            #[cfg(feature = "network")]
+            client: None,
+            #[cfg(feature = "network")]
+            connect: None,
+            offline: false,
+            cache_dir: cache_dir(),
+            rules: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Fetch remote configs with `client`.
+    #[cfg(feature = "network")]
+    pub fn with_client(mut self, client: Client) -> Loader {
+        self.client = Some(client);
+        self
+    }
+
+    /// Fetch remote configs with the client `connect` makes, called only
+    /// once there's one to fetch, so that configs that don't extend one
+    /// don't set up the network, or look for a token.
+    #[cfg(feature = "network")]
+    pub fn with_connect(mut self, connect: impl FnOnce() -> Option<Client> + 'static) -> Loader {
+        self.connect = Some(Box::new(connect));
+        self
+    }
+
+    /// Read the config at `path`, with everything it extends merged in.
+    pub fn load(&mut self, path: &Path) -> Result<Config, Vec<ConfigError>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| vec![error(format!("can't read {}: {}", path.display(), e))])?;
+        let config = self.parse(&text, path.display())?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        self.resolve(config, dir, &mut Vec::new())
+    }
+
+    fn parse(&self, text: &str, file: impl fmt::Display) -> Result<Config, Vec<ConfigError>> {
+        let rules: Vec<&str> = self.rules.iter().map(String::as_str).collect();
+        let file = file.to_string();
+        Config::parse(text, &rules)
+            .map_err(|errors| errors.into_iter().map(|e| e.in_file(&file)).collect())
+    }
+
+    fn resolve(
+        &mut self,
+        config: Config,
+        dir: &Path,
+        chain: &mut Vec<String>,
+    ) -> Result<Config, Vec<ConfigError>> {
+        let spec = match &config.extends {
+            Some(spec) => spec.clone(),
+            None => return Ok(config),
+        };
+        if chain.contains(&spec) || chain.len() >= MAX_EXTENDS {
+            chain.push(spec);
+            return Err(vec![error(format!(
+                "configs extend each other in a loop, or too deeply: {}",
+                chain.join(" -> ")
+            ))]);
+        }
+        chain.push(spec.clone());
+        let source = Source::parse(&spec, dir).map_err(|e| vec![e])?;
+        let text = self.read(&source).map_err(|e| vec![e])?;
+        let base = self.parse(&text, &source)?;
+        let base_dir = match &source {
+            Source::File(path) => path.parent().unwrap_or(dir).to_path_buf(),
+            _ => dir.to_path_buf(),
+        };
+        let base = self.resolve(base, &base_dir, chain)?;
+        Ok(base.merge(config))
+    }
+
+    fn cache_path(&self, source: &Source) -> Option<PathBuf> {
+        let name = format!("config-{}.toml", content_hash(&source.to_string()));
+        self.cache_dir.as_ref().map(|dir| dir.join(name))
+    }
+
+    /// The text of the config at `source`, from the network or the cache.
+    fn read(&mut self, source: &Source) -> Result<String, ConfigError> {
+        if let Source::File(path) = source {
+            return fs::read_to_string(path)
+                .map_err(|e| error(format!("can't read {}: {}", path.display(), e)));
+        }
+        let cached = self.cache_path(source);
+        let from_cache = |cached: &Option<PathBuf>| {
+            cached
+                .as_ref()
+                .and_then(|path| fs::read_to_string(path).ok())
+        };
+        if self.offline {
+            return from_cache(&cached).ok_or_else(|| {
+                error(format!(
+                    "{} isn't cached, and fetching it is off; run once without --offline",
+                    source
+                ))
+            });
+        }
+        match self.fetch(source) {
+            Ok(text) => {
+                if let Some(path) = &cached {
+                    let written = path
+                        .parent()
+                        .map_or(Ok(()), fs::create_dir_all)
+                        .and_then(|_| fs::write(path, &text));
+                    if let Err(e) = written {
+                        self.warnings
+                            .push(format!("couldn't cache {}: {}", source, e));
+                    }
+                }
+                Ok(text)
+            }
+            Err(e) => match from_cache(&cached) {
+                Some(text) => {
+                    self.warnings.push(format!(
+                        "couldn't fetch {} ({}), so the cached copy is used",
+                        source, e
+                    ));
+                    Ok(text)
+                }
+                None => Err(error(format!("couldn't fetch {}: {}", source, e))),
+            },
+        }
+    }
+
+    #[cfg(not(feature = "network"))]
+    fn fetch(&mut self, source: &Source) -> Result<String, String> {
+        debug_assert!(source.is_remote());
+        Err("this build has no network support".to_string())
+    }
+
+    #[cfg(feature = "network")]
+    fn fetch(&mut self, source: &Source) -> Result<String, String> {
+        debug_assert!(source.is_remote());
+        if self.client.is_none() {
+            self.client = self.connect.take().and_then(|connect| connect());
+        }
+        let client = self.client.as_ref().ok_or("there's no network client")?;
+        let request = match source {
+            Source::Url(url) => client.agent().get(url),
+            Source::GitHub {
+                owner,
+                repo,
+                path,
+                reference,
+            } => {
+                let mut api_path = format!("/repos/{}/{}/contents/{}", owner, repo, path);
+                if let Some(reference) = reference {
+                    api_path.push_str(&format!("?ref={}", reference));
+                }
+                client.get_raw(&api_path)
+            }
+            Source::File(_) => unreachable!("files are read directly"),
+        };
+        let mut response = request.call().map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("the server answered {}", status));
+        }
+        response
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,280 @@
+//! Checking a config's keys and values before it's used, so a misspelt
+//! setting is reported instead of leaving a rule quietly off.
+
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::lint::closest;
+use crate::schema::Target;
+
+use super::toml::Lines;
+use super::ConfigError;
+
+/// The kinds of value a setting can have.
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    /// A list of rule and group names.
+    Rules,
+    Count,
+    Target,
+    /// A table of rule names to severities.
+    Severities,
+    /// A list of strings.
+    Strings,
+    /// A table of names to counts.
+    Counts,
+    /// A table of settings, each of its own kind.
+    Table(&'static [(&'static str, Kind)]),
+    /// A string that's one of these.
+    Choice(&'static [&'static str]),
+}
+
+const SETTINGS: &[(&str, Kind)] = &[
+    ("deprecated", Kind::Strings),
+    ("disable", Kind::Rules),
+    ("enable", Kind::Rules),
+    ("extends", Kind::String),
+    ("ignore", Kind::Strings),
+    ("locale", Kind::String),
+    ("max-diagnostics-per-file", Kind::Count),
+    ("pinning", Kind::Table(PINNING)),
+    ("retry-action", Kind::String),
+    ("runners", Kind::Table(RUNNERS)),
+    ("severity", Kind::Severities),
+    ("shellcheck", Kind::String),
+    ("step-name", Kind::Table(STEP_NAME)),
+    ("target", Kind::Target),
+];
+
+/// The `[runners]` policy table.
+const RUNNERS: &[(&str, Kind)] = &[
+    ("approved", Kind::Strings),
+    ("cost", Kind::Counts),
+    ("min-minutes", Kind::Count),
+    ("min-steps", Kind::Count),
+];
+
+/// The `[pinning]` policy table.
+const PINNING: &[(&str, Kind)] = &[
+    ("exempt", Kind::Strings),
+    ("require", Kind::Choice(&["sha", "version"])),
+];
+
+/// The `[step-name]` table.
+const STEP_NAME: &[(&str, Kind)] = &[("exempt", Kind::Strings), ("min-run-lines", Kind::Count)];
+
+const SEVERITIES: &[&str] = &["error", "info", "warning"];
+
+/// What `value` is, for messages.
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "nothing",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "a table",
+    }
+}
+
+/// `name`, and a suggestion if it looks like a misspelling of one of `known`.
+fn unknown(name: &str, what: &str, known: &[&str]) -> String {
+    match closest(name, known) {
+        Some(suggestion) => format!("`{}` isn't {}; did you mean `{}`?", name, what, suggestion),
+        None => format!("`{}` isn't {}", name, what),
+    }
+}
+
+struct Checker<'a> {
+    lines: &'a Lines,
+    /// Every rule name and group; names aren't checked if it's empty.
+    rules: &'a [&'a str],
+    errors: Vec<ConfigError>,
+}
+
+impl<'a> Checker<'a> {
+    /// The line `path` was set on, or the nearest enclosing key's.
+    fn line(&self, path: &str) -> Option<usize> {
+        let mut path = path;
+        loop {
+            if let Some(&line) = self.lines.get(path) {
+                return Some(line);
+            }
+            path = &path[..path.rfind('.')?];
+        }
+    }
+
+    fn error(&mut self, path: &str, message: String) {
+        self.errors.push(ConfigError {
+            file: None,
+            line: self.line(path),
+            message,
+        });
+    }
+
+    fn rule(&mut self, path: &str, name: &str, what: &str, known: &[&str]) {
+        if !self.rules.is_empty() && !known.contains(&name) {
+            self.error(path, unknown(name, what, known));
+        }
+    }
+
+    /// Check each setting of `table`, at `prefix`, against `settings`.
+    fn table(&mut self, prefix: &str, settings: &[(&str, Kind)], table: &Map<String, Value>) {
+        let keys: Vec<&str> = settings.iter().map(|&(key, _)| key).collect();
+        let under = |key: &str| match prefix {
+            "" => key.to_string(),
+            prefix => format!("{}.{}", prefix, key),
+        };
+        for (key, value) in table {
+            let path = under(key);
+            match settings.iter().find(|&&(known, _)| known == key) {
+                Some(&(_, kind)) => self.setting(&path, kind, value),
+                None => {
+                    let message = match closest(key, &keys) {
+                        Some(suggestion) => format!(
+                            "`{}` isn't a setting; did you mean `{}`?",
+                            path,
+                            under(suggestion)
+                        ),
+                        None => format!("`{}` isn't a setting", path),
+                    };
+                    self.error(&path, message);
+                }
+            }
+        }
+    }
+
+    fn setting(&mut self, key: &str, kind: Kind, value: &Value) {
+        match (kind, value) {
+            (Kind::String, Value::String(_)) => {}
+            (Kind::Target, Value::String(target)) => {
+                if let Err(e) = Target::from_str(target) {
+                    self.error(key, e);
+                }
+            }
+            (Kind::Choice(choices), Value::String(choice)) => {
+                if !choices.contains(&choice.as_str()) {
+                    let message = match closest(choice, choices) {
+                        Some(suggestion) => format!(
+                            "`{}` can't be `{}`; did you mean `{}`?",
+                            key, choice, suggestion
+                        ),
+                        None => format!(
+                            "`{}` can't be `{}`; expected one of {}",
+                            key,
+                            choice,
+                            choices.join(", ")
+                        ),
+                    };
+                    self.error(key, message);
+                }
+            }
+            (Kind::Count, Value::Number(n)) if n.is_u64() => {}
+            (Kind::Count, Value::Number(n)) => self.error(
+                key,
+                format!("`{}` must be a whole number of 0 or more, not {}", key, n),
+            ),
+            (Kind::Rules, Value::Array(names)) => {
+                let rules = self.rules;
+                for (i, name) in names.iter().enumerate() {
+                    match name {
+                        Value::String(name) => self.rule(key, name, "a rule or group", rules),
+                        other => self.error(
+                            key,
+                            format!(
+                                "`{}` lists rule names, but item {} is {}",
+                                key,
+                                i + 1,
+                                describe(other)
+                            ),
+                        ),
+                    }
+                }
+            }
+            (Kind::Severities, Value::Object(table)) => {
+                let rules = self.rules;
+                for (rule, severity) in table {
+                    let path = format!("{}.{}", key, rule);
+                    self.rule(&path, rule, "a rule", rules);
+                    match severity {
+                        Value::String(severity) if SEVERITIES.contains(&severity.as_str()) => {}
+                        Value::String(severity) => {
+                            let message = match closest(severity, SEVERITIES) {
+                                Some(_) => unknown(severity, "a severity", SEVERITIES),
+                                None => format!(
+                                    "`{}` isn't a severity; expected one of info, warning, error",
+                                    severity
+                                ),
+                            };
+                            self.error(&path, message)
+                        }
+                        other => self.error(
+                            &path,
+                            format!(
+                                "the severity of `{}` must be a string, not {}",
+                                rule,
+                                describe(other)
+                            ),
+                        ),
+                    }
+                }
+            }
+            (Kind::Strings, Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    if !item.is_string() {
+                        self.error(
+                            key,
+                            format!(
+                                "`{}` lists strings, but item {} is {}",
+                                key,
+                                i + 1,
+                                describe(item)
+                            ),
+                        );
+                    }
+                }
+            }
+            (Kind::Counts, Value::Object(table)) => {
+                for (name, count) in table {
+                    self.setting(&format!("{}.{}", key, name), Kind::Count, count);
+                }
+            }
+            (Kind::Table(settings), Value::Object(table)) => self.table(key, settings, table),
+            (kind, value) => {
+                let expected = match kind {
+                    Kind::String | Kind::Target | Kind::Choice(_) => "a string",
+                    Kind::Count => "a number",
+                    Kind::Rules => "an array of rule names",
+                    Kind::Severities => "a table of rule names and severities",
+                    Kind::Strings => "an array of strings",
+                    Kind::Counts => "a table of numbers",
+                    Kind::Table(_) => "a table",
+                };
+                self.error(
+                    key,
+                    format!("`{}` must be {}, not {}", key, expected, describe(value)),
+                );
+            }
+        }
+    }
+}
+
+/// Every problem with the config `value`. `rules` are the rule names and
+/// groups it can refer to; with none, any name is accepted.
+pub(super) fn check(value: &Value, lines: &Lines, rules: &[&str]) -> Vec<ConfigError> {
+    let mut checker = Checker {
+        lines,
+        rules,
+        errors: Vec::new(),
+    };
+    let table = match value.as_object() {
+        Some(table) => table,
+        None => return checker.errors,
+    };
+    checker.table("", SETTINGS, table);
+    // Reported in the order they're in the file
+    checker.errors.sort_by_key(|e| e.line);
+    checker.errors
+}
@@ -0,0 +1,362 @@
+//! Reading the parts of TOML a config file uses into a JSON value, which
+//! serde then turns into a [`Config`](super::Config).
+//!
+//! Tables, dotted keys, strings, integers, floats, booleans, arrays and
+//! inline tables are understood. Multi-line strings, dates and arrays of
+//! tables aren't, and are reported as errors rather than misread.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+/// The line each key was set on, by its dotted path like `severity.step-name`.
+/// Keys inside inline tables are under the line of the table.
+pub type Lines = BTreeMap<String, usize>;
+
+/// Where reading stopped and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlError {
+    /// 1-based.
+    pub line: usize,
+    pub message: String,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn line(&self) -> usize {
+        self.text[..self.pos].matches('\n').count() + 1
+    }
+
+    fn error(&self, message: impl Into<String>) -> TomlError {
+        TomlError {
+            line: self.line(),
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skip spaces and tabs on the current line.
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace, newlines and comments, as between array items.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('\n') | Some('\r') => self.pos += 1,
+                Some('#') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.pos += 1;
+        }
+    }
+
+    /// The end of a line: a comment, or nothing, then a newline.
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_spaces();
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected the end of the line, found `{}`", c))),
+        }
+    }
+
+    fn key_part(&mut self) -> Result<String, TomlError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.text[start..self.pos].to_string())
+            }
+        }
+    }
+
+    /// A key like `a`, `"a b"` or `a.b`.
+    fn key(&mut self) -> Result<Vec<String>, TomlError> {
+        let mut parts = vec![self.key_part()?];
+        loop {
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(parts);
+            }
+            parts.push(self.key_part()?);
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        if self.text[self.pos..].starts_with("\"\"") {
+            return Err(self.error("multi-line strings aren't supported"));
+        }
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('u') => {
+                            let start = self.pos;
+                            let end = (start + 4).min(self.text.len());
+                            self.pos = end;
+                            u32::from_str_radix(&self.text[start..end], 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?
+                        }
+                        Some(c) => return Err(self.error(format!("unknown escape `\\{}`", c))),
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    s.push(escaped);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        if self.text[self.pos..].starts_with("''") {
+            return Err(self.error("multi-line strings aren't supported"));
+        }
+        let start = self.pos;
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => return Ok(self.text[start..self.pos - 1].to_string()),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, TomlError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, TomlError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            if !self.eat(',') {
+                self.skip_blank();
+                if self.eat(']') {
+                    return Ok(Value::Array(items));
+                }
+                return Err(self.error("expected `,` or `]` in the array"));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, TomlError> {
+        self.pos += 1;
+        let mut table = Map::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_spaces();
+            if !self.eat('=') {
+                return Err(self.error("expected `=` after the key"));
+            }
+            let value = self.value()?;
+            self.insert(&mut table, &key, value)?;
+            self.skip_spaces();
+            if self.eat('}') {
+                return Ok(Value::Object(table));
+            }
+            if !self.eat(',') {
+                return Err(self.error("expected `,` or `}` in the inline table"));
+            }
+        }
+    }
+
+    /// A boolean or a number.
+    fn scalar(&mut self) -> Result<Value, TomlError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '}' | '#'))
+        {
+            self.pos += 1;
+        }
+        let word = &self.text[start..self.pos];
+        let number = word.replace('_', "");
+        match word {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => {
+                if let Ok(n) = number.parse::<i64>() {
+                    Ok(Value::from(n))
+                } else if let Some(n) = number
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                {
+                    Ok(Value::Number(n))
+                } else {
+                    Err(self.error(format!(
+                        "`{}` isn't a value this reader understands; strings need quotes",
+                        word
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Put `value` at the dotted `key` under `table`.
+    fn insert(
+        &self,
+        table: &mut Map<String, Value>,
+        key: &[String],
+        value: Value,
+    ) -> Result<(), TomlError> {
+        let (last, parents) = key.split_last().expect("keys have a part");
+        let mut table = table;
+        for part in parents {
+            let entry = table
+                .entry(part.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            table = match entry {
+                Value::Object(inner) => inner,
+                _ => return Err(self.error(format!("`{}` is already a value", part))),
+            };
+        }
+        if table.contains_key(last) {
+            return Err(self.error(format!("`{}` is set twice", key.join("."))));
+        }
+        table.insert(last.clone(), value);
+        Ok(())
+    }
+}
+
+/// The table at `path` under `root`, made if it's new.
+fn table_at<'m>(
+    root: &'m mut Map<String, Value>,
+    path: &[String],
+) -> Option<&'m mut Map<String, Value>> {
+    let mut table = root;
+    for part in path {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        table = match entry {
+            Value::Object(inner) => inner,
+            _ => return None,
+        };
+    }
+    Some(table)
+}
+
+/// Read the TOML document `text` into a JSON object.
+pub fn parse(text: &str) -> Result<(Value, Lines), TomlError> {
+    let mut parser = Parser { text, pos: 0 };
+    let mut root = Map::new();
+    let mut lines = Lines::new();
+    let mut current: Vec<String> = Vec::new();
+    loop {
+        parser.skip_blank();
+        if parser.peek().is_none() {
+            return Ok((Value::Object(root), lines));
+        }
+        let line = parser.line();
+        if parser.eat('[') {
+            if parser.eat('[') {
+                return Err(parser.error("arrays of tables aren't supported"));
+            }
+            current = parser.key()?;
+            parser.skip_spaces();
+            if !parser.eat(']') {
+                return Err(parser.error("expected `]` after the table name"));
+            }
+            if table_at(&mut root, &current).is_none() {
+                return Err(parser.error(format!("`{}` is already a value", current.join("."))));
+            }
+            lines.entry(current.join(".")).or_insert(line);
+            parser.end_of_line()?;
+            continue;
+        }
+        let key = parser.key()?;
+        parser.skip_spaces();
+        if !parser.eat('=') {
+            return Err(parser.error("expected `=` after the key"));
+        }
+        let value = parser.value()?;
+        let table = match table_at(&mut root, &current) {
+            Some(table) => table,
+            None => return Err(parser.error("the table is already a value")),
+        };
+        parser.insert(table, &key, value)?;
+        lines.insert([&current[..], &key[..]].concat().join("."), line);
+        parser.end_of_line()?;
+    }
+}
@@ -0,0 +1,334 @@
+//! Checking a workflow against a template an organization requires, like a
+//! golden CI workflow every repository has to follow.
+//!
+//! The workflow conforms when it has everything the template has: its
+//! triggers, its jobs by id, each job's steps in the same order, and every
+//! setting the template gives, with the same value. It can have more of
+//! each. Steps are matched by the action they use, ignoring its version so
+//! pinning it to a commit is fine, or else by their script or name.
+//! `permissions` have to grant exactly what the template's do.
+
+use std::path::Path;
+
+use serde_yaml::Value;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::lint::{event_names, job_path, step_path};
+use crate::review::{grants, jobs, level_name, SCOPES};
+use crate::spans::{key_path, SpanMap};
+
+/// The rule name of every finding here.
+pub const RULE: &str = "workflow-conform";
+
+/// A value as it'd be written on one line.
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+/// How a step is told apart from the others in its job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Identity {
+    /// The action it uses, without the version.
+    Uses(String),
+    Run(String),
+    Name(String),
+}
+
+fn identity(step: &Value) -> Option<Identity> {
+    if let Some(uses) = step.get("uses").and_then(Value::as_str) {
+        let name = match uses.parse::<ActionRef>() {
+            Ok(action) => action.name(),
+            Err(_) => uses.to_string(),
+        };
+        return Some(Identity::Uses(name));
+    }
+    if let Some(run) = step.get("run").and_then(Value::as_str) {
+        return Some(Identity::Run(run.trim().to_string()));
+    }
+    let name = step.get("name").and_then(Value::as_str)?;
+    Some(Identity::Name(name.to_string()))
+}
+
+/// Whether `step` is the template's step `required`: it uses the same
+/// action, runs the same script, or has the same name.
+fn same_step(required: &Value, step: &Value) -> bool {
+    fn name(step: &Value) -> Option<&str> {
+        step.get("name").and_then(Value::as_str)
+    }
+    match identity(required) {
+        None => false,
+        // A step that uses an action has to use the same one
+        Some(Identity::Uses(action)) => identity(step) == Some(Identity::Uses(action)),
+        Some(other) => {
+            identity(step) == Some(other)
+                || (name(required).is_some() && name(required) == name(step))
+        }
+    }
+}
+
+/// What the step is called in a message: its name, or what it runs.
+fn label(step: &Value) -> String {
+    if let Some(name) = step.get("name").and_then(Value::as_str) {
+        return name.to_string();
+    }
+    match identity(step) {
+        Some(Identity::Uses(action)) => action,
+        Some(Identity::Run(run)) => format!("Run {}", run.lines().next().unwrap_or_default()),
+        Some(Identity::Name(name)) => name,
+        None => "a step".to_string(),
+    }
+}
+
+struct Conform<'a> {
+    path: &'a Path,
+    spans: SpanMap,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Conform<'_> {
+    fn report(&mut self, node: &str, id: &str, message: String) {
+        let mut diagnostic = Diagnostic::new(RULE, Severity::Error, message)
+            .with_path(self.path)
+            .with_location(self.spans.locate(node))
+            .with_message_id(id);
+        diagnostic.node = Some(node.to_string()).filter(|n| !n.is_empty());
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Check that `value`, at `node`, has everything `required` does: every
+    /// key of a mapping with a value that conforms, every item of a list,
+    /// and anything else the same.
+    fn settings(&mut self, required: &Value, value: Option<&Value>, node: &str) {
+        let value = match value {
+            Some(value) => value,
+            None => {
+                self.report(
+                    node,
+                    "missing-setting",
+                    format!(
+                        "`{}` is missing; the template sets it to `{}`",
+                        node,
+                        show(required)
+                    ),
+                );
+                return;
+            }
+        };
+        match (required, value) {
+            (Value::Mapping(required), Value::Mapping(mapping)) => {
+                for (key, required) in required {
+                    let name = match key.as_str() {
+                        Some(name) => name.to_string(),
+                        None => show(key),
+                    };
+                    self.settings(required, mapping.get(key), &key_path(node, &name));
+                }
+            }
+            (Value::Sequence(required), Value::Sequence(items)) => {
+                for item in required {
+                    if !items.contains(item) {
+                        self.report(
+                            node,
+                            "altered-setting",
+                            format!(
+                                "`{}` doesn't have `{}`, which the template's does",
+                                node,
+                                show(item)
+                            ),
+                        );
+                    }
+                }
+            }
+            (required, value) if required == value => {}
+            // `8` and `'8'` mean the same to GitHub
+            (required, value) if show(required) == show(value) => {}
+            (required, value) => self.report(
+                node,
+                "altered-setting",
+                format!(
+                    "`{}` is `{}`, and the template has `{}`",
+                    node,
+                    show(value),
+                    show(required)
+                ),
+            ),
+        }
+    }
+
+    /// Check that `permissions` grant exactly what the template's do.
+    fn permissions(&mut self, node: &str, required: Option<&Value>, value: Option<&Value>) {
+        let (required, granted) = match (grants(required), grants(value)) {
+            (Some(required), Some(granted)) => (required, granted),
+            (Some(_), None) => {
+                self.report(
+                    node,
+                    "permissions",
+                    format!(
+                        "`{}` is missing, so the token gets the repository's default \
+                         permissions instead of the template's",
+                        node
+                    ),
+                );
+                return;
+            }
+            _ => return,
+        };
+        let differences: Vec<String> = SCOPES
+            .iter()
+            .filter(|scope| required[*scope] != granted[*scope])
+            .map(|scope| {
+                format!(
+                    "`{}` is {} instead of {}",
+                    scope,
+                    level_name(granted[scope]),
+                    level_name(required[scope])
+                )
+            })
+            .collect();
+        if !differences.is_empty() {
+            self.report(
+                node,
+                "permissions",
+                format!(
+                    "`{}` doesn't match the template's: {}",
+                    node,
+                    differences.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn steps(&mut self, job_id: &str, required: &[Value], steps: &[Value]) {
+        let mut next = 0;
+        for step in required {
+            let found = steps[next.min(steps.len())..]
+                .iter()
+                .position(|s| same_step(step, s))
+                .map(|i| i + next);
+            let index = match found {
+                Some(index) => index,
+                None => {
+                    let (id, message) = if steps.iter().any(|s| same_step(step, s)) {
+                        (
+                            "reordered-step",
+                            format!(
+                                "job `{}` has the template's step `{}`, but not after the \
+                                 steps the template puts before it",
+                                job_id,
+                                label(step)
+                            ),
+                        )
+                    } else {
+                        (
+                            "missing-step",
+                            format!(
+                                "job `{}` is missing the template's step `{}`",
+                                job_id,
+                                label(step)
+                            ),
+                        )
+                    };
+                    self.report(&key_path(&job_path(job_id), "steps"), id, message);
+                    continue;
+                }
+            };
+            // What matched the steps is already the same, apart from versions
+            let mut settings = step.clone();
+            if let Some(mapping) = settings.as_mapping_mut() {
+                mapping.remove(&Value::String("uses".to_string()));
+            }
+            self.settings(&settings, Some(&steps[index]), &step_path(job_id, index));
+            next = index + 1;
+        }
+    }
+}
+
+/// The findings for `workflow`, from `source` at `path`, against the
+/// template `template`.
+pub fn conform(template: &Value, workflow: &Value, source: &str, path: &Path) -> Vec<Diagnostic> {
+    let mut cx = Conform {
+        path,
+        spans: SpanMap::parse(source),
+        diagnostics: Vec::new(),
+    };
+
+    let events = event_names(workflow);
+    for event in event_names(template) {
+        if !events.contains(&event) {
+            cx.report(
+                "on",
+                "missing-trigger",
+                format!(
+                    "the workflow doesn't run on `{}`, which the template does",
+                    event
+                ),
+            );
+        }
+    }
+
+    let mapping = match template.as_mapping() {
+        Some(mapping) => mapping,
+        None => return cx.diagnostics,
+    };
+    for (key, required) in mapping {
+        match key.as_str() {
+            Some("on") | Some("jobs") | Some("name") | None => continue,
+            Some("permissions") => {
+                cx.permissions("permissions", Some(required), workflow.get("permissions"))
+            }
+            Some(key) => cx.settings(required, workflow.get(key), key),
+        }
+    }
+
+    let workflow_jobs = jobs(workflow);
+    for (job_id, required) in jobs(template) {
+        let path = job_path(job_id);
+        let job = match workflow_jobs.get(job_id) {
+            Some(job) => *job,
+            None => {
+                cx.report(
+                    "jobs",
+                    "missing-job",
+                    format!("the workflow is missing the template's job `{}`", job_id),
+                );
+                continue;
+            }
+        };
+        let mapping = match required.as_mapping() {
+            Some(mapping) => mapping,
+            None => continue,
+        };
+        for (key, required) in mapping {
+            let name = match key.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let node = key_path(&path, name);
+            match name {
+                "steps" => {
+                    let required = required.as_sequence().map(Vec::as_slice).unwrap_or(&[]);
+                    let steps = job
+                        .get("steps")
+                        .and_then(Value::as_sequence)
+                        .map(Vec::as_slice)
+                        .unwrap_or(&[]);
+                    cx.steps(job_id, required, steps);
+                }
+                // A job without `permissions` has the workflow's
+                "permissions" => {
+                    let permissions = job
+                        .get("permissions")
+                        .or_else(|| workflow.get("permissions"));
+                    cx.permissions(&node, Some(required), permissions)
+                }
+                _ => cx.settings(required, job.get(name), &node),
+            }
+        }
+    }
+    cx.diagnostics
+}
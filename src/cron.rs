@@ -0,0 +1,224 @@
+//! The POSIX cron syntax of `on.schedule`, as GitHub supports it: five
+//! fields of minute, hour, day of the month, month and day of the week,
+//! each `*`, a value, a range `a-b`, a step `*/n`, `a-b/n` or `a/n`, or a
+//! list of those separated by commas. Months and days of the week can be
+//! written as their first three letters, like `JAN` and `MON`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The shortest interval GitHub runs scheduled workflows at; schedules
+/// that fire more often are throttled to it.
+pub const MIN_INTERVAL_MINUTES: u32 = 5;
+
+/// How long a day is, in minutes.
+const DAY: u32 = 24 * 60;
+
+const MONTHS: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A field of a cron expression, with the values it can be and the names
+/// that can be written for them, starting from its first value.
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+}
+
+const FIELDS: [Field; 5] = [
+    Field {
+        name: "minute",
+        min: 0,
+        max: 59,
+        names: &[],
+    },
+    Field {
+        name: "hour",
+        min: 0,
+        max: 23,
+        names: &[],
+    },
+    Field {
+        name: "day of the month",
+        min: 1,
+        max: 31,
+        names: &[],
+    },
+    Field {
+        name: "month",
+        min: 1,
+        max: 12,
+        names: MONTHS,
+    },
+    Field {
+        name: "day of the week",
+        min: 0,
+        max: 6,
+        names: WEEKDAYS,
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronError {
+    pub message: String,
+}
+
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CronError {}
+
+fn error(message: String) -> CronError {
+    CronError { message }
+}
+
+/// A parsed cron expression: the values each field matches, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    pub minutes: Vec<u32>,
+    pub hours: Vec<u32>,
+    pub days_of_month: Vec<u32>,
+    pub months: Vec<u32>,
+    pub days_of_week: Vec<u32>,
+}
+
+impl Field {
+    fn value(&self, text: &str) -> Result<u32, CronError> {
+        let named = self
+            .names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(text))
+            .map(|i| i as u32 + self.min);
+        let value = match named {
+            Some(value) => value,
+            None if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) => {
+                text.parse().map_err(|_| self.invalid(text))?
+            }
+            None => return Err(self.invalid(text)),
+        };
+        if value < self.min || value > self.max {
+            return Err(error(format!(
+                "`{}` is out of range for the {}, which is {} to {}",
+                text, self.name, self.min, self.max
+            )));
+        }
+        Ok(value)
+    }
+
+    fn invalid(&self, text: &str) -> CronError {
+        error(format!("`{}` isn't a valid {}", text, self.name))
+    }
+
+    /// The values one comma-separated part of the field matches.
+    fn part(&self, part: &str, values: &mut Vec<u32>) -> Result<(), CronError> {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = match step.parse() {
+                    Ok(step) if step > 0 => step,
+                    _ => {
+                        return Err(error(format!(
+                            "`{}` isn't a valid step for the {}",
+                            step, self.name
+                        )))
+                    }
+                };
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (self.min, self.max),
+            Some((start, end)) => (self.value(start)?, self.value(end)?),
+            // `a/n` runs from `a` to the end of the field
+            None if step.is_some() => (self.value(range)?, self.max),
+            None => {
+                let value = self.value(range)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            return Err(error(format!(
+                "the {} range `{}` runs backwards",
+                self.name, range
+            )));
+        }
+        values.extend((start..=end).step_by(step.unwrap_or(1) as usize));
+        Ok(())
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<u32>, CronError> {
+        let mut values = Vec::new();
+        for part in text.split(',') {
+            if part.is_empty() {
+                return Err(error(format!(
+                    "the {} `{}` has an empty list entry",
+                    self.name, text
+                )));
+            }
+            self.part(part, &mut values)?;
+        }
+        values.sort_unstable();
+        values.dedup();
+        Ok(values)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != FIELDS.len() {
+            return Err(error(format!(
+                "a cron expression has 5 fields (minute, hour, day of the month, month and \
+                 day of the week), but `{}` has {}",
+                s,
+                fields.len()
+            )));
+        }
+        let mut parsed = fields
+            .iter()
+            .zip(FIELDS.iter())
+            .map(|(text, field)| field.parse(text))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let mut next = || parsed.next().unwrap_or_default();
+        Ok(Cron {
+            minutes: next(),
+            hours: next(),
+            days_of_month: next(),
+            months: next(),
+            days_of_week: next(),
+        })
+    }
+}
+
+impl Cron {
+    /// The shortest time between two runs, in minutes, going by the times
+    /// of day it fires at. A schedule firing late one day and early the
+    /// next counts the gap across midnight, as if it fires on consecutive
+    /// days.
+    pub fn min_interval_minutes(&self) -> u32 {
+        let times: Vec<u32> = self
+            .hours
+            .iter()
+            .flat_map(|hour| self.minutes.iter().map(move |minute| hour * 60 + minute))
+            .collect();
+        let across_midnight = match (times.first(), times.last()) {
+            (Some(first), Some(last)) => first + DAY - last,
+            _ => DAY,
+        };
+        times
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .chain(std::iter::once(across_midnight))
+            .min()
+            .unwrap_or(DAY)
+    }
+}
@@ -1,8 +1,9 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::marker::PhantomData;
 use std::str::FromStr;
 
-use serde::de::{self, MapAccess, Visitor};
+use serde::de::{self, DeserializeOwned, MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 use void::Void;
 
@@ -20,9 +21,16 @@ impl<T> OneOrMany<T> {
             OneOrMany::Many(l) => l,
         }
     }
+
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            OneOrMany::One(i) => std::slice::from_ref(i),
+            OneOrMany::Many(l) => l,
+        }
+    }
 }
 
-fn string_or_struct<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+pub fn string_or_struct<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: Deserialize<'de> + FromStr<Err = Void>,
     D: Deserializer<'de>,
@@ -65,3 +73,103 @@ where
 
     deserializer.deserialize_any(StringOrStruct(PhantomData))
 }
+
+/// [`string_or_struct`] for an optional field, which is left out when it's
+/// `null`.
+pub fn optional_string_or_struct<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de> + FromStr<Err = Void>,
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(bound(deserialize = "T: Deserialize<'de> + FromStr<Err = Void>"))]
+    struct Wrapper<T>(#[serde(deserialize_with = "string_or_struct")] T);
+
+    Option::<Wrapper<T>>::deserialize(deserializer).map(|wrapper| wrapper.map(|Wrapper(t)| t))
+}
+
+/// A map whose values are scalars, read as strings the way GitHub passes
+/// them, so `retention-days: 5` and `CI: true` come through as `"5"` and
+/// `"true"`.
+pub fn scalar_map<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let map = HashMap::<String, serde_yaml::Value>::deserialize(deserializer)?;
+    map.into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_yaml::Value::String(s) => s,
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Null => String::new(),
+                _ => {
+                    return Err(de::Error::custom(format!(
+                        "`{}` must be a string, number or boolean",
+                        key
+                    )))
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// A list of scalars, read as strings, so `ports: [5432]` and
+/// `ports: ["5432:5432"]` come through alike.
+pub fn scalar_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let list = Vec::<serde_yaml::Value>::deserialize(deserializer)?;
+    list.into_iter()
+        .map(|value| match value {
+            serde_yaml::Value::String(s) => Ok(s),
+            serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+            serde_yaml::Value::Number(n) => Ok(n.to_string()),
+            _ => Err(de::Error::custom("list items must be strings or numbers")),
+        })
+        .collect()
+}
+
+/// `scalar_map` for an optional field, which is also left out when it's
+/// `null`, as it's written back when missing.
+pub fn optional_scalar_map<'de, D>(
+    deserializer: D,
+) -> Result<Option<HashMap<String, String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<serde_yaml::Value>::deserialize(deserializer)? {
+        Some(value) => scalar_map(value).map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// A single value or a list of them, as a list.
+pub fn one_or_many<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    OneOrMany::deserialize(deserializer).map(OneOrMany::into_vec)
+}
+
+/// Like [`one_or_many`], but failing with the error of the value that's
+/// wrong, like an unknown variant of `T`, rather than saying neither form
+/// matched.
+pub fn one_or_many_checked<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: DeserializeOwned,
+    D: Deserializer<'de>,
+{
+    match serde_yaml::Value::deserialize(deserializer)? {
+        serde_yaml::Value::Sequence(items) => items
+            .into_iter()
+            .map(|item| serde_yaml::from_value(item).map_err(de::Error::custom))
+            .collect(),
+        value => serde_yaml::from_value(value)
+            .map(|item| vec![item])
+            .map_err(de::Error::custom),
+    }
+}
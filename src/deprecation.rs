@@ -0,0 +1,126 @@
+//! Workflows on their way out, marked with a comment at the top of the file
+//!
+//! ```yaml
+//! # workflow-linter: deprecated since=2024-06 replacement=.github/workflows/ci.yml
+//! ```
+//!
+//! or listed under `deprecated` in the config, so changes to them can be
+//! pointed at their replacement and the summary can count what's left to
+//! migrate.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::glob::{self, Glob};
+use crate::repo;
+
+pub const RULE: &str = "deprecated-workflow";
+
+const DIRECTIVE: &str = "workflow-linter:";
+
+/// Why a workflow is deprecated, as far as anyone said.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deprecation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// The workflow to use instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+}
+
+impl Deprecation {
+    /// Like `since 2024-06, replaced by ci.yml`, or empty.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(since) = &self.since {
+            parts.push(format!("since {}", since));
+        }
+        if let Some(replacement) = &self.replacement {
+            parts.push(format!("replaced by {}", replacement));
+        }
+        parts.join(", ")
+    }
+}
+
+/// The deprecation comment in the comments at the top of `source`, before
+/// the first line of YAML.
+pub fn header(source: &str) -> Option<Deprecation> {
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line == "---" {
+            continue;
+        }
+        let comment = line.strip_prefix('#')?.trim();
+        let rest = match comment.strip_prefix(DIRECTIVE) {
+            Some(rest) => rest.trim(),
+            None => continue,
+        };
+        let mut words = rest.split_whitespace();
+        if words.next() != Some("deprecated") {
+            continue;
+        }
+        let mut deprecation = Deprecation::default();
+        for word in words {
+            match word.split_once('=') {
+                Some(("since", value)) => deprecation.since = Some(unquote(value)),
+                Some(("replacement", value)) => deprecation.replacement = Some(unquote(value)),
+                _ => {}
+            }
+        }
+        return Some(deprecation);
+    }
+    None
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches(['"', '\'']).to_string()
+}
+
+/// Whether the workflow at `path` is deprecated, by its header or by the
+/// config's `deprecated` patterns, which are matched against its path in
+/// the checkout. The header says more, so it wins.
+pub fn find(source: &str, path: &Path, listed: &[String]) -> Option<Deprecation> {
+    if let Some(deprecation) = header(source) {
+        return Some(deprecation);
+    }
+    let globs: Vec<Glob> = listed.iter().filter_map(|p| Glob::parse(p).ok()).collect();
+    Some(Deprecation::default()).filter(|_| glob::matches_all(&globs, &repo::checkout_path(path)))
+}
+
+/// A warning, for diff mode, that a change edits a deprecated workflow when
+/// it should be going to the replacement. Nothing if nothing but the
+/// comments changed.
+pub fn check_edit(
+    deprecation: &Deprecation,
+    base: &Value,
+    head: &Value,
+    path: &Path,
+) -> Option<Diagnostic> {
+    if base.is_null() || base == head {
+        return None;
+    }
+    let since = match &deprecation.since {
+        Some(since) => format!(" since {}", since),
+        None => String::new(),
+    };
+    let message = match &deprecation.replacement {
+        Some(replacement) => format!(
+            "this workflow is deprecated{}; make the change in {} instead",
+            since, replacement
+        ),
+        None => format!(
+            "this workflow is deprecated{}; changes to it are likely lost when it's removed",
+            since
+        ),
+    };
+    let mut diagnostic = Diagnostic::new(RULE, Severity::Warning, message)
+        .with_path(path)
+        .with_message_id("edited");
+    if let Some(replacement) = &deprecation.replacement {
+        diagnostic = diagnostic.with_param("replacement", replacement);
+    }
+    Some(diagnostic)
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// How bad a finding is. Ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "unknown severity `{}`, expected one of info, warning, error",
+                s
+            )),
+        }
+    }
+}
+
+/// A 1-based line and column in a workflow file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The stretch of a workflow file a diagnostic is about, `len` bytes from
+/// `offset`, ending at `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+    pub end: Location,
+}
+
+/// The stable code of each rule, for tools that key on a code rather than
+/// a name: `WF0xx` for reading the file, `WF1xx` for the rules and `WF2xx`
+/// for the commands'. New rules get the next number in their hundred, and
+/// numbers aren't reused.
+pub const CODES: &[(&str, &str)] = &[
+    ("parse", "WF001"),
+    ("io", "WF002"),
+    ("encoding", "WF003"),
+    ("tab-indentation", "WF004"),
+    ("partial-analysis", "WF005"),
+    ("max-diagnostics", "WF006"),
+    ("template-properties", "WF007"),
+    ("ambiguous-scalar", "WF101"),
+    ("artifact-name-collision", "WF102"),
+    ("artifact-retention", "WF103"),
+    ("artifact-v4", "WF104"),
+    ("comment-command-association", "WF105"),
+    ("comment-command-injection", "WF106"),
+    ("comment-command-permissions", "WF107"),
+    ("composite-outputs", "WF108"),
+    ("constant-condition", "WF109"),
+    ("continue-on-error", "WF110"),
+    ("dependabot-permissions", "WF111"),
+    ("dependabot-pull-request-target", "WF112"),
+    ("dependabot-secrets", "WF113"),
+    ("docker-action", "WF114"),
+    ("duplicate-jobs", "WF115"),
+    ("event-types", "WF116"),
+    ("generated-file", "WF117"),
+    ("hash-files", "WF118"),
+    ("indentation", "WF119"),
+    ("invalid-uses", "WF120"),
+    ("kubernetes-credentials", "WF121"),
+    ("kubernetes-image-tags", "WF122"),
+    ("kubernetes-production-environment", "WF123"),
+    ("workflow-limits", "WF124"),
+    ("log-hygiene", "WF125"),
+    ("pages-artifact", "WF126"),
+    ("pages-concurrency", "WF127"),
+    ("pages-environment", "WF128"),
+    ("pages-permissions", "WF129"),
+    ("path-safety", "WF130"),
+    ("publish-provenance", "WF131"),
+    ("retry", "WF132"),
+    ("runner-cost", "WF133"),
+    ("runner-policy", "WF134"),
+    ("runner-tools", "WF135"),
+    ("schedule-liveness", "WF136"),
+    ("service-health-check", "WF137"),
+    ("step-name", "WF138"),
+    ("target-features", "WF139"),
+    ("terraform-apply-protection", "WF140"),
+    ("terraform-credentials", "WF141"),
+    ("terraform-plan-permissions", "WF142"),
+    ("unknown-key", "WF143"),
+    ("unused-artifact", "WF144"),
+    ("untrusted-gate", "WF145"),
+    ("working-directory", "WF146"),
+    ("cron-schedule", "WF147"),
+    ("job-needs", "WF148"),
+    ("expression-syntax", "WF149"),
+    ("uses-or-run", "WF150"),
+    ("unpinned-action", "WF151"),
+    ("missing-local-action", "WF152"),
+    ("runner-label", "WF153"),
+    ("matrix-key", "WF154"),
+    ("call-inputs", "WF155"),
+    ("token-permissions", "WF156"),
+    ("concurrency-group", "WF157"),
+    ("pull-request-concurrency", "WF158"),
+    ("secret-leak", "WF159"),
+    ("script-injection", "WF160"),
+    ("shell-script", "WF161"),
+    ("workflow-diff", "WF201"),
+    ("workflow-conform", "WF202"),
+    ("import", "WF203"),
+    ("stale-pin", "WF204"),
+    ("unverified-action", "WF205"),
+    ("deprecated-workflow", "WF206"),
+];
+
+/// The code of the rule named `rule`, like `WF001` for `parse`.
+pub fn code(rule: &str) -> Option<&'static str> {
+    CODES
+        .iter()
+        .find(|&&(name, _)| name == rule)
+        .map(|&(_, code)| code)
+}
+
+/// Replace `len` bytes at `offset` in the source with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub offset: usize,
+    pub len: usize,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Edit {
+            offset,
+            len: 0,
+            replacement: text.into(),
+        }
+    }
+}
+
+/// A change that resolves a diagnostic, applied with `--fix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub description: String,
+    pub edits: Vec<Edit>,
+}
+
+/// Advice on resolving a diagnostic that can't be applied for the user,
+/// unlike a [`Fix`], such as a setting whose value depends on the workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    /// A snippet of YAML showing what the change looks like.
+    pub example: Option<String>,
+}
+
+impl Suggestion {
+    pub fn new(message: impl Into<String>) -> Self {
+        Suggestion {
+            message: message.into(),
+            example: None,
+        }
+    }
+
+    pub fn with_example(mut self, example: impl Into<String>) -> Self {
+        self.example = Some(example.into());
+        self
+    }
+}
+
+/// A single finding about a workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Name of the rule that produced this finding.
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    pub path: Option<PathBuf>,
+    pub location: Option<Location>,
+    /// All of what it's about, when that's known. Like fixes, it's for the
+    /// text that was linted, so it's dropped when that isn't the file's.
+    pub span: Option<Span>,
+    /// The path of the node it's about, like `jobs.build.steps[0]`, for
+    /// findings from rules. For jobs and steps this is the id
+    /// [`elements`](crate::elements) gives them.
+    pub node: Option<String>,
+    pub fix: Option<Fix>,
+    /// What to do about it, when that can't be a fix.
+    pub suggestions: Vec<Suggestion>,
+    /// Findings from other rules at the same place, folded into this one by
+    /// [`noise::group`](crate::noise::group).
+    pub related: Vec<Diagnostic>,
+    /// Which of its rule's messages this is, when the rule has several. A
+    /// catalog looks it up as `<rule>.<id>`.
+    pub message_id: Option<String>,
+    /// The values in the message, for a catalog's message to use.
+    pub params: Vec<(String, String)>,
+}
+
+impl Diagnostic {
+    pub fn new(rule: impl Into<String>, severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            rule: rule.into(),
+            severity,
+            message: message.into(),
+            path: None,
+            location: None,
+            span: None,
+            node: None,
+            fix: None,
+            suggestions: Vec::new(),
+            related: Vec::new(),
+            message_id: None,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.location = Some(Location { line, column });
+        self
+    }
+
+    pub fn with_location(mut self, location: Option<Location>) -> Self {
+        self.location = location;
+        self
+    }
+
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// The code of its rule, like `WF001`, if it has one.
+    pub fn code(&self) -> Option<&'static str> {
+        code(&self.rule)
+    }
+
+    /// Its severity and rule, like `error[WF001 parse]`.
+    pub fn label(&self) -> String {
+        match self.code() {
+            Some(code) => format!("{}[{} {}]", self.severity, code, self.rule),
+            None => format!("{}[{}]", self.severity, self.rule),
+        }
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    pub fn with_message_id(mut self, id: impl Into<String>) -> Self {
+        self.message_id = Some(id.into());
+        self
+    }
+
+    pub fn with_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.push((name.into(), value.to_string()));
+        self
+    }
+}
+
+/// Each suggestion on a line of its own after `indent`, followed by its
+/// example.
+fn write_suggestions(
+    f: &mut fmt::Formatter,
+    suggestions: &[Suggestion],
+    indent: &str,
+) -> fmt::Result {
+    for suggestion in suggestions {
+        write!(f, "\n{}help: {}", indent, suggestion.message)?;
+        for line in suggestion.example.iter().flat_map(|e| e.lines()) {
+            write!(f, "\n{}    | {}", indent, line)?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+            if let Some(location) = self.location {
+                write!(f, "{}:{}:", location.line, location.column)?;
+            }
+            f.write_str(" ")?;
+        }
+        write!(f, "{}: {}", self.label(), self.message)?;
+        write_suggestions(f, &self.suggestions, "    ")?;
+        for related in &self.related {
+            write!(f, "\n    also {}: {}", related.label(), related.message)?;
+            write_suggestions(f, &related.suggestions, "        ")?;
+        }
+        Ok(())
+    }
+}
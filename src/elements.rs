@@ -0,0 +1,108 @@
+//! Ids for the jobs and steps of a workflow, like `jobs.build.steps[3]`, for
+//! tools that need to point at one. They come from where the element is,
+//! not its name, so steps without a name have one too, and they're the same
+//! every run. Diagnostics about a job or step carry its id as their `node`.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::lint::{job_path, sorted_jobs, step_path};
+use crate::spans::SpanMap;
+use crate::workflow::{Step, Workflow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Job,
+    Step,
+}
+
+/// A job or step and its id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Element {
+    pub id: String,
+    pub kind: Kind,
+    /// The id of the job, or of the job the step is in.
+    pub job: String,
+    /// The step's index in its job, from 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<usize>,
+    /// The step's own `id`, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    /// What GitHub shows for it: its `name`, or for steps without one what
+    /// they run.
+    pub label: String,
+    /// 1-based.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+/// What GitHub calls a step without a name: `Run` and the first line of the
+/// script, or the action it uses.
+fn step_label(step: &Step) -> String {
+    if let Some(name) = &step.name {
+        return name.clone();
+    }
+    match &step.run {
+        Some(run) => format!("Run {}", run.lines().next().unwrap_or_default().trim()),
+        None => format!("Run {}", step.uses.as_deref().unwrap_or_default()),
+    }
+}
+
+/// Every job in id order, each followed by its steps.
+pub fn elements(workflow: &Workflow, spans: &SpanMap) -> Vec<Element> {
+    let mut elements = Vec::new();
+    for (job_id, job) in sorted_jobs(workflow) {
+        let id = job_path(job_id);
+        let location = spans.locate(&id);
+        elements.push(Element {
+            kind: Kind::Job,
+            job: job_id.to_string(),
+            step: None,
+            step_id: None,
+            label: job.name.clone().unwrap_or_else(|| job_id.to_string()),
+            line: location.map(|l| l.line),
+            column: location.map(|l| l.column),
+            id,
+        });
+        for (i, step) in job.steps.iter().enumerate() {
+            let id = step_path(job_id, i);
+            let location = spans.locate(&id);
+            elements.push(Element {
+                kind: Kind::Step,
+                job: job_id.to_string(),
+                step: Some(i),
+                step_id: step.id.clone(),
+                label: step_label(step),
+                line: location.map(|l| l.line),
+                column: location.map(|l| l.column),
+                id,
+            });
+        }
+    }
+    elements
+}
+
+/// One element a line: its id, where it is and its label.
+pub fn write_elements(out: &mut impl Write, elements: &[Element]) -> io::Result<()> {
+    let width = elements.iter().map(|e| e.id.len()).max().unwrap_or(0);
+    for element in elements {
+        let place = match (element.line, element.column) {
+            (Some(line), Some(column)) => format!("{}:{}", line, column),
+            _ => "-".to_string(),
+        };
+        writeln!(
+            out,
+            "{:width$}  {:>7}  {}",
+            element.id,
+            place,
+            element.label,
+            width = width
+        )?;
+    }
+    Ok(())
+}
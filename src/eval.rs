@@ -0,0 +1,442 @@
+//! Evaluating expressions against what's known ahead of a run.
+//!
+//! Most contexts aren't known until the workflow runs, so evaluation is
+//! partial: anything that depends on an unknown value comes out as `None`.
+//! Users can fill in values they do know, like their branch names, with a
+//! [`Stubs`] file.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::expr::{self, BinaryOp, Expr};
+
+/// Past this many scenarios, `one-of` values are left unknown instead.
+const MAX_SCENARIOS: usize = 256;
+
+/// Context values known ahead of time, read from YAML or JSON like:
+///
+/// ```yaml
+/// github:
+///   repository: acme/app
+///   ref_name:
+///     one-of: [main, develop]
+/// vars:
+///   DEPLOY_ENV: production
+/// ```
+///
+/// A map with just a `one-of` list stands for any one of those values, and
+/// an expression only counts as always true or false if it is for all of
+/// them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stubs {
+    values: Map<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct StubsError {
+    pub message: String,
+}
+
+impl fmt::Display for StubsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StubsError {}
+
+impl Stubs {
+    /// Parse a stubs file. JSON is YAML too, so either works.
+    pub fn parse(source: &str) -> Result<Stubs, StubsError> {
+        let value: Value = serde_yaml::from_str(source).map_err(|e| StubsError {
+            message: e.to_string(),
+        })?;
+        match value {
+            Value::Object(values) => Ok(Stubs { values }),
+            Value::Null => Ok(Stubs::default()),
+            _ => Err(StubsError {
+                message: "expected a map of context names to values".to_string(),
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Set `context.property` unless the stubs already say something about it.
+    pub fn set_default(&mut self, context: &str, property: &str, value: Value) {
+        let context = self
+            .values
+            .entry(context.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(properties) = context {
+            properties.entry(property.to_string()).or_insert(value);
+        }
+    }
+
+    /// A `one-of` value for `set_default`.
+    pub fn one_of(values: Vec<Value>) -> Value {
+        let mut map = Map::new();
+        map.insert("one-of".to_string(), Value::Array(values));
+        Value::Object(map)
+    }
+
+    /// The contexts of every combination of `one-of` values, each an object
+    /// of context names to values.
+    pub fn scenarios(&self) -> Vec<Value> {
+        expand(&Value::Object(self.values.clone()))
+    }
+}
+
+fn one_of(value: &Value) -> Option<&Vec<Value>> {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.get("one-of")?.as_array(),
+        _ => None,
+    }
+}
+
+/// Every value `value` can stand for.
+fn expand(value: &Value) -> Vec<Value> {
+    if let Some(options) = one_of(value) {
+        return options.iter().flat_map(expand).collect();
+    }
+    let map = match value {
+        Value::Object(map) => map,
+        _ => return vec![value.clone()],
+    };
+    let mut scenarios = vec![Map::new()];
+    for (key, value) in map {
+        let options = expand(value);
+        // Too many combinations: leave this one unknown
+        if scenarios.len() * options.len() > MAX_SCENARIOS {
+            continue;
+        }
+        scenarios = scenarios
+            .into_iter()
+            .flat_map(|scenario| {
+                options.iter().map(move |option| {
+                    let mut scenario = scenario.clone();
+                    scenario.insert(key.clone(), option.clone());
+                    scenario
+                })
+            })
+            .collect();
+    }
+    scenarios.into_iter().map(Value::Object).collect()
+}
+
+/// Whether a value counts as true in a condition.
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0 && !n.is_nan()),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+fn to_number(value: &Value) -> f64 {
+    match value {
+        Value::Null => 0.0,
+        Value::Bool(b) => f64::from(u8::from(*b)),
+        Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+        Value::String(s) => {
+            let s = s.trim();
+            if s.is_empty() {
+                0.0
+            } else if let Some(hex) = s.strip_prefix("0x") {
+                i64::from_str_radix(hex, 16).map_or(f64::NAN, |n| n as f64)
+            } else {
+                s.parse().unwrap_or(f64::NAN)
+            }
+        }
+        Value::Array(_) | Value::Object(_) => f64::NAN,
+    }
+}
+
+/// How a value turns into a string for `format()`, `join()` and friends.
+pub fn to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            _ => n.to_string(),
+        },
+        Value::String(s) => s.clone(),
+        Value::Array(_) => "Array".to_string(),
+        Value::Object(_) => "Object".to_string(),
+    }
+}
+
+fn number(n: f64) -> Value {
+    serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+/// `==` with GitHub's coercions: strings compare ignoring case, and values
+/// of different types are compared as numbers. Objects and arrays are only
+/// equal to themselves, which can't be told from here.
+fn loose_eq(a: &Value, b: &Value) -> Option<bool> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.to_lowercase() == b.to_lowercase()),
+        (Value::Array(_), _)
+        | (Value::Object(_), _)
+        | (_, Value::Array(_))
+        | (_, Value::Object(_)) => None,
+        (Value::Null, Value::Null) => Some(true),
+        (Value::Bool(a), Value::Bool(b)) => Some(a == b),
+        _ => Some(to_number(a) == to_number(b)),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+        _ => to_number(a).partial_cmp(&to_number(b)),
+    }
+}
+
+/// Look up a key the way GitHub does, ignoring case.
+fn get<'v>(map: &'v Map<String, Value>, key: &str) -> Option<&'v Value> {
+    map.get(key).or_else(|| {
+        map.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    })
+}
+
+/// Whether `expr` is (a property of) an object filter like `labels.*`, whose
+/// properties are taken from each element.
+fn filtered(expr: &Expr) -> bool {
+    match expr {
+        Expr::Filter(_) => true,
+        Expr::Property(base, _) => filtered(base),
+        _ => false,
+    }
+}
+
+/// Evaluate `expr` with `contexts` (an object of context names to values),
+/// or `None` if it depends on something unknown.
+pub fn evaluate(expr: &Expr, contexts: &Value) -> Option<Value> {
+    Some(match expr {
+        Expr::Null => Value::Null,
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Number(n) => number(*n),
+        Expr::String(s) => Value::String(s.clone()),
+        Expr::Context(name) => get(contexts.as_object()?, name)?.clone(),
+        Expr::Property(base, name) => {
+            let value = evaluate(base, contexts)?;
+            match value {
+                Value::Object(map) => get(&map, name)?.clone(),
+                Value::Array(items) if filtered(base) => Value::Array(
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_object().and_then(|m| get(m, name)).cloned())
+                        .collect(),
+                ),
+                _ => Value::Null,
+            }
+        }
+        Expr::Index(base, index) => {
+            let value = evaluate(base, contexts)?;
+            let index = evaluate(index, contexts)?;
+            match (value, index) {
+                (Value::Object(map), Value::String(key)) => get(&map, &key)?.clone(),
+                (Value::Array(items), index) => {
+                    let i = to_number(&index);
+                    if i >= 0.0 && i.fract() == 0.0 {
+                        items.get(i as usize).cloned().unwrap_or(Value::Null)
+                    } else {
+                        Value::Null
+                    }
+                }
+                _ => Value::Null,
+            }
+        }
+        Expr::Filter(base) => match evaluate(base, contexts)? {
+            Value::Object(map) => Value::Array(map.values().cloned().collect()),
+            Value::Array(items) => Value::Array(items),
+            _ => Value::Array(Vec::new()),
+        },
+        Expr::Call(name, args) => return call(name, args, contexts),
+        Expr::Not(operand) => Value::Bool(!truthy(operand, contexts)?),
+        Expr::Binary(left, BinaryOp::And, right) => {
+            let left = evaluate(left, contexts)?;
+            if !is_truthy(&left) {
+                left
+            } else {
+                evaluate(right, contexts)?
+            }
+        }
+        Expr::Binary(left, BinaryOp::Or, right) => {
+            let left = evaluate(left, contexts)?;
+            if is_truthy(&left) {
+                left
+            } else {
+                evaluate(right, contexts)?
+            }
+        }
+        Expr::Binary(left, op, right) => {
+            let left = evaluate(left, contexts)?;
+            let right = evaluate(right, contexts)?;
+            Value::Bool(match op {
+                BinaryOp::Eq => loose_eq(&left, &right)?,
+                BinaryOp::Ne => !loose_eq(&left, &right)?,
+                BinaryOp::Lt => compare(&left, &right) == Some(Ordering::Less),
+                BinaryOp::Le => matches!(
+                    compare(&left, &right),
+                    Some(Ordering::Less | Ordering::Equal)
+                ),
+                BinaryOp::Gt => compare(&left, &right) == Some(Ordering::Greater),
+                BinaryOp::Ge => matches!(
+                    compare(&left, &right),
+                    Some(Ordering::Greater | Ordering::Equal)
+                ),
+                BinaryOp::And | BinaryOp::Or => unreachable!(),
+            })
+        }
+    })
+}
+
+fn call(name: &str, args: &[Expr], contexts: &Value) -> Option<Value> {
+    let name = name.to_lowercase();
+    // Status functions depend on how the run went
+    match name.as_str() {
+        "always" => return Some(Value::Bool(true)),
+        "success" | "failure" | "cancelled" | "hashfiles" => return None,
+        _ => {}
+    }
+    let args: Vec<Value> = args
+        .iter()
+        .map(|arg| evaluate(arg, contexts))
+        .collect::<Option<_>>()?;
+    Some(match (name.as_str(), args.as_slice()) {
+        ("contains", [Value::Array(items), item]) => {
+            let mut found = Some(false);
+            for candidate in items {
+                match loose_eq(candidate, item) {
+                    Some(true) => return Some(Value::Bool(true)),
+                    Some(false) => {}
+                    None => found = None,
+                }
+            }
+            Value::Bool(found?)
+        }
+        ("contains", [search, item]) => Value::Bool(
+            to_string(search)
+                .to_lowercase()
+                .contains(&to_string(item).to_lowercase()),
+        ),
+        ("startswith", [s, prefix]) => Value::Bool(
+            to_string(s)
+                .to_lowercase()
+                .starts_with(&to_string(prefix).to_lowercase()),
+        ),
+        ("endswith", [s, suffix]) => Value::Bool(
+            to_string(s)
+                .to_lowercase()
+                .ends_with(&to_string(suffix).to_lowercase()),
+        ),
+        ("format", [template, rest @ ..]) => Value::String(format(&to_string(template), rest)?),
+        ("join", [Value::Array(items)]) => Value::String(join(items, ",")),
+        ("join", [Value::Array(items), separator]) => {
+            Value::String(join(items, &to_string(separator)))
+        }
+        ("join", [item]) | ("join", [item, _]) => Value::String(to_string(item)),
+        ("tojson", [value]) => Value::String(serde_json::to_string_pretty(value).ok()?),
+        ("fromjson", [value]) => serde_json::from_str(&to_string(value)).ok()?,
+        _ => return None,
+    })
+}
+
+fn join(items: &[Value], separator: &str) -> String {
+    items
+        .iter()
+        .map(to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// `format()`, where `{0}` is replaced by the first argument and `{{`/`}}`
+/// are literal braces.
+fn format(template: &str, args: &[Value]) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                out.push(c);
+                chars.next();
+            }
+            ('{', _) => {
+                let mut index = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    index.push(c);
+                }
+                out.push_str(&to_string(args.get(index.parse::<usize>().ok()?)?));
+            }
+            _ => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Whether `expr` is truthy, where `&&`, `||` and `!` can be decided by one
+/// known side even if the other isn't known.
+pub fn truthy(expr: &Expr, contexts: &Value) -> Option<bool> {
+    match expr {
+        Expr::Not(operand) => truthy(operand, contexts).map(|b| !b),
+        Expr::Binary(left, BinaryOp::And, right) => {
+            match (truthy(left, contexts), truthy(right, contexts)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            }
+        }
+        Expr::Binary(left, BinaryOp::Or, right) => {
+            match (truthy(left, contexts), truthy(right, contexts)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            }
+        }
+        _ => evaluate(expr, contexts).map(|v| is_truthy(&v)),
+    }
+}
+
+/// Parse an `if:`, which may or may not be wrapped in `${{ }}`. Conditions
+/// that mix text and expressions are strings, and aren't parsed.
+pub fn parse_condition(condition: &str) -> Option<Expr> {
+    let trimmed = condition.trim();
+    let body = if trimmed.contains("${{") {
+        let found = expr::find_expressions(trimmed);
+        match found.as_slice() {
+            [only] if only.offset == 0 && trimmed.ends_with("}}") => only.body,
+            _ => return None,
+        }
+    } else {
+        trimmed
+    };
+    expr::parse(body).ok()
+}
+
+/// How a condition comes out across `scenarios`: `Some` if it's known and
+/// the same for all of them.
+pub fn condition_outcome(expr: &Expr, scenarios: &[Value]) -> Option<bool> {
+    let mut outcome = None;
+    for scenario in scenarios {
+        let result = truthy(expr, scenario)?;
+        if outcome.is_some_and(|o| o != result) {
+            return None;
+        }
+        outcome = Some(result);
+    }
+    outcome
+}
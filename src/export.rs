@@ -0,0 +1,308 @@
+//! The workflow model as data, for policy engines like OPA or CUE to check
+//! workflows against the same view of them the rules have.
+//!
+//! The model is the workflow normalized and resolved: `on` is always a map
+//! of event to its settings, `runs-on` and `needs` are always lists, jobs
+//! and steps get the workflow's and job's `env` and `defaults` merged in,
+//! and what GitHub assumes when a key is left out is filled in, like a
+//! `timeout-minutes` of 360 and an `if` of `success()`. Matrices can also
+//! be expanded into the legs they run as.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::{json, Map, Value};
+
+use crate::matrix;
+use crate::runner::Os;
+use crate::workflow::{Job, Step, Workflow};
+
+/// How long GitHub lets a job or step run when it doesn't say.
+pub const DEFAULT_TIMEOUT_MINUTES: i32 = 360;
+
+/// The ways the model can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Cue,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "cue" => Ok(Format::Cue),
+            _ => Err(format!("unknown format `{}`, expected json or cue", s)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "json",
+            Format::Cue => "cue",
+        })
+    }
+}
+
+/// `on` as a map of event to its settings, `{}` for an event without any,
+/// from the workflow's plain YAML `document`.
+fn triggers(document: &serde_yaml::Value) -> Value {
+    let on = document.get("on").cloned().unwrap_or_default();
+    let on: Value = serde_json::to_value(on).unwrap_or_default();
+    let mut events = Map::new();
+    match on {
+        Value::String(event) => {
+            events.insert(event, json!({}));
+        }
+        Value::Array(list) => {
+            for event in list
+                .into_iter()
+                .filter_map(|e| e.as_str().map(str::to_string))
+            {
+                events.insert(event, json!({}));
+            }
+        }
+        Value::Object(map) => {
+            for (event, settings) in map {
+                let settings = match settings {
+                    Value::Null => json!({}),
+                    settings => settings,
+                };
+                events.insert(event, settings);
+            }
+        }
+        _ => {}
+    }
+    Value::Object(events)
+}
+
+/// The shell GitHub runs `run` steps with when nothing sets one, going by
+/// the job's labels, if they say.
+fn default_shell(job: &Job) -> Option<&'static str> {
+    let oses: Vec<Os> = job
+        .labels()
+        .iter()
+        .filter_map(|l| Os::of_label(l))
+        .collect();
+    if oses.contains(&Os::Windows) {
+        Some("pwsh")
+    } else if !oses.is_empty() {
+        Some("bash")
+    } else {
+        None
+    }
+}
+
+fn step(workflow: &Workflow, job: &Job, step: &Step, value: Value) -> Value {
+    let mut value = match value {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    // `run` steps don't use an action
+    if step.uses.is_none() {
+        value.remove("uses");
+    }
+    let settings = workflow.run_settings(job, step);
+    if step.run.is_some() {
+        let shell = settings.shell.or_else(|| default_shell(job));
+        value.insert("shell".to_string(), json!(shell));
+        value.insert(
+            "working-directory".to_string(),
+            json!(settings.working_directory),
+        );
+    }
+    let mut env: Map<String, Value> = Map::new();
+    for layer in [workflow.env.as_ref(), Some(&job.env), Some(&step.env)]
+        .iter()
+        .flatten()
+    {
+        for (key, v) in layer.iter() {
+            env.insert(key.clone(), json!(v));
+        }
+    }
+    value.insert("env".to_string(), Value::Object(env));
+    value.insert(
+        "if".to_string(),
+        json!(step.run_if.as_deref().unwrap_or("success()")),
+    );
+    value.insert(
+        "continue-on-error".to_string(),
+        json!(step.continue_on_error.unwrap_or(false)),
+    );
+    value.insert(
+        "timeout-minutes".to_string(),
+        json!(step
+            .timeout_minutes
+            .or(job.timeout_minutes)
+            .unwrap_or(DEFAULT_TIMEOUT_MINUTES)),
+    );
+    Value::Object(value)
+}
+
+fn job(workflow: &Workflow, job: &Job, value: Value, expand_matrices: bool) -> Value {
+    let mut value = match value {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    value.insert("runs-on".to_string(), json!(job.labels()));
+    value.insert("needs".to_string(), json!(job.needs));
+    value.insert(
+        "if".to_string(),
+        json!(job.run_if.as_deref().unwrap_or("success()")),
+    );
+    value.insert(
+        "timeout-minutes".to_string(),
+        json!(job.timeout_minutes.unwrap_or(DEFAULT_TIMEOUT_MINUTES)),
+    );
+    let continue_on_error = match job.continue_on_error.as_deref() {
+        None => json!(false),
+        Some("true") => json!(true),
+        Some("false") => json!(false),
+        Some(expression) => json!(expression),
+    };
+    value.insert("continue-on-error".to_string(), continue_on_error);
+    let mut env = Map::new();
+    for layer in [workflow.env.as_ref(), Some(&job.env)].iter().flatten() {
+        for (key, v) in layer.iter() {
+            env.insert(key.clone(), json!(v));
+        }
+    }
+    value.insert("env".to_string(), Value::Object(env));
+
+    if let (Some(strategy), Some(Value::Object(out))) = (&job.strategy, value.get_mut("strategy")) {
+        out.insert(
+            "fail-fast".to_string(),
+            json!(strategy.fail_fast.unwrap_or(true)),
+        );
+        if expand_matrices {
+            let legs = strategy
+                .matrix
+                .as_ref()
+                .and_then(|matrix| matrix::expand(matrix).ok())
+                .map(|expansion| {
+                    expansion
+                        .legs
+                        .iter()
+                        .map(matrix::Leg::context)
+                        .collect::<Vec<_>>()
+                });
+            out.insert("legs".to_string(), json!(legs));
+        }
+    }
+
+    let steps: Vec<Value> = match value.remove("steps") {
+        Some(Value::Array(values)) => job
+            .steps
+            .iter()
+            .zip(values)
+            .map(|(s, v)| step(workflow, job, s, v))
+            .collect(),
+        _ => Vec::new(),
+    };
+    value.insert("steps".to_string(), Value::Array(steps));
+    Value::Object(value)
+}
+
+/// The normalized model of `workflow`, whose plain YAML is `document`. With
+/// `expand_matrices`, each matrix job's `strategy` gets the `legs` it runs
+/// as, each the `matrix` context of one, or `null` if the matrix can only
+/// be worked out during the run.
+pub fn model(workflow: &Workflow, document: &serde_yaml::Value, expand_matrices: bool) -> Value {
+    let mut model = match serde_json::to_value(workflow) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    model.insert("on".to_string(), triggers(document));
+    let jobs = match model.remove("jobs") {
+        Some(Value::Object(jobs)) => jobs,
+        _ => Map::new(),
+    };
+    let jobs: Map<String, Value> = jobs
+        .into_iter()
+        .map(|(id, value)| {
+            let value = match workflow.jobs.get(&id) {
+                Some(typed) => job(workflow, typed, value, expand_matrices),
+                None => value,
+            };
+            (id, value)
+        })
+        .collect();
+    model.insert("jobs".to_string(), Value::Object(jobs));
+    Value::Object(model)
+}
+
+/// Whether `key` can be a CUE field name without quotes. Names starting
+/// with `_` or `#` mean something else in CUE, so they're quoted too.
+fn cue_identifier(key: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "false", "for", "if", "import", "in", "let", "null", "package", "true",
+    ];
+    key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&key)
+}
+
+fn write_cue(out: &mut String, value: &Value, depth: usize) {
+    let indent = "\t".repeat(depth);
+    match value {
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Object(map) => {
+            out.push_str("{\n");
+            write_cue_fields(out, map, depth + 1);
+            out.push_str(&indent);
+            out.push('}');
+        }
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for item in items {
+                out.push_str(&indent);
+                out.push('\t');
+                write_cue(out, item, depth + 1);
+                out.push_str(",\n");
+            }
+            out.push_str(&indent);
+            out.push(']');
+        }
+        // JSON's scalars are CUE's, and its escapes too
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+fn write_cue_fields(out: &mut String, map: &Map<String, Value>, depth: usize) {
+    for (key, value) in map {
+        out.push_str(&"\t".repeat(depth));
+        match cue_identifier(key) {
+            true => out.push_str(key),
+            false => out.push_str(&Value::String(key.clone()).to_string()),
+        }
+        out.push_str(": ");
+        write_cue(out, value, depth);
+        out.push('\n');
+    }
+}
+
+/// `model` written as `format`.
+pub fn render(model: &Value, format: Format) -> String {
+    match format {
+        Format::Json => format!(
+            "{}\n",
+            serde_json::to_string_pretty(model).unwrap_or_default()
+        ),
+        Format::Cue => {
+            let mut out = String::new();
+            match model {
+                Value::Object(map) => write_cue_fields(&mut out, map, 0),
+                value => {
+                    write_cue(&mut out, value, 0);
+                    out.push('\n');
+                }
+            }
+            out
+        }
+    }
+}
@@ -0,0 +1,851 @@
+//! GitHub Actions expressions, the `${{ ... }}` bits of a workflow.
+
+use std::fmt;
+
+/// An expression found in a string, with the byte offset of its `${{`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Embedded<'a> {
+    pub offset: usize,
+    /// The text between `${{` and `}}`.
+    pub body: &'a str,
+    /// Byte offset of `body` within the string.
+    pub body_offset: usize,
+}
+
+/// Find every `${{ ... }}` in `s`. A `}}` inside a string literal doesn't end
+/// the expression. An unterminated expression runs to the end of `s`.
+pub fn find_expressions(s: &str) -> Vec<Embedded<'_>> {
+    let mut found = Vec::new();
+    let mut rest = 0;
+    while let Some(start) = s[rest..].find("${{") {
+        let offset = rest + start;
+        let body_offset = offset + 3;
+        let mut in_string = false;
+        let mut end = None;
+        let bytes = s.as_bytes();
+        let mut i = body_offset;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\'' => in_string = !in_string,
+                b'}' if !in_string && bytes.get(i + 1) == Some(&b'}') => {
+                    end = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        let body_end = end.unwrap_or(s.len());
+        found.push(Embedded {
+            offset,
+            body: &s[body_offset..body_end],
+            body_offset,
+        });
+        rest = end.map_or(s.len(), |e| e + 2);
+    }
+    found
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// A `'...'` string, with `''` unescaped.
+    String(String),
+    Number(f64),
+    True,
+    False,
+    Null,
+    /// A context, property or function name.
+    Ident(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Comma,
+    /// `*` in a property filter like `steps.*.outcome`.
+    Star,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte offset within the expression body.
+    pub offset: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// Split an expression body into tokens.
+pub fn tokenize(body: &str) -> Result<Vec<Token>, TokenError> {
+    let bytes = body.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let kind = match c {
+            b'(' => TokenKind::LParen,
+            b')' => TokenKind::RParen,
+            b'[' => TokenKind::LBracket,
+            b']' => TokenKind::RBracket,
+            b',' => TokenKind::Comma,
+            b'*' => TokenKind::Star,
+            b'.' if !bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => TokenKind::Dot,
+            b'!' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 1;
+                TokenKind::Ne
+            }
+            b'!' => TokenKind::Not,
+            b'=' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 1;
+                TokenKind::Eq
+            }
+            b'<' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 1;
+                TokenKind::Le
+            }
+            b'<' => TokenKind::Lt,
+            b'>' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 1;
+                TokenKind::Ge
+            }
+            b'>' => TokenKind::Gt,
+            b'&' if bytes.get(i + 1) == Some(&b'&') => {
+                i += 1;
+                TokenKind::And
+            }
+            b'|' if bytes.get(i + 1) == Some(&b'|') => {
+                i += 1;
+                TokenKind::Or
+            }
+            b'\'' => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match body[j..].find('\'') {
+                        None => {
+                            return Err(TokenError {
+                                message: "unterminated string".to_string(),
+                                offset: start,
+                            })
+                        }
+                        Some(k) => {
+                            value.push_str(&body[j..j + k]);
+                            j += k + 1;
+                            if bytes.get(j) == Some(&b'\'') {
+                                value.push('\'');
+                                j += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                }
+                i = j - 1;
+                TokenKind::String(value)
+            }
+            b'0'..=b'9' | b'-' | b'+' | b'.' => {
+                let mut j = i + 1;
+                while j < bytes.len()
+                    && (bytes[j].is_ascii_alphanumeric()
+                        || bytes[j] == b'.'
+                        || ((bytes[j] == b'-' || bytes[j] == b'+')
+                            && matches!(bytes[j - 1], b'e' | b'E')))
+                {
+                    j += 1;
+                }
+                let text = &body[i..j];
+                let number = match text.strip_prefix("0x") {
+                    Some(hex) => i64::from_str_radix(hex, 16).ok().map(|n| n as f64),
+                    None => text.parse().ok(),
+                };
+                match number {
+                    Some(n) => {
+                        i = j - 1;
+                        TokenKind::Number(n)
+                    }
+                    None => {
+                        return Err(TokenError {
+                            message: format!("`{}` isn't a valid number", text),
+                            offset: start,
+                        })
+                    }
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let mut j = i + 1;
+                while j < bytes.len()
+                    && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_' || bytes[j] == b'-')
+                {
+                    j += 1;
+                }
+                let word = &body[i..j];
+                i = j - 1;
+                match word {
+                    "true" => TokenKind::True,
+                    "false" => TokenKind::False,
+                    "null" => TokenKind::Null,
+                    _ => TokenKind::Ident(word.to_string()),
+                }
+            }
+            _ => {
+                let c = body[i..].chars().next().unwrap_or_default();
+                return Err(TokenError {
+                    message: format!("unexpected character `{}`", c),
+                    offset: start,
+                });
+            }
+        };
+        i += 1;
+        tokens.push(Token {
+            kind,
+            offset: start,
+            len: i - start,
+        });
+    }
+    Ok(tokens)
+}
+
+/// A property path like `steps.build.outputs.version` in an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Byte offset of the context name within the expression body.
+    pub offset: usize,
+    /// The context and properties, with `['x']` read as `.x`, `[0]` as `.0`
+    /// and `.*` as `*`.
+    pub parts: Vec<String>,
+    /// Length in bytes of the whole path in the expression body.
+    pub len: usize,
+}
+
+/// The property paths in a tokenized expression. A dynamic index like
+/// `matrix[inputs.key]` ends the path before it; function names aren't paths.
+pub fn references(tokens: &[Token]) -> Vec<Reference> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let context = match &tokens[i].kind {
+            TokenKind::Ident(name) => name,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let after_dot = i > 0 && tokens[i - 1].kind == TokenKind::Dot;
+        let call = tokens
+            .get(i + 1)
+            .is_some_and(|t| t.kind == TokenKind::LParen);
+        if after_dot || call {
+            i += 1;
+            continue;
+        }
+
+        let offset = tokens[i].offset;
+        let mut parts = vec![context.clone()];
+        i += 1;
+        loop {
+            let kinds = (
+                tokens.get(i).map(|t| &t.kind),
+                tokens.get(i + 1).map(|t| &t.kind),
+                tokens.get(i + 2).map(|t| &t.kind),
+            );
+            match kinds {
+                (Some(TokenKind::Dot), Some(TokenKind::Ident(name)), _) => {
+                    parts.push(name.clone());
+                    i += 2;
+                }
+                (Some(TokenKind::Dot), Some(TokenKind::Star), _) => {
+                    parts.push("*".to_string());
+                    i += 2;
+                }
+                (
+                    Some(TokenKind::LBracket),
+                    Some(TokenKind::String(name)),
+                    Some(TokenKind::RBracket),
+                ) => {
+                    parts.push(name.clone());
+                    i += 3;
+                }
+                (
+                    Some(TokenKind::LBracket),
+                    Some(TokenKind::Number(index)),
+                    Some(TokenKind::RBracket),
+                ) => {
+                    parts.push(index.to_string());
+                    i += 3;
+                }
+                _ => break,
+            }
+        }
+        let last = &tokens[i - 1];
+        let len = last.offset + last.len - offset;
+        found.push(Reference { offset, parts, len });
+    }
+    found
+}
+
+/// The expressions in an `if:` condition, which is an expression whether or
+/// not it's wrapped in `${{ }}`.
+pub fn condition_expressions(condition: &str) -> Vec<&str> {
+    if condition.contains("${{") {
+        find_expressions(condition).iter().map(|e| e.body).collect()
+    } else {
+        vec![condition]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// A context like `github`.
+    Context(String),
+    /// `expr.name`
+    Property(Box<Expr>, String),
+    /// `expr[index]`
+    Index(Box<Expr>, Box<Expr>),
+    /// `expr.*`, every value of an object or array.
+    Filter(Box<Expr>),
+    /// A function call, with the name as written.
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+/// Parse an expression body, the text between `${{` and `}}`.
+pub fn parse(body: &str) -> Result<Expr, TokenError> {
+    let tokens = tokenize(body)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        end: body.len(),
+    };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(TokenError {
+            message: "unexpected token after the end of the expression".to_string(),
+            offset: token.offset,
+        }),
+    }
+}
+
+struct Parser<'t> {
+    tokens: &'t [Token],
+    pos: usize,
+    /// Offset reported for errors at the end of the input.
+    end: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn peek(&self) -> Option<&'t Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|t| t.kind == *kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> TokenError {
+        TokenError {
+            message: message.to_string(),
+            offset: self.peek().map_or(self.end, |t| t.offset),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<(), TokenError> {
+        if self.eat(&kind) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", what)))
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, TokenError> {
+        let mut left = self.and()?;
+        while self.eat(&TokenKind::Or) {
+            let right = self.and()?;
+            left = Expr::Binary(Box::new(left), BinaryOp::Or, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, TokenError> {
+        let mut left = self.equality()?;
+        while self.eat(&TokenKind::And) {
+            let right = self.equality()?;
+            left = Expr::Binary(Box::new(left), BinaryOp::And, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn equality(&mut self) -> Result<Expr, TokenError> {
+        let mut left = self.comparison()?;
+        loop {
+            let op = match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::Eq) => BinaryOp::Eq,
+                Some(TokenKind::Ne) => BinaryOp::Ne,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.comparison()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, TokenError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::Lt) => BinaryOp::Lt,
+                Some(TokenKind::Le) => BinaryOp::Le,
+                Some(TokenKind::Gt) => BinaryOp::Gt,
+                Some(TokenKind::Ge) => BinaryOp::Ge,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, TokenError> {
+        if self.eat(&TokenKind::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, TokenError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat(&TokenKind::Dot) {
+                match self.peek().map(|t| &t.kind) {
+                    Some(TokenKind::Ident(name)) => {
+                        self.pos += 1;
+                        expr = Expr::Property(Box::new(expr), name.clone());
+                    }
+                    Some(TokenKind::Star) => {
+                        self.pos += 1;
+                        expr = Expr::Filter(Box::new(expr));
+                    }
+                    _ => return Err(self.error("expected a property name after `.`")),
+                }
+            } else if self.eat(&TokenKind::LBracket) {
+                if self.eat(&TokenKind::Star) {
+                    expr = Expr::Filter(Box::new(expr));
+                } else {
+                    let index = self.or()?;
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                self.expect(TokenKind::RBracket, "`]`")?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, TokenError> {
+        let token = match self.peek() {
+            Some(token) => token,
+            None => return Err(self.error("expected a value")),
+        };
+        self.pos += 1;
+        Ok(match &token.kind {
+            TokenKind::Null => Expr::Null,
+            TokenKind::True => Expr::Bool(true),
+            TokenKind::False => Expr::Bool(false),
+            TokenKind::Number(n) => Expr::Number(*n),
+            TokenKind::String(s) => Expr::String(s.clone()),
+            TokenKind::Ident(name) if self.eat(&TokenKind::LParen) => {
+                let mut args = Vec::new();
+                if !self.eat(&TokenKind::RParen) {
+                    loop {
+                        args.push(self.or()?);
+                        if self.eat(&TokenKind::RParen) {
+                            break;
+                        }
+                        self.expect(TokenKind::Comma, "`,` or `)`")?;
+                    }
+                }
+                Expr::Call(name.clone(), args)
+            }
+            TokenKind::Ident(name) => Expr::Context(name.clone()),
+            TokenKind::LParen => {
+                let expr = self.or()?;
+                self.expect(TokenKind::RParen, "`)`")?;
+                expr
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected a value"));
+            }
+        })
+    }
+}
+
+/// The functions expressions can call, with the fewest and most arguments
+/// each takes; `None` for no limit. Names are matched ignoring case.
+pub const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+    ("contains", 2, Some(2)),
+    ("startsWith", 2, Some(2)),
+    ("endsWith", 2, Some(2)),
+    ("format", 1, None),
+    ("join", 1, Some(2)),
+    ("toJSON", 1, Some(1)),
+    ("fromJSON", 1, Some(1)),
+    ("hashFiles", 1, None),
+    ("success", 0, Some(0)),
+    ("always", 0, Some(0)),
+    ("cancelled", 0, Some(0)),
+    ("failure", 0, Some(0)),
+];
+
+/// The contexts expressions can refer to, matched ignoring case.
+pub const CONTEXTS: &[&str] = &[
+    "github", "env", "vars", "job", "jobs", "steps", "runner", "secrets", "strategy", "matrix",
+    "needs", "inputs",
+];
+
+/// Properties someone outside the repository can write, like an issue's
+/// title or a branch name, with who that is. A `*` stands for any one
+/// property, like the index of a commit.
+pub const UNTRUSTED: &[(&str, &str)] = &[
+    ("github.head_ref", "the pull request's author"),
+    (
+        "github.event.pull_request.title",
+        "the pull request's author",
+    ),
+    (
+        "github.event.pull_request.body",
+        "the pull request's author",
+    ),
+    (
+        "github.event.pull_request.head.ref",
+        "the pull request's author",
+    ),
+    (
+        "github.event.pull_request.head.label",
+        "the pull request's author",
+    ),
+    (
+        "github.event.pull_request.head.repo.default_branch",
+        "the pull request's author",
+    ),
+    ("github.event.issue.title", "whoever opened the issue"),
+    ("github.event.issue.body", "whoever opened the issue"),
+    ("github.event.comment.body", "whoever wrote the comment"),
+    ("github.event.review.body", "the reviewer"),
+    ("github.event.review_comment.body", "the reviewer"),
+    (
+        "github.event.discussion.title",
+        "whoever started the discussion",
+    ),
+    (
+        "github.event.discussion.body",
+        "whoever started the discussion",
+    ),
+    ("github.event.pages.*.page_name", "whoever edited the wiki"),
+    (
+        "github.event.commits.*.message",
+        "whoever wrote the commits",
+    ),
+    (
+        "github.event.commits.*.author.name",
+        "whoever wrote the commits",
+    ),
+    (
+        "github.event.commits.*.author.email",
+        "whoever wrote the commits",
+    ),
+    (
+        "github.event.head_commit.message",
+        "whoever wrote the commit",
+    ),
+    (
+        "github.event.head_commit.author.name",
+        "whoever wrote the commit",
+    ),
+    (
+        "github.event.head_commit.author.email",
+        "whoever wrote the commit",
+    ),
+    (
+        "github.event.workflow_run.head_branch",
+        "whoever pushed the branch the run was for",
+    ),
+    (
+        "github.event.workflow_run.head_commit.message",
+        "whoever wrote the commit the run was for",
+    ),
+    (
+        "github.event.workflow_run.head_commit.author.name",
+        "whoever wrote the commit the run was for",
+    ),
+    (
+        "github.event.workflow_run.head_commit.author.email",
+        "whoever wrote the commit the run was for",
+    ),
+    (
+        "github.event.workflow_run.pull_requests.*.head.ref",
+        "the pull request's author",
+    ),
+];
+
+/// Who can write the value of the reference `parts`, if it's one of the
+/// [`UNTRUSTED`] properties or inside one.
+pub fn untrusted(parts: &[String]) -> Option<&'static str> {
+    UNTRUSTED.iter().find_map(|&(pattern, who)| {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        let matches = parts.len() >= pattern.len()
+            && pattern
+                .iter()
+                .zip(parts)
+                .all(|(p, part)| *p == "*" || p.eq_ignore_ascii_case(part));
+        matches.then_some(who)
+    })
+}
+
+const GITHUB_PROPERTIES: &[&str] = &[
+    "action",
+    "action_path",
+    "action_ref",
+    "action_repository",
+    "action_status",
+    "actor",
+    "actor_id",
+    "api_url",
+    "base_ref",
+    "env",
+    "event",
+    "event_name",
+    "event_path",
+    "graphql_url",
+    "head_ref",
+    "job",
+    "path",
+    "ref",
+    "ref_name",
+    "ref_protected",
+    "ref_type",
+    "repository",
+    "repository_id",
+    "repository_owner",
+    "repository_owner_id",
+    "repositoryUrl",
+    "retention_days",
+    "run_attempt",
+    "run_id",
+    "run_number",
+    "secret_source",
+    "server_url",
+    "sha",
+    "token",
+    "triggering_actor",
+    "workflow",
+    "workflow_ref",
+    "workflow_sha",
+    "workspace",
+];
+
+const RUNNER_PROPERTIES: &[&str] = &[
+    "arch",
+    "debug",
+    "environment",
+    "name",
+    "os",
+    "temp",
+    "tool_cache",
+];
+
+const STRATEGY_PROPERTIES: &[&str] = &["fail-fast", "job-index", "job-total", "max-parallel"];
+
+/// The properties of `context`, for the contexts whose properties are
+/// fixed; the others, like `env` and `steps`, have whatever the workflow
+/// gives them.
+pub fn properties(context: &str) -> Option<&'static [&'static str]> {
+    match context.to_ascii_lowercase().as_str() {
+        "github" => Some(GITHUB_PROPERTIES),
+        "runner" => Some(RUNNER_PROPERTIES),
+        "strategy" => Some(STRATEGY_PROPERTIES),
+        _ => None,
+    }
+}
+
+/// What's wrong with part of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// The expression doesn't parse, like `github.ref = 'main'`.
+    Syntax(String),
+    UnknownFunction(String),
+    /// A function called with too few or too many arguments.
+    Arguments {
+        function: String,
+        given: usize,
+    },
+    UnknownContext(String),
+    /// A property that a context with fixed properties doesn't have, like
+    /// `github.evnet_name`. It's `null` rather than an error when the
+    /// workflow runs.
+    UnknownProperty {
+        context: String,
+        property: String,
+    },
+}
+
+/// A problem with an expression, `len` bytes from `offset` in its body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub offset: usize,
+    pub len: usize,
+    pub kind: ProblemKind,
+}
+
+/// The fewest and most arguments `function` takes, if it's a function.
+pub fn arity(function: &str) -> Option<(usize, Option<usize>)> {
+    FUNCTIONS
+        .iter()
+        .find(|(name, ..)| name.eq_ignore_ascii_case(function))
+        .map(|&(_, min, max)| (min, max))
+}
+
+/// How many arguments the call whose `(` is at `tokens[open]` has.
+fn argument_count(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    let mut count = 0;
+    for token in &tokens[open + 1..] {
+        match token.kind {
+            TokenKind::LParen | TokenKind::LBracket => depth += 1,
+            TokenKind::RParen | TokenKind::RBracket if depth > 0 => depth -= 1,
+            TokenKind::RParen => break,
+            TokenKind::Comma if depth == 0 => count += 1,
+            _ if count == 0 => count = 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+/// Check an expression body: that it parses, that its functions exist and
+/// are given the right number of arguments, and that its contexts exist,
+/// along with the properties of the contexts whose properties are fixed.
+pub fn validate(body: &str) -> Vec<Problem> {
+    if let Err(error) = parse(body) {
+        return vec![Problem {
+            offset: error.offset,
+            len: 0,
+            kind: ProblemKind::Syntax(error.message),
+        }];
+    }
+    let tokens = match tokenize(body) {
+        Ok(tokens) => tokens,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut problems = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let name = match &token.kind {
+            TokenKind::Ident(name) => name,
+            _ => continue,
+        };
+        let call = tokens
+            .get(i + 1)
+            .is_some_and(|t| t.kind == TokenKind::LParen);
+        let after_dot = i > 0 && tokens[i - 1].kind == TokenKind::Dot;
+        if !call || after_dot {
+            continue;
+        }
+        let kind = match arity(name) {
+            None => ProblemKind::UnknownFunction(name.clone()),
+            Some((min, max)) => {
+                let given = argument_count(&tokens, i + 1);
+                if given >= min && max.is_none_or(|max| given <= max) {
+                    continue;
+                }
+                ProblemKind::Arguments {
+                    function: name.clone(),
+                    given,
+                }
+            }
+        };
+        problems.push(Problem {
+            offset: token.offset,
+            len: token.len,
+            kind,
+        });
+    }
+
+    for reference in references(&tokens) {
+        let context = &reference.parts[0];
+        if !CONTEXTS.iter().any(|c| c.eq_ignore_ascii_case(context)) {
+            problems.push(Problem {
+                offset: reference.offset,
+                len: context.len(),
+                kind: ProblemKind::UnknownContext(context.clone()),
+            });
+            continue;
+        }
+        let (known, property) = match (properties(context), reference.parts.get(1)) {
+            (Some(known), Some(property)) if property != "*" => (known, property),
+            _ => continue,
+        };
+        if !known.iter().any(|k| k.eq_ignore_ascii_case(property)) {
+            problems.push(Problem {
+                offset: reference.offset,
+                len: reference.len,
+                kind: ProblemKind::UnknownProperty {
+                    context: context.clone(),
+                    property: property.clone(),
+                },
+            });
+        }
+    }
+    problems.sort_by_key(|p| p.offset);
+    problems
+}
@@ -0,0 +1,697 @@
+//! Moving steps out of a job into a reusable workflow.
+//!
+//! [`extract`] takes a run of a job's steps, writes them into a new
+//! `workflow_call` workflow, and rewrites the job to call it. Whatever the
+//! steps read from the caller, like `matrix.os`, `inputs.version` or
+//! `needs.build.outputs.tag`, becomes an input, and each secret they use
+//! becomes a required secret; both are passed from the calling job.
+//!
+//! A reusable workflow is called by a whole job, so the steps have to be
+//! the whole job, or the start or end of it. When they're only part of it,
+//! the call is a new job, with `needs` keeping the order the steps ran in.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::expr::{condition_expressions, find_expressions, references, tokenize, Reference};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractError {
+    pub message: String,
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
+fn error(message: impl Into<String>) -> ExtractError {
+    ExtractError {
+        message: message.into(),
+    }
+}
+
+/// Which steps to move, numbered from 0 as in `steps[2]`. Written like a
+/// Rust range: `2..6` is steps 2 to 5, `2..=6` includes 6, `2..` runs to
+/// the end and `3` is the one step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepRange {
+    pub start: usize,
+    /// One past the last step; the end of the job if `None`.
+    pub end: Option<usize>,
+}
+
+impl FromStr for StepRange {
+    type Err = ExtractError;
+
+    fn from_str(s: &str) -> Result<StepRange, ExtractError> {
+        let number = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| error(format!("`{}` isn't a step number or range like 2..6", s)))
+        };
+        let (start, end) = match s.split_once("..") {
+            None => {
+                let start = number(s)?;
+                (start, Some(start + 1))
+            }
+            Some((start, end)) => {
+                let start = if start.is_empty() { 0 } else { number(start)? };
+                let end = match end.strip_prefix('=') {
+                    Some(end) => Some(number(end)? + 1),
+                    None if end.is_empty() => None,
+                    None => Some(number(end)?),
+                };
+                (start, end)
+            }
+        };
+        Ok(StepRange { start, end })
+    }
+}
+
+/// A value the extracted steps read from the calling job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub name: String,
+    /// `string`, `number` or `boolean`.
+    pub kind: &'static str,
+    /// The reference it's passed as, like `matrix.os`.
+    pub value: String,
+}
+
+/// The result of [`extract`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extraction {
+    /// The new reusable workflow.
+    pub workflow: String,
+    /// The original workflow, calling it.
+    pub caller: String,
+    /// The id of the job that calls it.
+    pub call_job: String,
+    pub inputs: Vec<Input>,
+    pub secrets: Vec<String>,
+    /// Whether the call passes every secret, since the steps read them all.
+    pub inherit_secrets: bool,
+    /// Things that work differently now, to tell the user.
+    pub notes: Vec<String>,
+}
+
+/// Where the steps are in the job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Whole,
+    Start,
+    End,
+}
+
+/// Keys of a job that go with its steps to the reusable workflow.
+const RUNNER_KEYS: &[&str] = &[
+    "runs-on",
+    "environment",
+    "container",
+    "services",
+    "env",
+    "defaults",
+    "timeout-minutes",
+    "continue-on-error",
+];
+
+/// Keys of a job that a job calling a reusable workflow can have too.
+const CALL_KEYS: &[&str] = &[
+    "name",
+    "needs",
+    "if",
+    "permissions",
+    "strategy",
+    "concurrency",
+];
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+fn needs_list(needs: Option<&Value>) -> Vec<String> {
+    match needs {
+        Some(Value::String(id)) => vec![id.clone()],
+        Some(Value::Sequence(ids)) => ids
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn needs_value(ids: &[String]) -> Value {
+    match ids {
+        [id] => string(id),
+        _ => Value::Sequence(ids.iter().map(|id| string(id)).collect()),
+    }
+}
+
+/// The expressions in a string, as the byte offset of each body and the
+/// body. `if` values are expressions even without `${{ }}`.
+fn bodies(s: &str, is_condition: bool) -> Vec<(usize, &str)> {
+    if is_condition {
+        // `condition_expressions` returns slices of `s`
+        condition_expressions(s)
+            .into_iter()
+            .map(|body| (body.as_ptr() as usize - s.as_ptr() as usize, body))
+            .collect()
+    } else {
+        find_expressions(s)
+            .into_iter()
+            .map(|e| (e.body_offset, e.body))
+            .collect()
+    }
+}
+
+/// Call `f` with every reference in the strings under `value`, and replace
+/// each one it returns a new path for.
+fn rewrite(
+    value: &mut Value,
+    is_condition: bool,
+    f: &mut dyn FnMut(&Reference) -> Result<Option<String>, ExtractError>,
+) -> Result<(), ExtractError> {
+    match value {
+        Value::String(s) => {
+            let mut edits = Vec::new();
+            for (offset, body) in bodies(s, is_condition) {
+                let tokens = match tokenize(body) {
+                    Ok(tokens) => tokens,
+                    Err(_) => continue,
+                };
+                for reference in references(&tokens) {
+                    if let Some(replacement) = f(&reference)? {
+                        edits.push((offset + reference.offset, reference.len, replacement));
+                    }
+                }
+            }
+            for (offset, len, replacement) in edits.into_iter().rev() {
+                s.replace_range(offset..offset + len, &replacement);
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                rewrite(item, false, f)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, item) in map.iter_mut() {
+                rewrite(item, key.as_str() == Some("if"), f)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The step ids `steps` refer to.
+fn referenced_steps(steps: &[Value]) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut steps = Value::Sequence(steps.to_vec());
+    let _ = rewrite(&mut steps, false, &mut |reference| {
+        if reference.parts[0] == "steps" && reference.parts.len() > 1 {
+            found.push(reference.parts[1].clone());
+        }
+        Ok(None)
+    });
+    found
+}
+
+fn step_ids(steps: &[Value]) -> Vec<String> {
+    steps
+        .iter()
+        .filter_map(|step| step.get("id").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The type to give an input passed as `parts`.
+fn input_kind(document: &Value, parts: &[String]) -> &'static str {
+    match parts[0].as_str() {
+        "inputs" => {
+            let declared = ["workflow_dispatch", "workflow_call"]
+                .iter()
+                .find_map(|event| {
+                    document
+                        .get("on")
+                        .and_then(|on| on.get(*event))
+                        .and_then(|e| e.get("inputs"))
+                        .and_then(|inputs| inputs.get(parts[1].as_str()))
+                        .and_then(|input| input.get("type"))
+                        .and_then(Value::as_str)
+                });
+            match declared {
+                Some("boolean") => "boolean",
+                Some("number") => "number",
+                _ => "string",
+            }
+        }
+        "strategy" => match parts[1].as_str() {
+            "fail-fast" => "boolean",
+            _ => "number",
+        },
+        _ => "string",
+    }
+}
+
+/// Collects the inputs and secrets of the new workflow.
+struct Passed<'d> {
+    document: &'d Value,
+    extracted_ids: Vec<String>,
+    inputs: Vec<Input>,
+    secrets: Vec<String>,
+    inherit_secrets: bool,
+    needs: Vec<String>,
+}
+
+impl Passed<'_> {
+    /// The new path for `reference` within the reusable workflow, if it
+    /// changes.
+    fn visit(&mut self, reference: &Reference) -> Result<Option<String>, ExtractError> {
+        let parts = &reference.parts;
+        let path = parts.join(".");
+        match parts[0].as_str() {
+            "matrix" | "inputs" | "needs" | "strategy" => {
+                if parts.len() < 2 || parts.iter().any(|p| p == "*") {
+                    return Err(error(format!(
+                        "the steps use all of `{}`, which can't be passed as inputs",
+                        path
+                    )));
+                }
+                if parts[0] == "needs" && !self.needs.contains(&parts[1]) {
+                    self.needs.push(parts[1].clone());
+                }
+                Ok(Some(format!("inputs.{}", self.input(parts, &path))))
+            }
+            "secrets" => {
+                match parts.get(1).map(String::as_str) {
+                    None | Some("*") => self.inherit_secrets = true,
+                    Some("GITHUB_TOKEN") => {}
+                    Some(name) => {
+                        if !self.secrets.iter().any(|s| s == name) {
+                            self.secrets.push(name.to_string());
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            "steps" if parts.len() > 1 && !self.extracted_ids.contains(&parts[1]) => {
+                Err(error(format!(
+                    "the steps use `{}`, from a step that isn't being moved; set it as a job \
+                     output and pass it with `needs`, or move that step too",
+                    path
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The name of the input for `path`, adding it if it's new.
+    fn input(&mut self, parts: &[String], path: &str) -> String {
+        if let Some(input) = self.inputs.iter().find(|i| i.value == path) {
+            return input.name.clone();
+        }
+        let last = &parts[parts.len() - 1];
+        let parent = &parts[parts.len() - 2];
+        let taken = |name: &str| self.inputs.iter().any(|i| i.name == name);
+        let mut candidates = Vec::new();
+        if !matches!(last.as_str(), "result" | "outputs") {
+            candidates.push(last.clone());
+        }
+        candidates.push(format!("{}-{}", parent, last));
+        let base = candidates[candidates.len() - 1].clone();
+        let name = candidates
+            .into_iter()
+            .chain((2..).map(|n| format!("{}-{}", base, n)))
+            .find(|c| !taken(c))
+            .unwrap_or(base);
+        self.inputs.push(Input {
+            name: name.clone(),
+            kind: input_kind(self.document, parts),
+            value: path.to_string(),
+        });
+        name
+    }
+}
+
+/// An id for the calling job, from the workflow's file name.
+fn call_job_id(into: &str, jobs: &Mapping) -> String {
+    let file = into.rsplit('/').next().unwrap_or(into);
+    let stem = file
+        .trim_end_matches(".yml")
+        .trim_end_matches(".yaml")
+        .replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_',
+            "-",
+        );
+    let taken = |id: &str| jobs.contains_key(&string(id));
+    std::iter::once(stem.clone())
+        .chain((2..).map(|n| format!("{}-{}", stem, n)))
+        .find(|id| !taken(id))
+        .unwrap_or(stem)
+}
+
+fn is_checkout(step: &Value) -> bool {
+    step.get("uses")
+        .and_then(Value::as_str)
+        .is_some_and(|uses| uses.starts_with("actions/checkout@"))
+}
+
+fn to_yaml(value: &Value) -> Result<String, ExtractError> {
+    let yaml = serde_yaml::to_string(value).map_err(|e| error(e.to_string()))?;
+    Ok(format!("{}\n", yaml.trim_start_matches("---\n").trim_end()))
+}
+
+/// Move `range` of the steps of job `job_id` in the workflow `source` into a
+/// reusable workflow at `into`, a path from the root of the repository like
+/// `.github/workflows/build-lib.yml`.
+pub fn extract(
+    source: &str,
+    job_id: &str,
+    range: StepRange,
+    into: &str,
+) -> Result<Extraction, ExtractError> {
+    let into = into.trim_start_matches("./");
+    let file = into.strip_prefix(".github/workflows/").unwrap_or_default();
+    if file.is_empty() || file.contains('/') || !(file.ends_with(".yml") || file.ends_with(".yaml"))
+    {
+        return Err(error(format!(
+            "reusable workflows have to be a .yml file directly in .github/workflows, not `{}`",
+            into
+        )));
+    }
+
+    let document: Value = serde_yaml::from_str(source).map_err(|e| error(e.to_string()))?;
+    let jobs = document
+        .get("jobs")
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| error("the workflow has no jobs"))?;
+    let job = jobs
+        .get(&string(job_id))
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| error(format!("the workflow has no job `{}`", job_id)))?;
+    let steps = job
+        .get(&string("steps"))
+        .and_then(Value::as_sequence)
+        .ok_or_else(|| error(format!("`{}` has no steps", job_id)))?;
+
+    let start = range.start;
+    let end = range.end.unwrap_or(steps.len());
+    if start >= end || end > steps.len() {
+        return Err(error(format!(
+            "`{}` has steps 0 to {}, so there are no steps {}..{} to move",
+            job_id,
+            steps.len().saturating_sub(1),
+            start,
+            end
+        )));
+    }
+    let shape = match (start, end == steps.len()) {
+        (0, true) => Shape::Whole,
+        (0, false) => Shape::Start,
+        (_, true) => Shape::End,
+        _ => {
+            return Err(error(format!(
+                "a reusable workflow is called by a whole job, so the steps to move have to \
+                 be at the start or end of `{}`; steps {}..{} are in the middle",
+                job_id, start, end
+            )))
+        }
+    };
+    let extracted = &steps[start..end];
+    let kept: Vec<Value> = steps[..start]
+        .iter()
+        .chain(&steps[end..])
+        .cloned()
+        .collect();
+    let extracted_ids = step_ids(extracted);
+
+    if let Some(id) = referenced_steps(&kept)
+        .into_iter()
+        .find(|id| extracted_ids.contains(id))
+    {
+        return Err(error(format!(
+            "steps that stay in `{}` use the outputs of step `{}`, which would be moved",
+            job_id, id
+        )));
+    }
+    let outputs = job.get(&string("outputs"));
+    if shape != Shape::Whole {
+        let outputs = outputs.cloned().unwrap_or(Value::Null);
+        let moved = referenced_steps(std::slice::from_ref(&outputs))
+            .into_iter()
+            .find(|id| extracted_ids.contains(id));
+        if let Some(id) = moved {
+            return Err(error(format!(
+                "the outputs of `{}` come from step `{}`, which would be moved; move the \
+                 whole job instead",
+                job_id, id
+            )));
+        }
+    }
+
+    // The job in the reusable workflow
+    let mut notes = Vec::new();
+    let mut called = Mapping::new();
+    for key in RUNNER_KEYS {
+        if let Some(value) = job.get(&string(key)) {
+            called.insert(string(key), value.clone());
+        }
+    }
+    if shape == Shape::Whole {
+        if let Some(outputs) = outputs {
+            called.insert(string("outputs"), outputs.clone());
+        }
+    }
+    let mut moved_steps = extracted.to_vec();
+    if shape == Shape::End && !extracted.iter().any(is_checkout) {
+        if let Some(checkout) = steps[..start].iter().find(|step| is_checkout(step)) {
+            moved_steps.insert(0, checkout.clone());
+            notes.push("copied the checkout step, so the moved steps have the repository".into());
+        }
+    }
+    called.insert(string("steps"), Value::Sequence(moved_steps));
+
+    let mut workflow = Mapping::new();
+    let name = job
+        .get(&string("name"))
+        .cloned()
+        .unwrap_or_else(|| string(job_id));
+    workflow.insert(string("name"), name);
+    for key in &["env", "defaults"] {
+        if let Some(value) = document.get(*key) {
+            workflow.insert(string(key), value.clone());
+        }
+    }
+    let mut called_jobs = Mapping::new();
+    called_jobs.insert(string(job_id), Value::Mapping(called));
+    workflow.insert(string("jobs"), Value::Mapping(called_jobs));
+
+    let mut passed = Passed {
+        document: &document,
+        extracted_ids,
+        inputs: Vec::new(),
+        secrets: Vec::new(),
+        inherit_secrets: false,
+        needs: Vec::new(),
+    };
+    let mut workflow = Value::Mapping(workflow);
+    rewrite(&mut workflow, false, &mut |reference| {
+        passed.visit(reference)
+    })?;
+
+    // `on: workflow_call`, with what's passed in and out
+    let mut call = Mapping::new();
+    if !passed.inputs.is_empty() {
+        let mut inputs = Mapping::new();
+        for input in &passed.inputs {
+            let mut definition = Mapping::new();
+            definition.insert(string("type"), string(input.kind));
+            // The caller's own inputs can be left out by whoever runs it
+            let required = !input.value.starts_with("inputs.");
+            definition.insert(string("required"), Value::Bool(required));
+            inputs.insert(string(&input.name), Value::Mapping(definition));
+        }
+        call.insert(string("inputs"), Value::Mapping(inputs));
+    }
+    if !passed.secrets.is_empty() && !passed.inherit_secrets {
+        let mut secrets = Mapping::new();
+        for secret in &passed.secrets {
+            let mut definition = Mapping::new();
+            definition.insert(string("required"), Value::Bool(true));
+            secrets.insert(string(secret), Value::Mapping(definition));
+        }
+        call.insert(string("secrets"), Value::Mapping(secrets));
+    }
+    if let (Shape::Whole, Some(Value::Mapping(job_outputs))) = (shape, outputs) {
+        let mut outputs = Mapping::new();
+        for (name, _) in job_outputs {
+            let name = name.as_str().unwrap_or_default();
+            let mut definition = Mapping::new();
+            let value = format!("${{{{ jobs.{}.outputs.{} }}}}", job_id, name);
+            definition.insert(string("value"), string(&value));
+            outputs.insert(string(name), Value::Mapping(definition));
+        }
+        call.insert(string("outputs"), Value::Mapping(outputs));
+    }
+    let mut on = Mapping::new();
+    on.insert(string("workflow_call"), Value::Mapping(call));
+    // `on` goes after `name`, where workflows have it
+    let mut ordered = Mapping::new();
+    if let Value::Mapping(keys) = workflow {
+        for (key, value) in keys {
+            let is_name = key.as_str() == Some("name");
+            ordered.insert(key, value);
+            if is_name {
+                ordered.insert(string("on"), Value::Mapping(on.clone()));
+            }
+        }
+    }
+
+    // The job that calls it
+    let call_id = match shape {
+        Shape::Whole => job_id.to_string(),
+        _ => call_job_id(into, jobs),
+    };
+    let mut calling = Mapping::new();
+    let original_needs = needs_list(job.get(&string("needs")));
+    for key in CALL_KEYS {
+        let value = match job.get(&string(key)) {
+            Some(value) => value.clone(),
+            None => continue,
+        };
+        match (shape, *key) {
+            (_, "needs") | (_, "name") => {}
+            // The rest of the job has to have succeeded
+            (Shape::End, "if") => {}
+            // It would wait on the rest of its own job
+            (Shape::Start, "concurrency") | (Shape::End, "concurrency") => {
+                notes.push(format!(
+                    "`{}` doesn't have the job's concurrency group, since it would wait on \
+                     the rest of the job",
+                    call_id
+                ));
+            }
+            _ => {
+                calling.insert(string(key), value);
+            }
+        }
+    }
+    let mut needs = original_needs.clone();
+    if shape == Shape::End {
+        needs.push(job_id.to_string());
+    }
+    for id in &passed.needs {
+        if !needs.contains(id) {
+            needs.push(id.clone());
+        }
+    }
+    let mut head = Mapping::new();
+    if shape == Shape::Whole {
+        if let Some(name) = job.get(&string("name")) {
+            head.insert(string("name"), name.clone());
+        }
+    }
+    if !needs.is_empty() {
+        head.insert(string("needs"), needs_value(&needs));
+    }
+    head.extend(calling);
+    head.insert(string("uses"), string(&format!("./{}", into)));
+    if !passed.inputs.is_empty() {
+        let mut with = Mapping::new();
+        for input in &passed.inputs {
+            let value = format!("${{{{ {} }}}}", input.value);
+            with.insert(string(&input.name), string(&value));
+        }
+        head.insert(string("with"), Value::Mapping(with));
+    }
+    if passed.inherit_secrets {
+        head.insert(string("secrets"), string("inherit"));
+    } else if !passed.secrets.is_empty() {
+        let mut secrets = Mapping::new();
+        for secret in &passed.secrets {
+            let value = format!("${{{{ secrets.{} }}}}", secret);
+            secrets.insert(string(secret), string(&value));
+        }
+        head.insert(string("secrets"), Value::Mapping(secrets));
+    }
+    let calling = Value::Mapping(head);
+
+    // The rest of the original job, without the moved steps
+    let mut rest = job.clone();
+    rest.insert(string("steps"), Value::Sequence(kept));
+    if shape == Shape::Start {
+        let mut needs = original_needs;
+        needs.push(call_id.clone());
+        match rest.get_mut(&string("needs")) {
+            Some(value) => *value = needs_value(&needs),
+            None => {
+                rest.insert(string("needs"), needs_value(&needs));
+            }
+        }
+    }
+
+    let mut caller = document.clone();
+    if let Some(Value::Mapping(jobs)) = caller.get_mut("jobs") {
+        let mut rebuilt = Mapping::new();
+        for (id, job) in std::mem::take(jobs) {
+            if id.as_str() != Some(job_id) {
+                rebuilt.insert(id, job);
+                continue;
+            }
+            match shape {
+                Shape::Whole => {
+                    rebuilt.insert(id, calling.clone());
+                }
+                Shape::Start => {
+                    rebuilt.insert(string(&call_id), calling.clone());
+                    rebuilt.insert(id, Value::Mapping(rest.clone()));
+                }
+                Shape::End => {
+                    rebuilt.insert(id, Value::Mapping(rest.clone()));
+                    rebuilt.insert(string(&call_id), calling.clone());
+                }
+            }
+        }
+        // Jobs that waited for all of the job wait for the moved steps too
+        if shape == Shape::End {
+            for (id, other) in rebuilt.iter_mut() {
+                if id.as_str() == Some(call_id.as_str()) {
+                    continue;
+                }
+                let mut needs = needs_list(other.get("needs"));
+                if needs.iter().any(|n| n == job_id) {
+                    needs.push(call_id.clone());
+                    if let Some(value) = other.get_mut("needs") {
+                        *value = needs_value(&needs);
+                    }
+                }
+            }
+        }
+        *jobs = rebuilt;
+    }
+
+    if shape != Shape::Whole {
+        notes.push(format!(
+            "the moved steps run on a runner of their own, so files the rest of `{}` makes \
+             aren't there; pass any they need as artifacts",
+            job_id
+        ));
+    }
+    Ok(Extraction {
+        workflow: to_yaml(&Value::Mapping(ordered))?,
+        caller: to_yaml(&caller)?,
+        call_job: call_id,
+        inputs: passed.inputs,
+        secrets: passed.secrets,
+        inherit_secrets: passed.inherit_secrets,
+        notes,
+    })
+}
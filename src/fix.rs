@@ -0,0 +1,38 @@
+//! Applying the fixes attached to diagnostics.
+
+use crate::diagnostics::{Diagnostic, Edit};
+
+/// Apply every fix in `diagnostics` to `source`, returning the new source
+/// and how many fixes were applied. A fix with an edit overlapping one that
+/// was already taken is skipped; running again will pick it up.
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> (String, usize) {
+    let mut taken: Vec<&Edit> = Vec::new();
+    let mut applied = 0;
+    for fix in diagnostics.iter().filter_map(|d| d.fix.as_ref()) {
+        let overlaps = fix
+            .edits
+            .iter()
+            .any(|edit| taken.iter().any(|other| overlap(edit, other)));
+        if overlaps {
+            continue;
+        }
+        taken.extend(&fix.edits);
+        applied += 1;
+    }
+
+    // Apply back to front so earlier offsets stay valid
+    taken.sort_by_key(|edit| edit.offset);
+    let mut fixed = source.to_string();
+    for edit in taken.into_iter().rev() {
+        fixed.replace_range(edit.offset..edit.offset + edit.len, &edit.replacement);
+    }
+    (fixed, applied)
+}
+
+fn overlap(a: &Edit, b: &Edit) -> bool {
+    // Two insertions at the same point would come out in an arbitrary order
+    if a.offset == b.offset {
+        return true;
+    }
+    a.offset < b.offset + b.len && b.offset < a.offset + a.len
+}
@@ -0,0 +1,168 @@
+//! Linting a piece of a workflow on its own: a job, a step or a list of
+//! steps, as in documentation or the output of a template generator.
+//!
+//! The fragment is put inside a small workflow that triggers on `push`,
+//! linted, and the findings moved back to where they are in the fragment.
+//! Findings about the made-up parts, like the workflow's `on`, are left out.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::diagnostics::{Diagnostic, Location};
+
+/// The id of the job a fragment is put in.
+pub const JOB_ID: &str = "fragment";
+
+/// What a fragment is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fragment {
+    /// A job's mapping, the part under `jobs.<id>`.
+    Job,
+    /// One step's mapping.
+    Step,
+    /// A list of steps.
+    Steps,
+}
+
+impl FromStr for Fragment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "job" => Ok(Fragment::Job),
+            "step" => Ok(Fragment::Step),
+            "steps" => Ok(Fragment::Steps),
+            _ => Err(format!(
+                "unknown fragment `{}`, expected job, step or steps",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Fragment::Job => "job",
+            Fragment::Step => "step",
+            Fragment::Steps => "steps",
+        })
+    }
+}
+
+/// A fragment inside a workflow, and how to map findings back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wrapped {
+    /// The whole workflow.
+    pub text: String,
+    pub fragment: Fragment,
+    /// How many lines come before the fragment.
+    lines: usize,
+    /// The columns added in front of each of the fragment's lines.
+    indent: usize,
+    /// The columns taken off each line, when the fragment was indented.
+    dedent: usize,
+}
+
+/// The columns of leading spaces every non-blank line of `source` shares.
+fn common_indent(source: &str) -> usize {
+    source
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|line| line.len() - line.trim_start_matches(' ').len())
+        .min()
+        .unwrap_or(0)
+}
+
+/// Put `source`, a fragment of the kind `fragment`, in a workflow.
+pub fn wrap(source: &str, fragment: Fragment) -> Wrapped {
+    let header = match fragment {
+        Fragment::Job => format!("on: push\njobs:\n  {}:\n", JOB_ID),
+        Fragment::Step | Fragment::Steps => format!(
+            "on: push\njobs:\n  {}:\n    runs-on: ubuntu-latest\n    steps:\n",
+            JOB_ID
+        ),
+    };
+    // A step is made a list item by putting `- ` in front of its first line
+    // and lining the rest up with it
+    let indent = match fragment {
+        Fragment::Job => 4,
+        Fragment::Steps => 6,
+        Fragment::Step => 8,
+    };
+    let dedent = common_indent(source);
+    let mut text = header.clone();
+    let mut first = true;
+    for line in source.lines() {
+        let content = line.get(dedent..).unwrap_or_else(|| line.trim_start());
+        if content.is_empty() {
+            text.push('\n');
+            continue;
+        }
+        if fragment == Fragment::Step && first && !content.starts_with('#') {
+            text.push_str(&" ".repeat(indent - 2));
+            text.push_str("- ");
+            first = false;
+        } else {
+            text.push_str(&" ".repeat(indent));
+        }
+        text.push_str(content);
+        text.push('\n');
+    }
+    Wrapped {
+        text,
+        fragment,
+        lines: header.matches('\n').count(),
+        indent,
+        dedent,
+    }
+}
+
+impl Wrapped {
+    /// The path nodes in the fragment start with.
+    fn root(&self) -> String {
+        match self.fragment {
+            Fragment::Job => format!("jobs.{}", JOB_ID),
+            Fragment::Steps => format!("jobs.{}.steps", JOB_ID),
+            Fragment::Step => format!("jobs.{}.steps[0]", JOB_ID),
+        }
+    }
+
+    /// Where `location` in the workflow is in the fragment, if it's in it.
+    pub fn location(&self, location: Location) -> Option<Location> {
+        let line = location.line.checked_sub(self.lines)?;
+        if line == 0 {
+            return None;
+        }
+        // Columns in what was added in front are clamped to the first column
+        let column = (location.column + self.dedent)
+            .saturating_sub(self.indent)
+            .max(1);
+        Some(Location { line, column })
+    }
+
+    /// `diagnostics` about the workflow, as findings about the fragment.
+    /// Their nodes are made relative to it, like `steps[0]` for a job,
+    /// and their fixes and spans are dropped, as they're for the workflow's
+    /// text.
+    pub fn unwrap(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let root = self.root();
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                if let Some(node) = diagnostic.node.take() {
+                    let relative = node.strip_prefix(&root)?;
+                    let relative = relative.strip_prefix('.').unwrap_or(relative);
+                    diagnostic.node = Some(relative.to_string());
+                }
+                if let Some(location) = diagnostic.location {
+                    diagnostic.location = Some(self.location(location)?);
+                }
+                diagnostic.fix = None;
+                diagnostic.span = None;
+                diagnostic.related = self.unwrap(std::mem::take(&mut diagnostic.related));
+                Some(diagnostic)
+            })
+            .collect()
+    }
+}
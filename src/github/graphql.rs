@@ -0,0 +1,126 @@
+use std::fmt;
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Map, Value};
+
+use super::{Response, Scheduler};
+
+/// How many repositories to ask about per GraphQL request. Each aliased
+/// `repository` field costs a point, and the query is small either way.
+pub const BATCH_SIZE: usize = 50;
+
+/// `owner/name` of a repository referenced by a `uses:`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RepositoryName {
+    pub owner: String,
+    pub name: String,
+}
+
+impl RepositoryName {
+    pub fn new(owner: impl Into<String>, name: impl Into<String>) -> Self {
+        RepositoryName {
+            owner: owner.into(),
+            name: name.into(),
+        }
+    }
+}
+
+impl fmt::Display for RepositoryName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+/// What the linter needs to know about the repository behind a `uses:`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    pub default_branch: Option<String>,
+    /// Tag of the latest release, if the repository has any releases.
+    pub latest_release: Option<String>,
+    pub archived: bool,
+}
+
+/// Ask about every repository in `names` with a single query, returning the
+/// answers in the same order. The response's rate limit headers update the
+/// scheduler's, as REST responses do.
+pub fn query_repositories(
+    scheduler: &mut Scheduler,
+    names: &[RepositoryName],
+) -> Result<Vec<Response<Repository>>> {
+    let (query, variables) = build_query(names);
+    let mut response = scheduler
+        .client()
+        .graphql()
+        .send_json(json!({ "query": query, "variables": variables }))
+        .context("sending GraphQL request")?;
+    scheduler.observe(response.headers());
+
+    let status = response.status();
+    if !status.is_success() {
+        bail!("GraphQL request returned {}", status);
+    }
+    let body: Value = response
+        .body_mut()
+        .read_json()
+        .context("reading GraphQL response")?;
+
+    parse_response(names.len(), &body)
+}
+
+/// Alias each lookup as `r<index>` and pass the names as variables so they
+/// never need escaping.
+fn build_query(names: &[RepositoryName]) -> (String, Map<String, Value>) {
+    let mut params = Vec::new();
+    let mut fields = String::new();
+    let mut variables = Map::new();
+    for (i, name) in names.iter().enumerate() {
+        params.push(format!("$o{i}: String!, $n{i}: String!", i = i));
+        fields.push_str(&format!(
+            "r{i}: repository(owner: $o{i}, name: $n{i}) {{ \
+             defaultBranchRef {{ name }} latestRelease {{ tagName }} isArchived }} ",
+            i = i
+        ));
+        variables.insert(format!("o{}", i), Value::from(name.owner.as_str()));
+        variables.insert(format!("n{}", i), Value::from(name.name.as_str()));
+    }
+    let query = format!("query({}) {{ {}}}", params.join(", "), fields);
+    (query, variables)
+}
+
+fn parse_response(count: usize, body: &Value) -> Result<Vec<Response<Repository>>> {
+    // Missing repositories are reported as NOT_FOUND errors next to a null
+    // result; anything else (rate limiting, bad credentials) fails the batch.
+    if let Some(errors) = body["errors"].as_array() {
+        for error in errors {
+            if error["type"] != "NOT_FOUND" {
+                bail!(
+                    "{}",
+                    error["message"].as_str().unwrap_or("unknown GraphQL error")
+                );
+            }
+        }
+    }
+    let data = body["data"]
+        .as_object()
+        .context("GraphQL response has no data")?;
+
+    Ok((0..count)
+        .map(|i| match data.get(&format!("r{}", i)) {
+            Some(Value::Object(repo)) => Response::Found(Repository {
+                default_branch: repo
+                    .get("defaultBranchRef")
+                    .and_then(|r| r["name"].as_str())
+                    .map(String::from),
+                latest_release: repo
+                    .get("latestRelease")
+                    .and_then(|r| r["tagName"].as_str())
+                    .map(String::from),
+                archived: repo
+                    .get("isArchived")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            }),
+            _ => Response::NotFound,
+        })
+        .collect())
+}
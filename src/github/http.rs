@@ -0,0 +1,179 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ureq::tls::{parse_pem, PemItem, RootCerts, TlsConfig};
+use ureq::typestate::{WithBody, WithoutBody};
+use ureq::{Agent, Proxy, RequestBuilder};
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Network settings for talking to GitHub (or a GitHub Enterprise Server).
+///
+/// Proxies are configured the same way as most command line tools, through
+/// `HTTPS_PROXY` (or `ALL_PROXY`/`HTTP_PROXY`) and `NO_PROXY`. A custom CA
+/// bundle is needed behind proxies that intercept TLS, and can be given with
+/// `WORKFLOW_LINTER_CA_BUNDLE` or the more general `SSL_CERT_FILE`.
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+    /// Base URL of the REST API. For GHES this is `https://<host>/api/v3`.
+    pub api_url: Option<String>,
+
+    /// Proxy URI to use instead of the one from the environment.
+    pub proxy: Option<String>,
+
+    /// Path to a PEM file of root certificates. When set these replace the
+    /// bundled Mozilla roots rather than adding to them, matching `curl --cacert`.
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl HttpConfig {
+    pub fn from_env() -> Self {
+        HttpConfig {
+            api_url: env::var("GITHUB_API_URL").ok(),
+            proxy: None,
+            ca_bundle: env::var_os("WORKFLOW_LINTER_CA_BUNDLE")
+                .or_else(|| env::var_os("SSL_CERT_FILE"))
+                .map(PathBuf::from),
+        }
+    }
+}
+
+/// A configured HTTP client for the GitHub API.
+#[derive(Clone)]
+pub struct Client {
+    agent: Agent,
+    api_url: String,
+    token: Option<String>,
+}
+
+// Keep the token itself out of debug output
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("agent", &self.agent)
+            .field("api_url", &self.api_url)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl Client {
+    pub fn new(config: &HttpConfig) -> Result<Self> {
+        let proxy = match &config.proxy {
+            Some(uri) => Some(Proxy::new(uri).with_context(|| format!("invalid proxy {}", uri))?),
+            // Also picks up `NO_PROXY`
+            None => Proxy::try_from_env(),
+        };
+
+        let mut tls = TlsConfig::builder();
+        if let Some(path) = &config.ca_bundle {
+            tls = tls.root_certs(load_ca_bundle(path)?);
+        }
+
+        let agent = Agent::config_builder()
+            .proxy(proxy)
+            .tls_config(tls.build())
+            .user_agent(concat!("workflow-linter/", env!("CARGO_PKG_VERSION")))
+            // Rate limiting and missing repositories are answers, not errors
+            .http_status_as_error(false)
+            .build()
+            .new_agent();
+
+        Ok(Client {
+            agent,
+            api_url: config
+                .api_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            token: None,
+        })
+    }
+
+    /// Authenticate requests with `token`. Unauthenticated requests work, but
+    /// get a much smaller rate limit.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    pub fn agent(&self) -> &Agent {
+        &self.agent
+    }
+
+    /// Start a GET request for an API path with the headers GitHub expects.
+    pub fn get(&self, path: &str) -> RequestBuilder<WithoutBody> {
+        let request = self
+            .agent
+            .get(self.url(path))
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    /// Start a GET request for the raw contents of a file, from an API path
+    /// like `/repos/{owner}/{repo}/contents/{path}`.
+    pub fn get_raw(&self, path: &str) -> RequestBuilder<WithoutBody> {
+        let request = self
+            .agent
+            .get(self.url(path))
+            .header("Accept", "application/vnd.github.raw+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    /// Start a POST to the GraphQL endpoint.
+    pub fn graphql(&self) -> RequestBuilder<WithBody> {
+        let request = self.agent.post(self.graphql_url());
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    /// GraphQL lives at `/graphql` on github.com but at `/api/graphql` rather
+    /// than under `/api/v3` on GHES.
+    fn graphql_url(&self) -> String {
+        match self.api_url.strip_suffix("/v3") {
+            Some(base) => format!("{}/graphql", base),
+            None => format!("{}/graphql", self.api_url),
+        }
+    }
+
+    /// Resolve an API path like `/repos/{owner}/{repo}` against the base URL.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.api_url, path.trim_start_matches('/'))
+    }
+}
+
+fn load_ca_bundle(path: &Path) -> Result<RootCerts> {
+    let pem = fs::read(path).with_context(|| format!("reading CA bundle {}", path.display()))?;
+    let mut certs = Vec::new();
+    for item in parse_pem(&pem) {
+        match item.with_context(|| format!("parsing CA bundle {}", path.display()))? {
+            PemItem::Certificate(cert) => certs.push(cert),
+            // Bundles sometimes carry keys or CRLs along with the certificates
+            _ => continue,
+        }
+    }
+    anyhow::ensure!(
+        !certs.is_empty(),
+        "CA bundle {} contains no certificates",
+        path.display()
+    );
+    Ok(RootCerts::new_with_certs(&certs))
+}
@@ -0,0 +1,12 @@
+//! Access to the GitHub API for rules that need more than the workflow file
+//! itself, like checking that a referenced action actually exists.
+
+mod graphql;
+mod http;
+mod scheduler;
+mod token;
+
+pub use graphql::{Repository, RepositoryName};
+pub use http::{Client, HttpConfig};
+pub use scheduler::{Response, Scheduler};
+pub use token::{discover_token, Token, TokenSource};
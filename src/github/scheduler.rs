@@ -0,0 +1,401 @@
+use std::collections::{BTreeSet, HashMap};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+use ureq::http::HeaderMap;
+
+use crate::budget::Budget;
+
+use super::graphql::{self, Repository, RepositoryName};
+use super::Client;
+
+/// How many times a request is retried after hitting a secondary rate limit.
+const MAX_RETRIES: u32 = 2;
+
+/// GitHub asks clients to wait at least a minute after a secondary rate limit
+/// when it doesn't say how long with `retry-after`.
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The answer to a scheduled API call.
+#[derive(Debug, Clone)]
+pub enum Response<T = Value> {
+    Found(T),
+    NotFound,
+    /// The call was skipped or failed. Rules should treat the answer as unknown
+    /// rather than report anything based on it.
+    Unavailable,
+}
+
+impl<T> Response<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Response<U> {
+        match self {
+            Response::Found(value) => Response::Found(f(value)),
+            Response::NotFound => Response::NotFound,
+            Response::Unavailable => Response::Unavailable,
+        }
+    }
+}
+
+/// What the last response said about the primary rate limit.
+#[derive(Debug, Default, Clone, Copy)]
+struct RateLimit {
+    remaining: Option<u64>,
+    /// Unix timestamp when the budget refills.
+    reset: Option<u64>,
+}
+
+impl RateLimit {
+    fn update(&mut self, headers: &HeaderMap) {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+        };
+        if let Some(remaining) = number("x-ratelimit-remaining") {
+            self.remaining = Some(remaining);
+        }
+        if let Some(reset) = number("x-ratelimit-reset") {
+            self.reset = Some(reset);
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        match (self.remaining, self.reset) {
+            (Some(0), Some(reset)) => now() < reset,
+            (Some(0), None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Every GitHub API call made during a run goes through a single scheduler,
+/// which answers repeated requests from memory, keeps track of the rate limit
+/// and stops calling the API once it runs out rather than failing the run.
+///
+/// Problems are collected as warnings for the caller to report alongside the
+/// lint results, since a missing network answer shouldn't fail a lint.
+#[derive(Debug)]
+pub struct Scheduler {
+    client: Client,
+    responses: HashMap<String, Response>,
+    repositories: HashMap<RepositoryName, Response<Repository>>,
+    rate_limit: RateLimit,
+    warned_exhausted: bool,
+    warnings: Vec<String>,
+    budget: Budget,
+    warned_stopped: bool,
+}
+
+impl Scheduler {
+    pub fn new(client: Client) -> Self {
+        Scheduler {
+            client,
+            responses: HashMap::new(),
+            repositories: HashMap::new(),
+            rate_limit: RateLimit::default(),
+            warned_exhausted: false,
+            warnings: Vec::new(),
+            budget: Budget::default(),
+            warned_stopped: false,
+        }
+    }
+
+    /// Stop making requests once `budget` is cancelled or out of time, and
+    /// give each request no longer than what's left.
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Whether the budget has stopped the run. Like the rate limit, callers
+    /// doing their own requests should check this first.
+    pub fn is_stopped(&mut self) -> bool {
+        let stopped = match self.budget.stopped() {
+            Some(stopped) => stopped,
+            None => return false,
+        };
+        if !self.warned_stopped {
+            self.warned_stopped = true;
+            self.warnings.push(format!(
+                "{}; remaining network checks were skipped",
+                stopped.reason()
+            ));
+        }
+        true
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Fetch all `paths`, once each, so that later calls to [`get`] are
+    /// answered without touching the network. Collecting the paths for every
+    /// file before linting means an action used in fifty workflows costs one
+    /// request rather than fifty.
+    ///
+    /// [`get`]: Scheduler::get
+    pub fn prefetch<I, S>(&mut self, paths: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let unique: BTreeSet<String> = paths
+            .into_iter()
+            .map(|p| p.as_ref().to_string())
+            .filter(|p| !self.responses.contains_key(p))
+            .collect();
+        for path in unique {
+            self.get(&path);
+        }
+    }
+
+    /// GET an API path, returning the cached answer if it was already asked.
+    pub fn get(&mut self, path: &str) -> Response {
+        if let Some(response) = self.responses.get(path) {
+            return response.clone();
+        }
+        if self.in_missing_repository(path) {
+            return Response::NotFound;
+        }
+        let response = self.fetch(path);
+        self.responses.insert(path.to_string(), response.clone());
+        response
+    }
+
+    /// Record the answer to a request made some other way (e.g. as part of a
+    /// GraphQL batch) so that later calls to [`get`] reuse it.
+    ///
+    /// [`get`]: Scheduler::get
+    pub fn insert(&mut self, path: impl Into<String>, response: Response) {
+        self.responses.insert(path.into(), response);
+    }
+
+    /// Look up the repositories behind a set of `uses:` targets. Calling this
+    /// before checking them means that [`get`] answers the paths of any that
+    /// don't exist without asking.
+    ///
+    /// With a token this asks GraphQL about up to [`graphql::BATCH_SIZE`]
+    /// repositories per request; without one (GraphQL requires
+    /// authentication), or if GraphQL fails part way, the rest are looked up
+    /// with REST calls instead.
+    ///
+    /// [`get`]: Scheduler::get
+    pub fn resolve_repositories<I>(
+        &mut self,
+        names: I,
+    ) -> Vec<(RepositoryName, Response<Repository>)>
+    where
+        I: IntoIterator<Item = RepositoryName>,
+    {
+        let names: BTreeSet<RepositoryName> = names.into_iter().collect();
+        let pending: Vec<RepositoryName> = names
+            .iter()
+            .filter(|name| !self.repositories.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let mut rest = Vec::new();
+        let mut use_graphql = self.client.has_token();
+        for batch in pending.chunks(graphql::BATCH_SIZE) {
+            // Once the limit's gone, the REST lookups say so
+            if use_graphql && !self.is_exhausted() && !self.is_stopped() {
+                match graphql::query_repositories(self, batch) {
+                    Ok(found) => {
+                        self.repositories.extend(batch.iter().cloned().zip(found));
+                        continue;
+                    }
+                    Err(e) => {
+                        self.warn(format!(
+                            "GitHub GraphQL lookup failed, falling back to REST: {:#}",
+                            e
+                        ));
+                        use_graphql = false;
+                    }
+                }
+            }
+            rest.extend_from_slice(batch);
+        }
+
+        for name in rest {
+            let repository = self.fetch_repository(&name);
+            self.repositories.insert(name, repository);
+        }
+
+        names
+            .into_iter()
+            .map(|name| {
+                let repository = self.repositories[&name].clone();
+                (name, repository)
+            })
+            .collect()
+    }
+
+    /// Whether `path` is in a repository [`resolve_repositories`] found
+    /// doesn't exist, which there's no need to ask about again.
+    ///
+    /// [`resolve_repositories`]: Scheduler::resolve_repositories
+    fn in_missing_repository(&self, path: &str) -> bool {
+        let mut parts = match path.strip_prefix("/repos/") {
+            Some(rest) => rest.split(['/', '?']),
+            None => return false,
+        };
+        let name = match (parts.next(), parts.next()) {
+            (Some(owner), Some(name)) => RepositoryName::new(owner, name),
+            _ => return false,
+        };
+        matches!(self.repositories.get(&name), Some(Response::NotFound))
+    }
+
+    fn fetch_repository(&mut self, name: &RepositoryName) -> Response<Repository> {
+        let path = format!("/repos/{}/{}", name.owner, name.name);
+        let repository = match self.get(&path) {
+            Response::Found(value) => value,
+            Response::NotFound => return Response::NotFound,
+            Response::Unavailable => return Response::Unavailable,
+        };
+        let latest_release = match self.get(&format!("{}/releases/latest", path)) {
+            Response::Found(release) => release["tag_name"].as_str().map(String::from),
+            _ => None,
+        };
+        Response::Found(Repository {
+            default_branch: repository["default_branch"].as_str().map(String::from),
+            latest_release,
+            archived: repository["archived"].as_bool().unwrap_or(false),
+        })
+    }
+
+    /// Whether the rate limit has run out. Callers doing their own requests
+    /// should check this first.
+    pub fn is_exhausted(&mut self) -> bool {
+        if !self.rate_limit.exhausted() {
+            return false;
+        }
+        if !self.warned_exhausted {
+            self.warned_exhausted = true;
+            let until = match self.rate_limit.reset {
+                Some(reset) => format!(" for {} more seconds", reset.saturating_sub(now())),
+                None => String::new(),
+            };
+            self.warnings.push(format!(
+                "GitHub API rate limit exhausted{}; remaining network checks were skipped",
+                until
+            ));
+        }
+        true
+    }
+
+    /// Update the rate limit from a response to a request made some other way.
+    pub fn observe(&mut self, headers: &HeaderMap) {
+        self.rate_limit.update(headers);
+    }
+
+    pub fn warn(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into());
+    }
+
+    /// Take the warnings collected so far.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn fetch(&mut self, path: &str) -> Response {
+        for attempt in 0..=MAX_RETRIES {
+            if self.is_exhausted() || self.is_stopped() {
+                return Response::Unavailable;
+            }
+
+            let request = match self.budget.remaining() {
+                Some(left) => self
+                    .client
+                    .get(path)
+                    .config()
+                    .timeout_global(Some(left))
+                    .build(),
+                None => self.client.get(path),
+            };
+            let mut response = match request.call() {
+                Ok(response) => response,
+                Err(e) => {
+                    self.warn(format!("GitHub API request {} failed: {}", path, e));
+                    return Response::Unavailable;
+                }
+            };
+            self.observe(response.headers());
+
+            let status = response.status().as_u16();
+            match status {
+                200..=299 => {
+                    return match response.body_mut().read_json() {
+                        Ok(value) => Response::Found(value),
+                        Err(e) => {
+                            self.warn(format!(
+                                "GitHub API response for {} was invalid: {}",
+                                path, e
+                            ));
+                            Response::Unavailable
+                        }
+                    };
+                }
+                404 => return Response::NotFound,
+                403 | 429 if self.rate_limit.exhausted() => {
+                    // Primary limit; may be an hour away so don't wait for it
+                    self.is_exhausted();
+                    return Response::Unavailable;
+                }
+                403 | 429 if is_secondary_limit(status, &mut response) => {
+                    if attempt == MAX_RETRIES {
+                        break;
+                    }
+                    let wait = retry_after(response.headers())
+                        .unwrap_or(DEFAULT_BACKOFF * 2u32.pow(attempt));
+                    // No point waiting past the deadline
+                    if self.budget.remaining().is_some_and(|left| left < wait) {
+                        break;
+                    }
+                    thread::sleep(wait);
+                }
+                _ => {
+                    self.warn(format!("GitHub API returned {} for {}", status, path));
+                    return Response::Unavailable;
+                }
+            }
+        }
+
+        self.warn(format!(
+            "GitHub API kept rate limiting {}; gave up after {} retries",
+            path, MAX_RETRIES
+        ));
+        Response::Unavailable
+    }
+}
+
+/// Secondary limits come back as a 429, or as a 403 that either has a
+/// `retry-after` header or says so in the message. Other 403s are permission
+/// problems that retrying won't fix.
+fn is_secondary_limit(status: u16, response: &mut ureq::http::Response<ureq::Body>) -> bool {
+    if status == 429 || response.headers().contains_key("retry-after") {
+        return true;
+    }
+    response
+        .body_mut()
+        .read_to_string()
+        .map(|body| body.to_lowercase().contains("rate limit"))
+        .unwrap_or(false)
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,102 @@
+use std::env;
+use std::fmt;
+use std::process::Command;
+use std::str::FromStr;
+
+/// Somewhere a GitHub token can be found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    /// The `WORKFLOW_LINTER_TOKEN` environment variable, for a token meant
+    /// only for the linter.
+    WorkflowLinterToken,
+    /// The `GITHUB_TOKEN` environment variable, set in Actions.
+    GithubToken,
+    /// The `GH_TOKEN` environment variable, as used by the `gh` CLI.
+    GhToken,
+    /// Whatever `gh auth token` prints for a logged in `gh` CLI.
+    GhCli,
+}
+
+impl TokenSource {
+    /// Sources tried when none are given, in order.
+    pub const DEFAULT: &'static [TokenSource] = &[
+        TokenSource::WorkflowLinterToken,
+        TokenSource::GithubToken,
+        TokenSource::GhToken,
+        TokenSource::GhCli,
+    ];
+
+    fn read(self) -> Option<String> {
+        let token = match self {
+            TokenSource::WorkflowLinterToken => env::var("WORKFLOW_LINTER_TOKEN").ok()?,
+            TokenSource::GithubToken => env::var("GITHUB_TOKEN").ok()?,
+            TokenSource::GhToken => env::var("GH_TOKEN").ok()?,
+            TokenSource::GhCli => {
+                // Not having gh installed or logged in just means no token
+                let output = Command::new("gh").args(["auth", "token"]).output().ok()?;
+                if !output.status.success() {
+                    return None;
+                }
+                String::from_utf8(output.stdout).ok()?
+            }
+        };
+        let token = token.trim();
+        if token.is_empty() {
+            None
+        } else {
+            Some(token.to_string())
+        }
+    }
+}
+
+impl FromStr for TokenSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "workflow-linter-token" => Ok(TokenSource::WorkflowLinterToken),
+            "github-token" => Ok(TokenSource::GithubToken),
+            "gh-token" => Ok(TokenSource::GhToken),
+            "gh-cli" => Ok(TokenSource::GhCli),
+            _ => Err(format!(
+                "unknown token source `{}`, expected one of workflow-linter-token, github-token, gh-token, \
+                 gh-cli",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TokenSource::WorkflowLinterToken => "WORKFLOW_LINTER_TOKEN",
+            TokenSource::GithubToken => "GITHUB_TOKEN",
+            TokenSource::GhToken => "GH_TOKEN",
+            TokenSource::GhCli => "gh auth token",
+        })
+    }
+}
+
+/// A discovered token, and where it came from.
+#[derive(Clone)]
+pub struct Token {
+    pub value: String,
+    pub source: TokenSource,
+}
+
+// Keep the token itself out of debug output
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Token")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// Try each source in order and return the first token found.
+pub fn discover_token(sources: &[TokenSource]) -> Option<Token> {
+    sources
+        .iter()
+        .find_map(|&source| source.read().map(|value| Token { value, source }))
+}
@@ -0,0 +1,211 @@
+//! The glob patterns used by `hashFiles()`, path filters and friends.
+//!
+//! Patterns are matched a path segment at a time: `*` and `?` stay within a
+//! segment, `**` on its own matches any number of segments, and `[...]`
+//! matches one character from a set. Dotfiles aren't special.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobError {
+    pub message: String,
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+/// A parsed pattern. A leading `!` makes it a negation, which callers
+/// combine with other patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pub negated: bool,
+    segments: Vec<String>,
+}
+
+impl Glob {
+    pub fn parse(pattern: &str) -> Result<Self, GlobError> {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        if pattern.is_empty() {
+            return Err(GlobError {
+                message: "pattern is empty".to_string(),
+            });
+        }
+        if pattern.contains('\\') {
+            return Err(GlobError {
+                message: "patterns use `/` as a separator, not `\\`".to_string(),
+            });
+        }
+        check_brackets(pattern)?;
+
+        let segments = pattern
+            .trim_start_matches("./")
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        Ok(Glob { negated, segments })
+    }
+
+    /// Whether `path`, relative and `/` separated, matches.
+    pub fn matches(&self, path: &str) -> bool {
+        let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        match_segments(&self.segments, &parts)
+    }
+}
+
+/// Match `path` against a list of patterns, where the last pattern that
+/// matches decides, so a later `!pattern` excludes what earlier ones matched.
+pub fn matches_all(globs: &[Glob], path: &str) -> bool {
+    globs
+        .iter()
+        .rev()
+        .find(|glob| glob.matches(path))
+        .is_some_and(|glob| !glob.negated)
+}
+
+/// Whether `pattern` has anything in it that matches more than itself.
+pub fn is_pattern(pattern: &str) -> bool {
+    pattern.contains(&['*', '?', '['][..])
+}
+
+/// The files `pattern` matches, like `.github/workflows/*.yml`, sorted.
+/// Only the directories under the part of the pattern without wildcards
+/// are searched, and `.git` isn't.
+pub fn files(pattern: &str) -> Result<Vec<PathBuf>, GlobError> {
+    let glob = Glob::parse(pattern)?;
+    if glob.negated {
+        return Err(GlobError {
+            message: "a `!` pattern only leaves out what other patterns match".to_string(),
+        });
+    }
+    let mut start = match pattern.starts_with('/') {
+        true => PathBuf::from("/"),
+        false => PathBuf::new(),
+    };
+    for segment in glob.segments.iter().take_while(|s| !is_pattern(s)) {
+        start.push(segment);
+    }
+    let mut found = Vec::new();
+    let mut dirs = match start.as_os_str().is_empty() {
+        true => vec![PathBuf::from(".")],
+        false => vec![start],
+    };
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let path = path
+                .strip_prefix(".")
+                .map(Path::to_path_buf)
+                .unwrap_or(path);
+            if path.is_dir() {
+                if path.file_name().is_some_and(|name| name != ".git") {
+                    dirs.push(path);
+                }
+            } else if glob.matches(&path.to_string_lossy()) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+fn check_brackets(pattern: &str) -> Result<(), GlobError> {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '[' {
+            let mut closed = false;
+            let mut first = true;
+            for c in chars.by_ref() {
+                // `]` straight after `[` (or `[!`) is part of the set
+                if c == ']' && !first {
+                    closed = true;
+                    break;
+                }
+                first = c == '!' && first;
+            }
+            if !closed {
+                return Err(GlobError {
+                    message: "`[` is never closed".to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                match_segment(first.as_bytes(), segment.as_bytes())
+                    && match_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| match_segment(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && match_segment(rest, &text[1..]),
+        Some((b'[', rest)) => match (text.first(), class_end(rest)) {
+            (Some(&c), Some(end)) => {
+                class_matches(&rest[..end], c) && match_segment(&rest[end + 1..], &text[1..])
+            }
+            _ => false,
+        },
+        Some((&p, rest)) => text.first() == Some(&p) && match_segment(rest, &text[1..]),
+    }
+}
+
+/// Index of the `]` closing a class whose contents start at `class[0]`.
+fn class_end(class: &[u8]) -> Option<usize> {
+    let start = match class.first() {
+        Some(b'!') => 2,
+        _ => 1,
+    };
+    class
+        .iter()
+        .skip(start)
+        .position(|&c| c == b']')
+        .map(|i| i + start)
+}
+
+fn class_matches(class: &[u8], c: u8) -> bool {
+    let (negated, class) = match class.split_first() {
+        Some((b'!', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            found |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
@@ -0,0 +1,211 @@
+//! The jobs of a workflow and what each `needs`, as a graph.
+//!
+//! GitHub rejects a workflow whose jobs need a job that doesn't exist, or
+//! need each other in a cycle, since it can't work out an order to run them
+//! in. Both are found here, along with the order jobs start in and what
+//! each one waits for.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::workflow::Workflow;
+
+/// The jobs of a workflow by id, each with the ids it needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobGraph<'w> {
+    needs: BTreeMap<&'w str, Vec<&'w str>>,
+}
+
+/// A job needing one that isn't in the workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dangling<'w> {
+    pub job: &'w str,
+    /// Where the missing job is in the job's `needs`.
+    pub index: usize,
+    pub needs: &'w str,
+}
+
+impl<'w> JobGraph<'w> {
+    pub fn new(workflow: &'w Workflow) -> Self {
+        let needs = workflow
+            .jobs
+            .iter()
+            .map(|(id, job)| (id.as_str(), job.needs.iter().map(String::as_str).collect()))
+            .collect();
+        JobGraph { needs }
+    }
+
+    /// The ids of the jobs, sorted.
+    pub fn jobs(&self) -> impl Iterator<Item = &'w str> + '_ {
+        self.needs.keys().copied()
+    }
+
+    /// The jobs `job` needs directly that are in the workflow.
+    pub fn needs(&self, job: &str) -> impl Iterator<Item = &'w str> + '_ {
+        self.needs
+            .get(job)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(move |need| self.needs.contains_key(need))
+    }
+
+    /// Every job `job` waits for, directly or not. It only includes `job`
+    /// itself if it's in a cycle.
+    pub fn upstream(&self, job: &str) -> BTreeSet<&'w str> {
+        let mut found = BTreeSet::new();
+        let mut pending: Vec<&str> = self.needs(job).collect();
+        while let Some(id) = pending.pop() {
+            if found.insert(id) {
+                pending.extend(self.needs(id));
+            }
+        }
+        found
+    }
+
+    /// The needs that name a job the workflow doesn't have, by job.
+    pub fn dangling(&self) -> Vec<Dangling<'w>> {
+        let mut dangling = Vec::new();
+        for (&job, needs) in &self.needs {
+            for (index, &need) in needs.iter().enumerate() {
+                if !self.needs.contains_key(need) {
+                    dangling.push(Dangling {
+                        job,
+                        index,
+                        needs: need,
+                    });
+                }
+            }
+        }
+        dangling
+    }
+
+    /// Each cycle of jobs that need each other, as the jobs in the order
+    /// they need each other, starting from the first by id. A job that
+    /// needs itself is a cycle of one.
+    pub fn cycles(&self) -> Vec<Vec<&'w str>> {
+        // Tarjan's strongly connected components: every job in one with
+        // more than one job is on a cycle, and so is a job needing itself
+        struct State<'w> {
+            index: BTreeMap<&'w str, usize>,
+            low: BTreeMap<&'w str, usize>,
+            stack: Vec<&'w str>,
+            on_stack: BTreeSet<&'w str>,
+            components: Vec<Vec<&'w str>>,
+        }
+
+        fn visit<'w>(graph: &JobGraph<'w>, job: &'w str, state: &mut State<'w>) {
+            let index = state.index.len();
+            state.index.insert(job, index);
+            state.low.insert(job, index);
+            state.stack.push(job);
+            state.on_stack.insert(job);
+            for need in graph.needs(job) {
+                if !state.index.contains_key(need) {
+                    visit(graph, need, state);
+                    let low = state.low[job].min(state.low[need]);
+                    state.low.insert(job, low);
+                } else if state.on_stack.contains(need) {
+                    let low = state.low[job].min(state.index[need]);
+                    state.low.insert(job, low);
+                }
+            }
+            if state.low[job] == index {
+                let mut component = Vec::new();
+                while let Some(id) = state.stack.pop() {
+                    state.on_stack.remove(id);
+                    component.push(id);
+                    if id == job {
+                        break;
+                    }
+                }
+                state.components.push(component);
+            }
+        }
+
+        let mut state = State {
+            index: BTreeMap::new(),
+            low: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        };
+        for job in self.jobs() {
+            if !state.index.contains_key(job) {
+                visit(self, job, &mut state);
+            }
+        }
+
+        let mut cycles: Vec<Vec<&str>> = state
+            .components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.needs(component[0]).any(|need| need == component[0])
+            })
+            .map(|component| self.cycle_through(&component.into_iter().collect()))
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    /// The shortest cycle in a strongly connected `component` from its
+    /// first job by id back to it.
+    fn cycle_through(&self, component: &BTreeSet<&'w str>) -> Vec<&'w str> {
+        let start = match component.iter().next() {
+            Some(&start) => start,
+            None => return Vec::new(),
+        };
+        let mut previous: BTreeMap<&str, &str> = BTreeMap::new();
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some(job) = queue.pop_front() {
+            for need in self.needs(job).filter(|need| component.contains(need)) {
+                if need == start {
+                    let mut cycle = vec![job];
+                    let mut at = job;
+                    while let Some(&before) = previous.get(at) {
+                        cycle.push(before);
+                        at = before;
+                    }
+                    cycle.reverse();
+                    return cycle;
+                }
+                if !previous.contains_key(need) {
+                    previous.insert(need, job);
+                    queue.push_back(need);
+                }
+            }
+        }
+        vec![start]
+    }
+
+    /// The jobs in an order they can start in, each after the jobs it
+    /// needs, with ties broken by id; `None` if there's a cycle.
+    pub fn order(&self) -> Option<Vec<&'w str>> {
+        let mut waiting: BTreeMap<&str, usize> = self
+            .jobs()
+            .map(|job| (job, self.needs(job).collect::<BTreeSet<_>>().len()))
+            .collect();
+        let mut ready: BTreeSet<&str> = waiting
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&job, _)| job)
+            .collect();
+        let mut order = Vec::new();
+        while let Some(job) = ready.iter().next().copied() {
+            ready.remove(job);
+            waiting.remove(job);
+            order.push(job);
+            for (&other, count) in waiting.iter_mut() {
+                if self.needs(other).any(|need| need == job) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(other);
+                    }
+                }
+            }
+        }
+        match waiting.is_empty() {
+            true => Some(order),
+            false => None,
+        }
+    }
+}
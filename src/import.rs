@@ -0,0 +1,1029 @@
+//! Turning another CI system's pipeline into a GitHub Actions workflow, as
+//! a start for migrating it.
+//!
+//! Only GitLab CI is read so far. Its jobs become jobs, stages become
+//! `needs` on the jobs of the stage before, `before_script` and `script`
+//! become a `run` step after a checkout, `after_script` a step that always
+//! runs, `image` the job's `container` and `artifacts` upload and download
+//! steps. GitLab's predefined variables the scripts use are set from the
+//! matching contexts, like `CI_COMMIT_SHA` from `github.sha`.
+//!
+//! What doesn't map cleanly, like `rules`, `cache` or manual jobs, is left
+//! out and reported as a TODO finding at its line in the GitLab file, so
+//! the result runs but needs a read before it replaces the pipeline.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::preprocess::merge;
+use crate::spans::{key_path, SpanMap};
+
+/// The rule of the findings about what wasn't imported.
+pub const RULE: &str = "import";
+
+/// The CI systems pipelines can be imported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Gitlab,
+}
+
+impl FromStr for Origin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gitlab" => Ok(Origin::Gitlab),
+            _ => Err(format!("unknown CI `{}`, expected gitlab", s)),
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Origin::Gitlab => "gitlab",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+fn error(message: impl Into<String>) -> ImportError {
+    ImportError {
+        message: message.into(),
+    }
+}
+
+/// A workflow made from a pipeline, and what's left to do by hand.
+#[derive(Debug, Clone)]
+pub struct Imported {
+    pub workflow: String,
+    /// Findings about the pipeline's file, for what wasn't imported.
+    pub todos: Vec<Diagnostic>,
+}
+
+/// Make a workflow from the pipeline `source`, read from `path`.
+pub fn import(origin: Origin, source: &str, path: &Path) -> Result<Imported, ImportError> {
+    match origin {
+        Origin::Gitlab => gitlab(source, path),
+    }
+}
+
+/// Top-level keys of a GitLab pipeline that aren't jobs.
+const GITLAB_KEYWORDS: &[&str] = &[
+    "after_script",
+    "before_script",
+    "cache",
+    "default",
+    "image",
+    "include",
+    "services",
+    "stages",
+    "variables",
+    "workflow",
+];
+
+/// The keys of `default:`, which every job gets unless it sets them.
+const GITLAB_DEFAULTS: &[&str] = &[
+    "after_script",
+    "artifacts",
+    "before_script",
+    "cache",
+    "image",
+    "interruptible",
+    "retry",
+    "services",
+    "tags",
+    "timeout",
+];
+
+/// The stages of a pipeline that doesn't list its own.
+const GITLAB_STAGES: &[&str] = &[".pre", "build", "test", "deploy", ".post"];
+
+/// Keys of a job that are carried over.
+const GITLAB_IMPORTED: &[&str] = &[
+    "after_script",
+    "allow_failure",
+    "artifacts",
+    "before_script",
+    "dependencies",
+    "environment",
+    "extends",
+    "image",
+    "needs",
+    "parallel",
+    "resource_group",
+    "script",
+    "services",
+    "stage",
+    "tags",
+    "timeout",
+    "variables",
+    "when",
+];
+
+/// Keys of a job with no equivalent, and what to do instead.
+const GITLAB_TODOS: &[(&str, &str)] = &[
+    (
+        "cache",
+        "`cache` isn't imported; add an `actions/cache` step with a key from `hashFiles`",
+    ),
+    (
+        "coverage",
+        "`coverage` isn't imported; upload the report to a coverage service instead",
+    ),
+    (
+        "except",
+        "`except` isn't imported; add an `if:` on the job, or narrow the workflow's `on:`",
+    ),
+    (
+        "interruptible",
+        "`interruptible` isn't imported; use `concurrency` with `cancel-in-progress: true`",
+    ),
+    (
+        "only",
+        "`only` isn't imported; add an `if:` on the job, or narrow the workflow's `on:`",
+    ),
+    (
+        "release",
+        "`release` isn't imported; create the release with `gh release create` in a step",
+    ),
+    (
+        "retry",
+        "`retry` isn't imported; Actions has no retries, re-run failed jobs instead",
+    ),
+    (
+        "rules",
+        "`rules` isn't imported; add an `if:` on the job, or narrow the workflow's `on:`",
+    ),
+];
+
+/// GitLab's predefined variables, and the expressions with the same value.
+const GITLAB_VARIABLES: &[(&str, &str)] = &[
+    ("CI_COMMIT_BRANCH", "${{ github.ref_name }}"),
+    ("CI_COMMIT_REF_NAME", "${{ github.ref_name }}"),
+    ("CI_COMMIT_SHA", "${{ github.sha }}"),
+    (
+        "CI_COMMIT_TAG",
+        "${{ github.ref_type == 'tag' && github.ref_name || '' }}",
+    ),
+    (
+        "CI_DEFAULT_BRANCH",
+        "${{ github.event.repository.default_branch }}",
+    ),
+    ("CI_JOB_NAME", "${{ github.job }}"),
+    (
+        "CI_MERGE_REQUEST_IID",
+        "${{ github.event.pull_request.number }}",
+    ),
+    ("CI_PIPELINE_ID", "${{ github.run_id }}"),
+    ("CI_PIPELINE_IID", "${{ github.run_number }}"),
+    ("CI_PROJECT_DIR", "${{ github.workspace }}"),
+    ("CI_PROJECT_NAME", "${{ github.event.repository.name }}"),
+    ("CI_PROJECT_PATH", "${{ github.repository }}"),
+    ("CI_REGISTRY", "ghcr.io"),
+    ("CI_REGISTRY_IMAGE", "ghcr.io/${{ github.repository }}"),
+    ("CI_SERVER_URL", "${{ github.server_url }}"),
+];
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+/// A scalar as text, like a variable's value.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A string or a list of strings, as a list.
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(items)) => items.iter().filter_map(scalar).collect(),
+        Some(value) => scalar(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// A valid job id for the GitLab job `name`, which can have spaces and
+/// colons, unique among `taken`.
+fn job_id(name: &str, taken: &BTreeSet<String>) -> String {
+    let mut id: String = name
+        .trim()
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '-',
+            },
+        )
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    std::iter::once(id.clone())
+        .chain((2..).map(|n| format!("{}-{}", id, n)))
+        .find(|id| !taken.contains(id))
+        .unwrap_or(id)
+}
+
+/// Minutes in a GitLab duration like `1h 30m` or `2 days`, rounded up.
+fn minutes(duration: &str) -> Option<u64> {
+    let mut seconds = 0;
+    let mut rest = duration.trim();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number: u64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let unit = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit] {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+            "d" | "day" | "days" => 24 * 60 * 60,
+            "w" | "week" | "weeks" => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        seconds += number * scale;
+        rest = rest[unit..].trim_start_matches([' ', ',']);
+    }
+    Some(seconds.div_ceil(60)).filter(|&m| m > 0)
+}
+
+/// The variables of every combination of a `parallel: matrix:` entry.
+fn combinations(entry: &Mapping) -> Vec<Mapping> {
+    let mut combinations = vec![Mapping::new()];
+    for (key, values) in entry {
+        let values: Vec<Value> = match values {
+            Value::Sequence(values) => values.clone(),
+            value => vec![value.clone()],
+        };
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.insert(key.clone(), value.clone());
+                    combination
+                })
+            })
+            .collect()
+    }
+    combinations
+}
+
+/// A pipeline's job, with its defaults and what it extends applied.
+#[derive(Debug, Clone)]
+struct Job {
+    /// Its name in the pipeline, where it can have spaces and colons.
+    name: String,
+    /// Its id in the workflow.
+    id: String,
+    value: Value,
+}
+
+impl Job {
+    fn get(&self, key: &str) -> Option<&Value> {
+        self.value.get(key)
+    }
+
+    fn node(&self, key: &str) -> String {
+        key_path(&self.name, key)
+    }
+
+    /// Whether the job uploads artifacts.
+    fn uploads(&self) -> bool {
+        self.get("artifacts")
+            .is_some_and(|artifacts| !strings(artifacts.get("paths")).is_empty())
+    }
+}
+
+/// A GitLab pipeline being imported.
+struct Gitlab<'a> {
+    path: &'a Path,
+    spans: SpanMap,
+    document: &'a Mapping,
+    /// The stages in order, with `.pre` and `.post`.
+    stages: Vec<String>,
+    jobs: Vec<Job>,
+    /// GitLab's variables the pipeline uses, and where each is first used.
+    used: BTreeMap<String, String>,
+    todos: Vec<Diagnostic>,
+}
+
+impl Gitlab<'_> {
+    fn top(&self, key: &str) -> Option<&Value> {
+        self.document.get(&string(key))
+    }
+
+    fn todo(&mut self, node: &str, message: impl Into<String>) {
+        let diagnostic = Diagnostic::new(RULE, Severity::Warning, message)
+            .with_path(self.path)
+            .with_location(self.spans.locate(node))
+            .with_message_id("todo")
+            .with_param("node", node);
+        self.todos.push(diagnostic);
+    }
+
+    /// `job` with what it `extends:` under it, from the jobs and hidden jobs
+    /// of the pipeline.
+    fn extended(&mut self, name: &str, job: &Value, depth: usize) -> Value {
+        let bases = strings(job.get("extends"));
+        if bases.is_empty() {
+            return job.clone();
+        }
+        let mut extended = Value::Mapping(Mapping::new());
+        for base_name in &bases {
+            match self.document.get(&string(base_name)) {
+                Some(base) if depth < 10 => {
+                    let base = self.extended(base_name, base, depth + 1);
+                    merge(&mut extended, base);
+                }
+                _ => self.todo(
+                    &key_path(name, "extends"),
+                    format!(
+                        "`{}` isn't in this file, or extends itself, so it wasn't applied",
+                        base_name
+                    ),
+                ),
+            }
+        }
+        let mut job = job.clone();
+        if let Some(job) = job.as_mapping_mut() {
+            job.remove(&string("extends"));
+        }
+        merge(&mut extended, job);
+        extended
+    }
+
+    /// Read the jobs, with `default:` and what they extend applied.
+    fn read_jobs(&mut self) {
+        // `default:`, and the older top-level keys it replaced
+        let mut defaults = Mapping::new();
+        for key in GITLAB_DEFAULTS {
+            if let Some(value) = self.top(key) {
+                defaults.insert(string(key), value.clone());
+            }
+        }
+        if let Some(Value::Mapping(default)) = self.top("default") {
+            for (key, value) in default {
+                defaults.insert(key.clone(), value.clone());
+            }
+        }
+
+        let mut taken = BTreeSet::new();
+        let document = self.document;
+        for (name, job) in document {
+            let name = match name.as_str() {
+                Some(name) if !GITLAB_KEYWORDS.contains(&name) && !name.starts_with('.') => name,
+                _ => continue,
+            };
+            if !job.is_mapping() {
+                continue;
+            }
+            let mut value = Value::Mapping(defaults.clone());
+            let extended = self.extended(name, job, 0);
+            merge(&mut value, extended);
+            if value.get("trigger").is_some() {
+                self.todo(
+                    name,
+                    format!(
+                        "job `{}` triggers another pipeline, so it wasn't imported; call a \
+                         reusable workflow with `uses:` instead",
+                        name
+                    ),
+                );
+                continue;
+            }
+            let id = job_id(name, &taken);
+            taken.insert(id.clone());
+            self.jobs.push(Job {
+                name: name.to_string(),
+                id,
+                value,
+            });
+        }
+    }
+
+    fn id_of(&self, name: &str) -> Option<String> {
+        self.jobs
+            .iter()
+            .find(|job| job.name == name)
+            .map(|job| job.id.clone())
+    }
+
+    fn stage_of(&self, job: &Job) -> Option<usize> {
+        let stage = job.get("stage").and_then(Value::as_str).unwrap_or("test");
+        self.stages.iter().position(|s| s == stage)
+    }
+
+    /// The ids of the jobs `job` needs: those it lists, or every job of the
+    /// nearest stage before its own.
+    fn needs(&self, job: &Job) -> Vec<String> {
+        if let Some(Value::Sequence(needs)) = job.get("needs") {
+            return needs
+                .iter()
+                .filter_map(|need| match need {
+                    Value::Mapping(need) => need.get(&string("job")).and_then(scalar),
+                    need => scalar(need),
+                })
+                .filter_map(|need| self.id_of(&need))
+                .collect();
+        }
+        let stage = match self.stage_of(job) {
+            Some(stage) => stage,
+            None => return Vec::new(),
+        };
+        let earlier: Vec<(&Job, usize)> = self
+            .jobs
+            .iter()
+            .filter_map(|other| Some((other, self.stage_of(other)?)))
+            .filter(|&(_, other)| other < stage)
+            .collect();
+        let nearest = earlier.iter().map(|&(_, stage)| stage).max();
+        earlier
+            .iter()
+            .filter(|&&(_, other)| Some(other) == nearest)
+            .map(|(other, _)| other.id.clone())
+            .collect()
+    }
+
+    /// Note the GitLab variables `text`, at `node`, uses.
+    fn uses_variables(&mut self, text: &str, node: &str) {
+        for (at, _) in text.match_indices('$') {
+            let name = text[at + 1..].trim_start_matches('{');
+            let end = name
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(name.len());
+            let name = &name[..end];
+            if name.starts_with("CI_") || name.starts_with("GITLAB_") {
+                self.used
+                    .entry(name.to_string())
+                    .or_insert_with(|| node.to_string());
+            }
+        }
+    }
+
+    /// The workflow job for `job`.
+    fn job(&mut self, job: &Job) -> Mapping {
+        let mut out = Mapping::new();
+        if job.name != job.id {
+            out.insert(string("name"), string(&job.name));
+        }
+        if self.stage_of(job).is_none() {
+            self.todo(
+                &job.node("stage"),
+                format!("job `{}` is in a stage that isn't in `stages`", job.name),
+            );
+        }
+        let needs = self.needs(job);
+        if !needs.is_empty() {
+            out.insert(
+                string("needs"),
+                Value::Sequence(needs.iter().map(|n| string(n)).collect()),
+            );
+        }
+
+        match job.get("when").and_then(Value::as_str) {
+            None | Some("on_success") => {}
+            Some("always") => {
+                out.insert(string("if"), string("always()"));
+            }
+            Some("on_failure") => {
+                out.insert(string("if"), string("failure()"));
+            }
+            Some(when) => self.todo(
+                &job.node("when"),
+                format!(
+                    "`when: {}` isn't imported; for manual jobs, use an environment with \
+                     required reviewers or a `workflow_dispatch` trigger",
+                    when
+                ),
+            ),
+        }
+
+        let runs_on = self.runs_on(job);
+        out.insert(string("runs-on"), runs_on);
+        self.container(job, &mut out);
+
+        if let Some(environment) = job.get("environment") {
+            let environment = match environment {
+                Value::Mapping(environment) => {
+                    let mut only = Mapping::new();
+                    for key in &["name", "url"] {
+                        if let Some(value) = environment.get(&string(key)) {
+                            only.insert(string(key), value.clone());
+                        }
+                    }
+                    Value::Mapping(only)
+                }
+                environment => environment.clone(),
+            };
+            out.insert(string("environment"), environment);
+        }
+
+        if let Some(group) = job.get("resource_group").and_then(scalar) {
+            let mut concurrency = Mapping::new();
+            concurrency.insert(string("group"), string(&group));
+            concurrency.insert(string("cancel-in-progress"), Value::Bool(false));
+            out.insert(string("concurrency"), Value::Mapping(concurrency));
+        }
+
+        if let Some(timeout) = job.get("timeout").and_then(scalar) {
+            match minutes(&timeout) {
+                Some(minutes) => {
+                    out.insert(string("timeout-minutes"), Value::Number(minutes.into()));
+                }
+                None => self.todo(
+                    &job.node("timeout"),
+                    format!("timeout `{}` isn't a duration this can read", timeout),
+                ),
+            }
+        }
+
+        match job.get("allow_failure") {
+            Some(Value::Bool(true)) => {
+                out.insert(string("continue-on-error"), Value::Bool(true));
+            }
+            Some(Value::Mapping(_)) => self.todo(
+                &job.node("allow_failure"),
+                "`allow_failure: exit_codes` isn't imported; check the exit code in the script",
+            ),
+            _ => {}
+        }
+
+        let (mut env, checkout) = self.variables(job);
+        if let Some(strategy) = self.parallel(job, &mut env) {
+            out.insert(string("strategy"), strategy);
+        }
+        if !env.is_empty() {
+            out.insert(string("env"), Value::Mapping(env));
+        }
+        let steps = self.steps(job, &needs, checkout);
+        out.insert(string("steps"), Value::Sequence(steps));
+
+        for &(key, todo) in GITLAB_TODOS {
+            if job.get(key).is_none() {
+                continue;
+            }
+            let node = self.origin(&job.name, key, 0);
+            self.todo(&node, todo);
+        }
+        let own = self
+            .document
+            .get(&string(&job.name))
+            .and_then(Value::as_mapping);
+        let others: Vec<String> = own
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, _)| key.as_str())
+            .filter(|key| {
+                !GITLAB_IMPORTED.contains(key) && !GITLAB_TODOS.iter().any(|(todo, _)| todo == key)
+            })
+            .map(str::to_string)
+            .collect();
+        for key in others {
+            self.todo(&job.node(&key), format!("`{}` isn't imported", key));
+        }
+        out
+    }
+
+    /// Where the job `name` got `key` from: itself, what it extends, or
+    /// `default:`.
+    fn origin(&self, name: &str, key: &str, depth: usize) -> String {
+        let job = match self.document.get(&string(name)) {
+            Some(job) => job,
+            None => return String::new(),
+        };
+        if job.get(key).is_some() {
+            return key_path(name, key);
+        }
+        let from_base = strings(job.get("extends"))
+            .iter()
+            .filter(|_| depth < 10)
+            .map(|base| self.origin(base, key, depth + 1))
+            .find(|node| !node.is_empty());
+        match (from_base, self.top(key)) {
+            (Some(node), _) => node,
+            (None, Some(_)) if depth == 0 => key.to_string(),
+            (None, None) if depth == 0 => key_path("default", key),
+            (None, _) => String::new(),
+        }
+    }
+
+    /// Where the job runs: a hosted runner, or self-hosted ones with its
+    /// tags as labels.
+    fn runs_on(&mut self, job: &Job) -> Value {
+        let tags = strings(job.get("tags"));
+        if tags.is_empty() {
+            return string("ubuntu-latest");
+        }
+        self.todo(
+            &job.node("tags"),
+            format!(
+                "job `{}` picks runners by tag; check that self-hosted runners have the \
+                 labels {}",
+                job.name,
+                tags.join(", ")
+            ),
+        );
+        let labels = std::iter::once("self-hosted".to_string()).chain(tags);
+        Value::Sequence(labels.map(|label| string(&label)).collect())
+    }
+
+    /// The job's `container` and `services`, from its `image` and
+    /// `services`.
+    fn container(&mut self, job: &Job, out: &mut Mapping) {
+        let image = match job.get("image") {
+            Some(Value::Mapping(image)) => {
+                if image.contains_key(&string("entrypoint")) {
+                    self.todo(
+                        &key_path(&job.node("image"), "entrypoint"),
+                        "the image's `entrypoint` isn't imported; containers keep their own",
+                    );
+                }
+                image.get(&string("name")).and_then(scalar)
+            }
+            Some(image) => scalar(image),
+            None => None,
+        };
+        if let Some(image) = &image {
+            let mut container = Mapping::new();
+            container.insert(string("image"), string(image));
+            out.insert(string("container"), Value::Mapping(container));
+        }
+
+        let services = match job.get("services") {
+            Some(Value::Sequence(services)) => services,
+            _ => return,
+        };
+        let mut imported = Mapping::new();
+        for service in services {
+            let (service_image, alias) = match service {
+                Value::Mapping(service) => (
+                    service.get(&string("name")).and_then(scalar),
+                    service.get(&string("alias")).and_then(scalar),
+                ),
+                service => (scalar(service), None),
+            };
+            let service_image = match service_image {
+                Some(service_image) => service_image,
+                None => continue,
+            };
+            // GitLab's default hostname: the image's name, without its tag
+            let key = alias.unwrap_or_else(|| {
+                let last = service_image.rsplit('/').next().unwrap_or(&service_image);
+                last.split([':', '@']).next().unwrap_or(last).to_string()
+            });
+            let mut service = Mapping::new();
+            service.insert(string("image"), string(&service_image));
+            imported.insert(string(&key), Value::Mapping(service));
+        }
+        if image.is_none() {
+            self.todo(
+                &job.node("services"),
+                "services are only reachable by name from a job in a `container`; map their \
+                 ports to reach them on localhost",
+            );
+        }
+        out.insert(string("services"), Value::Mapping(imported));
+    }
+
+    /// The job's `env`, and the checkout's inputs from the variables that
+    /// set up GitLab's clone, or `None` if it doesn't clone.
+    fn variables(&mut self, job: &Job) -> (Mapping, Option<Mapping>) {
+        let mut env = Mapping::new();
+        let mut with = Mapping::new();
+        let mut clone = true;
+        if let Some(Value::Mapping(variables)) = job.get("variables") {
+            for (key, value) in variables {
+                let (key, value) = match (key.as_str(), variable(value)) {
+                    (Some(key), Some(value)) => (key, value),
+                    _ => continue,
+                };
+                match (key, value.as_str()) {
+                    ("GIT_STRATEGY", "none") => clone = false,
+                    ("GIT_DEPTH", depth) => {
+                        if let Ok(depth) = depth.parse::<u64>() {
+                            with.insert(string("fetch-depth"), Value::Number(depth.into()));
+                        }
+                    }
+                    ("GIT_SUBMODULE_STRATEGY", "recursive") => {
+                        with.insert(string("submodules"), string("recursive"));
+                    }
+                    ("GIT_SUBMODULE_STRATEGY", "normal") => {
+                        with.insert(string("submodules"), Value::Bool(true));
+                    }
+                    _ if key.starts_with("GIT_") => {}
+                    _ => {
+                        self.uses_variables(&value, &key_path(&job.node("variables"), key));
+                        env.insert(string(key), string(&value));
+                    }
+                }
+            }
+        }
+        (env, Some(with).filter(|_| clone))
+    }
+
+    /// The job's `strategy` for `parallel:`, with the variables each leg
+    /// gets added to `env`.
+    fn parallel(&mut self, job: &Job, env: &mut Mapping) -> Option<Value> {
+        let mut matrix = Mapping::new();
+        match job.get("parallel")? {
+            Value::Number(total) => {
+                let total = total.as_u64().unwrap_or(1);
+                let legs = (1..=total).map(|i| Value::Number(i.into())).collect();
+                matrix.insert(string("ci_node_index"), Value::Sequence(legs));
+                env.insert(
+                    string("CI_NODE_INDEX"),
+                    string("${{ matrix.ci_node_index }}"),
+                );
+                env.insert(string("CI_NODE_TOTAL"), string(&total.to_string()));
+            }
+            Value::Mapping(parallel) => {
+                let include: Vec<Mapping> = parallel
+                    .get(&string("matrix"))
+                    .and_then(Value::as_sequence)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_mapping)
+                    .flat_map(combinations)
+                    .collect();
+                if include.is_empty() {
+                    self.todo(
+                        &job.node("parallel"),
+                        "`parallel` isn't a number or a `matrix`",
+                    );
+                    return None;
+                }
+                let keys: BTreeSet<String> = include
+                    .iter()
+                    .flat_map(|leg| leg.iter().filter_map(|(key, _)| scalar(key)))
+                    .collect();
+                for key in keys {
+                    env.insert(string(&key), string(&format!("${{{{ matrix.{} }}}}", key)));
+                }
+                let include = include.into_iter().map(Value::Mapping).collect();
+                matrix.insert(string("include"), Value::Sequence(include));
+            }
+            _ => {
+                self.todo(
+                    &job.node("parallel"),
+                    "`parallel` isn't a number or a `matrix`",
+                );
+                return None;
+            }
+        }
+        let mut strategy = Mapping::new();
+        strategy.insert(string("matrix"), Value::Mapping(matrix));
+        Some(Value::Mapping(strategy))
+    }
+
+    /// The job's steps: the checkout, downloading the artifacts of the jobs
+    /// before it, its scripts, and uploading its own artifacts.
+    fn steps(&mut self, job: &Job, needs: &[String], checkout: Option<Mapping>) -> Vec<Value> {
+        let mut steps = Vec::new();
+        if let Some(with) = checkout {
+            let mut step = Mapping::new();
+            step.insert(string("uses"), string("actions/checkout@v4"));
+            if !with.is_empty() {
+                step.insert(string("with"), Value::Mapping(with));
+            }
+            steps.push(Value::Mapping(step));
+        }
+
+        // From the jobs it needs, or those `dependencies` lists
+        let from: Vec<String> = match job.get("dependencies") {
+            Some(dependencies) => strings(Some(dependencies))
+                .iter()
+                .filter_map(|name| self.id_of(name))
+                .collect(),
+            None => needs.to_vec(),
+        };
+        for id in from {
+            if !self
+                .jobs
+                .iter()
+                .any(|other| other.id == id && other.uploads())
+            {
+                continue;
+            }
+            let mut with = Mapping::new();
+            with.insert(string("name"), string(&id));
+            let mut step = Mapping::new();
+            step.insert(string("uses"), string("actions/download-artifact@v4"));
+            step.insert(string("with"), Value::Mapping(with));
+            steps.push(Value::Mapping(step));
+        }
+
+        // `before_script` runs in the same shell as `script`
+        let mut script = strings(job.get("before_script"));
+        script.extend(strings(job.get("script")));
+        if script.is_empty() {
+            self.todo(&job.name, format!("job `{}` has no `script`", job.name));
+        } else {
+            let run = script.join("\n");
+            self.uses_variables(&run, &job.node("script"));
+            let mut step = Mapping::new();
+            step.insert(string("run"), string(&run));
+            steps.push(Value::Mapping(step));
+        }
+        let after = strings(job.get("after_script"));
+        if !after.is_empty() {
+            let run = after.join("\n");
+            self.uses_variables(&run, &job.node("after_script"));
+            let mut step = Mapping::new();
+            step.insert(string("if"), string("always()"));
+            step.insert(string("run"), string(&run));
+            steps.push(Value::Mapping(step));
+        }
+
+        if let Some(Value::Mapping(artifacts)) = job.get("artifacts") {
+            steps.extend(self.upload(job, artifacts));
+        }
+        steps
+    }
+
+    /// The step uploading the job's `artifacts`.
+    fn upload(&mut self, job: &Job, artifacts: &Mapping) -> Option<Value> {
+        let node = job.node("artifacts");
+        if artifacts.contains_key(&string("reports")) {
+            self.todo(
+                &key_path(&node, "reports"),
+                "artifact `reports` aren't imported; upload them with the action for the \
+                 report's kind, like a test reporter",
+            );
+        }
+        let mut paths = strings(artifacts.get(&string("paths")));
+        if paths.is_empty() {
+            return None;
+        }
+        let excluded = strings(artifacts.get(&string("exclude")));
+        paths.extend(excluded.iter().map(|path| format!("!{}", path)));
+
+        let mut with = Mapping::new();
+        with.insert(string("name"), string(&job.id));
+        with.insert(string("path"), string(&paths.join("\n")));
+        if let Some(expire) = artifacts.get(&string("expire_in")).and_then(scalar) {
+            match minutes(&expire) {
+                Some(minutes) => {
+                    let days = minutes.div_ceil(24 * 60);
+                    with.insert(string("retention-days"), Value::Number(days.into()));
+                }
+                None => self.todo(
+                    &key_path(&node, "expire_in"),
+                    format!("`expire_in: {}` isn't a duration this can read", expire),
+                ),
+            }
+        }
+        let mut step = Mapping::new();
+        match artifacts.get(&string("when")).and_then(Value::as_str) {
+            Some("always") => {
+                step.insert(string("if"), string("always()"));
+            }
+            Some("on_failure") => {
+                step.insert(string("if"), string("failure()"));
+            }
+            _ => {}
+        }
+        step.insert(string("uses"), string("actions/upload-artifact@v4"));
+        step.insert(string("with"), Value::Mapping(with));
+        Some(Value::Mapping(step))
+    }
+
+    /// The workflow's `env`: the pipeline's variables, and GitLab's that
+    /// the jobs use, set from the contexts that have them.
+    fn env(&mut self) -> Mapping {
+        let mut env = Mapping::new();
+        if let Some(Value::Mapping(variables)) = self.top("variables") {
+            for (key, value) in variables.clone() {
+                if let (Some(key), Some(value)) = (key.as_str(), variable(&value)) {
+                    self.uses_variables(&value, &key_path("variables", key));
+                    env.insert(string(key), string(&value));
+                }
+            }
+        }
+        for (name, node) in std::mem::take(&mut self.used) {
+            match GITLAB_VARIABLES.iter().find(|(known, _)| *known == name) {
+                Some((_, expression)) => {
+                    env.insert(string(&name), string(expression));
+                }
+                None => self.todo(
+                    &node,
+                    format!(
+                        "`{}` is one of GitLab's variables, and no context here has its value",
+                        name
+                    ),
+                ),
+            }
+        }
+        env
+    }
+}
+
+/// A variable's value, given as is or as `value:` with a `description:`.
+fn variable(value: &Value) -> Option<String> {
+    match value {
+        Value::Mapping(value) => value.get(&string("value")).and_then(scalar),
+        value => scalar(value),
+    }
+}
+
+/// A GitHub Actions workflow for the GitLab pipeline `source`.
+fn gitlab(source: &str, path: &Path) -> Result<Imported, ImportError> {
+    let document: Value = serde_yaml::from_str(source).map_err(|e| error(e.to_string()))?;
+    let document = document
+        .as_mapping()
+        .ok_or_else(|| error("the pipeline isn't a mapping"))?;
+    let stages = match document.get(&string("stages")) {
+        Some(stages) => {
+            let mut listed = strings(Some(stages));
+            listed.insert(0, ".pre".to_string());
+            listed.push(".post".to_string());
+            listed
+        }
+        None => GITLAB_STAGES.iter().map(|s| s.to_string()).collect(),
+    };
+    let mut gitlab = Gitlab {
+        path,
+        spans: SpanMap::parse(source),
+        document,
+        stages,
+        jobs: Vec::new(),
+        used: BTreeMap::new(),
+        todos: Vec::new(),
+    };
+
+    if gitlab.top("include").is_some() {
+        gitlab.todo(
+            "include",
+            "`include` isn't imported; import the included files too, and call them as \
+             reusable workflows",
+        );
+    }
+    if gitlab.top("workflow").is_some() {
+        gitlab.todo(
+            "workflow",
+            "`workflow` isn't imported; the workflow runs on every push and pull request, so \
+             narrow its `on:`",
+        );
+    }
+
+    gitlab.read_jobs();
+    if gitlab.jobs.is_empty() {
+        return Err(error("the pipeline has no jobs"));
+    }
+    let mut imported = Mapping::new();
+    for job in gitlab.jobs.clone() {
+        let out = gitlab.job(&job);
+        imported.insert(string(&job.id), Value::Mapping(out));
+    }
+
+    let mut workflow = Mapping::new();
+    workflow.insert(string("name"), string("CI"));
+    workflow.insert(
+        string("on"),
+        Value::Sequence(vec![string("push"), string("pull_request")]),
+    );
+    let mut permissions = Mapping::new();
+    permissions.insert(string("contents"), string("read"));
+    workflow.insert(string("permissions"), Value::Mapping(permissions));
+    let env = gitlab.env();
+    if !env.is_empty() {
+        workflow.insert(string("env"), Value::Mapping(env));
+    }
+    workflow.insert(string("jobs"), Value::Mapping(imported));
+
+    let yaml =
+        serde_yaml::to_string(&Value::Mapping(workflow)).map_err(|e| error(e.to_string()))?;
+    let mut todos = gitlab.todos;
+    todos.sort_by_key(|d| d.location.map(|l| (l.line, l.column)));
+    Ok(Imported {
+        workflow: format!("{}\n", yaml.trim_start_matches("---\n").trim_end()),
+        todos,
+    })
+}
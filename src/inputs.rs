@@ -0,0 +1,242 @@
+//! A report of the `workflow_dispatch` inputs of a repository's workflows,
+//! for reviewing what anyone who can run a workflow by hand gets to choose.
+//!
+//! Two kinds of input are called out. Free-form strings that a `run` script
+//! uses through an expression are an injection surface: the text is pasted
+//! into the script before the shell sees it. Booleans whose default does
+//! the opposite of what their name suggests, like a `dry_run` that is
+//! `false` unless someone ticks it, make the risky choice the default one.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::expr;
+use crate::lint::step_path;
+use crate::review::jobs;
+use crate::spans::{key_path, SpanMap};
+
+/// Words in a boolean's name that mean `true` is the safe choice.
+const SAFE_WORDS: &[&str] = &[
+    "check", "dry", "dryrun", "no", "noop", "preview", "simulate", "skip",
+];
+
+/// Words in a boolean's name that mean `true` is the risky choice.
+const RISKY_WORDS: &[&str] = &[
+    "delete",
+    "deploy",
+    "destroy",
+    "force",
+    "overwrite",
+    "publish",
+    "purge",
+    "push",
+    "release",
+];
+
+/// Something about an input worth a reviewer's attention.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Flag {
+    /// A free-form string that these steps put into their `run` scripts.
+    Injection { steps: Vec<String> },
+    /// A boolean whose default is the risky choice its name suggests
+    /// isn't.
+    RiskyDefault { reason: String },
+}
+
+/// One `workflow_dispatch` input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Input {
+    pub file: PathBuf,
+    pub name: String,
+    /// GitHub's default is `string`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    pub required: bool,
+    /// 1-based.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub flags: Vec<Flag>,
+}
+
+/// A value as it'd be written in the workflow.
+fn show(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        other => serde_json::to_string(other).ok(),
+    }
+}
+
+/// The input a reference in an expression reads, from `inputs.<name>` or
+/// `github.event.inputs.<name>`.
+fn input_name(parts: &[String]) -> Option<&str> {
+    match parts {
+        [context, name, ..] if context == "inputs" => Some(name),
+        [github, event, inputs, name, ..]
+            if github == "github" && event == "event" && inputs == "inputs" =>
+        {
+            Some(name)
+        }
+        _ => None,
+    }
+}
+
+/// The inputs each step's `run` script reads through an expression, by the
+/// step's node path.
+fn script_inputs(workflow: &Value) -> Vec<(String, Vec<String>)> {
+    let mut found = Vec::new();
+    for (job_id, job) in jobs(workflow) {
+        let steps = job.get("steps").and_then(Value::as_sequence);
+        for (i, step) in steps.into_iter().flatten().enumerate() {
+            let run = match step.get("run").and_then(Value::as_str) {
+                Some(run) => run,
+                None => continue,
+            };
+            let mut names = Vec::new();
+            for embedded in expr::find_expressions(run) {
+                let tokens = match expr::tokenize(embedded.body) {
+                    Ok(tokens) => tokens,
+                    Err(_) => continue,
+                };
+                for reference in expr::references(&tokens) {
+                    if let Some(name) = input_name(&reference.parts) {
+                        names.push(name.to_lowercase());
+                    }
+                }
+            }
+            if !names.is_empty() {
+                found.push((step_path(job_id, i), names));
+            }
+        }
+    }
+    found
+}
+
+/// Why a boolean input's default is the risky choice, if it is.
+fn risky_default(name: &str, default: bool) -> Option<String> {
+    let lower = name.to_lowercase();
+    let words: Vec<&str> = lower.split(['_', '-', ' ']).collect();
+    let has = |list: &[&str]| words.iter().any(|word| list.contains(word));
+    if !default && has(SAFE_WORDS) {
+        Some(format!(
+            "defaults to false, so running the workflow by hand does what `{}` \
+             guards against unless it's ticked",
+            name
+        ))
+    } else if default && has(RISKY_WORDS) {
+        Some(format!(
+            "defaults to true, so running the workflow by hand does what `{}` \
+             says unless it's unticked",
+            name
+        ))
+    } else {
+        None
+    }
+}
+
+/// The `workflow_dispatch` inputs of `workflow`, from `source` at `file`.
+pub fn inputs(workflow: &Value, source: &str, file: &Path) -> Vec<Input> {
+    let dispatch = match workflow
+        .get("on")
+        .and_then(|on| on.get("workflow_dispatch"))
+    {
+        Some(dispatch) => dispatch,
+        None => return Vec::new(),
+    };
+    let declared = match dispatch.get("inputs").and_then(Value::as_mapping) {
+        Some(declared) => declared,
+        None => return Vec::new(),
+    };
+    let spans = SpanMap::parse(source);
+    let scripts = script_inputs(workflow);
+
+    let mut found = Vec::new();
+    for (name, input) in declared {
+        let name = match name.as_str() {
+            Some(name) => name,
+            None => continue,
+        };
+        let kind = input
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("string")
+            .to_string();
+        let default = input.get("default");
+        let mut flags = Vec::new();
+        if kind == "string" {
+            let steps: Vec<String> = scripts
+                .iter()
+                .filter(|(_, names)| names.contains(&name.to_lowercase()))
+                .map(|(step, _)| step.clone())
+                .collect();
+            if !steps.is_empty() {
+                flags.push(Flag::Injection { steps });
+            }
+        }
+        if kind == "boolean" {
+            // The text `'true'` means true too
+            let default = default.and_then(show).as_deref() == Some("true");
+            if let Some(reason) = risky_default(name, default) {
+                flags.push(Flag::RiskyDefault { reason });
+            }
+        }
+        let node = key_path(&key_path("on.workflow_dispatch", "inputs"), name);
+        found.push(Input {
+            file: file.to_path_buf(),
+            name: name.to_string(),
+            kind,
+            default: default.and_then(show),
+            required: input.get("required").and_then(Value::as_bool) == Some(true),
+            line: spans.locate(&node).map(|l| l.line),
+            flags,
+        });
+    }
+    found
+}
+
+/// Every workflow file in `dir`, in path order.
+pub fn workflow_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "yml" || e == "yaml"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub fn write_report(out: &mut impl Write, inputs: &[Input]) -> io::Result<()> {
+    for input in inputs {
+        match input.line {
+            Some(line) => writeln!(out, "{}:{}: {}", input.file.display(), line, input.name)?,
+            None => writeln!(out, "{}: {}", input.file.display(), input.name)?,
+        }
+        let mut details = vec![input.kind.clone()];
+        if let Some(default) = &input.default {
+            details.push(format!("default {}", default));
+        }
+        if input.required {
+            details.push("required".to_string());
+        }
+        writeln!(out, "    {}", details.join(", "))?;
+        for flag in &input.flags {
+            match flag {
+                Flag::Injection { steps } => writeln!(
+                    out,
+                    "    ! injection: a free-form string that {} put into a script; \
+                     pass it through `env` instead",
+                    steps.join(", ")
+                )?,
+                Flag::RiskyDefault { reason } => writeln!(out, "    ! risky default: {}", reason)?,
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+pub mod action;
+pub mod action_ref;
+#[cfg(feature = "network")]
+pub mod attestations;
+pub mod badge;
+pub mod budget;
+pub mod conditions;
+pub mod config;
+pub mod conform;
+pub mod cron;
+pub mod custom_types;
+pub mod deprecation;
+pub mod diagnostics;
+pub mod elements;
+pub mod eval;
+pub mod export;
+pub mod expr;
+#[cfg(feature = "fix")]
+pub mod extract;
+#[cfg(feature = "fix")]
+pub mod fix;
+pub mod fragment;
+#[cfg(feature = "network")]
+pub mod github;
+pub mod glob;
+pub mod graph;
+pub mod import;
+pub mod inputs;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod matrix;
+pub mod messages;
+pub mod noise;
+pub mod output;
+#[cfg(feature = "network")]
+pub mod pins;
+pub mod placeholders;
+pub mod preprocess;
+pub mod provenance;
+pub mod reachability;
+pub mod refactor;
+pub mod repo;
+pub mod report;
+pub mod review;
+pub mod runner;
+pub mod schema;
+pub mod segment;
+pub mod session;
+pub mod shell;
+pub mod source;
+pub mod spans;
+pub mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod workflow;
@@ -0,0 +1,297 @@
+//! Rules about what a step's `uses` points at, once it's parsed into an
+//! [`ActionRef`]. `uses` values that don't parse are `invalid-uses`'.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::action;
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::glob::Glob;
+use crate::spans::key_path;
+use crate::workflow::Job;
+
+use super::{sorted_jobs, step_path, Analysis, Context, Rule};
+
+/// What actions have to be pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PinLevel {
+    /// A version, like `v4`, or anything more exact.
+    Version,
+    /// A full commit SHA, or an image digest, which can't be moved.
+    Sha,
+}
+
+impl FromStr for PinLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "version" => Ok(PinLevel::Version),
+            "sha" => Ok(PinLevel::Sha),
+            _ => Err(format!("unknown pinning `{}`, expected version or sha", s)),
+        }
+    }
+}
+
+/// The pinning policy, from the `[pinning]` table of the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Pinning {
+    /// A version if it isn't set.
+    pub require: Option<PinLevel>,
+    /// Patterns for actions that only need a version even when `require`
+    /// is `sha`, like `actions/*` for the ones GitHub publishes.
+    pub exempt: Vec<String>,
+}
+
+impl Pinning {
+    /// `local` on top of `self`.
+    pub fn merge(self, local: Pinning) -> Pinning {
+        let mut exempt = self.exempt;
+        for pattern in local.exempt {
+            if !exempt.contains(&pattern) {
+                exempt.push(pattern);
+            }
+        }
+        Pinning {
+            require: local.require.or(self.require),
+            exempt,
+        }
+    }
+
+    /// Whether `action`, like `actions/checkout`, has to be at a full SHA.
+    fn requires_sha(&self, action: &str) -> bool {
+        self.require == Some(PinLevel::Sha) && !self.exempts(action)
+    }
+
+    /// Whether one of the `exempt` patterns matches `action`.
+    pub(super) fn exempts(&self, action: &str) -> bool {
+        self.exempt
+            .iter()
+            .filter_map(|pattern| Glob::parse(pattern).ok())
+            .any(|glob| glob.matches(action))
+    }
+}
+
+/// Actions used at a ref that isn't a version: a branch like `main`, which
+/// changes under the workflow with every push to it, or a container image
+/// without a tag or at `latest`. Abbreviated commit SHAs are errors, since
+/// GitHub only resolves full ones. When the pinning policy requires SHAs,
+/// actions at a version tag are reported too, since tags can be moved.
+#[derive(Debug, Clone, Default)]
+pub struct UnpinnedAction {
+    pub pinning: Pinning,
+}
+
+/// Whether `git_ref` is only hex digits, and so an abbreviated commit SHA
+/// if it's at least 7 long and not the full 40.
+fn is_hex(git_ref: &str) -> bool {
+    git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether `git_ref` looks like a release tag: a `v` and a number, like
+/// `v4` or `v1.2.3`, or a dotted version, like `2.0`.
+fn is_version(git_ref: &str) -> bool {
+    let numbered = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+    match git_ref.strip_prefix('v') {
+        Some(version) => numbered(version),
+        None => numbered(git_ref) && git_ref.contains('.'),
+    }
+}
+
+impl Rule for UnpinnedAction {
+    fn name(&self) -> &'static str {
+        "unpinned-action"
+    }
+
+    fn description(&self) -> &'static str {
+        "Actions used at a branch, an abbreviated SHA, an image without a tag, or a tag when SHAs are required"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                let action = match step.action() {
+                    Some(Ok(action)) if !action.is_pinned() => action,
+                    _ => continue,
+                };
+                let path = key_path(&step_path(job_id, i), "uses");
+                let name = action.name();
+                let diagnostic = match &action {
+                    ActionRef::Repository { git_ref, .. }
+                        if is_hex(git_ref) && (7..40).contains(&git_ref.len()) =>
+                    {
+                        cx.diagnostic(
+                            self,
+                            Severity::Error,
+                            &path,
+                            format!(
+                                "`{}@{}` is an abbreviated commit SHA, which GitHub won't \
+                                 resolve; use the full 40 characters",
+                                name, git_ref
+                            ),
+                        )
+                        .with_message_id("short-sha")
+                    }
+                    ActionRef::Repository { git_ref, .. } if !is_version(git_ref) => cx
+                        .diagnostic(
+                            self,
+                            Severity::Warning,
+                            &path,
+                            format!(
+                                "`{}` is used at `{}`, which isn't a version, so the action \
+                                 changes whenever `{}` moves",
+                                name, git_ref, git_ref
+                            ),
+                        )
+                        .with_message_id("branch")
+                        .with_param("ref", git_ref)
+                        .with_suggestion(Suggestion::new(
+                            "use a release tag, or the full commit SHA of one",
+                        )),
+                    ActionRef::Docker { tag, .. }
+                        if tag.as_deref().is_none_or(|tag| tag == "latest") =>
+                    {
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &path,
+                            format!(
+                                "`{}` has no version, so it runs whatever `latest` is when the \
+                                 job starts",
+                                name
+                            ),
+                        )
+                        .with_message_id("image-latest")
+                        .with_suggestion(Suggestion::new(
+                            "give the image a version tag, or pin it with `@sha256:...`",
+                        ))
+                    }
+                    ActionRef::Repository { git_ref, .. } if self.pinning.requires_sha(&name) => cx
+                        .diagnostic(
+                            self,
+                            Severity::Warning,
+                            &path,
+                            format!(
+                                "`{}` is used at the tag `{}`, but the pinning policy requires \
+                                 a full commit SHA, since tags can be moved",
+                                name, git_ref
+                            ),
+                        )
+                        .with_message_id("tag")
+                        .with_param("ref", git_ref)
+                        .with_suggestion(
+                            Suggestion::new("pin it to the commit the tag points at").with_example(
+                                format!("uses: {}@<40-character SHA> # {}", name, git_ref),
+                            ),
+                        ),
+                    ActionRef::Docker { tag: Some(tag), .. }
+                        if self.pinning.requires_sha(&name) =>
+                    {
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &path,
+                            format!(
+                                "`{}:{}` has no digest, but the pinning policy requires one, since \
+                                 tags can be moved",
+                                name, tag
+                            ),
+                        )
+                        .with_message_id("image-tag")
+                        .with_suggestion(Suggestion::new("pin it with `@sha256:...`"))
+                    }
+                    _ => continue,
+                };
+                diagnostics.push(diagnostic.with_param("action", name));
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Local actions, `uses: ./path`, whose directory has no `action.yml` in
+/// the repository, which fail the step once the job gets to it. Only
+/// checked when linting inside a checkout.
+#[derive(Debug, Clone, Default)]
+pub struct MissingLocalAction;
+
+/// Whether the job checks out another repository or checks out somewhere
+/// other than the workspace root, in which case local actions may not be
+/// in this repository.
+fn checks_out_elsewhere(job: &Job) -> bool {
+    job.steps.iter().any(|step| {
+        step.uses_action("actions/checkout")
+            && (step.with.contains_key("path") || step.with.contains_key("repository"))
+    })
+}
+
+impl Rule for MissingLocalAction {
+    fn name(&self) -> &'static str {
+        "missing-local-action"
+    }
+
+    fn description(&self) -> &'static str {
+        "Local actions whose directory has no `action.yml` in the repository"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn requires(&self) -> &'static [Analysis] {
+        &[Analysis::Checkout]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let root = match cx.repo_root {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            if checks_out_elsewhere(job) {
+                continue;
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                let path = match step.action() {
+                    Some(Ok(ActionRef::Local { path })) => path,
+                    _ => continue,
+                };
+                let dir = root.join(path.trim_start_matches("./"));
+                if action::find(&dir).is_some() {
+                    continue;
+                }
+                let message = match dir.is_dir() {
+                    true => format!(
+                        "`{}` has no `action.yml` or `action.yaml`, so it isn't an action",
+                        path
+                    ),
+                    false => format!("`{}` doesn't exist in the repository", path),
+                };
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &key_path(&step_path(job_id, i), "uses"),
+                        message,
+                    )
+                    .with_param("path", path),
+                );
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,104 @@
+use crate::diagnostics::{Diagnostic, Edit, Fix, Severity};
+
+use super::{Context, Rule};
+
+/// Unquoted values that YAML reads as something other than the string they
+/// look like: `go-version: 1.20` is the number 1.2, `zip: 01234` loses its
+/// zero, and `country: NO` is false to YAML 1.1 parsers. Only inputs,
+/// environment variables and matrix values are checked, since those are
+/// always used as strings. The fix quotes them.
+#[derive(Debug, Clone, Default)]
+pub struct AmbiguousScalar;
+
+/// The booleans of YAML 1.1 that YAML 1.2 left as strings.
+pub(super) const YAML_1_1_BOOLEANS: &[&str] = &[
+    "y", "Y", "yes", "Yes", "YES", "n", "N", "no", "No", "NO", "on", "On", "ON", "off", "Off",
+    "OFF",
+];
+
+/// Whether values at `path` end up as strings.
+fn is_string_value(path: &str) -> bool {
+    path.starts_with("env.")
+        || path.contains(".env.")
+        || path.contains(".with.")
+        || path.contains(".strategy.matrix.")
+}
+
+fn digits(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+/// What goes wrong with the unquoted `text`, if anything, with the id of
+/// the message.
+fn problem(text: &str) -> Option<(&'static str, String)> {
+    if YAML_1_1_BOOLEANS.contains(&text) {
+        return Some((
+            "boolean",
+            format!(
+                "`{}` is a boolean to YAML 1.1 parsers, so tools disagree on whether it's a \
+             string; quote it",
+                text
+            ),
+        ));
+    }
+    let unsigned = text.trim_start_matches(['-', '+']);
+    if let Some((whole, fraction)) = unsigned.split_once('.') {
+        if digits(whole) && digits(fraction) && fraction.ends_with('0') {
+            let number: f64 = text.parse().ok()?;
+            let message = format!(
+                "`{}` is the number {}, so it's passed on as `{}`; quote it",
+                text, number, number
+            );
+            return Some(("decimal", message));
+        }
+    }
+    if unsigned.len() > 1 && unsigned.starts_with('0') && digits(unsigned) {
+        let message = format!(
+            "`{}` is a number, so its leading zeros are dropped (or it's read as octal); \
+             quote it",
+            text
+        );
+        return Some(("leading-zero", message));
+    }
+    None
+}
+
+impl Rule for AmbiguousScalar {
+    fn name(&self) -> &'static str {
+        "ambiguous-scalar"
+    }
+
+    fn description(&self) -> &'static str {
+        "Unquoted values YAML reads as something other than the string they look like"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (path, text, mark) in cx.spans.plain_scalars() {
+            if !is_string_value(path) {
+                continue;
+            }
+            let (id, message) = match problem(text) {
+                Some(problem) => problem,
+                None => continue,
+            };
+            let mut diagnostic = cx
+                .diagnostic(self, Severity::Warning, path, message)
+                .with_message_id(id)
+                .with_param("value", text);
+            // Only quoted when the text is all on the one line
+            if cx.source[mark.offset..].starts_with(text) {
+                diagnostic = diagnostic.with_fix(Fix {
+                    description: format!("quote `{}`", text),
+                    edits: vec![Edit {
+                        offset: mark.offset,
+                        len: text.len(),
+                        replacement: format!("\"{}\"", text),
+                    }],
+                });
+            }
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+}
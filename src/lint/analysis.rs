@@ -0,0 +1,105 @@
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::repo;
+
+use super::secret_leak::{secret_refs, SecretRef};
+use super::{local_actions, Context, LocalAction};
+
+/// Work on a file that rules share, done once per file however many rules
+/// need it. Rules list the ones they can't do without in
+/// [`Rule::requires`](super::Rule::requires), and are skipped when one
+/// can't be done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Analysis {
+    /// Finding the checkout the workflow is in.
+    Checkout,
+    /// Reading and parsing the local actions the workflow uses.
+    LocalActions,
+    /// Listing the files in the checkout.
+    RepoFiles,
+    /// Finding the `secrets.<name>` references in the workflow.
+    Secrets,
+}
+
+impl Analysis {
+    /// The analyses this one is worked out from.
+    fn requires(self) -> &'static [Analysis] {
+        match self {
+            Analysis::Checkout | Analysis::Secrets => &[],
+            Analysis::LocalActions | Analysis::RepoFiles => &[Analysis::Checkout],
+        }
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Analysis::Checkout => "the checkout",
+            Analysis::LocalActions => "local actions",
+            Analysis::RepoFiles => "the checkout's files",
+            Analysis::Secrets => "the workflow's secrets",
+        })
+    }
+}
+
+/// The analyses of one file, worked out the first time a rule asks.
+#[derive(Default)]
+pub struct Analyses {
+    local_actions: OnceCell<BTreeMap<String, LocalAction>>,
+    repo_files: OnceCell<Vec<String>>,
+    secret_refs: OnceCell<Vec<SecretRef>>,
+}
+
+/// A rule that wasn't run, or whose findings were dropped, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+impl<'a> Context<'a> {
+    /// Why `analysis` can't be done for this file, if it can't.
+    pub fn unavailable(&self, analysis: Analysis) -> Option<String> {
+        for &required in analysis.requires() {
+            if let Some(reason) = self.unavailable(required) {
+                return Some(reason);
+            }
+        }
+        match analysis {
+            Analysis::Checkout if self.repo_root.is_none() => {
+                Some("the file isn't in a git checkout".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// The local actions the workflow uses, by `uses` path; none outside a
+    /// checkout.
+    pub fn local_actions(&self) -> &BTreeMap<String, LocalAction> {
+        self.analyses
+            .local_actions
+            .get_or_init(|| match self.repo_root {
+                Some(root) => local_actions(self, root),
+                None => BTreeMap::new(),
+            })
+    }
+
+    /// The files git has in the checkout, relative to its root; none outside
+    /// a checkout.
+    pub fn repo_files(&self) -> &[String] {
+        self.analyses
+            .repo_files
+            .get_or_init(|| match self.repo_root {
+                Some(root) => repo::files(root),
+                None => Vec::new(),
+            })
+    }
+
+    /// The `secrets.<name>` references in the workflow, in the order
+    /// they're written.
+    pub fn secret_refs(&self) -> &[SecretRef] {
+        self.analyses.secret_refs.get_or_init(|| secret_refs(self))
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::BTreeSet;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Edit, Fix, Severity};
+use crate::schema::Target;
+use crate::spans::key_path;
+use crate::workflow::Step;
+
+use super::artifacts::{artifact_name, matrix_axes, matrix_refs, DOWNLOAD, UPLOAD};
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// v1 to v3 of upload-artifact and download-artifact stopped working on
+/// github.com on 2025-01-30. Moving to v4 is usually just bumping the
+/// version, but v4 artifacts can't be added to once uploaded, uploads of the
+/// same name are no longer merged, and hidden files are left out, so the
+/// version is only bumped automatically when none of that applies. Both
+/// sides have to move together, since v3 can't download what v4 uploads,
+/// so a download is only bumped when each upload it downloads just needs a
+/// version bump too.
+///
+/// GHES doesn't have v4, and v3 keeps working there, so nothing is checked
+/// when `target` is a GHES release.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactV4 {
+    pub target: Target,
+}
+
+/// The major version in refs like `v3` or `v3.1.2`. Commit SHAs give `None`.
+fn major_version(step: &Step) -> Option<(u32, String)> {
+    let git_ref = match step.action()?.ok()? {
+        ActionRef::Repository { git_ref, .. } => git_ref,
+        _ => return None,
+    };
+    let digits: String = git_ref
+        .strip_prefix('v')?
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    Some((digits.parse().ok()?, git_ref))
+}
+
+/// Whether the legs of the job's matrix all upload an artifact called
+/// `name`, since it doesn't tell them apart.
+fn collides(name: &str, axes: &BTreeSet<String>) -> bool {
+    matrix_refs(name).is_some_and(|refs| axes.iter().any(|axis| !refs.contains(axis)))
+}
+
+/// Looks like it uploads dotfiles, which v4 skips unless told otherwise.
+fn uploads_hidden_files(step: &Step) -> bool {
+    if step.with.contains_key("include-hidden-files") {
+        return false;
+    }
+    let path = step
+        .with
+        .get("path")
+        .map(String::as_str)
+        .unwrap_or_default();
+    path.lines()
+        .map(|l| l.trim().trim_start_matches('!'))
+        .flat_map(|l| l.split('/'))
+        .any(|segment| segment.starts_with('.') && segment != "." && segment != "..")
+}
+
+/// Bump the `@ref` in `uses` to `v4`.
+fn bump_edit(cx: &Context, path: &str, git_ref: &str) -> Option<Edit> {
+    let start = cx.spans.get(&key_path(path, "uses"))?.value.offset;
+    let line = cx.source[start..].lines().next()?;
+    let at = line.find(&format!("@{}", git_ref))? + 1;
+    Some(Edit {
+        offset: start + at,
+        len: git_ref.len(),
+        replacement: "v4".to_string(),
+    })
+}
+
+/// Add `include-hidden-files: true` as the first input.
+fn hidden_files_edit(cx: &Context, path: &str) -> Option<Edit> {
+    let start = cx.spans.get(&key_path(path, "with"))?.value;
+    if cx.source[start.offset..].starts_with('{') {
+        return None;
+    }
+    Some(Edit::insert(
+        start.offset,
+        format!(
+            "include-hidden-files: true\n{}",
+            " ".repeat(start.column - 1)
+        ),
+    ))
+}
+
+impl Rule for ArtifactV4 {
+    fn name(&self) -> &'static str {
+        "artifact-v4"
+    }
+
+    fn description(&self) -> &'static str {
+        "upload-artifact and download-artifact versions before v4, which no longer work"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        if self.target.is_ghes() {
+            return Vec::new();
+        }
+        let jobs = sorted_jobs(cx.workflow);
+
+        // How many steps upload each (literal) name, across the whole run
+        let mut upload_counts: Vec<(&str, usize)> = Vec::new();
+        for (_, job) in &jobs {
+            for step in job.steps.iter().filter(|s| s.uses_action(UPLOAD)) {
+                let name = artifact_name(step);
+                match upload_counts.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, count)) => *count += 1,
+                    None => upload_counts.push((name, 1)),
+                }
+            }
+        }
+        let reused = |name: &str| {
+            !name.contains("${{")
+                && upload_counts
+                    .iter()
+                    .any(|&(n, count)| n == name && count > 1)
+        };
+        // The names of the uploads before v4 that need more than a version
+        // bump, which the downloads of them have to wait for
+        let mut involved_uploads: Vec<&str> = Vec::new();
+        for (_, job) in &jobs {
+            let axes = matrix_axes(job);
+            for step in job.steps.iter().filter(|s| s.uses_action(UPLOAD)) {
+                if major_version(step).is_none_or(|(major, _)| major >= 4) {
+                    continue;
+                }
+                let name = artifact_name(step);
+                if reused(name) || collides(name, &axes) || uploads_hidden_files(step) {
+                    involved_uploads.push(name);
+                }
+            }
+        }
+        let newer_upload = |name: &str| {
+            jobs.iter().any(|(_, job)| {
+                job.steps.iter().any(|s| {
+                    s.uses_action(UPLOAD)
+                        && artifact_name(s) == name
+                        && major_version(s).is_some_and(|(major, _)| major >= 4)
+                })
+            })
+        };
+
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in &jobs {
+            let axes = matrix_axes(job);
+            for (i, step) in job.steps.iter().enumerate() {
+                let upload = step.uses_action(UPLOAD);
+                if !upload && !step.uses_action(DOWNLOAD) {
+                    continue;
+                }
+                let (major, git_ref) = match major_version(step) {
+                    Some((major, git_ref)) if major < 4 => (major, git_ref),
+                    _ => continue,
+                };
+                let path = step_path(job_id, i);
+                let action = if upload { UPLOAD } else { DOWNLOAD };
+
+                let mut breaks = Vec::new();
+                // Reused names need restructuring, not just a version bump
+                let mut manual = false;
+                let mut hidden = false;
+                if upload {
+                    let name = artifact_name(step);
+                    if reused(name) || collides(name, &axes) {
+                        manual = true;
+                        breaks.push(format!(
+                            "artifact `{}` is uploaded more than once, which v4 refuses; \
+                             give each upload its own name and download them together with \
+                             `pattern:` and `merge-multiple: true`",
+                            name
+                        ));
+                    }
+                    hidden = uploads_hidden_files(step);
+                } else if let Some(name) = step.with.get("name") {
+                    if reused(name) {
+                        manual = true;
+                        breaks.push(format!(
+                            "v4 doesn't merge the uploads of `{}` into one artifact, \
+                             so they need unique names and `pattern:` with `merge-multiple: true` here",
+                            name
+                        ));
+                    }
+                    if newer_upload(name) {
+                        breaks.push(format!(
+                            "`{}` is uploaded with v4, which v{} can't download",
+                            name, major
+                        ));
+                    }
+                }
+                if !upload && !manual {
+                    // Without a name, or with one only known at run time, it
+                    // could be downloading any of them
+                    let named = step
+                        .with
+                        .get("name")
+                        .filter(|name| !name.is_empty() && !name.contains("${{"));
+                    match named {
+                        Some(name) if involved_uploads.contains(&name.as_str()) => {
+                            manual = true;
+                            breaks.push(format!(
+                                "the upload of `{}` needs more than a version bump, so this \
+                                 download is left to move with it",
+                                name
+                            ));
+                        }
+                        None if !involved_uploads.is_empty() => {
+                            manual = true;
+                            breaks.push(
+                                "some of the uploads it could download need more than a \
+                                 version bump, so this download is left to move with them"
+                                    .to_string(),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+
+                let mut message = format!(
+                    "`{}@{}` stopped working on github.com on 2025-01-30; move to v4",
+                    action, git_ref
+                );
+                if hidden {
+                    message.push_str(
+                        ", which leaves hidden files out unless `include-hidden-files: true` is set",
+                    );
+                }
+                for note in &breaks {
+                    message.push_str("; ");
+                    message.push_str(note);
+                }
+                let mut diagnostic = cx
+                    .diagnostic(self, Severity::Error, &key_path(&path, "uses"), message)
+                    .with_param("action", action)
+                    .with_param("ref", &git_ref);
+
+                if !manual {
+                    let mut edits: Vec<Edit> = bump_edit(cx, &path, &git_ref).into_iter().collect();
+                    if hidden {
+                        edits.extend(hidden_files_edit(cx, &path));
+                    }
+                    if edits.len() == 1 + hidden as usize {
+                        diagnostic = diagnostic.with_fix(Fix {
+                            description: format!("upgrade {} to v4", action),
+                            edits,
+                        });
+                    }
+                }
+                diagnostics.push(diagnostic);
+            }
+        }
+        diagnostics
+    }
+}
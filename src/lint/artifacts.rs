@@ -0,0 +1,355 @@
+use std::collections::BTreeSet;
+
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr;
+use crate::glob::Glob;
+use crate::graph::JobGraph;
+use crate::spans::key_path;
+use crate::workflow::{Axis, Job, Step};
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+pub(super) const UPLOAD: &str = "actions/upload-artifact";
+pub(super) const DOWNLOAD: &str = "actions/download-artifact";
+
+/// What upload-artifact calls an artifact that isn't given a name.
+const DEFAULT_NAME: &str = "artifact";
+
+/// `retention-days` outside the 1 to 90 days GitHub allows.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactRetention;
+
+impl Rule for ArtifactRetention {
+    fn name(&self) -> &'static str {
+        "artifact-retention"
+    }
+
+    fn description(&self) -> &'static str {
+        "`retention-days` outside the 1 to 90 days GitHub allows"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                if !step.uses_action(UPLOAD) {
+                    continue;
+                }
+                let days = match step.with.get("retention-days") {
+                    Some(days) if !days.contains("${{") => days.trim(),
+                    _ => continue,
+                };
+                let (id, message) = match days.parse::<i64>() {
+                    Ok(1..=90) => continue,
+                    Ok(_) => (
+                        "out-of-range",
+                        format!(
+                            "`retention-days` is {}, but artifacts can only be kept for 1 to \
+                             90 days",
+                            days
+                        ),
+                    ),
+                    Err(_) => (
+                        "not-a-number",
+                        format!(
+                            "`retention-days` must be a whole number of days, not `{}`",
+                            days
+                        ),
+                    ),
+                };
+                let path = key_path(&step_path(job_id, i), "with.retention-days");
+                diagnostics.push(
+                    cx.diagnostic(self, Severity::Error, &path, message)
+                        .with_message_id(id)
+                        .with_param("days", days),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Artifacts that nothing in the workflow downloads, or that are only
+/// downloaded by jobs that can start before the upload happens. Artifacts
+/// meant for people or other workflows can be listed in `consumers`.
+#[derive(Debug, Clone, Default)]
+pub struct UnusedArtifact {
+    /// Artifact names downloaded somewhere else.
+    pub consumers: Vec<String>,
+}
+
+struct Upload<'a> {
+    job_id: &'a str,
+    index: usize,
+    name: &'a str,
+}
+
+struct Download<'a> {
+    job_id: &'a str,
+    index: usize,
+    /// `None` downloads everything.
+    name: Option<&'a str>,
+    pattern: Option<&'a str>,
+}
+
+/// A name as a pattern, with each expression matching anything.
+fn name_glob(name: &str) -> Option<Glob> {
+    let mut pattern = String::new();
+    let mut rest = 0;
+    for embedded in expr::find_expressions(name) {
+        pattern.push_str(&name[rest..embedded.offset]);
+        pattern.push('*');
+        rest = (embedded.body_offset + embedded.body.len() + 2).min(name.len());
+    }
+    pattern.push_str(&name[rest..]);
+    Glob::parse(&pattern).ok()
+}
+
+impl<'a> Download<'a> {
+    fn matches(&self, upload: &str) -> bool {
+        let dynamic = upload.contains("${{");
+        match (self.name, self.pattern) {
+            (Some(name), _) if name.contains("${{") => {
+                dynamic || name_glob(name).is_some_and(|g| g.matches(upload))
+            }
+            (Some(name), _) => match name_glob(upload) {
+                Some(glob) if dynamic => glob.matches(name),
+                _ => name == upload,
+            },
+            (None, Some(pattern)) => {
+                dynamic
+                    || pattern.contains("${{")
+                    || Glob::parse(pattern).is_ok_and(|g| g.matches(upload))
+            }
+            (None, None) => true,
+        }
+    }
+}
+
+pub(super) fn artifact_name(step: &Step) -> &str {
+    step.with
+        .get("name")
+        .map(String::as_str)
+        .filter(|n| !n.is_empty())
+        .unwrap_or(DEFAULT_NAME)
+}
+
+impl Rule for UnusedArtifact {
+    fn name(&self) -> &'static str {
+        "unused-artifact"
+    }
+
+    fn description(&self) -> &'static str {
+        "Artifacts nothing downloads, or that are downloaded before they're uploaded"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut uploads = Vec::new();
+        let mut downloads = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (index, step) in job.steps.iter().enumerate() {
+                if step.uses_action(UPLOAD) {
+                    uploads.push(Upload {
+                        job_id,
+                        index,
+                        name: artifact_name(step),
+                    });
+                } else if step.uses_action(DOWNLOAD) && !step.with.contains_key("run-id") {
+                    downloads.push(Download {
+                        job_id,
+                        index,
+                        name: step.with.get("name").map(String::as_str),
+                        pattern: step.with.get("pattern").map(String::as_str),
+                    });
+                }
+            }
+        }
+
+        let graph = JobGraph::new(cx.workflow);
+        let mut diagnostics = Vec::new();
+        for upload in uploads {
+            if self.consumers.iter().any(|c| c == upload.name) {
+                continue;
+            }
+            let consumers: Vec<&Download> = downloads
+                .iter()
+                .filter(|d| d.matches(upload.name))
+                .collect();
+            let downstream = consumers.iter().any(|d| {
+                (d.job_id == upload.job_id && d.index > upload.index)
+                    || graph.upstream(d.job_id).contains(upload.job_id)
+            });
+            if downstream {
+                continue;
+            }
+
+            let path = step_path(upload.job_id, upload.index);
+            let diagnostic = match consumers.first() {
+                None => cx
+                    .diagnostic(
+                        self,
+                        Severity::Info,
+                        &path,
+                        format!(
+                            "artifact `{}` is uploaded but never downloaded in this workflow; \
+                         list it under `consumers` if it's meant for people or other workflows",
+                            upload.name
+                        ),
+                    )
+                    .with_message_id("never-downloaded"),
+                Some(download) => cx
+                    .diagnostic(
+                        self,
+                        Severity::Warning,
+                        &path,
+                        format!(
+                            "artifact `{}` is downloaded by job `{}`, which doesn't need `{}`, \
+                         so the download can run before the upload",
+                            upload.name, download.job_id, upload.job_id
+                        ),
+                    )
+                    .with_message_id("downloaded-too-early")
+                    .with_param("download_job", download.job_id),
+            };
+            let diagnostic = diagnostic
+                .with_param("artifact", upload.name)
+                .with_param("upload_job", upload.job_id);
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+}
+
+/// Artifacts uploaded from a matrix job under a name that doesn't tell the
+/// legs apart. upload-artifact@v4 fails when a name is reused, and older
+/// versions mix the legs' files together.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactNameCollision;
+
+/// Matrix keys that take more than one value.
+pub(super) fn matrix_axes(job: &Job) -> BTreeSet<String> {
+    let mut axes = BTreeSet::new();
+    let matrix = match job
+        .strategy
+        .as_ref()
+        .and_then(|s| s.matrix.as_ref()?.axes())
+    {
+        Some(matrix) => matrix,
+        None => return axes,
+    };
+    for (key, axis) in &matrix.axes {
+        if let Axis::Values(values) = axis {
+            if values.len() > 1 {
+                axes.insert(key.clone());
+            }
+        }
+    }
+    let values: Vec<(&String, &Value)> = matrix.includes().iter().flatten().collect();
+    for &(key, value) in &values {
+        if values.iter().any(|&(k, v)| k == key && v != value) {
+            axes.insert(key.clone());
+        }
+    }
+    axes
+}
+
+/// The matrix keys `name` refers to, or `None` if it's unique per leg anyway,
+/// e.g. by using the whole `matrix` or `strategy.job-index`.
+pub(super) fn matrix_refs(name: &str) -> Option<BTreeSet<String>> {
+    let mut refs = BTreeSet::new();
+    for embedded in expr::find_expressions(name) {
+        let tokens = expr::tokenize(embedded.body).ok()?;
+        for reference in expr::references(&tokens) {
+            match (reference.parts[0].as_str(), reference.parts.get(1)) {
+                ("matrix", Some(key)) => {
+                    refs.insert(key.clone());
+                }
+                ("matrix", None) => return None,
+                ("strategy", Some(key)) if key == "job-index" => return None,
+                _ => {}
+            }
+        }
+    }
+    Some(refs)
+}
+
+impl Rule for ArtifactNameCollision {
+    fn name(&self) -> &'static str {
+        "artifact-name-collision"
+    }
+
+    fn description(&self) -> &'static str {
+        "Artifacts uploaded from a matrix under a name the legs share"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let axes = matrix_axes(job);
+            if axes.is_empty() {
+                continue;
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                if !step.uses_action(UPLOAD) {
+                    continue;
+                }
+                let name = artifact_name(step);
+                let refs = match matrix_refs(name) {
+                    Some(refs) => refs,
+                    None => continue,
+                };
+                let missing: Vec<String> = axes
+                    .iter()
+                    .filter(|axis| !refs.contains(*axis))
+                    .map(|axis| format!("`matrix.{}`", axis))
+                    .collect();
+                if missing.is_empty() {
+                    continue;
+                }
+
+                let (id, message) = if refs.is_empty() {
+                    (
+                        "fixed-name",
+                        format!(
+                            "every leg of the `{}` matrix uploads an artifact named `{}`; \
+                         include {} in the name so they don't collide",
+                            job_id,
+                            name,
+                            missing.join(", ")
+                        ),
+                    )
+                } else {
+                    (
+                        "missing-matrix-values",
+                        format!(
+                            "artifact name `{}` doesn't include {}, so legs of the `{}` matrix \
+                         that only differ there upload under the same name",
+                            name,
+                            missing.join(", "),
+                            job_id
+                        ),
+                    )
+                };
+                let path = if step.with.contains_key("name") {
+                    key_path(&step_path(job_id, i), "with.name")
+                } else {
+                    step_path(job_id, i)
+                };
+                diagnostics.push(
+                    cx.diagnostic(self, Severity::Warning, &path, message)
+                        .with_message_id(id)
+                        .with_param("job", job_id)
+                        .with_param("artifact", name)
+                        .with_param("missing", missing.join(", ")),
+                );
+            }
+        }
+        diagnostics
+    }
+}
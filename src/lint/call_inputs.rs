@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use indexmap::IndexMap;
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::spans::key_path;
+use crate::workflow::{CallInput, Event, JobKind, Workflow};
+
+use super::unknown_key::closest;
+use super::{job_path, sorted_jobs, Analysis, Context, Rule};
+
+/// Jobs calling a reusable workflow in the same repository with inputs it
+/// doesn't declare, without ones it requires, or with a value of the wrong
+/// type, all of which make GitHub reject the calling workflow. So does
+/// calling a file that's missing or that isn't triggered by
+/// `workflow_call`. Only checked when linting inside a checkout, where the
+/// called workflow can be read.
+#[derive(Debug, Clone, Default)]
+pub struct CallInputs;
+
+/// What reading the called workflow found.
+enum Called {
+    Missing,
+    NotReusable,
+    Inputs(IndexMap<String, CallInput>),
+}
+
+/// The inputs the workflow at `path` declares, if it can be read and
+/// parsed.
+fn called(path: &Path) -> Option<Called> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(_) if !path.exists() => return Some(Called::Missing),
+        Err(_) => return None,
+    };
+    let mut events = Workflow::parse_events(&source).ok()?;
+    match events.swap_remove("workflow_call") {
+        Some(Event::WorkflowCall(settings)) => Some(Called::Inputs(
+            settings.map(|s| s.inputs).unwrap_or_default(),
+        )),
+        Some(_) => None,
+        None => Some(Called::NotReusable),
+    }
+}
+
+impl CallInputs {
+    fn check_inputs(
+        &self,
+        cx: &Context,
+        job_id: &str,
+        uses: &str,
+        with: &IndexMap<String, Value>,
+        inputs: &IndexMap<String, CallInput>,
+    ) -> Vec<Diagnostic> {
+        let job = job_path(job_id);
+        let node = key_path(&job, "with");
+        let mut diagnostics = Vec::new();
+        let declared: Vec<&str> = inputs.keys().map(String::as_str).collect();
+        for (name, value) in with {
+            let path = key_path(&node, name);
+            let input = match inputs.get(name) {
+                Some(input) => input,
+                None => {
+                    let mut diagnostic = cx
+                        .diagnostic(
+                            self,
+                            Severity::Error,
+                            &path,
+                            format!("`{}` doesn't declare an input named `{}`", uses, name),
+                        )
+                        .with_message_id("unknown")
+                        .with_param("input", name);
+                    if let Some(input) = closest(name, &declared) {
+                        diagnostic = diagnostic
+                            .with_suggestion(Suggestion::new(format!("did you mean `{}`?", input)));
+                    }
+                    diagnostics.push(diagnostic);
+                    continue;
+                }
+            };
+            if !input.input_type.accepts(value) {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &path,
+                        format!(
+                            "the input `{}` of `{}` is a {}, so GitHub rejects this value",
+                            name,
+                            uses,
+                            input.input_type.as_str()
+                        ),
+                    )
+                    .with_message_id("type")
+                    .with_param("input", name),
+                );
+            }
+        }
+        let missing = inputs.iter().filter(|(name, input)| {
+            input.required == Some(true) && input.default.is_none() && !with.contains_key(*name)
+        });
+        for (name, _) in missing {
+            let path = match with.is_empty() {
+                true => key_path(&job, "uses"),
+                false => node.clone(),
+            };
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    &path,
+                    format!(
+                        "`{}` requires the input `{}`, which job `{}` doesn't pass",
+                        uses, name, job_id
+                    ),
+                )
+                .with_message_id("missing-input")
+                .with_param("input", name),
+            );
+        }
+        diagnostics
+    }
+}
+
+impl Rule for CallInputs {
+    fn name(&self) -> &'static str {
+        "call-inputs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Calls to local reusable workflows with inputs they don't declare, of the wrong type, or \
+         without required ones"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn requires(&self) -> &'static [Analysis] {
+        &[Analysis::Checkout]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let root = match cx.repo_root {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let (uses, with) = match job.kind() {
+                JobKind::Call { uses, with, .. } => (uses, with),
+                JobKind::Steps { .. } => continue,
+            };
+            let relative = match uses.strip_prefix("./") {
+                Some(relative) => relative,
+                None => continue,
+            };
+            let path = key_path(&job_path(job_id), "uses");
+            let (id, message) = match called(&root.join(relative)) {
+                Some(Called::Inputs(inputs)) => {
+                    diagnostics.extend(self.check_inputs(cx, job_id, uses, with, &inputs));
+                    continue;
+                }
+                Some(Called::Missing) => (
+                    "missing",
+                    format!("`{}` doesn't exist in the repository", uses),
+                ),
+                Some(Called::NotReusable) => (
+                    "not-reusable",
+                    format!(
+                        "`{}` isn't triggered by `workflow_call`, so it can't be called",
+                        uses
+                    ),
+                ),
+                None => continue,
+            };
+            diagnostics.push(
+                cx.diagnostic(self, Severity::Error, &path, message)
+                    .with_message_id(id)
+                    .with_param("path", uses),
+            );
+        }
+        diagnostics
+    }
+}
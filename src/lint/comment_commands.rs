@@ -0,0 +1,330 @@
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::expr;
+use crate::spans::key_path;
+
+use super::{event_names, for_each_string, job_path, sorted_jobs, step_path, Context, Rule};
+
+const GROUP: &str = "comment-commands";
+
+/// Events that run on whatever anyone with an account writes in an issue,
+/// pull request or discussion.
+const COMMENT_EVENTS: &[&str] = &[
+    "discussion_comment",
+    "issue_comment",
+    "issues",
+    "pull_request_review_comment",
+];
+
+/// The objects of those events that have text anyone can write.
+const WRITTEN: &[&str] = &["comment", "discussion", "issue", "review"];
+
+/// Actions that check the commenter can write to the repository.
+const PERMISSION_ACTIONS: &[&str] = &[
+    "actions-cool/check-user-permission",
+    "lannonbr/repo-permission-check-action",
+    "sushichop/action-repository-permission",
+    "xt0rted/slash-command-action",
+];
+
+/// Whether the expression reference `parts` reads text anyone can write,
+/// like `github.event.comment.body` or `github.event.issue.title`.
+fn is_written(parts: &[String]) -> bool {
+    match parts {
+        [github, event, object, field, ..] => {
+            github == "github"
+                && event == "event"
+                && WRITTEN.contains(&object.as_str())
+                && (field == "body" || field == "title")
+        }
+        _ => false,
+    }
+}
+
+/// The byte offsets of the expressions in `s` that read written text.
+fn written_text(s: &str) -> Vec<usize> {
+    let mut found = Vec::new();
+    for embedded in expr::find_expressions(s) {
+        let tokens = match expr::tokenize(embedded.body) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+        let references = expr::references(&tokens);
+        if let Some(reference) = references.iter().find(|r| is_written(&r.parts)) {
+            found.push(embedded.body_offset + reference.offset);
+        }
+    }
+    found
+}
+
+/// Whether `s` reads written text, as an expression or as bare text in an
+/// `if:`, which is an expression even without `${{`.
+fn reads_written_text(s: &str) -> bool {
+    let bare = expr::tokenize(s)
+        .map(|tokens| {
+            expr::references(&tokens)
+                .iter()
+                .any(|r| is_written(&r.parts))
+        })
+        .unwrap_or(false);
+    bare || !written_text(s).is_empty()
+}
+
+/// Whether the workflow runs on comments.
+fn on_comments(cx: &Context) -> bool {
+    event_names(cx.document)
+        .iter()
+        .any(|event| COMMENT_EVENTS.contains(event))
+}
+
+/// The jobs that act on a command: those whose `if` or steps' `if` read
+/// what was written, like `startsWith(github.event.comment.body, '/deploy')`.
+fn command_jobs<'a>(cx: &Context<'a>) -> Vec<(&'a str, &'a Value)> {
+    if !on_comments(cx) {
+        return Vec::new();
+    }
+    sorted_jobs(cx.workflow)
+        .into_iter()
+        .filter_map(|(job_id, _)| {
+            let job = cx.job_node(job_id)?;
+            let mut conditions = vec![job.get("if")];
+            let steps = job.get("steps").and_then(Value::as_sequence);
+            conditions.extend(steps.into_iter().flatten().map(|step| step.get("if")));
+            let is_command = conditions
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .any(reads_written_text);
+            Some((job_id, job)).filter(|_| is_command)
+        })
+        .collect()
+}
+
+/// Whether the job checks who posted the command before acting on it.
+fn checks_association(job: &Value) -> bool {
+    let mut checked = false;
+    for_each_string(job, "", &mut |path, value| {
+        let lower = value.to_lowercase();
+        checked |= lower.contains("author_association")
+            // A script asking the API for the commenter's permission
+            || lower.contains("getcollaboratorpermissionlevel")
+            || (lower.contains("/collaborators/") && lower.contains("/permission"))
+            || (path.ends_with(".uses")
+                && PERMISSION_ACTIONS
+                    .iter()
+                    .any(|action| lower.starts_with(&format!("{}@", action))));
+    });
+    checked
+}
+
+/// Jobs that act on a slash command in a comment without checking who
+/// wrote it. Anyone with a GitHub account can comment on a public
+/// repository's issues and pull requests, so `/deploy` runs for them too
+/// unless the job checks the commenter's `author_association`.
+#[derive(Debug, Clone, Default)]
+pub struct CommentCommandAssociation;
+
+impl Rule for CommentCommandAssociation {
+    fn name(&self) -> &'static str {
+        "comment-command-association"
+    }
+
+    fn description(&self) -> &'static str {
+        "Comment commands that don't check who wrote the comment"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in command_jobs(cx) {
+            if checks_association(job) {
+                continue;
+            }
+            let node = match job.get("if") {
+                Some(_) => key_path(&job_path(job_id), "if"),
+                None => job_path(job_id),
+            };
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    &node,
+                    format!(
+                        "job `{}` acts on a command anyone can comment, without checking \
+                         who wrote it",
+                        job_id
+                    ),
+                )
+                .with_message_id("unchecked")
+                .with_param("job", job_id)
+                .with_suggestion(
+                    Suggestion::new("only act on commands from people with write access")
+                        .with_example(
+                            "if: contains(fromJSON('[\"OWNER\", \"MEMBER\", \"COLLABORATOR\"]'), \
+                             github.event.comment.author_association)",
+                        ),
+                ),
+            );
+        }
+        diagnostics
+    }
+}
+
+/// Scripts in comment-triggered workflows that put what was written, like
+/// `${{ github.event.comment.body }}`, straight into the script. The text is
+/// pasted in before the shell runs, so a comment with `"; curl ... | sh #`
+/// runs that command.
+#[derive(Debug, Clone, Default)]
+pub struct CommentCommandInjection;
+
+impl Rule for CommentCommandInjection {
+    fn name(&self) -> &'static str {
+        "comment-command-injection"
+    }
+
+    fn description(&self) -> &'static str {
+        "Comment text pasted straight into the scripts of comment commands"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if !on_comments(cx) {
+            return diagnostics;
+        }
+        for (job_id, _) in sorted_jobs(cx.workflow) {
+            let steps = cx
+                .job_node(job_id)
+                .and_then(|job| job.get("steps"))
+                .and_then(Value::as_sequence);
+            for (index, step) in steps.into_iter().flatten().enumerate() {
+                let path = step_path(job_id, index);
+                // Scripts for the shell, and for actions/github-script
+                let scripts = [
+                    ("run", step.get("run")),
+                    (
+                        "with.script",
+                        step.get("with").and_then(|w| w.get("script")),
+                    ),
+                ];
+                for (key, script) in scripts {
+                    let script = match script.and_then(Value::as_str) {
+                        Some(script) => script,
+                        None => continue,
+                    };
+                    let node = key_path(&path, key);
+                    for offset in written_text(script) {
+                        diagnostics.push(
+                            cx.diagnostic_in(
+                                self,
+                                Severity::Error,
+                                &node,
+                                offset,
+                                "what was written in the comment goes straight into the \
+                                 script, where it can run commands",
+                            )
+                            .with_message_id("interpolated")
+                            .with_suggestion(
+                                Suggestion::new(
+                                    "pass it through `env` and read it as a variable, after \
+                                     checking it's the command you expect",
+                                )
+                                .with_example(
+                                    "env:\n  BODY: ${{ github.event.comment.body }}\nrun: |\n  \
+                                     case \"$BODY\" in /deploy*) ;; *) exit 1;; esac",
+                                ),
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Command jobs that get more than a minimal token: no `permissions`, and
+/// so the repository's default, which can be write access to everything,
+/// or `write-all`. Commands are where an attacker gets to choose what runs.
+#[derive(Debug, Clone, Default)]
+pub struct CommentCommandPermissions;
+
+impl Rule for CommentCommandPermissions {
+    fn name(&self) -> &'static str {
+        "comment-command-permissions"
+    }
+
+    fn description(&self) -> &'static str {
+        "Comment command jobs with more than a minimal token"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in command_jobs(cx) {
+            let (node, permissions) = match job.get("permissions") {
+                Some(permissions) => (key_path(&job_path(job_id), "permissions"), permissions),
+                None => match cx.document.get("permissions") {
+                    Some(permissions) => ("permissions".to_string(), permissions),
+                    None => {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Warning,
+                                &job_path(job_id),
+                                format!(
+                                    "job `{}` acts on comments with the repository's default \
+                                     token permissions; set `permissions` to what the command \
+                                     needs",
+                                    job_id
+                                ),
+                            )
+                            .with_message_id("default")
+                            .with_param("job", job_id),
+                        );
+                        continue;
+                    }
+                },
+            };
+            if permissions.as_str() == Some("write-all") {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &node,
+                        format!(
+                            "job `{}` acts on comments with `write-all` permissions; grant \
+                             only what the command needs",
+                            job_id
+                        ),
+                    )
+                    .with_message_id("write-all")
+                    .with_param("job", job_id),
+                );
+            }
+        }
+        diagnostics
+    }
+}
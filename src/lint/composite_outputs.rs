@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr;
+use crate::spans::key_path;
+
+use super::{for_each_string, job_path, sorted_jobs, Analysis, Context, LocalAction, Rule};
+
+/// Outputs of local composite actions that aren't wired up: an output whose
+/// `value` points at a step the action doesn't have, or a workflow reading
+/// `steps.<id>.outputs.<name>` for an output the action doesn't declare.
+/// Either way the value is silently empty. Only checked when linting inside
+/// a checkout.
+#[derive(Debug, Clone, Default)]
+pub struct CompositeOutputs;
+
+/// The `steps.<id>.outputs.<name>` references in `s`, as `(id, name)`.
+fn step_outputs(s: &str) -> Vec<(String, String)> {
+    let mut found = Vec::new();
+    for embedded in expr::find_expressions(s) {
+        let tokens = match expr::tokenize(embedded.body) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+        for reference in expr::references(&tokens) {
+            if let [context, id, outputs, name, ..] = reference.parts.as_slice() {
+                if context == "steps" && outputs == "outputs" && id != "*" {
+                    found.push((id.clone(), name.clone()));
+                }
+            }
+        }
+    }
+    found
+}
+
+impl CompositeOutputs {
+    fn check_action(&self, local: &LocalAction, diagnostics: &mut Vec<Diagnostic>) {
+        let step_ids: Vec<&str> = local
+            .action
+            .runs
+            .steps
+            .iter()
+            .filter_map(|s| s.id.as_deref())
+            .collect();
+
+        let mut outputs: Vec<_> = local.action.outputs.iter().collect();
+        outputs.sort_by_key(|&(name, _)| name);
+        for (name, output) in outputs {
+            let node = key_path("outputs", name);
+            let (severity, message) = match &output.value {
+                None => (
+                    Severity::Error,
+                    format!("output `{}` of a composite action needs a `value`", name),
+                ),
+                Some(value) => {
+                    let missing = step_outputs(value)
+                        .into_iter()
+                        .find(|(id, _)| !step_ids.contains(&id.as_str()));
+                    match missing {
+                        Some((id, _)) => (
+                            Severity::Error,
+                            format!(
+                                "output `{}` comes from step `{}`, but the action has no step with that id",
+                                name, id
+                            ),
+                        ),
+                        None => continue,
+                    }
+                }
+            };
+            let node = match output.value {
+                Some(_) => key_path(&node, "value"),
+                None => node,
+            };
+            diagnostics.push(local.diagnostic(self, severity, &node, message));
+        }
+    }
+}
+
+impl Rule for CompositeOutputs {
+    fn name(&self) -> &'static str {
+        "composite-outputs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Outputs of local composite actions that aren't wired up"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn requires(&self) -> &'static [Analysis] {
+        &[Analysis::LocalActions]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let jobs = sorted_jobs(cx.workflow);
+
+        let actions: BTreeMap<&String, &LocalAction> = cx
+            .local_actions()
+            .iter()
+            .filter(|(_, local)| local.action.is_composite())
+            .collect();
+
+        let mut diagnostics = Vec::new();
+        for local in actions.values() {
+            self.check_action(local, &mut diagnostics);
+        }
+
+        // Steps contexts are per job, so each job's ids are looked up separately
+        for (job_id, job) in &jobs {
+            let by_id: BTreeMap<&str, &LocalAction> = job
+                .steps
+                .iter()
+                .filter_map(|step| match step.action() {
+                    Some(Ok(ActionRef::Local { path })) => {
+                        Some((step.id.as_deref()?, *actions.get(&path)?))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if by_id.is_empty() {
+                continue;
+            }
+            let node = match cx.job_node(job_id) {
+                Some(node) => node,
+                None => continue,
+            };
+            for_each_string(node, &job_path(job_id), &mut |path, value| {
+                for (id, output) in step_outputs(value) {
+                    let local = match by_id.get(id.as_str()) {
+                        Some(local) => local,
+                        None => continue,
+                    };
+                    if local.action.outputs.contains_key(&output) {
+                        continue;
+                    }
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Error,
+                            path,
+                            format!(
+                                "step `{}` has no output `{}`; {}",
+                                id,
+                                output,
+                                declared(local)
+                            ),
+                        )
+                        .with_param("step", &id)
+                        .with_param("output", &output),
+                    );
+                }
+            });
+        }
+        diagnostics
+    }
+}
+
+fn declared(local: &LocalAction) -> String {
+    let mut names: Vec<String> = local
+        .action
+        .outputs
+        .keys()
+        .map(|name| format!("`{}`", name))
+        .collect();
+    names.sort();
+    if names.is_empty() {
+        format!("{} declares no outputs", local.file.display())
+    } else {
+        format!(
+            "{} only declares {}",
+            local.file.display(),
+            names.join(", ")
+        )
+    }
+}
@@ -0,0 +1,209 @@
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::expr::{self, CONTEXTS};
+use crate::spans::key_path;
+use crate::workflow::{BoolOrExpression, Concurrency};
+
+use super::{event_names, for_each_string, job_path, sorted_jobs, Context, Rule};
+
+/// The contexts a workflow's `concurrency` can use, which is worked out
+/// before any job runs.
+const WORKFLOW_CONTEXTS: &[&str] = &["github", "inputs", "vars"];
+
+/// The contexts a job's `concurrency` can use.
+const JOB_CONTEXTS: &[&str] = &["github", "needs", "strategy", "matrix", "inputs", "vars"];
+
+const EXAMPLE: &str = "concurrency:\n  group: ${{ github.workflow }}-${{ github.event.pull_request.number || github.ref }}\n  cancel-in-progress: true";
+
+/// Concurrency groups GitHub won't accept: an empty group, one using a
+/// context that isn't available where it's set, like `env` or `secrets`,
+/// and a `cancel-in-progress` that's neither a boolean nor an expression.
+/// The workflow's group is worked out before any job runs, so it can't use
+/// `matrix` or `needs` the way a job's can.
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyGroup;
+
+impl ConcurrencyGroup {
+    fn check_group(
+        &self,
+        cx: &Context,
+        node: &str,
+        raw: Option<&Value>,
+        concurrency: &Concurrency,
+        allowed: &[&str],
+    ) -> Vec<Diagnostic> {
+        let level = match allowed == WORKFLOW_CONTEXTS {
+            true => "the workflow's",
+            false => "a job's",
+        };
+        let mut diagnostics = Vec::new();
+        if concurrency.group.trim().is_empty() {
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    node,
+                    "the `concurrency` group is empty, so GitHub rejects the workflow",
+                )
+                .with_message_id("empty"),
+            );
+        }
+        if let Some(BoolOrExpression::Expression(value)) = &concurrency.cancel_in_progress {
+            if !value.contains("${{") {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &key_path(node, "cancel-in-progress"),
+                        format!(
+                            "`cancel-in-progress` is the string `{}`, but GitHub only accepts \
+                             `true`, `false` or an expression",
+                            value
+                        ),
+                    )
+                    .with_message_id("cancel-in-progress"),
+                );
+            }
+        }
+        let raw = match raw {
+            Some(raw) => raw,
+            None => return diagnostics,
+        };
+        for_each_string(raw, node, &mut |path, value| {
+            for embedded in expr::find_expressions(value) {
+                let tokens = match expr::tokenize(embedded.body) {
+                    Ok(tokens) => tokens,
+                    Err(_) => continue,
+                };
+                for reference in expr::references(&tokens) {
+                    let context = reference.parts[0].to_ascii_lowercase();
+                    if allowed.contains(&context.as_str()) || !CONTEXTS.contains(&context.as_str())
+                    {
+                        continue;
+                    }
+                    diagnostics.push(
+                        cx.diagnostic_in(
+                            self,
+                            Severity::Error,
+                            path,
+                            embedded.body_offset + reference.offset,
+                            format!(
+                                "`{}` isn't available in {} `concurrency`, so GitHub rejects \
+                                 the workflow; it can use {}",
+                                reference.parts[0],
+                                level,
+                                allowed
+                                    .iter()
+                                    .map(|c| format!("`{}`", c))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                        )
+                        .with_message_id("context")
+                        .with_param("context", &reference.parts[0]),
+                    );
+                }
+            }
+        });
+        diagnostics
+    }
+}
+
+impl Rule for ConcurrencyGroup {
+    fn name(&self) -> &'static str {
+        "concurrency-group"
+    }
+
+    fn description(&self) -> &'static str {
+        "Concurrency groups that are empty or use a context that isn't available where they're set"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let Some(concurrency) = &cx.workflow.concurrency {
+            let raw = cx.document.get("concurrency");
+            diagnostics.extend(self.check_group(
+                cx,
+                "concurrency",
+                raw,
+                concurrency,
+                WORKFLOW_CONTEXTS,
+            ));
+        }
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let concurrency = match &job.concurrency {
+                Some(concurrency) => concurrency,
+                None => continue,
+            };
+            let node = key_path(&job_path(job_id), "concurrency");
+            let raw = cx.job_node(job_id).and_then(|job| job.get("concurrency"));
+            diagnostics.extend(self.check_group(cx, &node, raw, concurrency, JOB_CONTEXTS));
+        }
+        diagnostics
+    }
+}
+
+/// Pull request workflows without a concurrency group, where every push to
+/// the branch starts another run while the last one is still going, and
+/// ones whose group is the same for every pull request, so that runs for
+/// different pull requests wait for or cancel each other.
+#[derive(Debug, Clone, Default)]
+pub struct PullRequestConcurrency;
+
+impl Rule for PullRequestConcurrency {
+    fn name(&self) -> &'static str {
+        "pull-request-concurrency"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pull request workflows without a concurrency group to cancel superseded runs"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        if !event_names(cx.document).contains(&"pull_request") {
+            return Vec::new();
+        }
+        let node = match cx.document.get("on") {
+            Some(Value::Mapping(_)) => key_path("on", "pull_request"),
+            _ => "on".to_string(),
+        };
+        let jobs = sorted_jobs(cx.workflow);
+        let diagnostic = match &cx.workflow.concurrency {
+            Some(concurrency) if !concurrency.group.contains("${{") => cx
+                .diagnostic(
+                    self,
+                    Severity::Warning,
+                    "concurrency",
+                    format!(
+                        "every run of this workflow is in the `{}` group, so runs for different \
+                         pull requests wait for or cancel each other",
+                        concurrency.group
+                    ),
+                )
+                .with_message_id("fixed-group")
+                .with_param("group", &concurrency.group),
+            Some(_) => return Vec::new(),
+            None if jobs.iter().any(|(_, job)| job.concurrency.is_some()) => return Vec::new(),
+            None if jobs.is_empty() => return Vec::new(),
+            None => cx
+                .diagnostic(
+                    self,
+                    Severity::Info,
+                    &node,
+                    "this workflow runs on `pull_request` without a `concurrency` group, so \
+                     each push to a pull request starts another run while the last is still \
+                     going",
+                )
+                .with_message_id("missing"),
+        };
+        vec![diagnostic.with_suggestion(
+            Suggestion::new("group runs by pull request, cancelling the one they replace")
+                .with_example(EXAMPLE),
+        )]
+    }
+}
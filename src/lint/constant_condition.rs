@@ -0,0 +1,124 @@
+use serde_json::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::eval;
+use crate::expr::Expr;
+use crate::spans::key_path;
+
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// `if:` conditions that always come out the same, either on their own or
+/// given what's known about the run: the events the workflow is triggered by
+/// and any context values from a stubs file. A condition that's never true
+/// is a job or step that never runs.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantCondition;
+
+/// Whether the condition checks how earlier jobs or steps went, which
+/// changes when it's evaluated at all.
+fn has_status_check(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(name, args) => {
+            ["always", "success", "failure", "cancelled"]
+                .iter()
+                .any(|f| name.eq_ignore_ascii_case(f))
+                || args.iter().any(has_status_check)
+        }
+        Expr::Property(base, _) | Expr::Filter(base) | Expr::Not(base) => has_status_check(base),
+        Expr::Index(base, index) => has_status_check(base) || has_status_check(index),
+        Expr::Binary(left, _, right) => has_status_check(left) || has_status_check(right),
+        _ => false,
+    }
+}
+
+impl ConstantCondition {
+    fn check_condition(
+        &self,
+        cx: &Context,
+        scenarios: &[Value],
+        condition: &str,
+        node: &str,
+        what: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let expr = match eval::parse_condition(condition) {
+            Some(expr) => expr,
+            None => return,
+        };
+        let outcome = match eval::condition_outcome(&expr, scenarios) {
+            Some(outcome) => outcome,
+            None => return,
+        };
+        let on_its_own = eval::truthy(&expr, &Value::Object(Default::default())).is_some();
+        let (severity, id, message) = match (outcome, on_its_own) {
+            (false, true) => (
+                Severity::Warning,
+                "always-false",
+                format!("`if` is always false, so this {} never runs", what),
+            ),
+            (false, false) => (
+                Severity::Warning,
+                "never-true",
+                format!(
+                    "`if` can't be true for this workflow's events and the known context \
+                     values, so this {} never runs",
+                    what
+                ),
+            ),
+            // `if: always()` and friends mean something even though they're true
+            (true, _) if has_status_check(&expr) => return,
+            (true, true) => (
+                Severity::Info,
+                "always-true",
+                "`if` is always true, so it can be left out".to_string(),
+            ),
+            (true, false) => (
+                Severity::Info,
+                "true-for-events",
+                "`if` is always true for this workflow's events and the known context \
+                 values, so it can be left out"
+                    .to_string(),
+            ),
+        };
+        diagnostics.push(
+            cx.diagnostic(self, severity, node, message)
+                .with_message_id(id)
+                .with_param("what", what),
+        );
+    }
+}
+
+impl Rule for ConstantCondition {
+    fn name(&self) -> &'static str {
+        "constant-condition"
+    }
+
+    fn description(&self) -> &'static str {
+        "`if:` conditions that always come out the same"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let scenarios = cx.scenarios();
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            if let Some(condition) = &job.run_if {
+                let node = key_path(&job_path(job_id), "if");
+                self.check_condition(cx, &scenarios, condition, &node, "job", &mut diagnostics);
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                if let Some(condition) = &step.run_if {
+                    let node = key_path(&step_path(job_id, i), "if");
+                    self.check_condition(
+                        cx,
+                        &scenarios,
+                        condition,
+                        &node,
+                        "step",
+                        &mut diagnostics,
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
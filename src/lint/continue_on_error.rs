@@ -0,0 +1,258 @@
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+
+use super::log_hygiene::{commands, normalize};
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// What a step does that makes tolerating its failure worse than usual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Test,
+    Scan,
+    Deploy,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Test => "test",
+            Kind::Scan => "security scan",
+            Kind::Deploy => "deploy",
+        }
+    }
+
+    fn severity(self) -> Severity {
+        match self {
+            Kind::Test => Severity::Warning,
+            Kind::Scan | Kind::Deploy => Severity::Error,
+        }
+    }
+
+    /// What goes unnoticed when a step of this kind fails.
+    fn hides(self) -> &'static str {
+        match self {
+            Kind::Test => "failing tests pass the build",
+            Kind::Scan => "its findings never fail the build",
+            Kind::Deploy => "a failed deploy looks like a successful one",
+        }
+    }
+}
+
+/// Commands, at the start of a `run` script's commands, by what they do.
+const COMMANDS: &[(&str, Kind)] = &[
+    ("./gradlew test", Kind::Test),
+    ("bundle exec rspec", Kind::Test),
+    ("cargo nextest", Kind::Test),
+    ("cargo test", Kind::Test),
+    ("ctest", Kind::Test),
+    ("dotnet test", Kind::Test),
+    ("go test", Kind::Test),
+    ("gradle test", Kind::Test),
+    ("jest", Kind::Test),
+    ("make check", Kind::Test),
+    ("make test", Kind::Test),
+    ("mvn test", Kind::Test),
+    ("mvn verify", Kind::Test),
+    ("npm run test", Kind::Test),
+    ("npm test", Kind::Test),
+    ("npx jest", Kind::Test),
+    ("pnpm test", Kind::Test),
+    ("pytest", Kind::Test),
+    ("python -m pytest", Kind::Test),
+    ("python3 -m pytest", Kind::Test),
+    ("rspec", Kind::Test),
+    ("tox", Kind::Test),
+    ("yarn test", Kind::Test),
+    ("bandit", Kind::Scan),
+    ("cargo audit", Kind::Scan),
+    ("cargo deny", Kind::Scan),
+    ("gitleaks", Kind::Scan),
+    ("gosec", Kind::Scan),
+    ("govulncheck", Kind::Scan),
+    ("grype", Kind::Scan),
+    ("npm audit", Kind::Scan),
+    ("pip-audit", Kind::Scan),
+    ("safety check", Kind::Scan),
+    ("semgrep", Kind::Scan),
+    ("snyk test", Kind::Scan),
+    ("trivy", Kind::Scan),
+    ("yarn audit", Kind::Scan),
+    ("aws s3 sync", Kind::Deploy),
+    ("cargo publish", Kind::Deploy),
+    ("docker push", Kind::Deploy),
+    ("firebase deploy", Kind::Deploy),
+    ("flyctl deploy", Kind::Deploy),
+    ("gcloud app deploy", Kind::Deploy),
+    ("gcloud run deploy", Kind::Deploy),
+    ("helm install", Kind::Deploy),
+    ("helm upgrade", Kind::Deploy),
+    ("kubectl apply", Kind::Deploy),
+    ("kubectl rollout", Kind::Deploy),
+    ("netlify deploy", Kind::Deploy),
+    ("npm publish", Kind::Deploy),
+    ("terraform apply", Kind::Deploy),
+    ("twine upload", Kind::Deploy),
+    ("vercel deploy", Kind::Deploy),
+];
+
+/// Actions by what they do, matched case-insensitively before the `@`.
+const ACTIONS: &[(&str, Kind)] = &[
+    ("actions/dependency-review-action", Kind::Scan),
+    ("anchore/scan-action", Kind::Scan),
+    ("aquasecurity/trivy-action", Kind::Scan),
+    ("bridgecrewio/checkov-action", Kind::Scan),
+    ("github/codeql-action/analyze", Kind::Scan),
+    ("gitleaks/gitleaks-action", Kind::Scan),
+    ("ossf/scorecard-action", Kind::Scan),
+    ("returntocorp/semgrep-action", Kind::Scan),
+    ("snyk/actions", Kind::Scan),
+    ("trufflesecurity/trufflehog", Kind::Scan),
+    ("actions/deploy-pages", Kind::Deploy),
+    (
+        "aws-actions/amazon-ecs-deploy-task-definition",
+        Kind::Deploy,
+    ),
+    ("azure/webapps-deploy", Kind::Deploy),
+    ("jamesives/github-pages-deploy-action", Kind::Deploy),
+    ("peaceiris/actions-gh-pages", Kind::Deploy),
+    ("pypa/gh-action-pypi-publish", Kind::Deploy),
+];
+
+/// Whether `continue-on-error` is `true`, as a boolean or the text. An
+/// expression like `${{ matrix.experimental }}` is left alone, since it
+/// tolerates failure only where it's meant to.
+fn tolerates(node: &Value) -> bool {
+    match node.get("continue-on-error") {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => s.trim() == "true",
+        _ => false,
+    }
+}
+
+/// What the step does, if it's a test, scan or deploy.
+fn kind(step: &Value) -> Option<Kind> {
+    if let Some(uses) = step.get("uses").and_then(Value::as_str) {
+        let action = uses.split('@').next().unwrap_or_default().to_lowercase();
+        let found = ACTIONS
+            .iter()
+            .find(|(known, _)| action == *known || action.starts_with(&format!("{}/", known)));
+        if let Some(&(_, kind)) = found {
+            return Some(kind);
+        }
+    }
+    let script = step.get("run").and_then(Value::as_str)?;
+    commands(script)
+        .iter()
+        .filter_map(|(_, command)| {
+            let first = normalize(command.split('|').next().unwrap_or_default());
+            COMMANDS
+                .iter()
+                .find(|(known, _)| first == *known || first.starts_with(&format!("{} ", known)))
+                .map(|&(_, kind)| kind)
+        })
+        .max()
+}
+
+/// Every `continue-on-error: true`, on jobs and on steps. Tolerating
+/// failure across the board hides real regressions, so it's reported as a
+/// warning when the step runs tests, and an error when it runs a security
+/// scan or a deploy.
+#[derive(Debug, Clone, Default)]
+pub struct ContinueOnError;
+
+impl Rule for ContinueOnError {
+    fn name(&self) -> &'static str {
+        "continue-on-error"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every `continue-on-error: true`, worse for tests, scans and deploys"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, _) in sorted_jobs(cx.workflow) {
+            let job = match cx.job_node(job_id) {
+                Some(job) => job,
+                None => continue,
+            };
+            let steps: Vec<&Value> = job
+                .get("steps")
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .collect();
+
+            if tolerates(job) {
+                let node = key_path(&job_path(job_id), "continue-on-error");
+                let worst = steps.iter().filter_map(|step| kind(step)).max();
+                let (severity, message) = match worst {
+                    Some(kind) => (
+                        kind.severity(),
+                        format!(
+                            "job `{}` has `continue-on-error: true` and runs a {} step, so \
+                             {}",
+                            job_id,
+                            kind.as_str(),
+                            kind.hides()
+                        ),
+                    ),
+                    None => (
+                        Severity::Info,
+                        format!(
+                            "job `{}` has `continue-on-error: true`, so the workflow passes \
+                             when it fails",
+                            job_id
+                        ),
+                    ),
+                };
+                let mut diagnostic = cx
+                    .diagnostic(self, severity, &node, message)
+                    .with_message_id("job")
+                    .with_param("job", job_id);
+                if let Some(kind) = worst {
+                    diagnostic = diagnostic.with_param("kind", kind.as_str());
+                }
+                diagnostics.push(diagnostic);
+            }
+
+            for (index, step) in steps.iter().enumerate() {
+                if !tolerates(step) {
+                    continue;
+                }
+                let node = key_path(&step_path(job_id, index), "continue-on-error");
+                let (severity, message) = match kind(step) {
+                    Some(kind) => (
+                        kind.severity(),
+                        format!(
+                            "this {} step has `continue-on-error: true`, so {}",
+                            kind.as_str(),
+                            kind.hides()
+                        ),
+                    ),
+                    None => (
+                        Severity::Info,
+                        "this step has `continue-on-error: true`, so the job goes on when it \
+                         fails"
+                            .to_string(),
+                    ),
+                };
+                let mut diagnostic = cx
+                    .diagnostic(self, severity, &node, message)
+                    .with_message_id("step")
+                    .with_param("job", job_id);
+                if let Some(kind) = kind(step) {
+                    diagnostic = diagnostic.with_param("kind", kind.as_str());
+                }
+                diagnostics.push(diagnostic);
+            }
+        }
+        diagnostics
+    }
+}
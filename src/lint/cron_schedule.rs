@@ -0,0 +1,83 @@
+use serde_yaml::Value;
+
+use crate::cron::{Cron, MIN_INTERVAL_MINUTES};
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::spans::{index_path, key_path};
+
+use super::{Context, Rule};
+
+/// `on.schedule` crons GitHub won't accept, which make it reject the
+/// workflow, and crons that fire more often than every 5 minutes, which
+/// GitHub quietly runs less often than written.
+#[derive(Debug, Clone, Default)]
+pub struct CronSchedule;
+
+impl Rule for CronSchedule {
+    fn name(&self) -> &'static str {
+        "cron-schedule"
+    }
+
+    fn description(&self) -> &'static str {
+        "Schedules that aren't valid cron, or that fire more often than every 5 minutes"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let schedules = match cx.document.get("on").and_then(|on| on.get("schedule")) {
+            Some(Value::Sequence(schedules)) => schedules,
+            _ => return Vec::new(),
+        };
+        let mut diagnostics = Vec::new();
+        for (i, schedule) in schedules.iter().enumerate() {
+            let cron = match schedule.get("cron").and_then(Value::as_str) {
+                Some(cron) => cron,
+                None => continue,
+            };
+            let path = key_path(&index_path("on.schedule", i), "cron");
+            match cron.parse::<Cron>() {
+                Err(error) => diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &path,
+                        format!("`{}` isn't a valid cron expression: {}", cron, error),
+                    )
+                    .with_message_id("invalid")
+                    .with_param("cron", cron),
+                ),
+                Ok(parsed) => {
+                    let interval = parsed.min_interval_minutes();
+                    if interval >= MIN_INTERVAL_MINUTES {
+                        continue;
+                    }
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &path,
+                            format!(
+                                "`{}` runs as little as {} minute{} apart, but GitHub runs \
+                                 schedules at most every {} minutes",
+                                cron,
+                                interval,
+                                if interval == 1 { "" } else { "s" },
+                                MIN_INTERVAL_MINUTES
+                            ),
+                        )
+                        .with_message_id("too-frequent")
+                        .with_param("cron", cron)
+                        .with_param("minutes", interval)
+                        .with_suggestion(
+                            Suggestion::new("run it every 5 minutes or less often")
+                                .with_example("- cron: '*/5 * * * *'"),
+                        ),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
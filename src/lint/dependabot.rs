@@ -0,0 +1,289 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr;
+use crate::spans::key_path;
+use crate::workflow::{Access, Job};
+
+use super::{
+    event_names, for_each_string, job_path, scripts, sorted_jobs, step_path, Context, Rule,
+};
+
+/// Whether the condition is about Dependabot, like
+/// `github.actor == 'dependabot[bot]'`.
+fn mentions_dependabot(condition: Option<&str>) -> bool {
+    condition.is_some_and(|c| c.to_lowercase().contains("dependabot"))
+}
+
+/// Whether the job only runs for Dependabot, or has a step that does.
+fn for_dependabot(job: &Job) -> bool {
+    mentions_dependabot(job.run_if.as_deref())
+        || job
+            .steps
+            .iter()
+            .any(|step| mentions_dependabot(step.run_if.as_deref()))
+}
+
+/// The `secrets.<name>` references in `s`, leaving out `GITHUB_TOKEN`.
+pub fn secrets(s: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for embedded in expr::find_expressions(s) {
+        let tokens = match expr::tokenize(embedded.body) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+        for reference in expr::references(&tokens) {
+            if let [context, name, ..] = reference.parts.as_slice() {
+                if context == "secrets" && !name.eq_ignore_ascii_case("GITHUB_TOKEN") {
+                    found.push(name.clone());
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Runs for Dependabot don't get the repository's Actions secrets, only the
+/// ones added as Dependabot secrets, so a job meant for Dependabot that reads
+/// secrets gets empty strings unless they're added there too.
+#[derive(Debug, Clone, Default)]
+pub struct DependabotSecrets;
+
+impl Rule for DependabotSecrets {
+    fn name(&self) -> &'static str {
+        "dependabot-secrets"
+    }
+
+    fn description(&self) -> &'static str {
+        "Jobs for Dependabot reading secrets it doesn't get"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let events = event_names(cx.document);
+        if !events.iter().any(|&e| e == "pull_request" || e == "push") {
+            return Vec::new();
+        }
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            if !for_dependabot(job) {
+                continue;
+            }
+            let node = match cx.job_node(job_id) {
+                Some(node) => node,
+                None => continue,
+            };
+            let mut seen = Vec::new();
+            for_each_string(node, &job_path(job_id), &mut |path, value| {
+                for secret in secrets(value) {
+                    if seen.contains(&secret) {
+                        continue;
+                    }
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            path,
+                            format!(
+                                "job `{}` runs for Dependabot, whose runs only get Dependabot \
+                             secrets; `{}` is empty unless it's also added as a Dependabot secret",
+                                job_id, secret
+                            ),
+                        )
+                        .with_param("job", job_id)
+                        .with_param("secret", &secret),
+                    );
+                    seen.push(secret);
+                }
+            });
+        }
+        diagnostics
+    }
+}
+
+/// The permission the job's `GITHUB_TOKEN` has for `scope`, from the job's
+/// `permissions` or else the workflow's. `None` when neither sets any.
+fn permission(cx: &Context, job: &Job, scope: &str) -> Option<Access> {
+    cx.workflow.permissions_for(job)?.access(scope)
+}
+
+/// What an auto-merge or auto-approve step does, and the scopes it needs.
+fn token_needs(
+    run: &str,
+    uses_fetch_metadata: bool,
+) -> Option<(&'static str, &'static [&'static str])> {
+    if run.contains("gh pr merge") {
+        Some(("merges the pull request", &["contents", "pull-requests"]))
+    } else if run.contains("gh pr review") {
+        Some(("reviews the pull request", &["pull-requests"]))
+    } else if run.contains("gh pr edit") {
+        Some(("edits the pull request", &["pull-requests"]))
+    } else if uses_fetch_metadata && run.contains("gh ") {
+        Some(("uses the gh CLI", &["pull-requests"]))
+    } else {
+        None
+    }
+}
+
+/// Auto-merge and auto-approve steps for Dependabot pull requests without the
+/// `permissions` to do it. The token Dependabot's runs get is read-only
+/// unless the workflow asks for more.
+#[derive(Debug, Clone, Default)]
+pub struct DependabotPermissions;
+
+impl Rule for DependabotPermissions {
+    fn name(&self) -> &'static str {
+        "dependabot-permissions"
+    }
+
+    fn description(&self) -> &'static str {
+        "Dependabot auto-merge and approve steps without the permissions to do it"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let fetch_metadata = job
+                .steps
+                .iter()
+                .any(|s| s.uses_action("dependabot/fetch-metadata"));
+            if !fetch_metadata && !for_dependabot(job) {
+                continue;
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                let run = match &step.run {
+                    Some(run) => run,
+                    None => continue,
+                };
+                let (what, scopes) = match token_needs(run, fetch_metadata) {
+                    Some(needs) => needs,
+                    None => continue,
+                };
+                let missing: Vec<String> = scopes
+                    .iter()
+                    .filter(|&&scope| permission(cx, job, scope) != Some(Access::Write))
+                    .map(|scope| format!("`{}: write`", scope))
+                    .collect();
+                if missing.is_empty() {
+                    continue;
+                }
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &key_path(&step_path(job_id, i), "run"),
+                        format!(
+                            "this step {}, but Dependabot's token is read-only unless \
+                         the job's `permissions` grant {}",
+                            what,
+                            missing.join(" and ")
+                        ),
+                    )
+                    .with_param("what", what)
+                    .with_param("permissions", missing.join(" and ")),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Properties of the pull request that whoever opened it controls.
+const UNTRUSTED: &[&str] = &[
+    "github.event.pull_request.body",
+    "github.event.pull_request.head.label",
+    "github.event.pull_request.head.ref",
+    "github.event.pull_request.title",
+    "github.head_ref",
+];
+
+/// Moving a Dependabot workflow to `pull_request_target` to get secrets and a
+/// writable token back, then checking out or interpolating the pull request.
+/// Checks on `github.actor` don't make that safe: the actor is whoever caused
+/// the run, and anyone can get Dependabot to act on a branch they control.
+#[derive(Debug, Clone, Default)]
+pub struct DependabotPullRequestTarget;
+
+impl Rule for DependabotPullRequestTarget {
+    fn name(&self) -> &'static str {
+        "dependabot-pull-request-target"
+    }
+
+    fn description(&self) -> &'static str {
+        "Dependabot workflows on `pull_request_target` that run the pull request"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        if !event_names(cx.document).contains(&"pull_request_target") {
+            return Vec::new();
+        }
+        let local_actions = cx.local_actions();
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            if !for_dependabot(job) {
+                continue;
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                let path = step_path(job_id, i);
+                if step.uses_action("actions/checkout") {
+                    let git_ref = step.with.get("ref").map(String::as_str).unwrap_or_default();
+                    if git_ref.contains("pull_request.head") || git_ref.contains("github.head_ref")
+                    {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Error,
+                                &key_path(&path, "with.ref"),
+                                format!(
+                                    "job `{}` checks out the pull request's code on \
+                                     `pull_request_target`, where it runs with secrets and a \
+                                     write token; a Dependabot check on the actor doesn't stop \
+                                     others from getting code run this way",
+                                    job_id
+                                ),
+                            )
+                            .with_message_id("checkout")
+                            .with_param("job", job_id),
+                        );
+                    }
+                }
+                for script in scripts(step, &path, local_actions) {
+                    for embedded in expr::find_expressions(script.text) {
+                        let body = embedded.body.trim();
+                        let untrusted = match UNTRUSTED.iter().find(|u| body.contains(*u)) {
+                            Some(untrusted) => untrusted,
+                            None => continue,
+                        };
+                        diagnostics.push(
+                            cx.diagnostic_in(
+                                self,
+                                Severity::Error,
+                                &script.node,
+                                embedded.offset,
+                                format!(
+                                    "`{}` is interpolated into {} on `pull_request_target`; \
+                                     pass it through `env` instead, since the pull request's \
+                                     author controls it",
+                                    untrusted, script.description
+                                ),
+                            )
+                            .with_message_id("interpolation")
+                            .with_param("value", untrusted)
+                            .with_param("script", &script.description),
+                        );
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+}
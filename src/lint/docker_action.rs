@@ -0,0 +1,182 @@
+use std::path::Path;
+
+use crate::action::Action;
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr;
+use crate::spans::{index_path, key_path};
+
+use super::{Analysis, Context, LocalAction, Rule};
+
+/// Problems in the metadata of local Docker actions: an `image` that can't
+/// be built or pulled, `args` using inputs the action doesn't declare, and
+/// entrypoint scripts missing from the action's directory. Only checked when
+/// linting inside a checkout.
+#[derive(Debug, Clone, Default)]
+pub struct DockerAction;
+
+/// Extensions that make an entrypoint look like a script rather than a
+/// program already in the image.
+const SCRIPT_EXTENSIONS: &[&str] = &["bash", "js", "ps1", "py", "rb", "sh"];
+
+/// The `inputs.<name>` references in `s` that `action` doesn't declare.
+fn undeclared_inputs(action: &Action, s: &str) -> Vec<String> {
+    let mut undeclared = Vec::new();
+    for embedded in expr::find_expressions(s) {
+        let tokens = match expr::tokenize(embedded.body) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+        for reference in expr::references(&tokens) {
+            if let [context, name, ..] = reference.parts.as_slice() {
+                if context == "inputs" && name != "*" && !action.inputs.contains_key(name) {
+                    undeclared.push(name.clone());
+                }
+            }
+        }
+    }
+    undeclared
+}
+
+/// Whether the script `entrypoint` names is in `dir`. Paths are inside the
+/// container, so `/entrypoint.sh` is usually `entrypoint.sh` copied in by
+/// the Dockerfile.
+fn script_exists(dir: &Path, entrypoint: &str) -> bool {
+    let relative = entrypoint.trim_start_matches('/');
+    let file_name = relative.rsplit('/').next().unwrap_or(relative);
+    dir.join(relative).is_file() || dir.join(file_name).is_file()
+}
+
+impl DockerAction {
+    fn check_action(&self, local: &LocalAction, diagnostics: &mut Vec<Diagnostic>) {
+        let runs = &local.action.runs;
+        let dockerfile = match runs.image.as_deref() {
+            None => {
+                diagnostics.push(local.diagnostic(
+                    self,
+                    Severity::Error,
+                    "runs",
+                    "Docker actions need a `runs.image`",
+                ));
+                false
+            }
+            Some(image) if image.starts_with("docker://") => {
+                if let Err(e) = image.parse::<ActionRef>() {
+                    diagnostics.push(local.diagnostic(
+                        self,
+                        Severity::Error,
+                        "runs.image",
+                        format!("invalid image `{}`: {}", image, e),
+                    ));
+                }
+                false
+            }
+            Some(image) => {
+                if !local.dir.join(image).is_file() {
+                    diagnostics.push(local.diagnostic(
+                        self,
+                        Severity::Error,
+                        "runs.image",
+                        format!(
+                            "`{}` doesn't exist in the action's directory; `image` is either \
+                             a Dockerfile there or `docker://<image>`",
+                            image
+                        ),
+                    ));
+                }
+                true
+            }
+        };
+
+        for (i, arg) in runs.args.iter().enumerate() {
+            for name in undeclared_inputs(&local.action, arg) {
+                diagnostics.push(local.diagnostic(
+                    self,
+                    Severity::Error,
+                    &index_path("runs.args", i),
+                    format!(
+                        "`args` uses input `{}`, which the action doesn't declare",
+                        name
+                    ),
+                ));
+            }
+        }
+        let mut env: Vec<_> = runs.env.iter().collect();
+        env.sort();
+        for (key, value) in env {
+            for name in undeclared_inputs(&local.action, value) {
+                diagnostics.push(local.diagnostic(
+                    self,
+                    Severity::Error,
+                    &key_path("runs.env", key),
+                    format!(
+                        "`{}` uses input `{}`, which the action doesn't declare",
+                        key, name
+                    ),
+                ));
+            }
+        }
+
+        // Scripts in a pulled image can't be checked
+        if !dockerfile {
+            return;
+        }
+        let entrypoints = [
+            ("entrypoint", &runs.entrypoint),
+            ("pre-entrypoint", &runs.pre_entrypoint),
+            ("post-entrypoint", &runs.post_entrypoint),
+        ];
+        for (key, entrypoint) in entrypoints {
+            let script = match entrypoint.as_deref() {
+                Some(script) => script.trim(),
+                None => continue,
+            };
+            let is_script = script
+                .rsplit_once('.')
+                .is_some_and(|(_, ext)| SCRIPT_EXTENSIONS.contains(&ext));
+            if !is_script || script.contains(char::is_whitespace) {
+                continue;
+            }
+            if !script_exists(&local.dir, script) {
+                diagnostics.push(local.diagnostic(
+                    self,
+                    Severity::Warning,
+                    &key_path("runs", key),
+                    format!(
+                        "`{}` script `{}` isn't in the action's directory, so the image \
+                         won't have it unless the Dockerfile fetches it",
+                        key, script
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+impl Rule for DockerAction {
+    fn name(&self) -> &'static str {
+        "docker-action"
+    }
+
+    fn description(&self) -> &'static str {
+        "Local Docker actions that can't be built or run as written"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn requires(&self) -> &'static [Analysis] {
+        &[Analysis::LocalActions]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for local in cx.local_actions().values() {
+            if local.action.runs.using == "docker" {
+                self.check_action(local, &mut diagnostics);
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,48 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::refactor;
+
+use super::{Context, Rule};
+
+/// Jobs that are copies of each other apart from a few values, like one
+/// per OS. They drift apart as only some copies get fixed;
+/// `workflow-linter to-matrix` can merge them into one matrix job.
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateJobs;
+
+impl Rule for DuplicateJobs {
+    fn name(&self) -> &'static str {
+        "duplicate-jobs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Jobs that are copies of each other apart from a few values"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        refactor::find_groups(cx.document)
+            .iter()
+            .map(|group| {
+                let jobs: Vec<String> = group.jobs.iter().map(|j| format!("`{}`", j)).collect();
+                let differ: Vec<String> =
+                    group.axes.iter().map(|a| format!("`{}`", a.node)).collect();
+                cx.diagnostic(
+                    self,
+                    Severity::Info,
+                    &group.node(),
+                    format!(
+                        "jobs {} only differ in {}; `workflow-linter to-matrix` can merge \
+                         them into one matrix job",
+                        jobs.join(", "),
+                        differ.join(", ")
+                    ),
+                )
+                .with_param("jobs", jobs.join(", "))
+                .with_param("differ", differ.join(", "))
+            })
+            .collect()
+    }
+}
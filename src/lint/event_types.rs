@@ -0,0 +1,45 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+
+use super::{Context, Rule};
+
+/// Settings of an event GitHub won't accept, like a `types` entry that
+/// isn't one of the event's activities, such as `watch` with
+/// `types: [starred]` rather than `started`, both `branches` and
+/// `branches-ignore`, or a `choice` input without `options`. GitHub rejects
+/// the workflow, so it never runs.
+#[derive(Debug, Clone, Default)]
+pub struct EventTypes;
+
+impl Rule for EventTypes {
+    fn name(&self) -> &'static str {
+        "event-types"
+    }
+
+    fn description(&self) -> &'static str {
+        "Event settings GitHub won't accept, like an activity type the event doesn't have"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (name, event) in &cx.workflow.on {
+            let node = key_path("on", name);
+            for problem in event.problems() {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &node,
+                        format!("the `{}` event's settings are invalid: {}", name, problem),
+                    )
+                    .with_param("event", name),
+                );
+            }
+        }
+        diagnostics
+    }
+}
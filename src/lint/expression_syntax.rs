@@ -0,0 +1,139 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr::{self, ProblemKind, CONTEXTS, FUNCTIONS};
+
+use super::unknown_key::edit_distance;
+use super::{for_each_string, Context, Rule};
+
+/// Expressions GitHub can't evaluate: ones that don't parse, like
+/// `${{ github.ref = 'main' }}`, and ones calling a function or naming a
+/// context that doesn't exist, which GitHub rejects the workflow for. A
+/// property a context doesn't have, like `github.evnet_name`, is only
+/// `null` when the workflow runs, so it's a warning.
+#[derive(Debug, Clone, Default)]
+pub struct ExpressionSyntax;
+
+/// Whether `path` is the `if` of a job or a step, which is an expression
+/// even without `${{ }}`.
+fn is_condition(path: &str) -> bool {
+    match path.strip_suffix(".if") {
+        Some(parent) => {
+            parent.starts_with("jobs.")
+                && (parent.matches('.').count() == 1 || parent.ends_with(']'))
+        }
+        None => false,
+    }
+}
+
+/// The name in `known` that `name` was most likely meant to be, ignoring
+/// case.
+fn closest<'k>(name: &str, known: impl Iterator<Item = &'k str>) -> Option<&'k str> {
+    let name = name.to_ascii_lowercase();
+    known
+        .map(|k| (edit_distance(&name, &k.to_ascii_lowercase()), k))
+        .filter(|&(distance, k)| distance <= 2 && distance < k.len())
+        .min()
+        .map(|(_, k)| k)
+}
+
+fn did_you_mean(message: String, suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!("{}; did you mean `{}`?", message, suggestion),
+        None => message,
+    }
+}
+
+impl ExpressionSyntax {
+    fn problem(&self, cx: &Context, path: &str, offset: usize, kind: ProblemKind) -> Diagnostic {
+        let (severity, id, message) = match &kind {
+            ProblemKind::Syntax(message) => (
+                Severity::Error,
+                "syntax",
+                format!("this expression doesn't parse: {}", message),
+            ),
+            ProblemKind::UnknownFunction(name) => (
+                Severity::Error,
+                "unknown-function",
+                did_you_mean(
+                    format!("`{}` isn't a function", name),
+                    closest(name, FUNCTIONS.iter().map(|&(f, ..)| f)),
+                ),
+            ),
+            ProblemKind::Arguments { function, given } => {
+                let expected = match expr::arity(function) {
+                    Some((min, Some(max))) if min == max => format!("{}", min),
+                    Some((min, Some(max))) => format!("{} to {}", min, max),
+                    Some((min, None)) => format!("at least {}", min),
+                    None => "no".to_string(),
+                };
+                (
+                    Severity::Error,
+                    "arguments",
+                    format!(
+                        "`{}()` takes {} argument{}, but is given {}",
+                        function,
+                        expected,
+                        if expected == "1" { "" } else { "s" },
+                        given
+                    ),
+                )
+            }
+            ProblemKind::UnknownContext(name) => (
+                Severity::Error,
+                "unknown-context",
+                did_you_mean(
+                    format!("`{}` isn't a context", name),
+                    closest(name, CONTEXTS.iter().copied()),
+                ),
+            ),
+            ProblemKind::UnknownProperty { context, property } => (
+                Severity::Warning,
+                "unknown-property",
+                did_you_mean(
+                    format!(
+                        "the `{}` context has no `{}`, so this is always null",
+                        context, property
+                    ),
+                    expr::properties(context)
+                        .and_then(|known| closest(property, known.iter().copied())),
+                ),
+            ),
+        };
+        cx.diagnostic_in(self, severity, path, offset, message)
+            .with_message_id(id)
+    }
+}
+
+impl Rule for ExpressionSyntax {
+    fn name(&self) -> &'static str {
+        "expression-syntax"
+    }
+
+    fn description(&self) -> &'static str {
+        "Expressions that don't parse, or use a function, context or property that doesn't exist"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for_each_string(cx.document, "", &mut |path, value| {
+            let bodies: Vec<(usize, &str)> = if is_condition(path) && !value.contains("${{") {
+                vec![(0, value)]
+            } else {
+                expr::find_expressions(value)
+                    .iter()
+                    .map(|e| (e.body_offset, e.body))
+                    .collect()
+            };
+            for (start, body) in bodies {
+                for problem in expr::validate(body) {
+                    let offset = start + problem.offset;
+                    diagnostics.push(self.problem(cx, path, offset, problem.kind));
+                }
+            }
+        });
+        diagnostics
+    }
+}
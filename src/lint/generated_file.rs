@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::provenance::{self, content_hash};
+
+use super::{Context, Rule};
+
+/// Generated workflows that no longer match what they were generated from:
+/// edited by hand, so the next regeneration throws the edit away, or left
+/// behind after their source changed.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratedFile;
+
+impl Rule for GeneratedFile {
+    fn name(&self) -> &'static str {
+        "generated-file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Generated workflows that no longer match what they're generated from"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let provenance = match provenance::parse(cx.source) {
+            Some(provenance) => provenance,
+            None => return Vec::new(),
+        };
+        let diagnostic = |severity, id: &str, message: String| {
+            cx.diagnostic(self, severity, "", message)
+                .at(provenance.line, 1)
+                .with_message_id(id)
+                .with_param("source", &provenance.source)
+        };
+        let regenerate = match cx.path {
+            Some(path) => format!(
+                "`workflow-linter expand {} -o {}`",
+                provenance.source,
+                path.display()
+            ),
+            None => "`workflow-linter expand`".to_string(),
+        };
+
+        let mut diagnostics = Vec::new();
+        if content_hash(&cx.source[provenance.body_offset..]) != provenance.hash {
+            diagnostics.push(diagnostic(
+                Severity::Error,
+                "edited",
+                format!(
+                    "this file was edited after it was generated; make the change in `{}` \
+                     and run {}",
+                    provenance.source, regenerate
+                ),
+            ));
+        }
+
+        let source_path = match cx.repo_root {
+            Some(root) => root.join(&provenance.source),
+            None => PathBuf::from(&provenance.source),
+        };
+        match std::fs::read_to_string(&source_path) {
+            Ok(source) if content_hash(&source) != provenance.source_hash => {
+                diagnostics.push(diagnostic(
+                    Severity::Error,
+                    "source-changed",
+                    format!(
+                        "`{}` has changed since this file was generated; run {}",
+                        provenance.source, regenerate
+                    ),
+                ));
+            }
+            Ok(_) => {}
+            Err(_) => diagnostics.push(diagnostic(
+                Severity::Warning,
+                "source-missing",
+                format!(
+                    "this file was generated from `{}`, which doesn't exist",
+                    provenance.source
+                ),
+            )),
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,150 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr::{self, TokenKind};
+use crate::glob::{self, Glob};
+
+use super::{for_each_string, Context, Rule};
+
+/// Problems with the patterns given to `hashFiles()`. A pattern that matches
+/// nothing makes `hashFiles()` return an empty string, so a cache key built
+/// from it never changes and the cache goes stale without anyone noticing.
+#[derive(Debug, Clone, Default)]
+pub struct HashFiles;
+
+/// Where each `hashFiles(...)` call in `s` starts, and its string
+/// arguments. Calls with arguments that aren't plain strings are skipped.
+fn hash_files_calls(s: &str) -> Vec<(usize, Vec<String>)> {
+    let mut calls = Vec::new();
+    for embedded in expr::find_expressions(s) {
+        let tokens = match expr::tokenize(embedded.body) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+        let mut i = 0;
+        while i + 1 < tokens.len() {
+            let is_call = matches!(&tokens[i].kind, TokenKind::Ident(name) if name.eq_ignore_ascii_case("hashFiles"))
+                && tokens[i + 1].kind == TokenKind::LParen;
+            i += 1;
+            if !is_call {
+                continue;
+            }
+
+            let mut args = Vec::new();
+            let mut literal = true;
+            for token in &tokens[i + 1..] {
+                match &token.kind {
+                    TokenKind::String(arg) => args.push(arg.clone()),
+                    TokenKind::Comma => {}
+                    TokenKind::RParen => break,
+                    _ => {
+                        literal = false;
+                        break;
+                    }
+                }
+            }
+            if literal {
+                calls.push((embedded.body_offset + tokens[i - 1].offset, args));
+            }
+        }
+    }
+    calls
+}
+
+impl Rule for HashFiles {
+    fn name(&self) -> &'static str {
+        "hash-files"
+    }
+
+    fn description(&self) -> &'static str {
+        "`hashFiles()` patterns that are malformed or match nothing"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for_each_string(cx.document, "", &mut |path, value| {
+            for (offset, args) in hash_files_calls(value) {
+                if args.is_empty() {
+                    diagnostics.push(
+                        cx.diagnostic_in(
+                            self,
+                            Severity::Error,
+                            path,
+                            offset,
+                            "hashFiles() needs at least one pattern",
+                        )
+                        .with_message_id("no-patterns"),
+                    );
+                    continue;
+                }
+
+                let mut globs = Vec::new();
+                for arg in &args {
+                    match Glob::parse(arg) {
+                        Ok(glob) => globs.push(glob),
+                        Err(e) => diagnostics.push(
+                            cx.diagnostic_in(
+                                self,
+                                Severity::Error,
+                                path,
+                                offset,
+                                format!("invalid hashFiles() pattern `{}`: {}", arg, e),
+                            )
+                            .with_message_id("invalid-pattern")
+                            .with_param("pattern", arg)
+                            .with_param("error", e),
+                        ),
+                    }
+                }
+                if globs.len() != args.len() {
+                    continue;
+                }
+                if globs.iter().all(|g| g.negated) {
+                    diagnostics.push(
+                        cx.diagnostic_in(
+                            self,
+                            Severity::Warning,
+                            path,
+                            offset,
+                            "hashFiles() only has negated patterns, so it matches nothing",
+                        )
+                        .with_message_id("only-negated"),
+                    );
+                    continue;
+                }
+
+                // Absolute paths are outside the repository
+                if cx.repo_root.is_none() || args.iter().any(|a| a.starts_with(['/', '$'])) {
+                    continue;
+                }
+                // Only listed if there's a call to check
+                let files = cx.repo_files();
+                if !files.iter().any(|f| glob::matches_all(&globs, f)) {
+                    let patterns = args
+                        .iter()
+                        .map(|a| format!("'{}'", a))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    diagnostics.push(
+                        cx.diagnostic_in(
+                            self,
+                            Severity::Warning,
+                            path,
+                            offset,
+                            format!(
+                                "hashFiles({}) matches no files in the repository, \
+                                 so it will return an empty string",
+                                patterns
+                            ),
+                        )
+                        .with_message_id("no-matches")
+                        .with_param("patterns", patterns),
+                    );
+                }
+            }
+        });
+        diagnostics
+    }
+}
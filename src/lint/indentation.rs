@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use crate::diagnostics::{Diagnostic, Location, Severity};
+
+use super::{Context, Rule};
+
+/// Indentation that doesn't step by the same number of spaces as the rest
+/// of the file. It's valid YAML, but it makes nesting hard to follow and
+/// is often a key that ended up under the wrong parent.
+#[derive(Debug, Clone, Default)]
+pub struct Indentation;
+
+/// Where lines are indented with tabs, which YAML doesn't allow. The serde
+/// error for them doesn't say so, so they're found separately.
+pub fn tab_indentation(source: &str) -> Vec<Location> {
+    source
+        .lines()
+        .enumerate()
+        // Tabs on blank lines, or after a block scalar's indentation, are fine
+        .filter(|(_, line)| line.starts_with('\t') && !line.trim().is_empty())
+        .map(|(i, _)| Location {
+            line: i + 1,
+            column: 1,
+        })
+        .collect()
+}
+
+/// The text of a line without a trailing comment. Good enough for telling
+/// whether it ends with `:`; `#` inside quotes is rare in keys.
+fn without_comment(line: &str) -> &str {
+    match line.find(" #") {
+        Some(i) => &line[..i],
+        None => line,
+    }
+    .trim_end()
+}
+
+/// Whether a line starts a block scalar, like `run: |` or `- >-`.
+fn starts_block_scalar(content: &str) -> bool {
+    let last = content.rsplit([' ', ':']).next().unwrap_or_default();
+    (last.starts_with('|') || last.starts_with('>'))
+        && last[1..]
+            .chars()
+            .all(|c| c == '-' || c == '+' || c.is_ascii_digit())
+}
+
+/// How far in each line's own content starts: past the indentation, and
+/// past any `- ` that makes it a list item.
+fn content_indent(line: &str) -> usize {
+    let indent = line.len() - line.trim_start().len();
+    let mut rest = &line[indent..];
+    let mut column = indent;
+    while let Some(item) = rest.strip_prefix('-') {
+        let after = item.len() - item.trim_start_matches(' ').len();
+        if after == 0 {
+            break;
+        }
+        column += 1 + after;
+        rest = &item[after..];
+    }
+    column
+}
+
+/// The lines nested under a mapping key, with how much deeper each is than
+/// its parent's content.
+fn steps(source: &str) -> Vec<(Location, usize)> {
+    let mut steps = Vec::new();
+    let mut parent: Option<(usize, bool)> = None;
+    let mut block_scalar: Option<usize> = None;
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || line.starts_with('\t') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if let Some(scalar_indent) = block_scalar {
+            if indent > scalar_indent {
+                continue;
+            }
+            block_scalar = None;
+        }
+
+        if let Some((parent_content, opens_mapping)) = parent {
+            if opens_mapping && indent > parent_content {
+                let location = Location {
+                    line: i + 1,
+                    column: indent + 1,
+                };
+                steps.push((location, indent - parent_content));
+            }
+        }
+
+        let content = without_comment(line);
+        if starts_block_scalar(content) {
+            block_scalar = Some(indent);
+        }
+        parent = Some((content_indent(line), content.ends_with(':')));
+    }
+    steps
+}
+
+impl Rule for Indentation {
+    fn name(&self) -> &'static str {
+        "indentation"
+    }
+
+    fn description(&self) -> &'static str {
+        "Indentation that doesn't step like the rest of the file"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let steps = steps(cx.source);
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for &(_, width) in &steps {
+            *counts.entry(width).or_default() += 1;
+        }
+        // The most common width, or the narrowest of those tied
+        let usual = match counts
+            .iter()
+            .max_by_key(|&(width, count)| (count, -(*width as i64)))
+        {
+            Some((&width, _)) => width,
+            None => return Vec::new(),
+        };
+
+        steps
+            .into_iter()
+            .filter(|&(_, width)| width != usual)
+            .map(|(location, width)| {
+                let message = format!(
+                    "indented {} spaces deeper than the line it's under, but the rest of the \
+                     file uses {}",
+                    width, usual
+                );
+                cx.diagnostic(self, Severity::Warning, "", message)
+                    .at(location.line, location.column)
+                    .with_param("width", width)
+                    .with_param("usual", usual)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,36 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// `uses` values that GitHub won't be able to resolve, like a missing `@ref`
+/// or a malformed image tag. These otherwise only fail once the job starts.
+#[derive(Debug, Clone, Default)]
+pub struct InvalidUses;
+
+impl Rule for InvalidUses {
+    fn name(&self) -> &'static str {
+        "invalid-uses"
+    }
+
+    fn description(&self) -> &'static str {
+        "`uses` values GitHub won't be able to resolve"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                if let Some(Err(e)) = step.action() {
+                    let path = key_path(&step_path(job_id, i), "uses");
+                    diagnostics.push(cx.diagnostic(self, Severity::Error, &path, e.to_string()));
+                }
+            }
+        }
+        diagnostics
+    }
+}
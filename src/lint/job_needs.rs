@@ -0,0 +1,75 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::graph::JobGraph;
+use crate::spans::{index_path, key_path};
+
+use super::{job_path, Context, Rule};
+
+/// Jobs that need a job the workflow doesn't have, and jobs that need each
+/// other in a cycle. GitHub can't work out when to run them, so it rejects
+/// the workflow.
+#[derive(Debug, Clone, Default)]
+pub struct JobNeeds;
+
+impl Rule for JobNeeds {
+    fn name(&self) -> &'static str {
+        "job-needs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Jobs that need a job that doesn't exist, or that need each other in a cycle"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let graph = JobGraph::new(cx.workflow);
+        let mut diagnostics = Vec::new();
+        for dangling in graph.dangling() {
+            let needs = key_path(&job_path(dangling.job), "needs");
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    &index_path(&needs, dangling.index),
+                    format!(
+                        "`{}` needs `{}`, but there's no job with that id",
+                        dangling.job, dangling.needs
+                    ),
+                )
+                .with_message_id("unknown-job")
+                .with_param("job", dangling.job)
+                .with_param("needs", dangling.needs),
+            );
+        }
+        for cycle in graph.cycles() {
+            let first = cycle[0];
+            let message = match cycle.len() {
+                1 => format!("`{}` needs itself, so it can never start", first),
+                _ => {
+                    let path: Vec<String> = cycle
+                        .iter()
+                        .chain(std::iter::once(&first))
+                        .map(|job| format!("`{}`", job))
+                        .collect();
+                    format!(
+                        "these jobs need each other in a cycle, so none of them can start: {}",
+                        path.join(" needs ")
+                    )
+                }
+            };
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    &key_path(&job_path(first), "needs"),
+                    message,
+                )
+                .with_message_id("cycle")
+                .with_param("jobs", cycle.join(", ")),
+            );
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,448 @@
+//! Rules for jobs that deploy to Kubernetes with kubectl, Helm or
+//! Kustomize. They're off by default and turned on together with
+//! `--enable kubernetes`.
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+use crate::workflow::{Env, Job, Step, Workflow};
+
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// The group the rules here are enabled by.
+pub const GROUP: &str = "kubernetes";
+
+/// Commands that change what runs in a cluster.
+const DEPLOY_COMMANDS: &[&str] = &[
+    "kubectl apply",
+    "kubectl create",
+    "kubectl replace",
+    "kubectl patch",
+    "kubectl set image",
+    "kubectl rollout restart",
+    "helm install",
+    "helm upgrade",
+    "kustomize build",
+];
+
+/// Actions that deploy to a cluster.
+const DEPLOY_ACTIONS: &[&str] = &["azure/k8s-deploy", "deliverybot/helm"];
+
+/// Actions that set the cluster a job talks to.
+const CONTEXT_ACTIONS: &[&str] = &[
+    "azure/k8s-set-context",
+    "azure/setup-kubectl",
+    "google-github-actions/get-gke-credentials",
+];
+
+/// Inputs of those actions that name the cluster or context.
+const CONTEXT_INPUTS: &[&str] = &["context", "cluster_name", "cluster-name"];
+
+/// Environment variables that hold a kubeconfig or a cluster token.
+const CREDENTIAL_VARIABLES: &[&str] = &[
+    "KUBECONFIG_DATA",
+    "KUBE_CONFIG",
+    "KUBE_CONFIG_DATA",
+    "KUBE_TOKEN",
+    "K8S_TOKEN",
+    "HELM_REGISTRY_PASSWORD",
+];
+
+/// Words in a context, namespace or environment name that mean production.
+const PRODUCTION_WORDS: &[&str] = &["prod", "production", "prd", "live"];
+
+/// Lines of a kubeconfig that carry credentials.
+const KUBECONFIG_SECRETS: &[&str] = &[
+    "client-key-data:",
+    "client-certificate-data:",
+    "token:",
+    "password:",
+];
+
+fn deploys(step: &Step) -> bool {
+    DEPLOY_ACTIONS.iter().any(|action| step.uses_action(action))
+        || step
+            .run
+            .as_deref()
+            .is_some_and(|run| DEPLOY_COMMANDS.iter().any(|c| run.contains(c)))
+}
+
+fn talks_to_cluster(job: &Job) -> bool {
+    job.steps.iter().any(|step| {
+        deploys(step)
+            || CONTEXT_ACTIONS
+                .iter()
+                .any(|action| step.uses_action(action))
+            || step
+                .run
+                .as_deref()
+                .is_some_and(|run| run.contains("kubectl ") || run.contains("helm "))
+    })
+}
+
+/// Whether `value` is written out rather than coming from an expression or
+/// a shell variable.
+fn is_literal(value: &str) -> bool {
+    let value = value.trim().trim_matches(['"', '\'']);
+    !value.is_empty() && !value.contains("${{") && !value.starts_with('$')
+}
+
+fn is_production(name: &str) -> bool {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| PRODUCTION_WORDS.contains(&word))
+}
+
+/// The words of a command line, keeping each `${{ }}` in one piece.
+fn words(line: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    for (i, c) in line.char_indices() {
+        if line[i..].starts_with("${{") {
+            depth += 1;
+        } else if depth > 0 && line[i..].starts_with("}}") {
+            depth -= 1;
+        }
+        if c.is_whitespace() && depth == 0 {
+            if let Some(s) = start.take() {
+                words.push(&line[s..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    words.extend(start.map(|s| &line[s..]));
+    words
+}
+
+/// The value after `flag` in a command, as `--flag value` or `--flag=value`.
+fn flag_value<'r>(run: &'r str, flag: &str) -> Vec<&'r str> {
+    run.match_indices(flag)
+        .filter_map(|(i, _)| {
+            let rest = &run[i + flag.len()..];
+            let rest = rest.strip_prefix('=').or_else(|| rest.strip_prefix(' '))?;
+            words(rest).into_iter().next()
+        })
+        .collect()
+}
+
+/// Kubeconfigs and cluster tokens written into the workflow rather than
+/// coming from `secrets` or an OIDC login.
+#[derive(Debug, Clone, Default)]
+pub struct KubernetesCredentials;
+
+impl KubernetesCredentials {
+    fn literal(&self, cx: &Context, path: &str, what: &str) -> Diagnostic {
+        let message = format!(
+            "{} is written into the workflow, where anyone who can read the repository sees \
+             it; use a secret or a cloud login with OIDC",
+            what
+        );
+        cx.diagnostic(self, Severity::Error, path, message)
+            .with_message_id("literal-credential")
+            .with_param("credential", what)
+    }
+
+    fn check_env(&self, cx: &Context, env: &Env, node: &str, diagnostics: &mut Vec<Diagnostic>) {
+        let mut names: Vec<&String> = env.keys().collect();
+        names.sort();
+        for name in names {
+            if CREDENTIAL_VARIABLES.contains(&name.as_str()) && is_literal(&env[name]) {
+                let path = key_path(&key_path(node, "env"), name);
+                diagnostics.push(self.literal(cx, &path, &format!("`{}`", name)));
+            }
+        }
+    }
+}
+
+impl Rule for KubernetesCredentials {
+    fn name(&self) -> &'static str {
+        "kubernetes-credentials"
+    }
+
+    fn description(&self) -> &'static str {
+        "Kubeconfigs and cluster tokens written into the workflow"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let jobs: Vec<_> = sorted_jobs(cx.workflow)
+            .into_iter()
+            .filter(|(_, job)| talks_to_cluster(job))
+            .collect();
+        if jobs.is_empty() {
+            return diagnostics;
+        }
+        if let Some(env) = &cx.workflow.env {
+            self.check_env(cx, env, "", &mut diagnostics);
+        }
+        for (job_id, job) in jobs {
+            self.check_env(cx, &job.env, &job_path(job_id), &mut diagnostics);
+            for (i, step) in job.steps.iter().enumerate() {
+                let node = step_path(job_id, i);
+                self.check_env(cx, &step.env, &node, &mut diagnostics);
+                if step.uses_action("azure/k8s-set-context") {
+                    if let Some(kubeconfig) = step.with.get("kubeconfig") {
+                        if is_literal(kubeconfig) {
+                            let path = key_path(&key_path(&node, "with"), "kubeconfig");
+                            diagnostics.push(self.literal(cx, &path, "the kubeconfig"));
+                        }
+                    }
+                }
+                let run = match step.run.as_deref() {
+                    Some(run) => run,
+                    None => continue,
+                };
+                let path = key_path(&node, "run");
+                let token = flag_value(run, "--token")
+                    .into_iter()
+                    .chain(flag_value(run, "--password"))
+                    .any(is_literal);
+                if token {
+                    diagnostics.push(self.literal(cx, &path, "a cluster token or password"));
+                }
+                // A kubeconfig written out by the script
+                let inline = run.lines().any(|line| {
+                    let line = line.trim();
+                    KUBECONFIG_SECRETS
+                        .iter()
+                        .any(|key| line.strip_prefix(key).is_some_and(is_literal))
+                });
+                if inline && run.contains("apiVersion") {
+                    diagnostics.push(self.literal(cx, &path, "a kubeconfig"));
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Deploys to a production cluster, context or namespace from a job with no
+/// `environment`, which is where required reviewers and branch rules hold
+/// a deploy back.
+#[derive(Debug, Clone, Default)]
+pub struct KubernetesProductionEnvironment;
+
+/// The production cluster, context or namespace `step` deploys to, if it
+/// names one.
+fn production_target(
+    workflow: &Workflow,
+    job: &Job,
+    steps: &[Step],
+    step: &Step,
+) -> Option<String> {
+    let mut names: Vec<String> = Vec::new();
+    if let Some(run) = step.run.as_deref() {
+        for flag in &["--context", "--kube-context", "--namespace", "-n"] {
+            names.extend(flag_value(run, flag).into_iter().map(str::to_string));
+        }
+    }
+    for name in &[
+        "KUBE_CONTEXT",
+        "KUBECTL_CONTEXT",
+        "HELM_KUBECONTEXT",
+        "NAMESPACE",
+    ] {
+        names.extend(workflow.env_value(job, step, name).map(str::to_string));
+    }
+    names.extend(step.with.get("namespace").cloned());
+    // Contexts chosen by earlier steps
+    for earlier in steps {
+        if let Some(run) = earlier.run.as_deref() {
+            names.extend(
+                flag_value(run, "use-context")
+                    .into_iter()
+                    .map(str::to_string),
+            );
+        }
+        if CONTEXT_ACTIONS
+            .iter()
+            .any(|action| earlier.uses_action(action))
+        {
+            names.extend(
+                CONTEXT_INPUTS
+                    .iter()
+                    .filter_map(|input| earlier.with.get(*input).cloned()),
+            );
+        }
+        if std::ptr::eq(earlier, step) {
+            break;
+        }
+    }
+    names.into_iter().find(|name| is_production(name))
+}
+
+impl Rule for KubernetesProductionEnvironment {
+    fn name(&self) -> &'static str {
+        "kubernetes-production-environment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Production deploys from jobs without an `environment`"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let has_environment = cx
+                .job_node(job_id)
+                .and_then(|job| job.get("environment"))
+                .is_some();
+            if has_environment {
+                continue;
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                if !deploys(step) {
+                    continue;
+                }
+                let target = match production_target(cx.workflow, job, &job.steps, step) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                let message = format!(
+                    "this step deploys to `{}`, which looks like production, from a job with \
+                     no `environment`; add one so deploys can be reviewed",
+                    target
+                );
+                diagnostics.push(
+                    cx.diagnostic(self, Severity::Warning, &step_path(job_id, i), message)
+                        .with_param("target", target),
+                );
+                break;
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Images deployed by a tag written into the workflow, like `app:latest` or
+/// `app:1.2`. Tags can be pushed over, so what runs isn't what was tested;
+/// deploy the digest, or the tag an earlier step or job built.
+#[derive(Debug, Clone, Default)]
+pub struct KubernetesImageTags;
+
+/// The images a step deploys, as written.
+fn deployed_images(step: &Step) -> Vec<String> {
+    let mut images = Vec::new();
+    if step.uses_action("azure/k8s-deploy") {
+        if let Some(list) = step.with.get("images") {
+            images.extend(
+                list.lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_string),
+            );
+        }
+    }
+    let run = match step.run.as_deref() {
+        Some(run) => run,
+        None => return images,
+    };
+    for line in run.lines() {
+        let words = words(line);
+        let joined = words.join(" ");
+        // `kubectl set image deploy/app app=registry/app:tag`
+        // `kustomize edit set image app=registry/app:tag`
+        let set_image = [
+            "kubectl set image",
+            "oc set image",
+            "kustomize edit set image",
+        ];
+        if set_image.iter().any(|command| joined.contains(command)) {
+            images.extend(
+                words
+                    .iter()
+                    .filter(|w| w.contains('=') && !w.starts_with('-'))
+                    .filter_map(|w| w.split_once('=').map(|(_, image)| image.to_string())),
+            );
+        }
+        // `helm upgrade ... --set image.tag=1.2`
+        if joined.contains("helm ") {
+            for value in flag_value(line, "--set") {
+                for setting in value.split(',') {
+                    if let Some((key, tag)) = setting.split_once('=') {
+                        if key.ends_with(".tag") || key == "tag" {
+                            images.push(format!("{}={}", key, tag));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    images
+}
+
+/// Whether `image` pins what it deploys.
+fn is_pinned(image: &str) -> bool {
+    let image = image.trim_matches(['"', '\'']);
+    image.contains("@sha256:")
+        || image.contains("steps.")
+        || image.contains("needs.")
+        || image.starts_with('$')
+        || (image.contains('$') && !image.contains("${{"))
+}
+
+impl Rule for KubernetesImageTags {
+    fn name(&self) -> &'static str {
+        "kubernetes-image-tags"
+    }
+
+    fn description(&self) -> &'static str {
+        "Images deployed by a tag that can be pushed over"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                for image in deployed_images(step) {
+                    if is_pinned(&image) {
+                        continue;
+                    }
+                    let message = format!(
+                        "this step deploys `{}` by a tag, which can be pushed over; deploy \
+                         the digest, or the tag an earlier step built",
+                        image
+                    );
+                    diagnostics.push(
+                        cx.diagnostic(self, Severity::Warning, &step_path(job_id, i), message)
+                            .with_param("image", &image),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
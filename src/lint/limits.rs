@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::matrix;
+
+use super::{job_path, key_path, sorted_jobs, Context, Rule};
+
+/// Past this GitHub stops serving a file's contents from its API, and a
+/// workflow this large fails to load.
+pub const MAX_FILE_BYTES: usize = 1024 * 1024;
+
+/// The most jobs a single matrix can make.
+pub const MAX_MATRIX_JOBS: usize = 256;
+
+/// How deeply reusable workflows can call each other, counting the caller.
+pub const MAX_NESTING: usize = 10;
+
+/// The most distinct reusable workflows a run can call, nested calls
+/// included.
+pub const MAX_REUSABLE_WORKFLOWS: usize = 50;
+
+/// The reusable workflows the jobs of `document` call, as written.
+fn calls(document: &Value) -> Vec<(String, String)> {
+    let jobs = match document.get("jobs").and_then(Value::as_mapping) {
+        Some(jobs) => jobs,
+        None => return Vec::new(),
+    };
+    let mut calls: Vec<(String, String)> = jobs
+        .iter()
+        .filter_map(|(id, job)| {
+            let uses = job.get("uses")?.as_str()?;
+            Some((id.as_str()?.to_string(), uses.to_string()))
+        })
+        .collect();
+    calls.sort();
+    calls
+}
+
+/// The file of a reusable workflow in the same repository.
+fn local_file(root: Option<&Path>, uses: &str) -> Option<PathBuf> {
+    let relative = uses.strip_prefix("./")?;
+    Some(match root {
+        Some(root) => root.join(relative),
+        None => Path::new(relative).to_path_buf(),
+    })
+}
+
+/// Walks the tree of reusable workflow calls, as far as it's in the
+/// repository.
+struct Calls<'a> {
+    root: Option<&'a Path>,
+    /// Every workflow called, as written in `uses`.
+    seen: BTreeSet<String>,
+    /// The longest chain of calls found, starting with the caller.
+    deepest: Vec<String>,
+}
+
+impl<'a> Calls<'a> {
+    fn walk(&mut self, document: &Value, chain: &mut Vec<String>) {
+        if chain.len() > self.deepest.len() {
+            self.deepest = chain.clone();
+        }
+        // Far enough to say how deep a chain that's too deep is
+        if chain.len() > 2 * MAX_NESTING {
+            return;
+        }
+        for (_, uses) in calls(document) {
+            self.seen.insert(uses.clone());
+            // A loop is an error of its own, and would never finish
+            if chain.contains(&uses) {
+                continue;
+            }
+            let called = local_file(self.root, &uses)
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .and_then(|source| serde_yaml::from_str::<Value>(&source).ok());
+            chain.push(uses);
+            match called {
+                Some(called) => self.walk(&called, chain),
+                // Somewhere else, so only its own level is known
+                None => {
+                    if chain.len() > self.deepest.len() {
+                        self.deepest = chain.clone();
+                    }
+                }
+            }
+            chain.pop();
+        }
+    }
+}
+
+/// Workflows that go over GitHub's hard limits, which GitHub only reports
+/// when the workflow runs, or fails to load it without saying why.
+#[derive(Debug, Clone, Default)]
+pub struct Limits;
+
+impl Limits {
+    /// The error for a file of `bytes` bytes, if that's over the limit. It's
+    /// made apart from [`Rule::check`] for files linted a segment at a time,
+    /// whose segments are each smaller than the file.
+    pub fn file_size(bytes: usize) -> Option<Diagnostic> {
+        if bytes <= MAX_FILE_BYTES {
+            return None;
+        }
+        let diagnostic = Diagnostic::new(
+            "workflow-limits",
+            Severity::Error,
+            format!(
+                "this file is {} KiB, and GitHub can't load workflows over {} KiB; move jobs \
+                 into reusable workflows",
+                bytes / 1024,
+                MAX_FILE_BYTES / 1024
+            ),
+        );
+        Some(diagnostic.at(1, 1).with_message_id("file-size"))
+    }
+}
+
+impl Rule for Limits {
+    fn name(&self) -> &'static str {
+        "workflow-limits"
+    }
+
+    fn description(&self) -> &'static str {
+        "Workflows over GitHub's hard limits"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let Some(mut diagnostic) = Limits::file_size(cx.source.len()) {
+            if let Some(path) = cx.path {
+                diagnostic = diagnostic.with_path(path);
+            }
+            diagnostics.push(diagnostic);
+        }
+
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let matrix = match job.strategy.as_ref().and_then(|s| s.matrix.as_ref()) {
+                Some(matrix) => matrix,
+                None => continue,
+            };
+            let legs = match matrix::expand(matrix) {
+                Ok(expansion) => expansion.legs.len(),
+                Err(_) => continue,
+            };
+            if legs > MAX_MATRIX_JOBS {
+                let node = key_path(&key_path(&job_path(job_id), "strategy"), "matrix");
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &node,
+                        format!(
+                            "this matrix makes {} jobs, and GitHub runs at most {} from one \
+                             matrix; split it across jobs",
+                            legs, MAX_MATRIX_JOBS
+                        ),
+                    )
+                    .with_message_id("matrix-jobs")
+                    .with_param("jobs", legs.to_string()),
+                );
+            }
+        }
+
+        let mut walk = Calls {
+            root: cx.repo_root,
+            seen: BTreeSet::new(),
+            deepest: Vec::new(),
+        };
+        walk.walk(cx.document, &mut vec![String::new()]);
+        // The first call of the deepest chain is where it can be fixed
+        let first_call = |uses: &str| {
+            calls(cx.document)
+                .into_iter()
+                .find(|(_, u)| u == uses)
+                .map(|(id, _)| key_path(&job_path(&id), "uses"))
+                .unwrap_or_default()
+        };
+        if walk.deepest.len() > MAX_NESTING {
+            let chain = &walk.deepest[1..];
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    &first_call(&chain[0]),
+                    format!(
+                        "this starts {} levels of reusable workflows, and GitHub allows {}, \
+                         counting this one: {}",
+                        walk.deepest.len(),
+                        MAX_NESTING,
+                        chain.join(" -> ")
+                    ),
+                )
+                .with_message_id("nesting")
+                .with_param("levels", walk.deepest.len().to_string()),
+            );
+        }
+        if walk.seen.len() > MAX_REUSABLE_WORKFLOWS {
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Error,
+                    "jobs",
+                    format!(
+                        "this workflow calls {} different reusable workflows, counting nested \
+                         calls, and GitHub allows {}",
+                        walk.seen.len(),
+                        MAX_REUSABLE_WORKFLOWS
+                    ),
+                )
+                .with_message_id("reusable-workflows")
+                .with_param("workflows", walk.seen.len().to_string()),
+            );
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,352 @@
+use crate::diagnostics::{Diagnostic, Edit, Fix, Severity, Suggestion};
+use crate::spans::key_path;
+use crate::workflow::{Job, Step};
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// Commands that print every environment variable when run on their own.
+const ENV_DUMPS: &[&str] = &[
+    "declare -p",
+    "dir env:",
+    "env",
+    "export",
+    "export -p",
+    "gci env:",
+    "get-childitem env:",
+    "printenv",
+    "set",
+];
+
+/// Files that usually hold credentials, matched at the end of the path.
+const CREDENTIAL_FILES: &[&str] = &[
+    ".docker/config.json",
+    ".env",
+    ".git-credentials",
+    ".kube/config",
+    ".netrc",
+    ".npmrc",
+    ".pem",
+    ".pypirc",
+    "id_rsa",
+    "kubeconfig",
+];
+
+/// Files that are usually too long to be worth reading in a log.
+const LARGE_FILES: &[&str] = &[
+    ".log",
+    ".min.js",
+    "Cargo.lock",
+    "package-lock.json",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+];
+
+/// Commands whose output is long and rarely read unless they fail.
+const VERBOSE_COMMANDS: &[&str] = &[
+    "apt install",
+    "apt-get install",
+    "apt-get update",
+    "brew install",
+    "bundle install",
+    "composer install",
+    "conda install",
+    "docker build",
+    "docker pull",
+    "gem install",
+    "go mod download",
+    "ls -r",
+    "npm ci",
+    "npm install",
+    "pip install",
+    "pip3 install",
+    "pnpm install",
+    "python -m pip install",
+    "python3 -m pip install",
+    "tree",
+    "yarn install",
+];
+
+/// Words that come before the command they run.
+const PREFIXES: &[&str] = &["sudo", "time", "exec", "command"];
+
+/// A command without the words before it, lowercased with its spaces
+/// collapsed.
+pub(super) fn normalize(command: &str) -> String {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let start = words
+        .iter()
+        .position(|word| !PREFIXES.contains(word))
+        .unwrap_or(words.len());
+    words[start..].join(" ").to_lowercase()
+}
+
+/// The commands of a script with the lines they start on, from 0, joining
+/// lines continued with `\` and splitting on `;`, `&&` and `||`.
+pub(super) fn commands(script: &str) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    let mut continued: Option<(usize, String)> = None;
+    for (i, line) in script.lines().enumerate() {
+        let trimmed = line.trim();
+        let (start, mut text) = continued.take().unwrap_or((i, String::new()));
+        if let Some(rest) = trimmed.strip_suffix('\\') {
+            text.push_str(rest);
+            text.push(' ');
+            continued = Some((start, text));
+            continue;
+        }
+        text.push_str(trimmed);
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        for command in text
+            .split(';')
+            .flat_map(|c| c.split("&&"))
+            .flat_map(|c| c.split("||"))
+        {
+            let command = command.trim();
+            if !command.is_empty() {
+                found.push((start, command.to_string()));
+            }
+        }
+    }
+    found
+}
+
+/// The pipeline's first command, if it dumps the environment to the log.
+/// Piping it through `grep` picks out variables, which is fine.
+fn dumps_env(command: &str) -> Option<String> {
+    let mut pipeline = command.split('|');
+    let first = normalize(pipeline.next()?);
+    let filtered = pipeline.any(|next| normalize(next).starts_with("grep"));
+    Some(first).filter(|first| ENV_DUMPS.contains(&first.as_str()) && !filtered)
+}
+
+/// The files `cat` prints in full.
+fn catted(command: &str) -> Vec<String> {
+    let first = command.split('|').next().unwrap_or_default();
+    let words: Vec<&str> = first.split_whitespace().collect();
+    let start = match words.iter().position(|word| !PREFIXES.contains(word)) {
+        Some(start) if words[start] == "cat" => start + 1,
+        _ => return Vec::new(),
+    };
+    words[start..]
+        .iter()
+        .filter(|word| !word.starts_with('-') && !word.starts_with(['<', '>']))
+        .map(|word| word.trim_matches(['"', '\'']).to_string())
+        .collect()
+}
+
+fn is_verbose(command: &str) -> bool {
+    let command = normalize(command);
+    command.contains(" --verbose")
+        || command.contains(" -vvv")
+        || VERBOSE_COMMANDS
+            .iter()
+            .any(|verbose| command == *verbose || command.starts_with(&format!("{} ", verbose)))
+}
+
+/// Runs of consecutive lines of `script` whose commands are all verbose,
+/// as first and last line, from 0. `None` if the script is nothing but,
+/// since a step's log already folds on its own.
+fn verbose_blocks(script: &str) -> Option<Vec<(usize, usize)>> {
+    let commands = commands(script);
+    if commands.iter().all(|(_, command)| is_verbose(command)) {
+        return None;
+    }
+    let lines: Vec<&str> = script.lines().collect();
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    for (i, (line, command)) in commands.iter().enumerate() {
+        if !is_verbose(command) {
+            continue;
+        }
+        // The command runs to the line before the next one starts
+        let next = commands.get(i + 1).map_or(lines.len(), |(next, _)| *next);
+        let mut last = next.saturating_sub(1).max(*line);
+        while last > *line && lines[last].trim().is_empty() {
+            last -= 1;
+        }
+        match blocks.last_mut() {
+            Some(block) if block.1 + 1 >= *line => block.1 = last.max(block.1),
+            _ => blocks.push((*line, last)),
+        }
+    }
+    // A line with a verbose command and another after `;` isn't split up
+    blocks.retain(|&(first, last)| {
+        commands
+            .iter()
+            .filter(|(line, _)| (first..=last).contains(line))
+            .all(|(_, command)| is_verbose(command))
+    });
+    Some(blocks)
+}
+
+/// Byte offsets of the start of each line of `source`.
+fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Wrap each block of the literal `run` at `node` in `::group::` and
+/// `::endgroup::`, lined up with the lines around it.
+fn group_fix(cx: &Context, node: &str, script: &str, blocks: &[(usize, usize)]) -> Option<Fix> {
+    if !cx.spans.is_literal(node) {
+        return None;
+    }
+    let start = cx.spans.get(node)?.value;
+    let starts = line_starts(cx.source);
+    let lines: Vec<&str> = script.lines().collect();
+    let mut edits = Vec::new();
+    for &(first, last) in blocks {
+        let first_start = *starts.get(start.line - 1 + first)?;
+        let last_end = match starts.get(start.line + last) {
+            Some(&next) => next - 1,
+            None => cx.source.len(),
+        };
+        let last_end = if cx.source[..last_end].ends_with('\r') {
+            last_end - 1
+        } else {
+            last_end
+        };
+        let line = lines[first];
+        let indent = format!(
+            "{}{}",
+            " ".repeat(start.column - 1),
+            &line[..line.len() - line.trim_start().len()]
+        );
+        let title: String = line.trim().trim_end_matches('\\').trim().replace('"', "'");
+        edits.push(Edit::insert(
+            first_start,
+            format!("{}echo \"::group::{}\"\n", indent, title),
+        ));
+        edits.push(Edit::insert(
+            last_end,
+            format!("\n{}echo \"::endgroup::\"", indent),
+        ));
+    }
+    Some(Fix {
+        description: "fold verbose output with ::group::".to_string(),
+        edits,
+    })
+}
+
+/// Whether the step runs in `cmd`, where `echo "..."` keeps its quotes.
+fn is_cmd(cx: &Context, job: &Job, step: &Step) -> bool {
+    cx.workflow.run_settings(job, step).shell_program() == Some("cmd")
+}
+
+/// Logs that leak or drown out what matters: scripts that print every
+/// environment variable, or `cat` a file that holds credentials or runs to
+/// thousands of lines, and long install and build output that would be
+/// easier to read folded into a `::group::`.
+#[derive(Debug, Clone, Default)]
+pub struct LogHygiene;
+
+impl Rule for LogHygiene {
+    fn name(&self) -> &'static str {
+        "log-hygiene"
+    }
+
+    fn description(&self) -> &'static str {
+        "Scripts that leak secrets into the log or drown it out"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (index, step) in job.steps.iter().enumerate() {
+                let script = match &step.run {
+                    Some(run) => run,
+                    None => continue,
+                };
+                let node = key_path(&step_path(job_id, index), "run");
+                for (_, command) in commands(script) {
+                    if let Some(dump) = dumps_env(&command) {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Warning,
+                                &node,
+                                format!(
+                                    "`{}` prints every environment variable to the log, \
+                                     where values derived from secrets aren't masked",
+                                    dump
+                                ),
+                            )
+                            .with_message_id("env-dump")
+                            .with_param("command", dump)
+                            .with_suggestion(Suggestion::new(
+                                "print only the variables you need, like `printenv PATH`",
+                            )),
+                        );
+                    }
+                    for file in catted(&command) {
+                        let (severity, id, why) =
+                            if CREDENTIAL_FILES.iter().any(|name| file.ends_with(name)) {
+                                (
+                                    Severity::Warning,
+                                    "credentials",
+                                    "usually holds credentials, which end up in the log",
+                                )
+                            } else if LARGE_FILES.iter().any(|name| file.ends_with(name)) {
+                                (
+                                    Severity::Info,
+                                    "large-file",
+                                    "is usually thousands of lines, which bury the rest of the log",
+                                )
+                            } else {
+                                continue;
+                            };
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                severity,
+                                &node,
+                                format!("`cat {}` prints a file that {}", file, why),
+                            )
+                            .with_message_id(id)
+                            .with_param("file", file),
+                        );
+                    }
+                }
+
+                if script.contains("::group::") || is_cmd(cx, job, step) {
+                    continue;
+                }
+                let blocks = match verbose_blocks(script) {
+                    Some(blocks) if !blocks.is_empty() => blocks,
+                    _ => continue,
+                };
+                let lines: Vec<&str> = script.lines().collect();
+                let first = lines[blocks[0].0].trim().trim_end_matches('\\').trim();
+                let mut diagnostic = cx
+                    .diagnostic(
+                        self,
+                        Severity::Info,
+                        &node,
+                        format!(
+                            "`{}` prints a lot in the middle of the step's log; fold it \
+                             with `::group::` so the rest is easy to find",
+                            first
+                        ),
+                    )
+                    .with_message_id("ungrouped")
+                    .with_param("command", first);
+                match group_fix(cx, &node, script, &blocks) {
+                    Some(fix) => diagnostic = diagnostic.with_fix(fix),
+                    None => {
+                        diagnostic = diagnostic.with_suggestion(
+                            Suggestion::new("wrap it in a group").with_example(format!(
+                                "echo \"::group::{}\"\n{}\necho \"::endgroup::\"",
+                                first.replace('"', "'"),
+                                first
+                            )),
+                        )
+                    }
+                }
+                diagnostics.push(diagnostic);
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,235 @@
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::expr::{self, TokenKind};
+use crate::spans::{index_path, key_path};
+use crate::workflow::{Job, MatrixAxes};
+
+use super::unknown_key::edit_distance;
+use super::{for_each_string, job_path, sorted_jobs, Context, Rule};
+
+/// Matrix keys that don't line up. `${{ matrix.<key> }}` for a key the
+/// job's matrix doesn't have is an empty string on every leg, and an
+/// `exclude` key that isn't an axis makes GitHub reject the workflow.
+/// `include` can add keys of its own, so only ones a letter or two off an
+/// axis, and not read anywhere, are reported as likely typos. Matrices
+/// with expressions in them are only known during the run and are
+/// skipped.
+#[derive(Debug, Clone, Default)]
+pub struct MatrixKey;
+
+/// A `matrix.<key>` in one of the job's strings.
+struct KeyRef {
+    path: String,
+    offset: usize,
+    key: String,
+    /// Whether it's `matrix.<key> || 'default'`, written for legs without
+    /// the key.
+    defaulted: bool,
+}
+
+/// The `matrix.<key>` references in the job, outside its `strategy`, where
+/// the matrix itself is.
+fn key_refs(cx: &Context, job_id: &str) -> Vec<KeyRef> {
+    let node = match cx.job_node(job_id) {
+        Some(node) => node,
+        None => return Vec::new(),
+    };
+    let strategy = key_path(&job_path(job_id), "strategy");
+    let mut refs = Vec::new();
+    for_each_string(node, &job_path(job_id), &mut |path, value| {
+        if path == strategy || path.starts_with(&format!("{}.", strategy)) {
+            return;
+        }
+        for embedded in expr::find_expressions(value) {
+            let tokens = match expr::tokenize(embedded.body) {
+                Ok(tokens) => tokens,
+                Err(_) => continue,
+            };
+            for reference in expr::references(&tokens) {
+                let key = match reference.parts.as_slice() {
+                    [context, key, ..] if context.eq_ignore_ascii_case("matrix") && key != "*" => {
+                        key
+                    }
+                    _ => continue,
+                };
+                let end = reference.offset + reference.len;
+                let defaulted = tokens
+                    .iter()
+                    .find(|token| token.offset >= end)
+                    .is_some_and(|token| token.kind == TokenKind::Or);
+                refs.push(KeyRef {
+                    path: path.to_string(),
+                    offset: embedded.body_offset + reference.offset,
+                    key: key.clone(),
+                    defaulted,
+                });
+            }
+        }
+    });
+    refs
+}
+
+/// The axis `key` is a letter or two off, if there is one.
+fn near_axis<'m>(key: &str, matrix: &'m MatrixAxes) -> Option<&'m str> {
+    let key = key.to_ascii_lowercase();
+    matrix
+        .axes
+        .keys()
+        .map(|axis| (edit_distance(&key, &axis.to_ascii_lowercase()), axis))
+        .filter(|&(distance, axis)| distance > 0 && distance <= 2 && distance * 2 < axis.len())
+        .min()
+        .map(|(_, axis)| axis.as_str())
+}
+
+impl MatrixKey {
+    fn unknown_refs(
+        &self,
+        cx: &Context,
+        job_id: &str,
+        job: &Job,
+        refs: &[KeyRef],
+    ) -> Vec<Diagnostic> {
+        let matrix = job.strategy.as_ref().and_then(|s| s.matrix.as_ref());
+        let mut diagnostics = Vec::new();
+        for reference in refs.iter().filter(|r| !r.defaulted) {
+            let (message, id, near) = match matrix {
+                None => (
+                    format!(
+                        "job `{}` has no matrix, so `matrix.{}` is always empty",
+                        job_id, reference.key
+                    ),
+                    "no-matrix",
+                    None,
+                ),
+                Some(matrix) => match matrix.axes() {
+                    Some(axes) if axes.has_expression() => continue,
+                    Some(axes) if axes.has_key(&reference.key) => continue,
+                    Some(axes) => (
+                        format!(
+                            "the matrix of job `{}` has no `{}` key, so `matrix.{}` is always \
+                             empty",
+                            job_id, reference.key, reference.key
+                        ),
+                        "unknown-key",
+                        near_axis(&reference.key, axes),
+                    ),
+                    None => continue,
+                },
+            };
+            let mut diagnostic = cx
+                .diagnostic_in(
+                    self,
+                    Severity::Warning,
+                    &reference.path,
+                    reference.offset,
+                    message,
+                )
+                .with_message_id(id)
+                .with_param("job", job_id)
+                .with_param("key", &reference.key);
+            if let Some(axis) = near {
+                diagnostic = diagnostic
+                    .with_suggestion(Suggestion::new(format!("did you mean `matrix.{}`?", axis)));
+            }
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+
+    fn entry_keys(
+        &self,
+        cx: &Context,
+        job_id: &str,
+        matrix: &MatrixAxes,
+        refs: &[KeyRef],
+    ) -> Vec<Diagnostic> {
+        let node = key_path(&key_path(&job_path(job_id), "strategy"), "matrix");
+        let mut diagnostics = Vec::new();
+        for (i, exclude) in matrix.excludes().iter().enumerate() {
+            let path = index_path(&key_path(&node, "exclude"), i);
+            for key in exclude.keys().filter(|&k| !matrix.axes.contains_key(k)) {
+                let mut diagnostic = cx
+                    .diagnostic(
+                        self,
+                        Severity::Error,
+                        &key_path(&path, key),
+                        format!(
+                            "`exclude` entry has `{}`, which isn't an axis of the matrix, so \
+                             GitHub rejects the workflow",
+                            key
+                        ),
+                    )
+                    .with_message_id("exclude")
+                    .with_param("job", job_id)
+                    .with_param("key", key);
+                if let Some(axis) = near_axis(key, matrix) {
+                    diagnostic = diagnostic
+                        .with_suggestion(Suggestion::new(format!("did you mean `{}`?", axis)));
+                }
+                diagnostics.push(diagnostic);
+            }
+        }
+        for (i, include) in matrix.includes().iter().enumerate() {
+            let path = index_path(&key_path(&node, "include"), i);
+            for key in include.keys().filter(|&k| !matrix.axes.contains_key(k)) {
+                let axis = match near_axis(key, matrix) {
+                    Some(axis) => axis,
+                    None => continue,
+                };
+                if refs.iter().any(|r| r.key.eq_ignore_ascii_case(key)) {
+                    continue;
+                }
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Warning,
+                        &key_path(&path, key),
+                        format!(
+                            "`include` entry adds `{}`, which nothing reads; did you mean the \
+                             `{}` axis?",
+                            key, axis
+                        ),
+                    )
+                    .with_message_id("include")
+                    .with_param("job", job_id)
+                    .with_param("key", key)
+                    .with_suggestion(Suggestion::new(format!(
+                        "rename it `{}` if it's meant to pick legs by that axis",
+                        axis
+                    ))),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+impl Rule for MatrixKey {
+    fn name(&self) -> &'static str {
+        "matrix-key"
+    }
+
+    fn description(&self) -> &'static str {
+        "References to matrix keys the matrix doesn't have, and `include` or `exclude` keys that \
+         aren't axes"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let refs = key_refs(cx, job_id);
+            diagnostics.extend(self.unknown_refs(cx, job_id, job, &refs));
+            let matrix = job
+                .strategy
+                .as_ref()
+                .and_then(|s| s.matrix.as_ref()?.axes());
+            if let Some(matrix) = matrix.filter(|m| !m.has_expression()) {
+                diagnostics.extend(self.entry_keys(cx, job_id, matrix, &refs));
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,543 @@
+//! The checks run against a parsed workflow.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::Value;
+
+use crate::action::{self, Action};
+use crate::action_ref::ActionRef;
+use crate::budget::Budget;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::eval::Stubs;
+use crate::reachability;
+use crate::repo;
+use crate::schema::Target;
+use crate::spans::{index_path, key_path, SpanMap};
+use crate::workflow::{Job, Workflow};
+
+mod action_refs;
+mod ambiguous_scalar;
+mod analysis;
+mod artifact_v4;
+mod artifacts;
+mod call_inputs;
+mod comment_commands;
+mod composite_outputs;
+mod concurrency;
+mod constant_condition;
+mod continue_on_error;
+mod cron_schedule;
+mod dependabot;
+mod docker_action;
+mod duplicate_jobs;
+mod event_types;
+mod expression_syntax;
+mod generated_file;
+mod hash_files;
+mod indentation;
+mod invalid_uses;
+mod job_needs;
+mod kubernetes;
+mod limits;
+mod log_hygiene;
+mod matrix_key;
+mod pages;
+mod path_safety;
+mod publish_provenance;
+mod retry;
+mod runner_cost;
+mod runner_label;
+mod runner_policy;
+mod runner_tools;
+mod schedule_liveness;
+mod script_injection;
+mod scripts;
+mod secret_leak;
+mod service_health_check;
+mod shell_script;
+mod step_name;
+mod target_features;
+mod terraform;
+mod token_permissions;
+mod unknown_key;
+mod untrusted_gate;
+mod uses_or_run;
+mod working_directory;
+
+pub use action_refs::{MissingLocalAction, PinLevel, Pinning, UnpinnedAction};
+pub use ambiguous_scalar::AmbiguousScalar;
+pub use analysis::{Analyses, Analysis, Skipped};
+pub use artifact_v4::ArtifactV4;
+pub use artifacts::{ArtifactNameCollision, ArtifactRetention, UnusedArtifact};
+pub use call_inputs::CallInputs;
+pub use comment_commands::{
+    CommentCommandAssociation, CommentCommandInjection, CommentCommandPermissions,
+};
+pub use composite_outputs::CompositeOutputs;
+pub use concurrency::{ConcurrencyGroup, PullRequestConcurrency};
+pub use constant_condition::ConstantCondition;
+pub use continue_on_error::ContinueOnError;
+pub use cron_schedule::CronSchedule;
+pub use dependabot::{
+    secrets, DependabotPermissions, DependabotPullRequestTarget, DependabotSecrets,
+};
+pub use docker_action::DockerAction;
+pub use duplicate_jobs::DuplicateJobs;
+pub use event_types::EventTypes;
+pub use expression_syntax::ExpressionSyntax;
+pub use generated_file::GeneratedFile;
+pub use hash_files::HashFiles;
+pub use indentation::{tab_indentation, Indentation};
+pub use invalid_uses::InvalidUses;
+pub use job_needs::JobNeeds;
+pub use kubernetes::{KubernetesCredentials, KubernetesImageTags, KubernetesProductionEnvironment};
+pub use limits::Limits;
+pub use log_hygiene::LogHygiene;
+pub use matrix_key::MatrixKey;
+pub use pages::{PagesArtifact, PagesConcurrency, PagesEnvironment, PagesPermissions};
+pub use path_safety::PathSafety;
+pub use publish_provenance::PublishProvenance;
+pub use retry::Retry;
+pub use runner_cost::RunnerCost;
+pub use runner_label::RunnerLabel;
+pub use runner_policy::{RunnerPolicy, Runners};
+pub use runner_tools::RunnerTools;
+pub use schedule_liveness::ScheduleLiveness;
+pub use script_injection::ScriptInjection;
+pub use scripts::{scripts, Language, Script};
+pub use secret_leak::{SecretLeak, SecretPlace, SecretRef};
+pub use service_health_check::ServiceHealthCheck;
+pub use shell_script::ShellScript;
+pub use step_name::{StepName, StepNames};
+pub use target_features::TargetFeatures;
+pub use terraform::{TerraformApplyProtection, TerraformCredentials, TerraformPlanPermissions};
+pub use token_permissions::TokenPermissions;
+pub use unknown_key::{closest, UnknownKey};
+pub use untrusted_gate::UntrustedGate;
+pub use uses_or_run::UsesOrRun;
+pub use working_directory::WorkingDirectory;
+
+/// Everything a rule gets to look at for one workflow file.
+pub struct Context<'a> {
+    pub path: Option<&'a Path>,
+    pub source: &'a str,
+    pub workflow: &'a Workflow,
+    /// The file as plain YAML, for rules that look at every value.
+    pub document: &'a Value,
+    pub spans: &'a SpanMap,
+    /// Root of the checkout the workflow is in, when linting inside one.
+    pub repo_root: Option<&'a Path>,
+    /// Context values the user says are known ahead of the run.
+    pub stubs: &'a Stubs,
+    /// What rules share, worked out as they ask for it.
+    pub analyses: Analyses,
+    /// How long the run may take, for rules that do a lot of work to check.
+    pub budget: Budget,
+}
+
+impl<'a> Context<'a> {
+    /// Start a diagnostic for the node at `node` (a path like
+    /// `jobs.build.steps[0]`), filling in the file and location.
+    pub fn diagnostic(
+        &self,
+        rule: &dyn Rule,
+        severity: Severity,
+        node: &str,
+        message: impl Into<String>,
+    ) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(rule.name(), severity, message)
+            .with_location(self.spans.locate(node))
+            .with_span(self.spans.span(node));
+        if let Some(path) = self.path {
+            diagnostic = diagnostic.with_path(path);
+        }
+        if !node.is_empty() {
+            diagnostic.node = Some(node.to_string());
+        }
+        diagnostic
+    }
+
+    /// Start a diagnostic about byte `offset` of the string at `node`, like
+    /// an expression in a script, located at that point of the string
+    /// rather than at its start.
+    pub fn diagnostic_in(
+        &self,
+        rule: &dyn Rule,
+        severity: Severity,
+        node: &str,
+        offset: usize,
+        message: impl Into<String>,
+    ) -> Diagnostic {
+        let diagnostic = self.diagnostic(rule, severity, node, message);
+        match self.spans.locate_in(self.source, node, offset) {
+            Some(location) => diagnostic.with_location(Some(location)),
+            None => diagnostic,
+        }
+    }
+
+    /// The plain YAML of the job `job_id`.
+    pub fn job_node(&self, job_id: &str) -> Option<&'a Value> {
+        self.document.get("jobs")?.get(job_id)
+    }
+
+    /// The scenarios to evaluate conditions in: the stubs, plus
+    /// `github.event_name` being one of the workflow's events unless the
+    /// stubs say otherwise. A reusable workflow sees its caller's event,
+    /// which could be anything.
+    pub fn scenarios(&self) -> Vec<serde_json::Value> {
+        let mut stubs = self.stubs.clone();
+        let names = event_names(self.document);
+        let events: Vec<_> = names
+            .iter()
+            .map(|e| serde_json::Value::String(e.to_string()))
+            .collect();
+        if !events.is_empty() && !names.contains(&"workflow_call") {
+            stubs.set_default("github", "event_name", Stubs::one_of(events));
+        }
+        stubs.scenarios()
+    }
+}
+
+pub trait Rule {
+    /// The kebab-case name used to refer to the rule.
+    fn name(&self) -> &'static str;
+
+    /// What the rule finds, in a line, for listing the rules.
+    fn description(&self) -> &'static str;
+
+    /// The most severe a finding of the rule can be, before the config or
+    /// an exposed trigger changes it.
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Opinionated rules are only run when asked for.
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// The pack of rules this one belongs to, like `terraform`, which
+    /// `--enable` can turn on as a whole.
+    fn group(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Security rules find what an attacker could use. Their findings are
+    /// raised a level in workflows that run on [`EXPOSED_EVENTS`].
+    fn security(&self) -> bool {
+        false
+    }
+
+    /// The analyses the rule can't do anything without. It's skipped for
+    /// files they can't be done for, like local actions outside a checkout.
+    fn requires(&self) -> &'static [Analysis] {
+        &[]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic>;
+}
+
+/// Events that people outside the repository can cause, which run with the
+/// repository's secrets and a write token.
+pub const EXPOSED_EVENTS: &[&str] = &["issue_comment", "pull_request_target", "workflow_run"];
+
+/// Raise `diagnostic` a level because the workflow runs on `event`.
+fn escalate(diagnostic: &mut Diagnostic, event: &str) {
+    diagnostic.severity = match diagnostic.severity {
+        Severity::Info => Severity::Warning,
+        Severity::Warning => Severity::Error,
+        Severity::Error => return,
+    };
+    diagnostic.message.push_str(&format!(
+        " (raised to {} because the workflow runs on `{}`, which others can trigger)",
+        diagnostic.severity, event
+    ));
+    diagnostic
+        .params
+        .push(("exposed_event".to_string(), event.to_string()));
+}
+
+/// Settings for the rules that take them.
+#[derive(Debug, Clone, Default)]
+pub struct RuleOptions {
+    /// The retry action the organization uses, like `nick-fields/retry`.
+    pub retry_action: Option<String>,
+    /// Where the workflows will run, for features that aren't everywhere.
+    pub target: Target,
+    /// The runners jobs can use and what they cost.
+    pub runners: Runners,
+    /// What actions have to be pinned to.
+    pub pinning: Pinning,
+    /// The `shellcheck` to check `run` scripts with.
+    pub shellcheck: Option<String>,
+    /// Which steps can go without a name.
+    pub step_names: StepNames,
+}
+
+pub fn builtin_rules() -> Vec<Box<dyn Rule>> {
+    configured_rules(&RuleOptions::default())
+}
+
+/// The built-in rules, set up with `options`.
+pub fn configured_rules(options: &RuleOptions) -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(AmbiguousScalar),
+        Box::new(ArtifactNameCollision),
+        Box::new(ArtifactRetention),
+        Box::new(ArtifactV4 {
+            target: options.target,
+        }),
+        Box::new(CallInputs),
+        Box::new(CommentCommandAssociation),
+        Box::new(CommentCommandInjection),
+        Box::new(CommentCommandPermissions),
+        Box::new(CompositeOutputs),
+        Box::new(ConcurrencyGroup),
+        Box::new(ConstantCondition),
+        Box::new(ContinueOnError),
+        Box::new(CronSchedule),
+        Box::new(DependabotPermissions),
+        Box::new(DependabotPullRequestTarget),
+        Box::new(DependabotSecrets),
+        Box::new(DockerAction),
+        Box::new(DuplicateJobs),
+        Box::new(EventTypes),
+        Box::new(ExpressionSyntax),
+        Box::new(GeneratedFile),
+        Box::new(HashFiles),
+        Box::new(Indentation),
+        Box::new(InvalidUses),
+        Box::new(JobNeeds),
+        Box::new(KubernetesCredentials),
+        Box::new(KubernetesImageTags),
+        Box::new(KubernetesProductionEnvironment),
+        Box::new(Limits),
+        Box::new(LogHygiene),
+        Box::new(MatrixKey),
+        Box::new(MissingLocalAction),
+        Box::new(PagesArtifact),
+        Box::new(PagesConcurrency),
+        Box::new(PagesEnvironment),
+        Box::new(PagesPermissions),
+        Box::new(PathSafety),
+        Box::new(PublishProvenance),
+        Box::new(PullRequestConcurrency),
+        Box::new(Retry {
+            blessed_action: options.retry_action.clone(),
+            ..Retry::default()
+        }),
+        Box::new(RunnerCost),
+        Box::new(RunnerLabel),
+        Box::new(RunnerPolicy {
+            runners: options.runners.clone(),
+        }),
+        Box::new(RunnerTools),
+        Box::new(ScheduleLiveness),
+        Box::new(ScriptInjection),
+        Box::new(SecretLeak {
+            pinning: options.pinning.clone(),
+        }),
+        Box::new(ServiceHealthCheck),
+        Box::new(ShellScript {
+            shellcheck: options.shellcheck.clone(),
+        }),
+        Box::new(StepName::from(&options.step_names)),
+        Box::new(TargetFeatures {
+            target: options.target,
+        }),
+        Box::new(TerraformApplyProtection),
+        Box::new(TerraformCredentials),
+        Box::new(TerraformPlanPermissions),
+        Box::new(TokenPermissions),
+        Box::new(UnknownKey),
+        Box::new(UnpinnedAction {
+            pinning: options.pinning.clone(),
+        }),
+        Box::new(UnusedArtifact::default()),
+        Box::new(UntrustedGate),
+        Box::new(UsesOrRun),
+        Box::new(WorkingDirectory),
+    ]
+}
+
+/// A local action used by the workflow, read from the checkout.
+pub struct LocalAction {
+    /// The directory holding the metadata file.
+    pub dir: PathBuf,
+    /// Relative to the current directory when it's under it, like the
+    /// workflow's own path usually is.
+    pub file: PathBuf,
+    pub spans: SpanMap,
+    pub action: Action,
+}
+
+impl LocalAction {
+    /// Like `Context::diagnostic`, but for a node of the metadata file.
+    pub fn diagnostic(
+        &self,
+        rule: &dyn Rule,
+        severity: Severity,
+        node: &str,
+        message: impl Into<String>,
+    ) -> Diagnostic {
+        Diagnostic::new(rule.name(), severity, message)
+            .with_path(&self.file)
+            .with_location(self.spans.locate(node))
+    }
+}
+
+/// Every local action the workflow uses that can be read and parsed, by
+/// `uses` path.
+pub fn local_actions(cx: &Context, root: &Path) -> BTreeMap<String, LocalAction> {
+    let mut actions = BTreeMap::new();
+    for (_, job) in sorted_jobs(cx.workflow) {
+        for step in &job.steps {
+            let path = match step.action() {
+                Some(Ok(ActionRef::Local { path })) => path,
+                _ => continue,
+            };
+            if actions.contains_key(&path) {
+                continue;
+            }
+            let (file, source) = match action::load_local(root, &path) {
+                Some(loaded) => loaded,
+                None => continue,
+            };
+            let action = match action::parse_str(&source) {
+                Ok(action) => action,
+                Err(_) => continue,
+            };
+            let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            let file = repo::relative_to_cwd(file);
+            let spans = SpanMap::parse(&source);
+            actions.insert(
+                path,
+                LocalAction {
+                    dir,
+                    file,
+                    spans,
+                    action,
+                },
+            );
+        }
+    }
+    actions
+}
+
+/// Jobs in id order, so that output doesn't depend on hash order.
+pub fn sorted_jobs(workflow: &Workflow) -> Vec<(&str, &Job)> {
+    let mut jobs: Vec<(&str, &Job)> = workflow
+        .jobs
+        .iter()
+        .map(|(id, job)| (id.as_str(), job))
+        .collect();
+    jobs.sort_by_key(|&(id, _)| id);
+    jobs
+}
+
+/// The path of a job, e.g. `jobs.build`.
+pub fn job_path(job_id: &str) -> String {
+    key_path("jobs", job_id)
+}
+
+/// The path of a step, e.g. `jobs.build.steps[0]`.
+pub fn step_path(job_id: &str, index: usize) -> String {
+    index_path(&key_path(&job_path(job_id), "steps"), index)
+}
+
+/// The names of the events that trigger the workflow, from `on:` as a
+/// string, list or map.
+pub fn event_names(document: &Value) -> Vec<&str> {
+    match document.get("on") {
+        Some(Value::String(event)) => vec![event.as_str()],
+        Some(Value::Sequence(events)) => events.iter().filter_map(Value::as_str).collect(),
+        Some(Value::Mapping(events)) => events.iter().filter_map(|(k, _)| k.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Call `f` with the path and contents of every string value under `value`.
+pub fn for_each_string<'v>(value: &'v Value, path: &str, f: &mut dyn FnMut(&str, &'v str)) {
+    match value {
+        Value::String(s) => f(path, s),
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                for_each_string(item, &index_path(path, i), f);
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, item) in map {
+                let key = match key {
+                    Value::String(key) => key.clone(),
+                    other => serde_yaml::to_string(other)
+                        .unwrap_or_default()
+                        .trim_start_matches("---")
+                        .trim()
+                        .to_string(),
+                };
+                for_each_string(item, &key_path(path, &key), f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Run `rules` against a workflow, in order.
+pub fn lint(cx: &Context, rules: &[Box<dyn Rule>]) -> Vec<Diagnostic> {
+    lint_reporting_skipped(cx, rules).0
+}
+
+/// Run `rules` against a workflow, in order, also returning the rules that
+/// were skipped because what they need couldn't be worked out.
+pub fn lint_reporting_skipped(
+    cx: &Context,
+    rules: &[Box<dyn Rule>],
+) -> (Vec<Diagnostic>, Vec<Skipped>) {
+    let events = event_names(cx.document);
+    let exposed = EXPOSED_EVENTS.iter().find(|event| events.contains(event));
+    let unreachable = reachability::unreachable(cx);
+    let mut diagnostics = Vec::new();
+    let mut skipped = Vec::new();
+    for rule in rules {
+        let missing = rule
+            .requires()
+            .iter()
+            .find_map(|&analysis| Some((analysis, cx.unavailable(analysis)?)));
+        if let Some((analysis, reason)) = missing {
+            skipped.push(Skipped {
+                rule: rule.name(),
+                reason: format!("it needs {}, and {}", analysis, reason),
+            });
+            continue;
+        }
+        if let Some(stopped) = cx.budget.stopped() {
+            skipped.push(Skipped {
+                rule: rule.name(),
+                reason: stopped.reason().to_string(),
+            });
+            continue;
+        }
+        let started = cx.budget.start_rule();
+        let mut found = rule.check(cx);
+        if let Some(took) = cx.budget.finish_rule(started) {
+            skipped.push(Skipped {
+                rule: rule.name(),
+                reason: format!(
+                    "it took {:.2?}, over the limit of {:?}, so its findings were dropped",
+                    took,
+                    cx.budget.rule_timeout.unwrap_or_default()
+                ),
+            });
+            continue;
+        }
+        for diagnostic in &mut found {
+            if let (true, Some(event)) = (rule.security(), exposed) {
+                escalate(diagnostic, event);
+            }
+            reachability::demote(diagnostic, &unreachable);
+        }
+        diagnostics.extend(found);
+    }
+    (diagnostics, skipped)
+}
@@ -0,0 +1,341 @@
+//! Rules for workflows that deploy a GitHub Pages site with
+//! actions/deploy-pages. They're off by default and turned on together with
+//! `--enable pages`.
+
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::graph::JobGraph;
+use crate::workflow::{Concurrency, Job, Workflow};
+
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// The group the rules here are enabled by.
+pub const GROUP: &str = "pages";
+
+const DEPLOY: &str = "actions/deploy-pages";
+const UPLOAD: &str = "actions/upload-pages-artifact";
+
+/// What both actions call the artifact unless told otherwise.
+const DEFAULT_ARTIFACT: &str = "github-pages";
+
+/// The environment Pages deployments are recorded under.
+const ENVIRONMENT: &str = "github-pages";
+
+/// The jobs that deploy, with the index of the deploy step.
+fn deploy_jobs(workflow: &Workflow) -> Vec<(&str, &Job, usize)> {
+    sorted_jobs(workflow)
+        .into_iter()
+        .filter_map(|(id, job)| {
+            let i = job.steps.iter().position(|step| step.uses_action(DEPLOY))?;
+            Some((id, job, i))
+        })
+        .collect()
+}
+
+/// The access the job's token has to `scope`, from the job's `permissions`
+/// or else the workflow's.
+fn permission<'a>(cx: &Context<'a>, job_id: &str, scope: &str) -> &'a str {
+    let permissions = cx
+        .job_node(job_id)
+        .and_then(|job| job.get("permissions"))
+        .or_else(|| cx.document.get("permissions"));
+    match permissions {
+        Some(Value::String(all)) if all == "write-all" => "write",
+        Some(Value::String(all)) if all == "read-all" => "read",
+        Some(Value::Mapping(_)) => permissions
+            .and_then(|p| p.get(scope))
+            .and_then(Value::as_str)
+            .unwrap_or("none"),
+        // The repository default has neither `pages` nor `id-token`
+        _ => "none",
+    }
+}
+
+/// Deploy jobs whose token can't write `pages` and `id-token`, which
+/// deploy-pages needs to create the deployment and to prove where it came
+/// from.
+#[derive(Debug, Clone, Default)]
+pub struct PagesPermissions;
+
+impl Rule for PagesPermissions {
+    fn name(&self) -> &'static str {
+        "pages-permissions"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages deploy jobs whose token can't write `pages` and `id-token`"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, _, _) in deploy_jobs(cx.workflow) {
+            let missing: Vec<&str> = ["pages", "id-token"]
+                .iter()
+                .copied()
+                .filter(|scope| permission(cx, job_id, scope) != "write")
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+            let grants: Vec<String> = missing.iter().map(|s| format!("`{}: write`", s)).collect();
+            let message = format!(
+                "this job deploys to GitHub Pages, which needs {} in its `permissions`",
+                grants.join(" and ")
+            );
+            diagnostics.push(
+                cx.diagnostic(self, Severity::Error, &job_path(job_id), message)
+                    .with_param("permissions", missing.join(", ")),
+            );
+        }
+        diagnostics
+    }
+}
+
+/// Deploy jobs that aren't in the `github-pages` environment, which the
+/// repository's Pages settings protect and where the site's URL shows up.
+#[derive(Debug, Clone, Default)]
+pub struct PagesEnvironment;
+
+impl Rule for PagesEnvironment {
+    fn name(&self) -> &'static str {
+        "pages-environment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages deploy jobs outside the `github-pages` environment"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, _, _) in deploy_jobs(cx.workflow) {
+            let environment = cx.job_node(job_id).and_then(|job| job.get("environment"));
+            let name = match environment {
+                Some(Value::String(name)) => Some(name.as_str()),
+                Some(environment) => environment.get("name").and_then(Value::as_str),
+                None => None,
+            };
+            let (id, message) = match name {
+                Some(ENVIRONMENT) => continue,
+                Some(other) => (
+                    "wrong-environment",
+                    format!(
+                        "this job deploys to GitHub Pages from the environment `{}`; Pages \
+                         deployments go in `{}`",
+                        other, ENVIRONMENT
+                    ),
+                ),
+                None => (
+                    "missing-environment",
+                    format!(
+                        "this job deploys to GitHub Pages without an `environment`; use `{}`, \
+                         with `url: ${{{{ steps.<deploy step id>.outputs.page_url }}}}`",
+                        ENVIRONMENT
+                    ),
+                ),
+            };
+            diagnostics.push(
+                cx.diagnostic(self, Severity::Warning, &job_path(job_id), message)
+                    .with_message_id(id)
+                    .with_param("environment", name.unwrap_or_default()),
+            );
+        }
+        diagnostics
+    }
+}
+
+/// deploy-pages without an upload-pages-artifact it can deploy, and the
+/// other way round. The upload has to be in the deploy job or a job it
+/// needs, under the name deploy-pages looks for; actions/upload-artifact
+/// doesn't make the tar archive Pages expects.
+#[derive(Debug, Clone, Default)]
+pub struct PagesArtifact;
+
+impl Rule for PagesArtifact {
+    fn name(&self) -> &'static str {
+        "pages-artifact"
+    }
+
+    fn description(&self) -> &'static str {
+        "deploy-pages without an upload-pages-artifact to deploy, or the other way round"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let jobs = sorted_jobs(cx.workflow);
+        let uploads: Vec<(&str, usize, &str)> = jobs
+            .iter()
+            .flat_map(|&(job_id, job)| {
+                job.steps
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, step)| step.uses_action(UPLOAD))
+                    .map(move |(i, step)| {
+                        let name = step.with.get("name").map(String::as_str);
+                        (job_id, i, name.unwrap_or(DEFAULT_ARTIFACT))
+                    })
+            })
+            .collect();
+        let deploys = deploy_jobs(cx.workflow);
+        let graph = JobGraph::new(cx.workflow);
+
+        for &(job_id, job, i) in &deploys {
+            let node = step_path(job_id, i);
+            let wanted = job.steps[i]
+                .with
+                .get("artifact_name")
+                .map(String::as_str)
+                .unwrap_or(DEFAULT_ARTIFACT);
+            let mut reachable = graph.upstream(job_id);
+            reachable.insert(job_id);
+            let candidates: Vec<&(&str, usize, &str)> = uploads
+                .iter()
+                .filter(|(upload_job, upload_step, _)| {
+                    reachable.contains(upload_job) && (*upload_job != job_id || *upload_step < i)
+                })
+                .collect();
+            if candidates.iter().any(|(_, _, name)| *name == wanted) {
+                continue;
+            }
+            let message = if let Some((_, _, name)) = candidates.first() {
+                format!(
+                    "this step deploys the artifact `{}`, but upload-pages-artifact uploads \
+                     `{}`; give them the same name",
+                    wanted, name
+                )
+            } else if let Some((upload_job, _, _)) = uploads.first() {
+                format!(
+                    "this step deploys an artifact that `{}` uploads, but `{}` doesn't need \
+                     `{}`, so it can run first; add it to `needs`",
+                    upload_job, job_id, upload_job
+                )
+            } else if jobs.iter().any(|(_, job)| {
+                job.steps
+                    .iter()
+                    .any(|s| s.uses_action(super::artifacts::UPLOAD))
+            }) {
+                "this step deploys a Pages artifact, but the workflow uploads with \
+                 actions/upload-artifact; use actions/upload-pages-artifact, which makes the \
+                 archive Pages needs"
+                    .to_string()
+            } else {
+                "this step deploys a Pages artifact, but nothing uploads one; add \
+                 actions/upload-pages-artifact before it"
+                    .to_string()
+            };
+            diagnostics.push(
+                cx.diagnostic(self, Severity::Error, &node, message)
+                    .with_param("artifact", wanted),
+            );
+        }
+
+        if deploys.is_empty() {
+            for &(job_id, i, _) in &uploads {
+                diagnostics.push(cx.diagnostic(
+                    self,
+                    Severity::Warning,
+                    &step_path(job_id, i),
+                    "this step uploads a Pages artifact, but nothing in the workflow deploys \
+                     it; add actions/deploy-pages",
+                ));
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Deploy jobs that can run more than once at a time. Pages keeps one
+/// deployment, so two runs deploying at once can leave the older site up;
+/// the usual setup is `concurrency: pages` without cancelling a deployment
+/// that's underway.
+#[derive(Debug, Clone, Default)]
+pub struct PagesConcurrency;
+
+impl Rule for PagesConcurrency {
+    fn name(&self) -> &'static str {
+        "pages-concurrency"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages deploy jobs that can run more than once at a time"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job, _) in deploy_jobs(cx.workflow) {
+            let concurrency = cx.workflow.concurrency_for(job);
+            let path = job_path(job_id);
+            let group = concurrency.map(|c| c.group.as_str());
+            let cancels = concurrency.is_some_and(Concurrency::cancels);
+            let (id, message) = match group {
+                None => (
+                    "missing-concurrency",
+                    "this job deploys to GitHub Pages without a `concurrency` group, so two \
+                     runs can deploy at once",
+                ),
+                Some(group) if group.contains("${{") => (
+                    "expression-group",
+                    "this job's `concurrency` group is an expression, so runs with different \
+                     values can deploy to GitHub Pages at once; use a fixed group like `pages`",
+                ),
+                Some(_) if cancels => (
+                    "cancel-in-progress",
+                    "this job's `concurrency` cancels a GitHub Pages deployment that's underway \
+                     when another run starts; set `cancel-in-progress: false`",
+                ),
+                Some(_) => continue,
+            };
+            let mut diagnostic = cx
+                .diagnostic(self, Severity::Warning, &path, message)
+                .with_message_id(id);
+            if group.is_none() {
+                diagnostic = diagnostic.with_suggestion(
+                    Suggestion::new("add a group that waits for the deployment underway")
+                        .with_example("concurrency:\n  group: pages\n  cancel-in-progress: false"),
+                );
+            }
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,193 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::runner::Os;
+use crate::spans::key_path;
+use crate::workflow::Job;
+
+use super::artifacts::{DOWNLOAD, UPLOAD};
+use super::runner_tools::job_labels;
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// Actions whose `path` input is a list of paths, one a line.
+const PATH_ACTIONS: &[&str] = &[
+    UPLOAD,
+    DOWNLOAD,
+    "actions/cache",
+    "actions/cache/restore",
+    "actions/cache/save",
+];
+
+/// Characters upload-artifact refuses in an artifact's name, on every OS.
+const NAME_CHARACTERS: &[char] = &['"', ':', '<', '>', '|', '*', '?', '\\', '/'];
+
+/// Characters Windows refuses in a file name. `*` and `?` are left out of
+/// paths, where the actions read them as globs.
+const WINDOWS_CHARACTERS: &[char] = &['"', ':', '<', '>', '|'];
+
+/// Names Windows keeps for devices, with any extension.
+const RESERVED_NAMES: &[&str] = &[
+    "aux", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9", "con", "lpt1",
+    "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9", "nul", "prn",
+];
+
+/// What's wrong with one name or path segment, and whether it's only a
+/// problem on Windows.
+fn problem(segment: &str, refused: &[char]) -> Option<(String, bool)> {
+    if segment.contains('\u{fffd}') {
+        return Some(("has bytes that aren't valid UTF-8".to_string(), false));
+    }
+    if segment.chars().any(char::is_control) {
+        return Some(("has a control character".to_string(), false));
+    }
+    if let Some(c) = segment.chars().find(|c| refused.contains(c)) {
+        return Some((format!("has `{}`", c), true));
+    }
+    if segment != "." && segment != ".." && (segment.ends_with(' ') || segment.ends_with('.')) {
+        let what = if segment.ends_with(' ') {
+            "a space"
+        } else {
+            "a dot"
+        };
+        return Some((format!("ends with {} that Windows drops", what), true));
+    }
+    let stem = segment.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.contains(&stem.to_lowercase().as_str()) {
+        return Some((format!("is `{}`, a device name on Windows", stem), true));
+    }
+    None
+}
+
+/// What's wrong with a path a step reads or writes, skipping the drive in
+/// `C:\...`, expressions and a leading `!` for exclusions.
+fn path_problem(path: &str) -> Option<(String, bool)> {
+    if path.contains("${{") {
+        return None;
+    }
+    let path = path.trim_start().strip_prefix('!').unwrap_or(path);
+    let path = match path.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => &path[2..],
+        _ => path,
+    };
+    path.trim_start()
+        .split(['/', '\\'])
+        .filter(|segment| !segment.is_empty())
+        .find_map(|segment| problem(segment, WINDOWS_CHARACTERS))
+}
+
+/// Whether the job can run on Windows, as far as its labels say.
+fn on_windows(job: &Job) -> bool {
+    job_labels(job)
+        .iter()
+        .any(|label| Os::of_label(label) == Some(Os::Windows))
+}
+
+/// Workflow files, artifact names and cache and artifact paths with
+/// characters that break on some runners' filesystems: `:` and the others
+/// Windows refuses, names ending in a space or dot, which Windows drops, and
+/// bytes that aren't UTF-8. These only fail when the job runs, on the OS
+/// they break on, so they're a warning for jobs that run on Windows and
+/// information for the rest.
+#[derive(Debug, Clone, Default)]
+pub struct PathSafety;
+
+impl Rule for PathSafety {
+    fn name(&self) -> &'static str {
+        "path-safety"
+    }
+
+    fn description(&self) -> &'static str {
+        "Paths and names with characters some runners' filesystems refuse"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let Some(name) = cx.path.and_then(|path| path.file_name()) {
+            let found = match name.to_str() {
+                Some(name) => problem(name, WINDOWS_CHARACTERS).map(|(why, _)| why),
+                None => Some("has bytes that aren't valid UTF-8".to_string()),
+            };
+            if let Some(why) = found {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Warning,
+                        "",
+                        format!(
+                            "the workflow's file name {}, so it can't be checked out on every \
+                             runner",
+                            why
+                        ),
+                    )
+                    .with_message_id("file-name"),
+                );
+            }
+        }
+
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let windows = on_windows(job);
+            for (index, step) in job.steps.iter().enumerate() {
+                let with = key_path(&step_path(job_id, index), "with");
+                if step.uses_action(UPLOAD) {
+                    let name = step.with.get("name").map(String::as_str).unwrap_or("");
+                    let found = match name.contains("${{") {
+                        true => None,
+                        false => problem(name, NAME_CHARACTERS),
+                    };
+                    if let Some((why, _)) = found {
+                        // Names that upload fine can still break downloading
+                        // them into a directory of that name
+                        let refused = name.contains(|c: char| {
+                            NAME_CHARACTERS.contains(&c) || c.is_control() || c == '\u{fffd}'
+                        });
+                        let breaks = match refused {
+                            true => "so upload-artifact refuses it",
+                            false => "so downloading it breaks on Windows",
+                        };
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Warning,
+                                &key_path(&with, "name"),
+                                format!("artifact name `{}` {}, {}", name, why, breaks),
+                            )
+                            .with_message_id("artifact-name")
+                            .with_param("name", name),
+                        );
+                    }
+                }
+
+                if !PATH_ACTIONS.iter().any(|action| step.uses_action(action)) {
+                    continue;
+                }
+                let paths = match step.with.get("path") {
+                    Some(paths) => paths,
+                    None => continue,
+                };
+                for path in paths.lines().map(str::trim_end).filter(|p| !p.is_empty()) {
+                    let (why, windows_only) = match path_problem(path) {
+                        Some(found) => found,
+                        None => continue,
+                    };
+                    let severity = match windows_only && !windows {
+                        true => Severity::Info,
+                        false => Severity::Warning,
+                    };
+                    let breaks = match windows_only {
+                        true => "so it breaks on Windows runners",
+                        false => "so it breaks on some runners",
+                    };
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            severity,
+                            &key_path(&with, "path"),
+                            format!("path `{}` {}, {}", path.trim(), why, breaks),
+                        )
+                        .with_message_id("path")
+                        .with_param("path", path.trim()),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,343 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::workflow::{Access, Job, Step, Workflow};
+
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// Steps that publish packages or images in a way that can't be traced back
+/// to the run that built them: npm packages without provenance, PyPI uploads
+/// without attestations, images that aren't signed or attested, publishing
+/// with a long-lived token where the registry takes OIDC trusted publishing
+/// instead, and publishing jobs without an environment to protect them.
+#[derive(Debug, Clone, Default)]
+pub struct PublishProvenance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Registry {
+    Npm,
+    PyPi,
+    Crates,
+    Container,
+}
+
+impl Registry {
+    fn name(self) -> &'static str {
+        match self {
+            Registry::Npm => "npm",
+            Registry::PyPi => "PyPI",
+            Registry::Crates => "crates.io",
+            Registry::Container => "a container registry",
+        }
+    }
+
+    /// Environment variables that hold a token for the registry.
+    fn token_variables(self) -> &'static [&'static str] {
+        match self {
+            Registry::Npm => &["NODE_AUTH_TOKEN", "NPM_TOKEN"],
+            Registry::PyPi => &["TWINE_PASSWORD"],
+            Registry::Crates => &["CARGO_REGISTRY_TOKEN"],
+            Registry::Container => &[],
+        }
+    }
+
+    /// How to publish without a token, where the registry allows it.
+    fn trusted_publishing(self) -> Option<&'static str> {
+        match self {
+            Registry::Npm => Some("configure a trusted publisher for the package on npmjs.com"),
+            Registry::PyPi => Some(
+                "configure a trusted publisher on PyPI and publish with \
+                 pypa/gh-action-pypi-publish without a password",
+            ),
+            Registry::Crates => Some(
+                "configure trusted publishing on crates.io and get a token from \
+                 rust-lang/crates-io-auth-action",
+            ),
+            Registry::Container => None,
+        }
+    }
+}
+
+/// A step that publishes.
+struct Publish {
+    registry: Registry,
+    /// The secret the step authenticates with, if it uses one.
+    token: Option<String>,
+    /// Whether the step says to make provenance, or turns it off.
+    provenance: Option<bool>,
+    /// Whether it publishes with an OIDC token from the run.
+    trusted: bool,
+}
+
+/// Commands that publish, matched anywhere in a `run` script.
+const PUBLISH_COMMANDS: &[(&str, Registry)] = &[
+    ("npm publish", Registry::Npm),
+    ("pnpm publish", Registry::Npm),
+    ("yarn npm publish", Registry::Npm),
+    ("twine upload", Registry::PyPi),
+    ("uv publish", Registry::PyPi),
+    ("cargo publish", Registry::Crates),
+    ("docker push", Registry::Container),
+    ("podman push", Registry::Container),
+];
+
+/// Actions that publish, and the input they take a token in.
+const PUBLISH_ACTIONS: &[(&str, Registry, &str)] = &[
+    ("JS-DevTools/npm-publish", Registry::Npm, "token"),
+    ("pypa/gh-action-pypi-publish", Registry::PyPi, "password"),
+    ("katyo/publish-crates", Registry::Crates, "registry-token"),
+    ("docker/build-push-action", Registry::Container, ""),
+];
+
+/// Steps in a job that sign or attest what it builds.
+const ATTEST_ACTIONS: &[&str] = &["actions/attest-build-provenance", "actions/attest"];
+
+/// The name of the secret in `value`, like `NPM_TOKEN` in
+/// `${{ secrets.NPM_TOKEN }}`.
+fn secret_in(value: &str) -> Option<String> {
+    let start = value.find("secrets.")? + "secrets.".len();
+    let name: String = value[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    if name.is_empty() || name == "GITHUB_TOKEN" {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn publish(workflow: &Workflow, job: &Job, step: &Step) -> Option<Publish> {
+    let env_token = |registry: Registry| {
+        registry
+            .token_variables()
+            .iter()
+            .find_map(|name| workflow.env_value(job, step, name).and_then(secret_in))
+    };
+    if let Some(&(_, registry, input)) = PUBLISH_ACTIONS
+        .iter()
+        .find(|(action, _, _)| step.uses_action(action))
+    {
+        if registry == Registry::Container
+            && step.with.get("push").map(String::as_str) != Some("true")
+        {
+            return None;
+        }
+        let provenance = match registry {
+            Registry::Npm => Some(step.with.get("provenance").is_some_and(|p| p == "true")),
+            Registry::PyPi => step.with.get("attestations").map(|a| a == "true"),
+            _ => None,
+        };
+        let token = step
+            .with
+            .get(input)
+            .and_then(|value| secret_in(value))
+            .or_else(|| env_token(registry));
+        return Some(Publish {
+            registry,
+            trusted: token.is_none() && registry == Registry::PyPi,
+            token,
+            provenance,
+        });
+    }
+
+    let run = step.run.as_deref()?;
+    let registry = PUBLISH_COMMANDS
+        .iter()
+        .find(|(command, _)| run.contains(command))
+        .map(|&(_, registry)| registry)
+        .or_else(|| {
+            let buildx = run.contains("docker buildx build") || run.contains("docker build");
+            (buildx && run.contains("--push")).then_some(Registry::Container)
+        })?;
+    let provenance = match registry {
+        Registry::Npm => {
+            let env = workflow.env_value(job, step, "NPM_CONFIG_PROVENANCE");
+            Some(run.contains("--provenance") || env == Some("true"))
+        }
+        _ => None,
+    };
+    let token = env_token(registry).or_else(|| {
+        // A token on the command line, like `cargo publish --token ...`
+        ["--token", "--password", " -p "]
+            .iter()
+            .find_map(|flag| run.find(flag).and_then(|i| secret_in(&run[i..])))
+    });
+    // npm and uv use OIDC when there's no token; cargo needs one made for it
+    let trusted = token.is_none()
+        && match registry {
+            Registry::Npm => true,
+            Registry::PyPi => run.contains("uv publish"),
+            Registry::Crates => job
+                .steps
+                .iter()
+                .any(|step| step.uses_action("rust-lang/crates-io-auth-action")),
+            Registry::Container => false,
+        };
+    Some(Publish {
+        registry,
+        token,
+        provenance,
+        trusted,
+    })
+}
+
+/// The access the job's token has to `id-token`, from the job's
+/// `permissions` or else the workflow's. The repository default never
+/// includes it.
+fn id_token(cx: &Context, job: &Job) -> Access {
+    cx.workflow
+        .permissions_for(job)
+        .and_then(|permissions| permissions.access("id-token"))
+        .unwrap_or(Access::None)
+}
+
+/// Whether `job` signs or attests what it builds.
+fn attests(job: &Job) -> bool {
+    job.steps.iter().any(|step| {
+        ATTEST_ACTIONS.iter().any(|action| step.uses_action(action))
+            || step
+                .run
+                .as_deref()
+                .is_some_and(|run| run.contains("cosign sign") || run.contains("cosign attest"))
+    })
+}
+
+/// Whether signing in `job` is keyless, which needs an OIDC token.
+fn keyless_signing(job: &Job) -> bool {
+    job.steps.iter().any(|step| {
+        ATTEST_ACTIONS.iter().any(|action| step.uses_action(action))
+            || step.run.as_deref().is_some_and(|run| {
+                (run.contains("cosign sign") || run.contains("cosign attest"))
+                    && !run.contains("--key")
+            })
+    })
+}
+
+impl Rule for PublishProvenance {
+    fn name(&self) -> &'static str {
+        "publish-provenance"
+    }
+
+    fn description(&self) -> &'static str {
+        "Packages and images published without provenance"
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let mut publishes_in_job = false;
+            let mut needs_id_token = None;
+            for (i, step) in job.steps.iter().enumerate() {
+                let publish = match publish(cx.workflow, job, step) {
+                    Some(publish) => publish,
+                    None => continue,
+                };
+                publishes_in_job = true;
+                let node = step_path(job_id, i);
+                let registry = publish.registry.name();
+
+                if let (Some(secret), Some(instead)) =
+                    (&publish.token, publish.registry.trusted_publishing())
+                {
+                    let message = format!(
+                        "this step publishes to {} with the long-lived token `{}`; {} so runs \
+                         get a short-lived one instead",
+                        registry, secret, instead
+                    );
+                    diagnostics.push(
+                        cx.diagnostic(self, Severity::Warning, &node, message)
+                            .with_message_id("token-publishing")
+                            .with_param("registry", registry)
+                            .with_param("secret", secret),
+                    );
+                }
+
+                // Trusted publishing makes provenance without being asked
+                let trusted = publish.trusted;
+                match publish.registry {
+                    Registry::Npm if !trusted && publish.provenance == Some(false) => {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Warning,
+                                &node,
+                                "this step publishes to npm without provenance; add \
+                                 `--provenance`, or set `NPM_CONFIG_PROVENANCE: true`",
+                            )
+                            .with_message_id("missing-provenance")
+                            .with_param("registry", registry),
+                        );
+                    }
+                    Registry::PyPi if publish.provenance == Some(false) => {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Warning,
+                                &node,
+                                "this step turns off PyPI attestations; remove \
+                                 `attestations: false`",
+                            )
+                            .with_message_id("missing-provenance")
+                            .with_param("registry", registry),
+                        );
+                    }
+                    Registry::Container if !attests(job) => {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Warning,
+                                &node,
+                                "this step pushes an image that the job doesn't sign or \
+                                 attest; add actions/attest-build-provenance or `cosign sign`",
+                            )
+                            .with_message_id("unsigned-image")
+                            .with_param("registry", registry),
+                        );
+                    }
+                    _ => {}
+                }
+
+                let uses_oidc = match publish.registry {
+                    Registry::Npm => trusted || publish.provenance == Some(true),
+                    Registry::PyPi | Registry::Crates => trusted,
+                    Registry::Container => keyless_signing(job),
+                };
+                if uses_oidc && needs_id_token.is_none() {
+                    needs_id_token = Some((node, registry));
+                }
+            }
+
+            if let Some((node, registry)) = needs_id_token {
+                if id_token(cx, job) != Access::Write {
+                    let message = format!(
+                        "publishing to {} this way needs an OIDC token, but the job's \
+                         `permissions` don't grant `id-token: write`",
+                        registry
+                    );
+                    diagnostics.push(
+                        cx.diagnostic(self, Severity::Warning, &node, message)
+                            .with_message_id("missing-id-token")
+                            .with_param("registry", registry),
+                    );
+                }
+            }
+            if publishes_in_job && job.environment.is_none() {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Warning,
+                        &job_path(job_id),
+                        "this job publishes but has no `environment`, so nothing like \
+                         required reviewers or branch rules stands between a push and a \
+                         release",
+                    )
+                    .with_message_id("missing-environment"),
+                );
+            }
+        }
+        diagnostics
+    }
+}
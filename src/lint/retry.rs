@@ -0,0 +1,198 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+use crate::workflow::Step;
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// Retries done by hand in `run` scripts, and retry actions that can go on
+/// forever. A retry without a limit or a timeout turns a flaky step into one
+/// that hangs until the job's 6 hour limit.
+///
+/// Set `blessed_action` to the retry action the organization has settled on
+/// and other ways of retrying are pointed at it.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    pub blessed_action: Option<String>,
+    /// Attempt counts above this are as good as unbounded.
+    pub max_attempts: u32,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            blessed_action: None,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// Retry actions and the input that limits their attempts.
+const RETRY_ACTIONS: &[(&str, &str)] = &[
+    ("nick-fields/retry", "max_attempts"),
+    ("nick-invision/retry", "max_attempts"),
+    ("Wandalen/wretry.action", "attempt_limit"),
+];
+
+/// Inputs those actions take to limit each attempt's time.
+const TIMEOUT_INPUTS: &[&str] = &["timeout_minutes", "timeout_seconds", "time_out"];
+
+/// Whether a loop condition or body counts attempts.
+fn counts_attempts(text: &str) -> bool {
+    ["-ge", "-gt", "-le", "-lt", "-eq", "((", "seq "]
+        .iter()
+        .any(|op| text.contains(op))
+}
+
+/// The retry loops in a script, as whether each one has an attempt limit.
+fn retry_loops(script: &str) -> Vec<bool> {
+    let lines: Vec<&str> = script.lines().map(str::trim).collect();
+    let mut loops = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let retries_until_success = line.starts_with("until ") || line.starts_with("while ! ");
+        let forever = line.starts_with("while true")
+            || line.starts_with("while :")
+            || line.starts_with("for ((;;))");
+        let counted = line.starts_with("for ");
+        if !retries_until_success && !forever && !counted {
+            i += 1;
+            continue;
+        }
+
+        // The loop runs to the first `done`, which may be on the same line
+        let end = if line.contains("done") {
+            i
+        } else {
+            (i + 1..lines.len())
+                .find(|&j| lines[j].starts_with("done") || lines[j].ends_with("done"))
+                .unwrap_or(lines.len() - 1)
+        };
+        let body = lines[i..=end].join("\n");
+        let breaks = body.contains("break");
+        let sleeps = body.contains("sleep");
+        if (retries_until_success && sleeps) || (forever && breaks) {
+            loops.push(counts_attempts(&body));
+        } else if counted && breaks && sleeps {
+            loops.push(true);
+        }
+        i = end + 1;
+    }
+    loops
+}
+
+impl Retry {
+    fn suggestion(&self) -> String {
+        match &self.blessed_action {
+            Some(action) => format!("use `{}` instead", action),
+            None => "a retry action with a `timeout-minutes` on the step keeps \
+                     retries and timeouts consistent"
+                .to_string(),
+        }
+    }
+
+    fn check_action(&self, step: &Step, limit: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        match step.with.get(limit).map(|n| n.trim()) {
+            None => {}
+            Some(n) if n.contains("${{") => {}
+            Some(n) => match n.parse::<u32>() {
+                Ok(0) => problems.push(format!("`{}: 0` retries without end", limit)),
+                Ok(attempts) if attempts > self.max_attempts => problems.push(format!(
+                    "`{}: {}` is as good as unbounded; a step that fails that often \
+                     is broken, not flaky",
+                    limit, attempts
+                )),
+                _ => {}
+            },
+        }
+        let attempt_timeout = TIMEOUT_INPUTS.iter().any(|i| step.with.contains_key(*i));
+        if !attempt_timeout && step.timeout_minutes.is_none() {
+            problems.push(
+                "attempts have no timeout, so one that hangs is never retried; \
+                 set the action's timeout or the step's `timeout-minutes`"
+                    .to_string(),
+            );
+        }
+        problems
+    }
+}
+
+impl Rule for Retry {
+    fn name(&self) -> &'static str {
+        "retry"
+    }
+
+    fn description(&self) -> &'static str {
+        "Retries written by hand, and retry actions that can go on forever"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                let path = step_path(job_id, i);
+
+                if let Some(run) = &step.run {
+                    for bounded in retry_loops(run) {
+                        let (severity, id, message) = if bounded {
+                            (
+                                Severity::Info,
+                                "hand-rolled",
+                                format!("hand-rolled retry loop; {}", self.suggestion()),
+                            )
+                        } else {
+                            (
+                                Severity::Warning,
+                                "unbounded",
+                                format!(
+                                    "retry loop has no attempt limit, so a lasting failure \
+                                     spins until the job times out; {}",
+                                    self.suggestion()
+                                ),
+                            )
+                        };
+                        let node = key_path(&path, "run");
+                        diagnostics.push(
+                            cx.diagnostic(self, severity, &node, message)
+                                .with_message_id(id)
+                                .with_param("suggestion", self.suggestion()),
+                        );
+                    }
+                    continue;
+                }
+
+                let blessed = self
+                    .blessed_action
+                    .as_deref()
+                    .is_some_and(|action| step.uses_action(action));
+                let known = RETRY_ACTIONS
+                    .iter()
+                    .find(|(action, _)| step.uses_action(action));
+                if let Some(&(action, limit)) = known {
+                    let node = key_path(&path, "uses");
+                    for problem in self.check_action(step, limit) {
+                        diagnostics.push(cx.diagnostic(self, Severity::Warning, &node, problem));
+                    }
+                    if let (Some(blessed_action), false) = (&self.blessed_action, blessed) {
+                        diagnostics.push(
+                            cx.diagnostic(
+                                self,
+                                Severity::Info,
+                                &node,
+                                format!(
+                                    "retries with `{}`, but this organization uses `{}`",
+                                    action, blessed_action
+                                ),
+                            )
+                            .with_message_id("other-action")
+                            .with_param("action", action)
+                            .with_param("blessed", blessed_action),
+                        );
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,227 @@
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::runner::Os;
+use crate::spans::key_path;
+use crate::workflow::{Job, Step, Workflow};
+
+use super::{job_path, sorted_jobs, Context, Rule};
+
+/// macOS and Windows minutes are billed at a multiple of Linux minutes. Jobs
+/// that would do the same thing anywhere, like building in a container or
+/// running linters, are cheaper on Ubuntu.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerCost;
+
+/// Known linters and formatters, matched against the start of each command.
+const LINT_COMMANDS: &[&str] = &[
+    "actionlint",
+    "black --check",
+    "cargo clippy",
+    "cargo fmt",
+    "eslint",
+    "flake8",
+    "golangci-lint",
+    "hadolint",
+    "markdownlint",
+    "mypy",
+    "npm run lint",
+    "npx eslint",
+    "npx prettier",
+    "prettier",
+    "pylint",
+    "rubocop",
+    "ruff",
+    "rustfmt",
+    "shellcheck",
+    "yamllint",
+    "yarn lint",
+];
+
+/// Actions that do the same thing on every OS and don't say anything about
+/// what the job is for.
+const NEUTRAL_ACTIONS: &[&str] = &[
+    "actions/cache",
+    "actions/checkout",
+    "actions/download-artifact",
+    "actions/upload-artifact",
+];
+
+/// Billing multiplier for GitHub-hosted runner minutes.
+fn multiplier(os: Os) -> u32 {
+    match os {
+        Os::Linux => 1,
+        Os::Windows => 2,
+        Os::MacOs => 10,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    Neutral,
+    Container,
+    Lint,
+    Other,
+}
+
+fn classify(workflow: &Workflow, job: &Job, step: &Step) -> StepKind {
+    let shell = workflow.run_settings(job, step).shell_program();
+    if let Some(shell) = shell {
+        if ["pwsh", "powershell", "cmd"].contains(&shell) {
+            return StepKind::Other;
+        }
+    }
+
+    if let Some(run) = &step.run {
+        let commands: Vec<&str> = run
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .collect();
+        return if commands.is_empty() {
+            StepKind::Neutral
+        } else if commands
+            .iter()
+            .all(|c| c.starts_with("docker ") || c.starts_with("docker-compose "))
+        {
+            StepKind::Container
+        } else if commands
+            .iter()
+            .all(|c| LINT_COMMANDS.iter().any(|l| c.starts_with(l)))
+        {
+            StepKind::Lint
+        } else {
+            StepKind::Other
+        };
+    }
+
+    match step.action() {
+        Some(Ok(ActionRef::Docker { .. })) => StepKind::Container,
+        Some(Ok(ActionRef::Repository { owner, repo, .. })) => {
+            let name = format!("{}/{}", owner, repo);
+            if owner == "docker" {
+                StepKind::Container
+            } else if repo.contains("lint") {
+                StepKind::Lint
+            } else if NEUTRAL_ACTIONS.contains(&name.as_str()) {
+                StepKind::Neutral
+            } else {
+                StepKind::Other
+            }
+        }
+        _ => StepKind::Other,
+    }
+}
+
+/// Why the job doesn't depend on the OS, if it doesn't.
+fn os_agnostic(workflow: &Workflow, job: &Job) -> Option<&'static str> {
+    if job.container.is_some() {
+        return Some("runs in a container");
+    }
+    let kinds: Vec<StepKind> = job
+        .steps
+        .iter()
+        .map(|step| classify(workflow, job, step))
+        .collect();
+    if kinds.contains(&StepKind::Other) {
+        None
+    } else if kinds.contains(&StepKind::Container) {
+        Some("only builds or runs containers")
+    } else if kinds.contains(&StepKind::Lint) {
+        Some("only runs linters")
+    } else {
+        None
+    }
+}
+
+/// The OS of each way the job can run: one for a plain job, one per leg for
+/// `runs-on: ${{ matrix.<axis> }}`. `None` means not worked out.
+fn job_oses(job: &Job) -> Option<Vec<Os>> {
+    let labels = job.labels();
+    let label = labels.first()?;
+    let axis = label
+        .trim()
+        .strip_prefix("${{")
+        .and_then(|l| l.strip_suffix("}}"))
+        .map(str::trim)
+        .and_then(|l| l.strip_prefix("matrix."));
+    let axis = match axis {
+        Some(axis) => axis,
+        // Self-hosted label sets start with `self-hosted`, which gives no OS
+        None => return Os::of_label(label).map(|os| vec![os]),
+    };
+
+    let matrix = job.strategy.as_ref()?.matrix.as_ref()?.axes()?;
+    let values = matrix.values(axis);
+    values
+        .into_iter()
+        .map(|v| v.as_str().and_then(Os::of_label))
+        .collect()
+}
+
+impl Rule for RunnerCost {
+    fn name(&self) -> &'static str {
+        "runner-cost"
+    }
+
+    fn description(&self) -> &'static str {
+        "Jobs on macOS or Windows that would run the same, and cheaper, on Linux"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let oses = match job_oses(job) {
+                Some(oses) if oses.iter().any(|&os| os != Os::Linux) => oses,
+                _ => continue,
+            };
+            let reason = match os_agnostic(cx.workflow, job) {
+                Some(reason) => reason,
+                None => continue,
+            };
+
+            let billed: u32 = oses.iter().map(|&os| multiplier(os)).sum();
+            let expensive = oses.iter().filter(|&&os| os != Os::Linux).count();
+            let (id, message) = if oses.len() == 1 {
+                (
+                    "single",
+                    format!(
+                        "job `{}` {} but runs on {} at {}x the cost of Linux minutes; \
+                     `ubuntu-latest` would do the same for a {} of the price",
+                        job_id,
+                        reason,
+                        if oses[0] == Os::MacOs {
+                            "macOS"
+                        } else {
+                            "Windows"
+                        },
+                        billed,
+                        if billed == 10 { "tenth" } else { "half" },
+                    ),
+                )
+            } else {
+                (
+                    "matrix",
+                    format!(
+                        "job `{}` {} but {} of its {} matrix legs run on macOS or Windows; \
+                     running them all on Ubuntu would cut billed minutes from {}x to {}x",
+                        job_id,
+                        reason,
+                        expensive,
+                        oses.len(),
+                        billed,
+                        oses.len(),
+                    ),
+                )
+            };
+            let path = key_path(&job_path(job_id), "runs-on");
+            diagnostics.push(
+                cx.diagnostic(self, Severity::Warning, &path, message)
+                    .with_message_id(id)
+                    .with_param("job", job_id)
+                    .with_param("reason", reason)
+                    .with_param("multiplier", billed),
+            );
+        }
+        diagnostics
+    }
+}
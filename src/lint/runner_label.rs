@@ -0,0 +1,123 @@
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::runner::{self, Image, Runner, Status, IMAGES};
+use crate::spans::key_path;
+
+use super::runner_tools::job_labels;
+use super::unknown_key::closest;
+use super::{job_path, sorted_jobs, Context, Rule};
+
+/// Jobs on a GitHub-hosted image that's been retired, which fail without
+/// starting, or that's deprecated and will be. Labels that look like a
+/// hosted image but are a letter or two off one, like `ubuntu-lastest`,
+/// are reported too, since no runner picks them up and the job waits until
+/// it's cancelled; ones with a version number GitHub may have added since,
+/// like `ubuntu-28.04`, aren't. Each leg of a `runs-on: ${{ matrix.os }}` matrix is
+/// checked.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerLabel;
+
+impl RunnerLabel {
+    fn image(&self, cx: &Context, path: &str, image: &Image, leg: bool) -> Option<Diagnostic> {
+        let on = match leg {
+            true => format!("`{}`, which a leg of the matrix runs on,", image.label),
+            false => format!("`{}`", image.label),
+        };
+        let (severity, id, message) = match image.status {
+            Status::Current => return None,
+            Status::Deprecated { removal } => (
+                Severity::Warning,
+                "deprecated",
+                format!(
+                    "{} is deprecated, and GitHub removes the image in {}",
+                    on, removal
+                ),
+            ),
+            Status::Retired { since } => (
+                Severity::Error,
+                "retired",
+                format!(
+                    "{} was retired in {}, so the job fails without starting",
+                    on, since
+                ),
+            ),
+        };
+        Some(
+            cx.diagnostic(self, severity, path, message)
+                .with_message_id(id)
+                .with_param("label", image.label)
+                .with_suggestion(
+                    Suggestion::new(format!("move to `{}`", image.replacement))
+                        .with_example(format!("runs-on: {}", image.replacement)),
+                ),
+        )
+    }
+
+    fn misspelt(&self, cx: &Context, path: &str, label: &str) -> Option<Diagnostic> {
+        if !runner::looks_hosted(label) || runner::is_versioned(label) {
+            return None;
+        }
+        let known: Vec<&str> = IMAGES.iter().map(|image| image.label).collect();
+        let suggestion = closest(&label.to_ascii_lowercase(), &known)?;
+        Some(
+            cx.diagnostic(
+                self,
+                Severity::Warning,
+                path,
+                format!(
+                    "`{}` isn't a GitHub-hosted runner, so the job waits for one that never \
+                     comes; did you mean `{}`?",
+                    label, suggestion
+                ),
+            )
+            .with_message_id("unknown")
+            .with_param("label", label)
+            .with_suggestion(Suggestion::new(
+                "if it's the label of a self-hosted or larger runner, list `self-hosted` too, or \
+                 disable this rule",
+            )),
+        )
+    }
+}
+
+impl Rule for RunnerLabel {
+    fn name(&self) -> &'static str {
+        "runner-label"
+    }
+
+    fn description(&self) -> &'static str {
+        "Jobs on retired or deprecated hosted runner images, or on a misspelt one"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let path = key_path(&job_path(job_id), "runs-on");
+            let runs_on = match &job.runs_on {
+                Some(runs_on) => runs_on,
+                None => continue,
+            };
+            match runs_on.runner() {
+                Runner::Hosted(image) => {
+                    diagnostics.extend(self.image(cx, &path, image, false));
+                }
+                Runner::Custom(labels) if labels.len() == 1 => {
+                    diagnostics.extend(self.misspelt(cx, &path, labels[0]));
+                }
+                Runner::Expression(_) => {
+                    for label in job_labels(job) {
+                        match runner::image_for(&label) {
+                            Some(image) => diagnostics.extend(self.image(cx, &path, image, true)),
+                            None => diagnostics.extend(self.misspelt(cx, &path, &label)),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use serde::Deserialize;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+use crate::workflow::Job;
+
+use super::runner_tools::job_labels;
+use super::{job_path, sorted_jobs, Context, Rule};
+
+/// Jobs on larger runners with fewer steps than this are small enough for
+/// a standard one, unless the config says otherwise.
+const MIN_STEPS: usize = 3;
+
+/// Likewise for jobs with a `timeout-minutes` below this.
+const MIN_MINUTES: u64 = 10;
+
+/// The runners an organization allows and what they cost, from the
+/// `[runners]` table of the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Runners {
+    /// The labels jobs can run on; any if empty.
+    pub approved: Vec<String>,
+    /// What a minute on each label costs, as a multiple of a standard
+    /// runner's. Labels that aren't listed cost 1, unless they're named
+    /// for their size like `ubuntu-latest-16-cores`.
+    pub cost: BTreeMap<String, u64>,
+    pub min_steps: Option<usize>,
+    pub min_minutes: Option<u64>,
+}
+
+impl Runners {
+    /// The cost tier of `label`. GitHub's larger runners cost in proportion
+    /// to their cores, and the standard ones have 2 to 4.
+    pub fn cost(&self, label: &str) -> u64 {
+        if let Some((_, &cost)) = self
+            .cost
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(label))
+        {
+            return cost;
+        }
+        let cores = label
+            .split('-')
+            .zip(label.split('-').skip(1))
+            .find(|(_, unit)| *unit == "core" || *unit == "cores")
+            .and_then(|(cores, _)| cores.parse::<u64>().ok());
+        match cores {
+            Some(cores) if cores > 4 => cores / 2,
+            _ => 1,
+        }
+    }
+
+    /// `local` on top of `self`.
+    pub fn merge(self, local: Runners) -> Runners {
+        let mut cost = self.cost;
+        cost.extend(local.cost);
+        Runners {
+            approved: if local.approved.is_empty() {
+                self.approved
+            } else {
+                local.approved
+            },
+            cost,
+            min_steps: local.min_steps.or(self.min_steps),
+            min_minutes: local.min_minutes.or(self.min_minutes),
+        }
+    }
+}
+
+/// The labels the job can run on that are written out, with each leg's
+/// for `runs-on: ${{ matrix.<axis> }}`.
+fn labels(job: &Job) -> Vec<String> {
+    let labels = job.labels();
+    if labels.len() == 1 {
+        return job_labels(job);
+    }
+    labels
+        .iter()
+        .filter(|label| !label.contains("${{"))
+        .map(|label| label.trim().to_lowercase())
+        .collect()
+}
+
+/// Jobs on runners the organization hasn't approved, and jobs on larger
+/// runners that are too small to need them, for FinOps policies set in the
+/// config's `[runners]` table.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerPolicy {
+    pub runners: Runners,
+}
+
+impl Rule for RunnerPolicy {
+    fn name(&self) -> &'static str {
+        "runner-policy"
+    }
+
+    fn description(&self) -> &'static str {
+        "Jobs on runners the organization's policy doesn't allow"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let runners = &self.runners;
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let node = key_path(&job_path(job_id), "runs-on");
+            let labels = labels(job);
+
+            if !runners.approved.is_empty() {
+                let unapproved: Vec<&String> = labels
+                    .iter()
+                    .filter(|label| {
+                        !runners
+                            .approved
+                            .iter()
+                            .any(|a| a.eq_ignore_ascii_case(label))
+                    })
+                    .collect();
+                for label in unapproved {
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Error,
+                            &node,
+                            format!(
+                                "job `{}` runs on `{}`, which isn't one of the approved \
+                                 runners: {}",
+                                job_id,
+                                label,
+                                runners.approved.join(", ")
+                            ),
+                        )
+                        .with_message_id("unapproved")
+                        .with_param("job", job_id)
+                        .with_param("label", label),
+                    );
+                }
+            }
+
+            let (label, cost) = match labels
+                .iter()
+                .map(|label| (label, runners.cost(label)))
+                .max_by_key(|&(_, cost)| cost)
+            {
+                Some((label, cost)) if cost > 1 => (label, cost),
+                _ => continue,
+            };
+            let steps = job.steps.len();
+            let minutes = job.timeout_minutes.and_then(|m| u64::try_from(m).ok());
+            let small = if steps < runners.min_steps.unwrap_or(MIN_STEPS) {
+                match steps {
+                    1 => "only has 1 step".to_string(),
+                    steps => format!("only has {} steps", steps),
+                }
+            } else {
+                match minutes {
+                    Some(minutes) if minutes < runners.min_minutes.unwrap_or(MIN_MINUTES) => {
+                        format!("has a timeout of {} minutes", minutes)
+                    }
+                    _ => continue,
+                }
+            };
+            diagnostics.push(
+                cx.diagnostic(
+                    self,
+                    Severity::Warning,
+                    &node,
+                    format!(
+                        "job `{}` runs on `{}`, at {}x the cost of a standard runner, but \
+                         {}; a standard runner would likely do",
+                        job_id, label, cost, small
+                    ),
+                )
+                .with_message_id("oversized")
+                .with_param("job", job_id)
+                .with_param("label", label)
+                .with_param("cost", cost),
+            );
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,265 @@
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::runner::Os;
+use crate::spans::key_path;
+use crate::workflow::{Job, Step};
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// A tool a hosted runner image doesn't have.
+struct Absent {
+    /// The OSes of the images without it.
+    oses: &'static [Os],
+    /// The labels of the only images of those OSes without it, if it's
+    /// only some.
+    only: &'static [&'static str],
+    tool: &'static str,
+    /// Why it isn't there, finishing "`tool` ...".
+    reason: &'static str,
+    /// What to do instead.
+    instead: &'static str,
+    /// Whether the image used to have it.
+    removed: bool,
+}
+
+impl Absent {
+    /// Whether the image `label`, in lower case, picks doesn't have it.
+    fn lacks(&self, label: &str) -> bool {
+        let os_lacks = Os::of_label(label).is_some_and(|os| self.oses.contains(&os));
+        os_lacks && (self.only.is_empty() || self.only.contains(&label))
+    }
+}
+
+/// What's missing from GitHub's hosted images, from the software lists in
+/// `actions/runner-images`.
+const ABSENT: &[Absent] = &[
+    Absent {
+        oses: &[Os::MacOs],
+        only: &[],
+        tool: "docker",
+        reason: "isn't installed on macOS runners, which can't run containers",
+        instead: "run the job on `ubuntu-latest`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::MacOs],
+        only: &[],
+        tool: "docker-compose",
+        reason: "isn't installed on macOS runners, which can't run containers",
+        instead: "run the job on `ubuntu-latest`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::Linux, Os::Windows],
+        only: &[],
+        tool: "docker-compose",
+        reason: "was removed from the runner images in July 2024",
+        instead: "use `docker compose`, which is installed",
+        removed: true,
+    },
+    Absent {
+        oses: &[Os::MacOs],
+        only: &[],
+        tool: "apt-get",
+        reason: "isn't on macOS runners",
+        instead: "install packages with `brew`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::MacOs],
+        only: &[],
+        tool: "apt",
+        reason: "isn't on macOS runners",
+        instead: "install packages with `brew`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::Windows],
+        only: &[],
+        tool: "apt-get",
+        reason: "isn't on Windows runners",
+        instead: "install packages with `choco`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::Windows],
+        only: &[],
+        tool: "apt",
+        reason: "isn't on Windows runners",
+        instead: "install packages with `choco`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::Windows],
+        only: &[],
+        tool: "brew",
+        reason: "isn't installed on Windows runners",
+        instead: "install packages with `choco`",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::Linux, Os::MacOs],
+        only: &[],
+        tool: "choco",
+        reason: "is only installed on Windows runners",
+        instead: "install packages with the OS's own package manager",
+        removed: false,
+    },
+    Absent {
+        oses: &[Os::Linux],
+        only: &["ubuntu-24.04", "ubuntu-24.04-arm", "ubuntu-latest"],
+        tool: "mono",
+        reason: "isn't installed on Ubuntu 24.04 runners, though 22.04 had it",
+        instead: "install it first, or pin `ubuntu-22.04`",
+        removed: true,
+    },
+];
+
+/// Words that come before the command they run.
+const PREFIXES: &[&str] = &["sudo", "time", "exec", "command", "env"];
+
+/// The runner labels the job can run on: one for a plain job, one per leg
+/// for `runs-on: ${{ matrix.<axis> }}`. Self-hosted runners and labels that
+/// can't be worked out are left out, since what they have isn't known.
+pub(super) fn job_labels(job: &Job) -> Vec<String> {
+    let labels = job.labels();
+    let label = match labels.first() {
+        Some(label) if labels.len() == 1 => label.trim(),
+        _ => return Vec::new(),
+    };
+    let axis = label
+        .strip_prefix("${{")
+        .and_then(|l| l.strip_suffix("}}"))
+        .map(str::trim)
+        .and_then(|l| l.strip_prefix("matrix."));
+    let axis = match axis {
+        Some(axis) => axis,
+        None if label.contains("${{") => return Vec::new(),
+        None => return vec![label.to_lowercase()],
+    };
+
+    let matrix = match job
+        .strategy
+        .as_ref()
+        .and_then(|s| s.matrix.as_ref()?.axes())
+    {
+        Some(matrix) => matrix,
+        None => return Vec::new(),
+    };
+    let values = matrix.values(axis);
+    let mut labels: Vec<String> = values
+        .into_iter()
+        .filter_map(Value::as_str)
+        .map(str::to_lowercase)
+        .collect();
+    labels.dedup();
+    labels
+}
+
+/// The programs a script runs, as the first word of each command.
+pub(super) fn commands(script: &str) -> Vec<&str> {
+    let mut commands = Vec::new();
+    for line in script.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        for command in line.split([';', '|', '&']) {
+            let program = command
+                .split_whitespace()
+                .find(|word| !PREFIXES.contains(word) && !word.contains('='));
+            if let Some(program) = program {
+                commands.push(program);
+            }
+        }
+    }
+    commands
+}
+
+/// Whether a step before `index` sets `tool` up, by installing it or using
+/// an action named after it.
+fn set_up(job: &Job, index: usize, tool: &str) -> bool {
+    job.steps[..index].iter().any(|step: &Step| {
+        let installs = step
+            .run
+            .as_deref()
+            .is_some_and(|run| run.contains("install") && run.contains(tool));
+        installs
+            || step
+                .uses
+                .as_deref()
+                .is_some_and(|uses| uses.to_lowercase().contains(tool))
+    })
+}
+
+/// Run steps that use a tool the job's hosted runner image doesn't have,
+/// like `docker` on macOS, which fail with "command not found" only once
+/// they run.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerTools;
+
+impl Rule for RunnerTools {
+    fn name(&self) -> &'static str {
+        "runner-tools"
+    }
+
+    fn description(&self) -> &'static str {
+        "Scripts using tools the job's hosted runner doesn't have"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            // A container brings its own tools
+            if job.container.is_some() {
+                continue;
+            }
+            let labels = job_labels(job);
+            if labels.is_empty() {
+                continue;
+            }
+            for (index, step) in job.steps.iter().enumerate() {
+                let run = match &step.run {
+                    Some(run) => run,
+                    None => continue,
+                };
+                let commands = commands(run);
+                for absent in ABSENT {
+                    if !commands.contains(&absent.tool) || set_up(job, index, absent.tool) {
+                        continue;
+                    }
+                    let missing: Vec<&str> = labels
+                        .iter()
+                        .filter(|label| absent.lacks(label))
+                        .map(String::as_str)
+                        .collect();
+                    if missing.is_empty() {
+                        continue;
+                    }
+                    let runners = missing
+                        .iter()
+                        .map(|label| format!("`{}`", label))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let node = key_path(&step_path(job_id, index), "run");
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &node,
+                            format!(
+                                "`{}` {}, and this job runs on {}; {}",
+                                absent.tool, absent.reason, runners, absent.instead
+                            ),
+                        )
+                        .with_message_id(if absent.removed { "removed" } else { "absent" })
+                        .with_param("tool", absent.tool)
+                        .with_param("runners", runners),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
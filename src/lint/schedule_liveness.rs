@@ -0,0 +1,105 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::repo;
+use crate::spans::key_path;
+
+use super::{event_names, sorted_jobs, Context, Rule};
+
+/// GitHub disables scheduled workflows in public repositories after this
+/// many days without activity.
+const INACTIVE_DAYS: u64 = 60;
+
+/// How long before that a checkout's last commit is worth warning about.
+const WARN_DAYS: u64 = 45;
+
+/// Actions that keep a repository active so its schedules aren't disabled.
+const KEEPALIVE_ACTIONS: &[&str] = &[
+    "entepotenz/keep-github-actions-alive-min-dependencies",
+    "gautamkrishnar/keepalive-workflow",
+    "liskin/gh-workflow-keepalive",
+];
+
+/// Scheduled workflows that GitHub will disable once the repository has
+/// gone 60 days without activity, with no keepalive step and no
+/// `workflow_dispatch` to run them by hand once they've stopped.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleLiveness;
+
+impl Rule for ScheduleLiveness {
+    fn name(&self) -> &'static str {
+        "schedule-liveness"
+    }
+
+    fn description(&self) -> &'static str {
+        "Scheduled workflows GitHub will disable after 60 days without activity"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let events = event_names(cx.document);
+        if !events.contains(&"schedule") || events.contains(&"workflow_dispatch") {
+            return Vec::new();
+        }
+        let keepalive = sorted_jobs(cx.workflow).iter().any(|(_, job)| {
+            job.steps.iter().any(|step| {
+                let uses = step.uses.as_deref().unwrap_or_default().to_lowercase();
+                KEEPALIVE_ACTIONS
+                    .iter()
+                    .any(|action| uses.starts_with(&format!("{}@", action)))
+            })
+        });
+        if keepalive {
+            return Vec::new();
+        }
+
+        let node = match cx.document.get("on") {
+            Some(Value::Mapping(_)) => key_path("on", "schedule"),
+            _ => "on".to_string(),
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let idle = cx
+            .repo_root
+            .and_then(repo::last_commit)
+            .map(|last| now.saturating_sub(last) / (24 * 60 * 60));
+        let (severity, id, message) = match idle {
+            Some(days) if days >= WARN_DAYS => (
+                Severity::Warning,
+                "idle",
+                format!(
+                    "the last commit was {} days ago, and GitHub disables schedules in \
+                     public repositories after {} days without activity",
+                    days, INACTIVE_DAYS
+                ),
+            ),
+            _ => (
+                Severity::Info,
+                "disabled-when-idle",
+                format!(
+                    "GitHub disables schedules in public repositories after {} days \
+                     without activity, and this workflow can't be run by hand once it has",
+                    INACTIVE_DAYS
+                ),
+            ),
+        };
+        let mut diagnostic = cx
+            .diagnostic(self, severity, &node, message)
+            .with_message_id(id)
+            .with_suggestion(
+                Suggestion::new("add `workflow_dispatch` so it can be run by hand").with_example(
+                    "on:\n  schedule:\n    - cron: '0 6 * * 1'\n  workflow_dispatch:",
+                ),
+            )
+            .with_suggestion(Suggestion::new(
+                "or add a keepalive step, like gautamkrishnar/keepalive-workflow",
+            ));
+        if let Some(days) = idle {
+            diagnostic = diagnostic.with_param("days", days);
+        }
+        vec![diagnostic]
+    }
+}
@@ -0,0 +1,137 @@
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::expr::{self, Token, TokenKind};
+
+use super::{scripts, sorted_jobs, step_path, Context, Language, Rule, Script};
+
+/// Functions whose result doesn't carry their arguments' text.
+const OPAQUE_FUNCTIONS: &[&str] = &["contains", "startsWith", "endsWith", "hashFiles"];
+
+/// Scripts with a value someone outside the repository writes, like
+/// `${{ github.event.issue.title }}` or `${{ github.head_ref }}`,
+/// interpolated into them. The expression is replaced with the text before
+/// the script runs, so a title of `"; curl ... | sh #` runs that command,
+/// with the job's token and secrets. Expressions that only compare the
+/// value, like `${{ contains(github.head_ref, 'release') }}`, are safe and
+/// aren't reported.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptInjection;
+
+/// Whether the expression's value can be the text of what it reads, rather
+/// than a boolean or a hash worked out from it.
+fn yields_text(tokens: &[Token]) -> bool {
+    tokens
+        .iter()
+        .enumerate()
+        .all(|(i, token)| match &token.kind {
+            TokenKind::Not
+            | TokenKind::Eq
+            | TokenKind::Ne
+            | TokenKind::Lt
+            | TokenKind::Le
+            | TokenKind::Gt
+            | TokenKind::Ge => false,
+            TokenKind::Ident(name) => {
+                let call = tokens
+                    .get(i + 1)
+                    .is_some_and(|t| t.kind == TokenKind::LParen);
+                !call
+                    || !OPAQUE_FUNCTIONS
+                        .iter()
+                        .any(|f| f.eq_ignore_ascii_case(name))
+            }
+            _ => true,
+        })
+}
+
+/// How `script` reads the environment variable `name`.
+fn variable(script: &Script, name: &str) -> String {
+    match script.language {
+        Language::Shell => format!("\"${}\"", name),
+        Language::PowerShell => format!("$env:{}", name),
+        Language::JavaScript => format!("process.env.{}", name),
+        Language::Arguments => format!("${}", name),
+    }
+}
+
+impl ScriptInjection {
+    fn check_script(&self, cx: &Context, script: &Script) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for embedded in expr::find_expressions(script.text) {
+            let tokens = match expr::tokenize(embedded.body) {
+                Ok(tokens) => tokens,
+                Err(_) => continue,
+            };
+            if !yields_text(&tokens) {
+                continue;
+            }
+            for reference in expr::references(&tokens) {
+                let who = match expr::untrusted(&reference.parts) {
+                    Some(who) => who,
+                    None => continue,
+                };
+                let value = &embedded.body[reference.offset..reference.offset + reference.len];
+                let name = reference
+                    .parts
+                    .last()
+                    .map(|part| part.to_ascii_uppercase())
+                    .unwrap_or_default();
+                diagnostics.push(
+                    cx.diagnostic_in(
+                        self,
+                        Severity::Error,
+                        &script.node,
+                        embedded.body_offset + reference.offset,
+                        format!(
+                            "`{}` is interpolated into {}, and {} writes it, so it can run \
+                             commands of their choosing",
+                            value, script.description, who
+                        ),
+                    )
+                    .with_message_id("interpolated")
+                    .with_param("value", value)
+                    .with_param("script", &script.description)
+                    .with_suggestion(
+                        Suggestion::new(format!(
+                            "pass it through `env` and read it as `{}`",
+                            variable(script, &name)
+                        ))
+                        .with_example(format!("env:\n  {}: ${{{{ {} }}}}", name, value)),
+                    ),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+impl Rule for ScriptInjection {
+    fn name(&self) -> &'static str {
+        "script-injection"
+    }
+
+    fn description(&self) -> &'static str {
+        "Values from outside the repository, like issue titles or branch names, interpolated \
+         into scripts"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let local_actions = cx.local_actions();
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                for script in scripts(step, &step_path(job_id, i), local_actions) {
+                    diagnostics.extend(self.check_script(cx, &script));
+                }
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use crate::action_ref::ActionRef;
+use crate::expr;
+use crate::spans::key_path;
+use crate::workflow::Step;
+
+use super::LocalAction;
+
+/// What runs a piece of code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Shell,
+    PowerShell,
+    JavaScript,
+    /// Arguments to a container's entrypoint, split on spaces.
+    Arguments,
+}
+
+impl Language {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Language::Shell => "a shell script",
+            Language::PowerShell => "a PowerShell script",
+            Language::JavaScript => "JavaScript",
+            Language::Arguments => "container arguments",
+        }
+    }
+}
+
+/// Code in a step that gets run: its `run`, or an input of an action that
+/// runs the input as code. Expressions in any of them are expanded before
+/// the code runs, so they're as open to injection as `run` is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script<'s> {
+    /// The path of the `run` or `with` value.
+    pub node: String,
+    pub text: &'s str,
+    pub language: Language,
+    /// How to refer to it in a message, like "the `script` input of
+    /// `actions/github-script`".
+    pub description: String,
+}
+
+/// Inputs of well-known actions that are run as code.
+const INTERPRETED_INPUTS: &[(&str, &str, Language)] = &[
+    ("actions/github-script", "script", Language::JavaScript),
+    ("appleboy/ssh-action", "script", Language::Shell),
+    ("azure/cli", "inlineScript", Language::Shell),
+    ("azure/powershell", "inlineScript", Language::PowerShell),
+    ("cypress-io/github-action", "command", Language::Shell),
+    (
+        "garygrossgarten/github-action-ssh",
+        "command",
+        Language::Shell,
+    ),
+    ("nick-fields/retry", "command", Language::Shell),
+];
+
+fn input<'s>(
+    step: &'s Step,
+    node: &str,
+    name: &str,
+    language: Language,
+    action: &str,
+) -> Option<Script<'s>> {
+    let text = step.with.get(name)?;
+    Some(Script {
+        node: key_path(node, &format!("with.{}", name)),
+        text,
+        language,
+        description: format!("the `{}` input of `{}`", name, action),
+    })
+}
+
+/// The code the step at `node` runs. `local_actions` are the local actions
+/// from [`local_actions`](super::local_actions), whose inputs count when a
+/// composite step interpolates them into its `run`.
+pub fn scripts<'s>(
+    step: &'s Step,
+    node: &str,
+    local_actions: &BTreeMap<String, LocalAction>,
+) -> Vec<Script<'s>> {
+    let mut scripts = Vec::new();
+    if let Some(run) = &step.run {
+        let language = match step.shell.as_deref() {
+            Some("pwsh") | Some("powershell") => Language::PowerShell,
+            _ => Language::Shell,
+        };
+        scripts.push(Script {
+            node: key_path(node, "run"),
+            text: run,
+            language,
+            description: "a script".to_string(),
+        });
+    }
+
+    match step.action() {
+        Some(Ok(ActionRef::Docker { .. })) => {
+            for name in ["args", "entrypoint"] {
+                scripts.extend(input(
+                    step,
+                    node,
+                    name,
+                    Language::Arguments,
+                    step.uses.as_deref().unwrap_or_default(),
+                ));
+            }
+        }
+        Some(Ok(ActionRef::Local { path })) => {
+            let action = match local_actions.get(&path) {
+                Some(local) => &local.action,
+                None => return scripts,
+            };
+            let mut names: Vec<&String> = step.with.keys().collect();
+            names.sort();
+            for name in names {
+                let reference = format!("inputs.{}", name);
+                let run = action.runs.steps.iter().find(|s| {
+                    s.run.as_deref().is_some_and(|run| {
+                        expr::find_expressions(run)
+                            .iter()
+                            .any(|e| e.body.contains(&reference))
+                    })
+                });
+                if let Some(run) = run {
+                    let language = match run.shell.as_deref() {
+                        Some("pwsh") | Some("powershell") => Language::PowerShell,
+                        _ => Language::Shell,
+                    };
+                    scripts.extend(input(step, node, name, language, &path));
+                }
+            }
+        }
+        _ => {
+            for &(action, name, language) in INTERPRETED_INPUTS {
+                if step.uses_action(action) {
+                    scripts.extend(input(step, node, name, language, action));
+                }
+            }
+        }
+    }
+    scripts
+}
@@ -0,0 +1,343 @@
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::expr;
+use crate::spans::key_path;
+use crate::workflow::{Job, Step};
+
+use super::log_hygiene::commands;
+use super::{for_each_string, job_path, sorted_jobs, step_path, Analysis, Context, Pinning, Rule};
+
+/// Owners whose actions are GitHub's own.
+const GITHUB_OWNERS: &[&str] = &["actions", "github"];
+
+/// Commands that print their arguments to the log.
+const PRINTS: &[&str] = &["echo", "printf", "write-host", "write-output"];
+
+/// Where a secret is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretPlace {
+    /// Interpolated into a step's script.
+    Run,
+    /// The value of an environment variable, by name.
+    Env(String),
+    /// An input of the action or reusable workflow, by name.
+    With(String),
+    /// Anywhere else, like a step's `if` or a job's `secrets`.
+    Other,
+}
+
+/// A `${{ secrets.<name> }}` in the workflow.
+#[derive(Debug, Clone)]
+pub struct SecretRef {
+    /// The secret's name, as written.
+    pub name: String,
+    /// The node the reference is in, and where it starts in the string.
+    pub path: String,
+    pub offset: usize,
+    /// The job and step it's in, unless it's in the workflow's `env`.
+    pub job: Option<String>,
+    pub step: Option<usize>,
+    pub place: SecretPlace,
+}
+
+impl SecretRef {
+    /// Whether it's the job's own token rather than a secret someone added.
+    pub fn is_github_token(&self) -> bool {
+        self.name.eq_ignore_ascii_case("GITHUB_TOKEN")
+    }
+}
+
+/// Where the string at `relative`, a path inside a job, sits: the step it's
+/// in, and how it's used.
+fn place(relative: &str) -> (Option<usize>, SecretPlace) {
+    let (step, field) = match relative.strip_prefix("steps[") {
+        Some(rest) => match rest.split_once("].") {
+            Some((index, field)) => (index.parse().ok(), field),
+            None => return (None, SecretPlace::Other),
+        },
+        None => (None, relative),
+    };
+    let place = match field.split_once('.') {
+        None if field == "run" && step.is_some() => SecretPlace::Run,
+        Some(("env", name)) => SecretPlace::Env(name.to_string()),
+        Some(("with", name)) => SecretPlace::With(name.to_string()),
+        _ => SecretPlace::Other,
+    };
+    (step, place)
+}
+
+fn push_refs(
+    refs: &mut Vec<SecretRef>,
+    path: &str,
+    value: &str,
+    job: Option<&str>,
+    (step, place): (Option<usize>, SecretPlace),
+) {
+    for embedded in expr::find_expressions(value) {
+        let tokens = match expr::tokenize(embedded.body) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+        for reference in expr::references(&tokens) {
+            if let [context, name, ..] = reference.parts.as_slice() {
+                if context.eq_ignore_ascii_case("secrets") && name != "*" {
+                    refs.push(SecretRef {
+                        name: name.clone(),
+                        path: path.to_string(),
+                        offset: embedded.body_offset + reference.offset,
+                        job: job.map(str::to_string),
+                        step,
+                        place: place.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Every `secrets.<name>` reference in the workflow's `env` and its jobs.
+pub(super) fn secret_refs(cx: &Context) -> Vec<SecretRef> {
+    let mut refs = Vec::new();
+    if let Some(env) = cx.document.get("env") {
+        for_each_string(env, "env", &mut |path, value| {
+            let name = path.strip_prefix("env.").unwrap_or_default();
+            let place = (None, SecretPlace::Env(name.to_string()));
+            push_refs(&mut refs, path, value, None, place);
+        });
+    }
+    for (job_id, _) in sorted_jobs(cx.workflow) {
+        let node = match cx.job_node(job_id) {
+            Some(node) => node,
+            None => continue,
+        };
+        let job = job_path(job_id);
+        let prefix = format!("{}.", job);
+        for_each_string(node, &job, &mut |path, value| {
+            let relative = path.strip_prefix(&prefix).unwrap_or_default();
+            push_refs(&mut refs, path, value, Some(job_id), place(relative));
+        });
+    }
+    refs
+}
+
+/// Whether `command` expands the environment variable `name`, whichever
+/// shell it's written for.
+fn expands(command: &str, name: &str) -> bool {
+    let bounded = |needle: &str| {
+        command.match_indices(needle).any(|(i, _)| {
+            !command[i + needle.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+        })
+    };
+    bounded(&format!("${}", name))
+        || bounded(&format!("$env:{}", name))
+        || command.contains(&format!("${{{}}}", name))
+        || command.contains(&format!("%{}%", name))
+}
+
+/// How the script reads the environment variable `name` in the step's
+/// shell.
+fn variable(cx: &Context, job: &Job, step: &Step, name: &str) -> String {
+    match cx.workflow.run_settings(job, step).shell_program() {
+        Some("pwsh") | Some("powershell") => format!("$env:{}", name),
+        Some("cmd") => format!("%{}%", name),
+        _ => format!("\"${}\"", name),
+    }
+}
+
+/// Secrets that can end up somewhere they shouldn't: interpolated straight
+/// into a script, printed to the log, or handed to a third-party action
+/// that isn't pinned to a commit. GitHub masks a secret's exact value in
+/// the log, but not one that's been encoded or split across lines, and an
+/// interpolated secret is written into the script file, where its quotes
+/// can end the string it's in. An action at a tag runs whatever its owner
+/// points the tag at next. Actions the pinning policy exempts, and GitHub's
+/// own, are trusted with secrets; so is the job's `GITHUB_TOKEN`, which
+/// only lasts as long as the job.
+#[derive(Debug, Clone, Default)]
+pub struct SecretLeak {
+    pub pinning: Pinning,
+}
+
+impl SecretLeak {
+    fn interpolated(&self, cx: &Context, reference: &SecretRef, env: &str) -> Diagnostic {
+        cx.diagnostic_in(
+            self,
+            Severity::Warning,
+            &reference.path,
+            reference.offset,
+            format!(
+                "`secrets.{}` is interpolated into the script, which writes it into the script \
+                 file and can break the quoting around it",
+                reference.name
+            ),
+        )
+        .with_message_id("run-interpolation")
+        .with_param("secret", &reference.name)
+        .with_suggestion(
+            Suggestion::new("pass it in an environment variable").with_example(format!(
+                "env:\n  {}: ${{{{ secrets.{} }}}}\nrun: ... {}",
+                reference.name.to_ascii_uppercase(),
+                reference.name,
+                env
+            )),
+        )
+    }
+
+    /// The secrets the step's script prints, each with its finding.
+    fn printed<'r>(
+        &self,
+        cx: &Context,
+        job_id: &str,
+        (index, step): (usize, &Step),
+        refs: &[&'r SecretRef],
+    ) -> Vec<(&'r str, Diagnostic)> {
+        let script = match &step.run {
+            Some(run) => run,
+            None => return Vec::new(),
+        };
+        let node = key_path(&step_path(job_id, index), "run");
+        let visible: Vec<(&str, &str)> = refs
+            .iter()
+            .filter(|r| r.step.is_none_or(|s| s == index))
+            .filter_map(|r| match &r.place {
+                SecretPlace::Env(variable) => Some((variable.as_str(), r.name.as_str())),
+                _ => None,
+            })
+            .collect();
+        let mut diagnostics = Vec::new();
+        for (line, command) in commands(script) {
+            let program = command.split_whitespace().next().unwrap_or_default();
+            if !PRINTS.contains(&program.to_ascii_lowercase().as_str())
+                || command.contains('|')
+                || command.contains('>')
+            {
+                continue;
+            }
+            let mut secrets: Vec<&str> = refs
+                .iter()
+                .filter(|r| r.step == Some(index) && r.place == SecretPlace::Run)
+                .filter(|r| command.contains(&format!("secrets.{}", r.name)))
+                .map(|r| r.name.as_str())
+                .collect();
+            secrets.extend(
+                visible
+                    .iter()
+                    .filter(|&&(variable, _)| expands(&command, variable))
+                    .map(|&(_, secret)| secret),
+            );
+            secrets.dedup();
+            let offset = script.lines().take(line).map(|l| l.len() + 1).sum();
+            for secret in secrets {
+                diagnostics.push((
+                    secret,
+                    cx.diagnostic_in(
+                        self,
+                        Severity::Warning,
+                        &node,
+                        offset,
+                        format!(
+                            "`{}` prints `secrets.{}` to the log, where it's only masked if it's \
+                             printed exactly as it's stored",
+                            program, secret
+                        ),
+                    )
+                    .with_message_id("printed")
+                    .with_param("secret", secret)
+                    .with_suggestion(Suggestion::new(
+                        "pipe it to the command that needs it instead, like \
+                         `--password-stdin`, or leave it out of the log",
+                    )),
+                ));
+            }
+        }
+        diagnostics
+    }
+
+    fn third_party(&self, cx: &Context, reference: &SecretRef, step: &Step) -> Option<Diagnostic> {
+        if reference.is_github_token() {
+            return None;
+        }
+        let action = match step.action() {
+            Some(Ok(action)) if !action.is_pinned() => action,
+            _ => return None,
+        };
+        if let ActionRef::Repository { owner, .. } = &action {
+            let owner = owner.to_ascii_lowercase();
+            if GITHUB_OWNERS.contains(&owner.as_str()) {
+                return None;
+            }
+        }
+        let name = action.name();
+        if self.pinning.exempts(&name) {
+            return None;
+        }
+        Some(
+            cx.diagnostic_in(
+                self,
+                Severity::Warning,
+                &reference.path,
+                reference.offset,
+                format!(
+                    "`secrets.{}` is passed to `{}`, a third-party action that isn't pinned to a \
+                     commit, so whoever can move its tag gets the secret",
+                    reference.name, name
+                ),
+            )
+            .with_message_id("third-party")
+            .with_param("secret", &reference.name)
+            .with_param("action", &name)
+            .with_suggestion(Suggestion::new(
+                "pin the action to the full commit SHA of a release you've reviewed",
+            )),
+        )
+    }
+}
+
+impl Rule for SecretLeak {
+    fn name(&self) -> &'static str {
+        "secret-leak"
+    }
+
+    fn description(&self) -> &'static str {
+        "Secrets interpolated into scripts, printed to the log, or passed to unpinned third-party \
+         actions"
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn requires(&self) -> &'static [Analysis] {
+        &[Analysis::Secrets]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let refs = cx.secret_refs();
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let in_job: Vec<&SecretRef> = refs
+                .iter()
+                .filter(|r| r.job.is_none() || r.job.as_deref() == Some(job_id))
+                .collect();
+            for (index, step) in job.steps.iter().enumerate() {
+                let printed = self.printed(cx, job_id, (index, step), &in_job);
+                let in_step = in_job.iter().filter(|r| r.step == Some(index));
+                for reference in in_step {
+                    match &reference.place {
+                        SecretPlace::Run if !printed.iter().any(|&(s, _)| s == reference.name) => {
+                            let name = reference.name.to_ascii_uppercase();
+                            let env = variable(cx, job, step, &name);
+                            diagnostics.push(self.interpolated(cx, reference, &env));
+                        }
+                        SecretPlace::Env(_) | SecretPlace::With(_) => {
+                            diagnostics.extend(self.third_party(cx, reference, step));
+                        }
+                        _ => {}
+                    }
+                }
+                diagnostics.extend(printed.into_iter().map(|(_, diagnostic)| diagnostic));
+            }
+        }
+        diagnostics
+    }
+}
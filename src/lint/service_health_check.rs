@@ -0,0 +1,188 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::workflow::{Service, Step};
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// Steps that connect to a service container without a health check. The
+/// runner only waits for services that have one, so without it the first
+/// query races the database starting up, which shows up as flaky tests.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceHealthCheck;
+
+/// Common service images: what their name contains, the port they listen
+/// on, the clients that talk to them and a health check that works.
+struct Known {
+    image: &'static str,
+    port: &'static str,
+    clients: &'static [&'static str],
+    health_cmd: &'static str,
+}
+
+const KNOWN: &[Known] = &[
+    Known {
+        image: "postgres",
+        port: "5432",
+        clients: &["psql", "pg_dump", "pg_restore"],
+        health_cmd: "pg_isready",
+    },
+    Known {
+        image: "mysql",
+        port: "3306",
+        clients: &["mysql", "mysqldump"],
+        health_cmd: "\"mysqladmin ping\"",
+    },
+    Known {
+        image: "mariadb",
+        port: "3306",
+        clients: &["mysql", "mariadb", "mysqldump"],
+        health_cmd: "\"healthcheck.sh --connect\"",
+    },
+    Known {
+        image: "redis",
+        port: "6379",
+        clients: &["redis-cli"],
+        health_cmd: "\"redis-cli ping\"",
+    },
+    Known {
+        image: "mongo",
+        port: "27017",
+        clients: &["mongosh", "mongo"],
+        health_cmd: "\"mongosh --eval 'db.runCommand({ping: 1})'\"",
+    },
+    Known {
+        image: "rabbitmq",
+        port: "5672",
+        clients: &["rabbitmqadmin"],
+        health_cmd: "\"rabbitmq-diagnostics -q ping\"",
+    },
+    Known {
+        image: "elasticsearch",
+        port: "9200",
+        clients: &[],
+        health_cmd: "\"curl -f localhost:9200/_cluster/health\"",
+    },
+];
+
+/// Commands that wait for a service themselves; steps after one are fine.
+const WAITS: &[&str] = &[
+    "pg_isready",
+    "wait-for-it",
+    "dockerize -wait",
+    "until ",
+    "nc -z",
+];
+
+fn known(service: &Service) -> Option<&'static Known> {
+    let image = service.image.as_deref()?.to_ascii_lowercase();
+    let name = image.rsplit('/').next().unwrap_or_default();
+    KNOWN.iter().find(|k| name.starts_with(k.image))
+}
+
+/// The ports the service can be reached on, as written and by default.
+fn ports(service: &Service) -> Vec<String> {
+    let mut ports: Vec<String> = service
+        .ports
+        .iter()
+        .flat_map(|p| p.split(':').map(|p| p.split('/').next().unwrap_or(p)))
+        .map(str::to_string)
+        .collect();
+    ports.extend(known(service).map(|k| k.port.to_string()));
+    ports
+}
+
+/// Whether `text` has `host:port`, as an address rather than part of a
+/// longer name or port.
+fn mentions(text: &str, host: &str, port: &str) -> bool {
+    let address = format!("{}:{}", host, port);
+    text.match_indices(&address).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + address.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+            && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+/// Whether a line of `run` starts with `command`.
+fn runs_command(run: &str, command: &str) -> bool {
+    run.lines().any(|line| {
+        line.split(['|', '&', ';'])
+            .map(str::trim)
+            .any(|part| part.split_whitespace().next() == Some(command))
+    })
+}
+
+/// Whether `step` connects to the service `name`.
+fn connects(step: &Step, name: &str, service: &Service) -> bool {
+    let run = step.run.as_deref().unwrap_or_default();
+    let texts: Vec<&str> = std::iter::once(run)
+        .chain(step.env.values().map(String::as_str))
+        .chain(step.with.values().map(String::as_str))
+        .collect();
+    let reference = format!("job.services.{}.", name);
+    let ports = ports(service);
+    let addressed = texts.iter().any(|text| {
+        text.contains(&reference)
+            || ports.iter().any(|port| {
+                ["localhost", "127.0.0.1", name]
+                    .iter()
+                    .any(|host| mentions(text, host, port))
+            })
+    });
+    addressed || known(service).is_some_and(|k| k.clients.iter().any(|c| runs_command(run, c)))
+}
+
+impl Rule for ServiceHealthCheck {
+    fn name(&self) -> &'static str {
+        "service-health-check"
+    }
+
+    fn description(&self) -> &'static str {
+        "Steps using a service container that has no health check"
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let unchecked: Vec<(&String, &Service)> = job
+                .services
+                .iter()
+                .filter(|(_, s)| s.health_check().is_none())
+                .filter(|(_, s)| s.image.as_deref().is_some_and(|i| !i.is_empty()))
+                .collect();
+            let mut reported = Vec::new();
+            for (i, step) in job.steps.iter().enumerate() {
+                for &(name, service) in &unchecked {
+                    if reported.contains(&name) || !connects(step, name, service) {
+                        continue;
+                    }
+                    reported.push(name);
+                    let health_cmd = match known(service) {
+                        Some(known) => format!("`--health-cmd {}`", known.health_cmd),
+                        None => "a `--health-cmd`".to_string(),
+                    };
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &step_path(job_id, i),
+                            format!(
+                                "this step connects to the `{}` service, which has no health \
+                                 check, so it can run before `{}` is ready; add {} to the \
+                                 service's `options`",
+                                name, name, health_cmd
+                            ),
+                        )
+                        .with_param("service", name)
+                        .with_param("health_cmd", health_cmd),
+                    );
+                }
+                // Steps after one that waits by hand are fine
+                let run = step.run.as_deref().unwrap_or_default();
+                if WAITS.iter().any(|wait| run.contains(wait)) {
+                    break;
+                }
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,117 @@
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::shell::{self, Shell};
+use crate::spans::key_path;
+
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// Problems in the `bash` and `sh` scripts of `run` steps, in the shell
+/// GitHub runs each with, going by the step's `shell` and the job's and
+/// workflow's `defaults`. With a `shellcheck` to run, its findings are
+/// reported, under their `SC` codes; without one, variables expanded
+/// without quotes are. Either way, a script of more than one line run by a
+/// `shell` that doesn't stop at a failing command, like `bash {0}`, and
+/// that doesn't `set -e` itself, is reported, since the step passes as long
+/// as its last command does. Off by default.
+#[derive(Debug, Clone, Default)]
+pub struct ShellScript {
+    /// The `shellcheck` to run, if there's one to use.
+    pub shellcheck: Option<String>,
+}
+
+impl Rule for ShellScript {
+    fn name(&self) -> &'static str {
+        "shell-script"
+    }
+
+    fn description(&self) -> &'static str {
+        "Problems in `run` scripts, from shellcheck or a few built-in checks"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut shellcheck = self.shellcheck.as_deref();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                let script = match &step.run {
+                    Some(run) => run,
+                    None => continue,
+                };
+                let effective = shell::effective(cx.workflow, job, step);
+                if effective.shell == Shell::Other {
+                    continue;
+                }
+                let node = key_path(&step_path(job_id, i), "run");
+                if !effective.errexit
+                    && script.trim().lines().count() > 1
+                    && !shell::script_sets_errexit(script)
+                {
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &node,
+                            format!(
+                                "this script runs with `{}`, which carries on past a failing \
+                                 command, so the step only fails if its last command does",
+                                effective.command
+                            ),
+                        )
+                        .with_message_id("errexit")
+                        .with_param("shell", &effective.command)
+                        .with_suggestion(
+                            Suggestion::new("stop at the first failing command")
+                                .with_example("run: |\n  set -e\n  ..."),
+                        ),
+                    );
+                }
+                let findings = match shellcheck {
+                    Some(program) => {
+                        match shell::shellcheck(program, script, effective.shell, &cx.budget) {
+                            Ok(findings) => findings,
+                            Err(error) => {
+                                diagnostics.push(
+                                    cx.diagnostic(
+                                        self,
+                                        Severity::Info,
+                                        "",
+                                        format!(
+                                            "`{}` couldn't be run ({}), so only the built-in \
+                                         checks were done",
+                                            program, error
+                                        ),
+                                    )
+                                    .with_message_id("shellcheck-unavailable")
+                                    .with_param("program", program),
+                                );
+                                shellcheck = None;
+                                shell::builtin_checks(script)
+                            }
+                        }
+                    }
+                    None => shell::builtin_checks(script),
+                };
+                for finding in findings {
+                    diagnostics.push(
+                        cx.diagnostic_in(
+                            self,
+                            finding.severity,
+                            &node,
+                            finding.offset,
+                            finding.message,
+                        )
+                        .with_message_id(&finding.code),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
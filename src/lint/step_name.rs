@@ -0,0 +1,180 @@
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Edit, Fix, Severity};
+use crate::workflow::Step;
+
+use super::ambiguous_scalar::YAML_1_1_BOOLEANS;
+use super::{sorted_jobs, step_path, Context, Rule};
+
+/// Steps without a `name` show up in the run log as `Run owner/action@v1` or
+/// the first line of their script, which isn't much help when scanning for
+/// the step that failed.
+///
+/// This is a matter of taste, so it isn't enabled by default.
+#[derive(Debug, Clone)]
+pub struct StepName {
+    /// `run` steps shorter than this many lines are allowed to go without a
+    /// name, since a one-liner usually describes itself.
+    pub min_run_lines: usize,
+
+    /// Actions that don't need a name, matched as prefixes of `uses`
+    /// (e.g. `actions/checkout`).
+    pub exempt: Vec<String>,
+}
+
+impl Default for StepName {
+    fn default() -> Self {
+        StepName {
+            min_run_lines: 1,
+            exempt: Vec::new(),
+        }
+    }
+}
+
+/// Which steps can go without a name, from the `[step-name]` table of the
+/// config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct StepNames {
+    /// 1 if it isn't set.
+    pub min_run_lines: Option<usize>,
+    pub exempt: Vec<String>,
+}
+
+impl StepNames {
+    /// `local` on top of `self`.
+    pub fn merge(self, local: StepNames) -> StepNames {
+        let mut exempt = self.exempt;
+        for prefix in local.exempt {
+            if !exempt.contains(&prefix) {
+                exempt.push(prefix);
+            }
+        }
+        StepNames {
+            min_run_lines: local.min_run_lines.or(self.min_run_lines),
+            exempt,
+        }
+    }
+}
+
+impl From<&StepNames> for StepName {
+    fn from(names: &StepNames) -> Self {
+        let default = StepName::default();
+        StepName {
+            min_run_lines: names.min_run_lines.unwrap_or(default.min_run_lines),
+            exempt: names.exempt.clone(),
+        }
+    }
+}
+
+impl StepName {
+    fn needs_name(&self, step: &Step) -> bool {
+        if step.name.is_some() {
+            return false;
+        }
+        match &step.run {
+            Some(run) => run.lines().count() >= self.min_run_lines,
+            None => !self.exempt.iter().any(|e| {
+                step.uses
+                    .as_deref()
+                    .is_some_and(|u| u.starts_with(e.as_str()))
+            }),
+        }
+    }
+}
+
+impl Rule for StepName {
+    fn name(&self) -> &'static str {
+        "step-name"
+    }
+
+    fn description(&self) -> &'static str {
+        "Steps without a `name`"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            for (i, step) in job.steps.iter().enumerate() {
+                if !self.needs_name(step) {
+                    continue;
+                }
+                let path = step_path(job_id, i);
+                let mut diagnostic =
+                    cx.diagnostic(self, Severity::Warning, &path, "step has no `name`");
+                if let Some(fix) = name_fix(cx, &path, step) {
+                    diagnostic = diagnostic.with_fix(fix);
+                }
+                diagnostics.push(diagnostic);
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Insert a `name:` as the first key of the step, lined up with the others.
+fn name_fix(cx: &Context, path: &str, step: &Step) -> Option<Fix> {
+    let start = cx.spans.get(path)?.value;
+    // Only block mappings; `- { uses: ... }` would need a different edit
+    if cx.source[start.offset..].starts_with('{') {
+        return None;
+    }
+    let name = derive_name(step)?;
+    let indent = " ".repeat(start.column - 1);
+    Some(Fix {
+        description: format!("name the step {}", name),
+        edits: vec![Edit::insert(
+            start.offset,
+            format!("name: {}\n{}", quote(&name), indent),
+        )],
+    })
+}
+
+/// Come up with a name from the first line of the script, or the action's
+/// name for `uses` steps.
+fn derive_name(step: &Step) -> Option<String> {
+    if let Some(run) = &step.run {
+        let line = run.lines().map(str::trim).find(|l| !l.is_empty())?;
+        let line = line.trim_end_matches('\\').trim_end();
+        return Some(match line.char_indices().nth(60) {
+            Some((i, _)) => format!("{}...", &line[..i]),
+            None => line.to_string(),
+        });
+    }
+
+    let action = match step.action()?.ok()? {
+        ActionRef::Repository { repo, path, .. } => match path {
+            Some(path) => path.rsplit('/').next().unwrap_or_default().to_string(),
+            None => repo,
+        },
+        ActionRef::Local { path } => path.trim_end_matches('/').rsplit('/').next()?.to_string(),
+        ActionRef::Docker { image, .. } => image.rsplit('/').next()?.to_string(),
+    };
+    let words = action.replace(['-', '_'], " ");
+    let mut chars = words.trim().chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+/// Quote a string for use as a YAML scalar if it needs it, including when
+/// it would be read as something else, like `true`, `null` or `123`.
+fn quote(s: &str) -> String {
+    let special = s.starts_with(|c: char| "!&*-?[]{}|>'\"%@`#,".contains(c))
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.ends_with(':')
+        || s != s.trim()
+        || YAML_1_1_BOOLEANS.contains(&s)
+        || !matches!(serde_yaml::from_str(s), Ok(Value::String(_)));
+    if special {
+        format!("'{}'", s.replace('\'', "''"))
+    } else {
+        s.to_string()
+    }
+}
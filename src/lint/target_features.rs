@@ -0,0 +1,62 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::schema::{self, Target};
+
+use super::{Context, Rule};
+
+/// Features the server the workflow is for doesn't have yet, which make
+/// the workflow fail to run or behave differently. Set `target` to the
+/// GHES release with `--target ghes-3.10`; on github.com everything is
+/// available.
+#[derive(Debug, Clone, Default)]
+pub struct TargetFeatures {
+    pub target: Target,
+}
+
+impl Rule for TargetFeatures {
+    fn name(&self) -> &'static str {
+        "target-features"
+    }
+
+    fn description(&self) -> &'static str {
+        "Features the GHES release the workflow is for doesn't have"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for used in schema::features_used(cx.document) {
+            if self.target.supports(&used.feature) {
+                continue;
+            }
+            let (id, message) = match used.feature.ghes {
+                Some((major, minor)) => (
+                    "too-new",
+                    format!(
+                        "{} needs GHES {}.{} or later, but this workflow is for {}",
+                        used.feature.name, major, minor, self.target
+                    ),
+                ),
+                None => (
+                    "not-on-ghes",
+                    format!(
+                        "{} isn't available on GHES, which this workflow is for",
+                        used.feature.name
+                    ),
+                ),
+            };
+            let mut diagnostic = cx
+                .diagnostic(self, Severity::Error, &used.node, message)
+                .with_message_id(id)
+                .with_param("feature", used.feature.name)
+                .with_param("target", self.target);
+            if let Some((major, minor)) = used.feature.ghes {
+                diagnostic = diagnostic.with_param("since", format!("{}.{}", major, minor));
+            }
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,461 @@
+//! Rules for jobs that run Terraform, OpenTofu or Terragrunt. They're off by
+//! default and turned on together with `--enable terraform`.
+
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::spans::key_path;
+use crate::workflow::{AllAccess, Job, Permissions, Step};
+
+use super::{event_names, job_path, sorted_jobs, step_path, Context, Rule};
+
+/// The group the rules here are enabled by.
+pub const GROUP: &str = "terraform";
+
+/// The programs that run plans and applies.
+const PROGRAMS: &[&str] = &["terraform", "tofu", "terragrunt"];
+
+/// Actions that run a plan or an apply.
+const PLAN_ACTIONS: &[&str] = &["dflook/terraform-plan", "dflook/tofu-plan"];
+const APPLY_ACTIONS: &[&str] = &[
+    "dflook/terraform-apply",
+    "dflook/terraform-destroy",
+    "dflook/tofu-apply",
+    "dflook/tofu-destroy",
+];
+
+/// Inputs of cloud login actions that pick the identity a job acts as.
+const IDENTITY_INPUTS: &[(&str, &str)] = &[
+    ("aws-actions/configure-aws-credentials", "role-to-assume"),
+    ("azure/login", "client-id"),
+    ("google-github-actions/auth", "service_account"),
+];
+
+/// Environment variables and action inputs that hold credentials for a
+/// state backend or the provider it's in.
+const CREDENTIAL_VARIABLES: &[&str] = &[
+    "ARM_ACCESS_KEY",
+    "ARM_CLIENT_CERTIFICATE_PASSWORD",
+    "ARM_CLIENT_SECRET",
+    "ARM_SAS_TOKEN",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+    "GOOGLE_CREDENTIALS",
+    "GOOGLE_BACKEND_CREDENTIALS",
+    "TF_HTTP_PASSWORD",
+    "TF_TOKEN_app_terraform_io",
+];
+const CREDENTIAL_INPUTS: &[(&str, &str)] = &[
+    ("aws-actions/configure-aws-credentials", "aws-access-key-id"),
+    (
+        "aws-actions/configure-aws-credentials",
+        "aws-secret-access-key",
+    ),
+    ("google-github-actions/auth", "credentials_json"),
+    ("hashicorp/setup-terraform", "cli_config_credentials_token"),
+];
+
+/// `-backend-config` settings that are credentials.
+const BACKEND_SECRETS: &[&str] = &[
+    "access_key",
+    "secret_key",
+    "token",
+    "password",
+    "client_secret",
+    "sas_token",
+    "credentials",
+];
+
+/// Whether `run` has `program` run `command`, like `terraform plan` or
+/// `terraform -chdir=infra plan`.
+fn runs(run: &str, command: &str) -> bool {
+    run.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        while let Some(word) = words.next() {
+            if !PROGRAMS.contains(&word) {
+                continue;
+            }
+            let rest: Vec<&str> = words
+                .clone()
+                .skip_while(|w| w.starts_with('-') || *w == "run-all")
+                .take(1)
+                .collect();
+            if rest.first() == Some(&command) {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+fn applies(step: &Step) -> bool {
+    APPLY_ACTIONS.iter().any(|action| step.uses_action(action))
+        || step
+            .run
+            .as_deref()
+            .is_some_and(|run| runs(run, "apply") || runs(run, "destroy"))
+}
+
+fn plans(step: &Step) -> bool {
+    PLAN_ACTIONS.iter().any(|action| step.uses_action(action))
+        || step.run.as_deref().is_some_and(|run| runs(run, "plan"))
+}
+
+fn is_plan_job(job: &Job) -> bool {
+    job.steps.iter().any(plans) && !job.steps.iter().any(applies)
+}
+
+fn is_apply_job(job: &Job) -> bool {
+    job.steps.iter().any(applies)
+}
+
+fn runs_terraform(job: &Job) -> bool {
+    job.steps.iter().any(|step| {
+        plans(step)
+            || applies(step)
+            || step.uses_action("hashicorp/setup-terraform")
+            || step.uses_action("opentofu/setup-opentofu")
+            || step.run.as_deref().is_some_and(|run| runs(run, "init"))
+    })
+}
+
+/// The identities a job logs in to its cloud as, as each login action and
+/// the value that picks the identity.
+fn identities(job: &Job) -> Vec<(&'static str, &str)> {
+    job.steps
+        .iter()
+        .flat_map(|step| {
+            IDENTITY_INPUTS
+                .iter()
+                .filter(move |(action, _)| step.uses_action(action))
+                .filter_map(move |&(action, input)| {
+                    step.with.get(input).map(|value| (action, value.as_str()))
+                })
+        })
+        .collect()
+}
+
+/// Plan jobs that run for pull requests with what an apply needs: a token
+/// that can write, the identity an apply job logs in as, or an apply.
+/// Anyone who can open a pull request can change what a plan runs, through
+/// the configuration or a provider, so a plan job should only be able to
+/// read.
+#[derive(Debug, Clone, Default)]
+pub struct TerraformPlanPermissions;
+
+/// Scopes plan jobs often need to write: comments with the plan, and OIDC.
+const PLAN_WRITE_SCOPES: &[&str] = &["id-token", "pull-requests", "issues"];
+
+impl Rule for TerraformPlanPermissions {
+    fn name(&self) -> &'static str {
+        "terraform-plan-permissions"
+    }
+
+    fn description(&self) -> &'static str {
+        "Terraform plans for pull requests that can do what an apply can"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let events = event_names(cx.document);
+        if !events
+            .iter()
+            .any(|e| *e == "pull_request" || *e == "pull_request_target")
+        {
+            return Vec::new();
+        }
+        let jobs = sorted_jobs(cx.workflow);
+        let mut diagnostics = Vec::new();
+        for &(job_id, job) in &jobs {
+            let path = job_path(job_id);
+            // An `if` usually keeps the apply to pushes
+            let ungated_apply = job
+                .steps
+                .iter()
+                .position(|step| applies(step) && step.run_if.is_none());
+            if let (None, Some(i)) = (&job.run_if, ungated_apply) {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &step_path(job_id, i),
+                        "this job runs an apply for pull requests, so a pull request can \
+                         change infrastructure before anyone reviews it",
+                    )
+                    .with_message_id("apply-on-pull-request"),
+                );
+                continue;
+            }
+            if !is_plan_job(job) {
+                continue;
+            }
+
+            let permissions = cx.workflow.permissions_for(job);
+            let writes: Vec<&str> = match permissions {
+                None => vec!["the repository default"],
+                Some(Permissions::All(AllAccess::WriteAll)) => vec!["write-all"],
+                Some(permissions) => permissions
+                    .writes()
+                    .into_iter()
+                    .filter(|scope| !PLAN_WRITE_SCOPES.contains(scope))
+                    .collect(),
+            };
+            if !writes.is_empty() {
+                let message = if permissions.is_none() {
+                    "this plan job runs for pull requests with the default token permissions, \
+                     which can include write; give it `contents: read`"
+                        .to_string()
+                } else {
+                    format!(
+                        "this plan job runs for pull requests with a token that can write \
+                         {}; plans only need to read",
+                        writes.join(", ")
+                    )
+                };
+                diagnostics.push(
+                    cx.diagnostic(self, Severity::Warning, &path, message)
+                        .with_message_id("write-token")
+                        .with_param("scopes", writes.join(", ")),
+                );
+            }
+
+            let apply_identities: Vec<(&str, &str, &str)> = jobs
+                .iter()
+                .filter(|(_, other)| is_apply_job(other))
+                .flat_map(|&(other_id, other)| {
+                    identities(other)
+                        .into_iter()
+                        .map(move |(action, identity)| (other_id, action, identity))
+                })
+                .collect();
+            for (action, identity) in identities(job) {
+                if let Some((apply_job, _, _)) = apply_identities
+                    .iter()
+                    .find(|(_, a, i)| *a == action && *i == identity)
+                {
+                    let message = format!(
+                        "this plan job runs for pull requests as `{}`, the identity the apply \
+                         job `{}` uses; give plans a read-only identity",
+                        identity, apply_job
+                    );
+                    diagnostics.push(
+                        cx.diagnostic(self, Severity::Warning, &path, message)
+                            .with_message_id("apply-identity")
+                            .with_param("identity", identity)
+                            .with_param("apply_job", *apply_job),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Apply jobs without an `environment` or a `concurrency` group. The
+/// environment is where reviews and branch rules hold applies back, and
+/// without a concurrency group two runs can apply at once and fight over
+/// the state lock, or apply an older plan over a newer one.
+#[derive(Debug, Clone, Default)]
+pub struct TerraformApplyProtection;
+
+impl Rule for TerraformApplyProtection {
+    fn name(&self) -> &'static str {
+        "terraform-apply-protection"
+    }
+
+    fn description(&self) -> &'static str {
+        "Terraform applies without an `environment` or `concurrency` group"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            if !is_apply_job(job) {
+                continue;
+            }
+            let node = cx.job_node(job_id);
+            let path = job_path(job_id);
+            if node.and_then(|job| job.get("environment")).is_none() {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Warning,
+                        &path,
+                        "this job applies without an `environment`, so nothing like required \
+                         reviewers or branch rules holds it back",
+                    )
+                    .with_message_id("missing-environment"),
+                );
+            }
+            if cx.workflow.concurrency_for(job).is_none() {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Warning,
+                        &path,
+                        "this job applies without a `concurrency` group, so two runs can apply \
+                         at once",
+                    )
+                    .with_message_id("missing-concurrency")
+                    .with_suggestion(
+                        Suggestion::new(
+                            "add a group for the state it applies to, without cancelling \
+                             an apply that's underway",
+                        )
+                        .with_example(format!(
+                            "concurrency:\n  group: terraform-{}\n  cancel-in-progress: false",
+                            job_id
+                        )),
+                    ),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Credentials for a state backend or provider written into the workflow
+/// rather than coming from `secrets` or an OIDC login.
+#[derive(Debug, Clone, Default)]
+pub struct TerraformCredentials;
+
+/// Whether `value` is written out rather than coming from an expression.
+fn is_literal(value: &str) -> bool {
+    !value.trim().is_empty() && !value.contains("${{")
+}
+
+impl TerraformCredentials {
+    fn literal(&self, cx: &Context, path: &str, what: &str) -> Diagnostic {
+        let message = format!(
+            "{} is written into the workflow, where anyone who can read the repository sees \
+             it; use a secret or log in with OIDC",
+            what
+        );
+        cx.diagnostic(self, Severity::Error, path, message)
+            .with_message_id("literal-credential")
+            .with_param("credential", what)
+    }
+
+    fn check_env(
+        &self,
+        cx: &Context,
+        env: &crate::workflow::Env,
+        node: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let mut names: Vec<&String> = env.keys().collect();
+        names.sort();
+        for name in names {
+            let credential =
+                CREDENTIAL_VARIABLES.contains(&name.as_str()) || name.starts_with("TF_TOKEN_");
+            if credential && is_literal(&env[name]) {
+                let path = key_path(&key_path(node, "env"), name);
+                diagnostics.push(self.literal(cx, &path, &format!("`{}`", name)));
+            }
+        }
+    }
+}
+
+impl Rule for TerraformCredentials {
+    fn name(&self) -> &'static str {
+        "terraform-credentials"
+    }
+
+    fn description(&self) -> &'static str {
+        "Terraform backend and provider credentials written into the workflow"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(GROUP)
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let jobs: Vec<_> = sorted_jobs(cx.workflow)
+            .into_iter()
+            .filter(|(_, job)| runs_terraform(job))
+            .collect();
+        if jobs.is_empty() {
+            return diagnostics;
+        }
+        if let Some(env) = &cx.workflow.env {
+            self.check_env(cx, env, "", &mut diagnostics);
+        }
+        for (job_id, job) in jobs {
+            let path = job_path(job_id);
+            self.check_env(cx, &job.env, &path, &mut diagnostics);
+            for (i, step) in job.steps.iter().enumerate() {
+                let node = step_path(job_id, i);
+                self.check_env(cx, &step.env, &node, &mut diagnostics);
+                for (action, input) in CREDENTIAL_INPUTS {
+                    let value = match step.with.get(*input) {
+                        Some(value) if step.uses_action(action) => value,
+                        _ => continue,
+                    };
+                    if is_literal(value) {
+                        let path = key_path(&key_path(&node, "with"), input);
+                        let what = format!("`{}`", input);
+                        diagnostics.push(self.literal(cx, &path, &what));
+                    }
+                }
+                let run = match step.run.as_deref() {
+                    Some(run) => run,
+                    None => continue,
+                };
+                for setting in run.split("-backend-config").skip(1) {
+                    let setting = setting
+                        .trim_start_matches(['=', ' '])
+                        .trim_start_matches(['"', '\'']);
+                    let key = setting.split('=').next().unwrap_or_default().trim();
+                    let value = setting[key.len()..]
+                        .trim_start_matches('=')
+                        .split(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+                        .next()
+                        .unwrap_or_default();
+                    if BACKEND_SECRETS.contains(&key)
+                        && is_literal(value)
+                        && !value.starts_with('$')
+                    {
+                        let what = format!("the backend's `{}`", key);
+                        diagnostics.push(self.literal(cx, &key_path(&node, "run"), &what));
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,160 @@
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::spans::key_path;
+use crate::workflow::{AllAccess, Job, JobKind, Permissions};
+
+use super::runner_tools::commands;
+use super::{event_names, job_path, sorted_jobs, Context, Rule};
+
+/// Actions that write to the repository, its issues or pull requests, or
+/// its packages with the job's token.
+const WRITING_ACTIONS: &[&str] = &[
+    "actions/attest-build-provenance",
+    "actions/create-release",
+    "actions/deploy-pages",
+    "actions/github-script",
+    "actions/labeler",
+    "actions/stale",
+    "docker/login-action",
+    "github/codeql-action/analyze",
+    "github/codeql-action/upload-sarif",
+    "googleapis/release-please-action",
+    "ncipollo/release-action",
+    "peter-evans/create-or-update-comment",
+    "peter-evans/create-pull-request",
+    "release-drafter/release-drafter",
+    "softprops/action-gh-release",
+    "stefanzweifel/git-auto-commit-action",
+];
+
+const EXAMPLE: &str = "permissions:\n  contents: read";
+
+/// Workflows that leave the `GITHUB_TOKEN` with the repository's default
+/// permissions, which for many repositories is write access to most
+/// scopes, and ones that grant `write-all` when nothing in the job looks
+/// like it writes. A token that can only read is one less thing a
+/// compromised step can use. Reusable workflows get their caller's
+/// permissions, so they aren't reported for leaving them out.
+#[derive(Debug, Clone, Default)]
+pub struct TokenPermissions;
+
+/// Whether a step of `job` looks like it writes with the token: an action
+/// that does, `gh`, or `git push`. A job calling a reusable workflow might
+/// do anything.
+fn writes_with_token(job: &Job) -> bool {
+    if let JobKind::Call { .. } = job.kind() {
+        return true;
+    }
+    job.steps.iter().any(|step| {
+        WRITING_ACTIONS.iter().any(|&name| step.uses_action(name))
+            || step
+                .run
+                .as_deref()
+                .is_some_and(|run| run.contains("git push") || commands(run).contains(&"gh"))
+    })
+}
+
+impl TokenPermissions {
+    fn write_all(&self, cx: &Context, node: &str, scope: &str) -> Diagnostic {
+        cx.diagnostic(
+            self,
+            Severity::Warning,
+            node,
+            format!(
+                "{} grant `write-all`, but no step looks like it writes with the token",
+                scope
+            ),
+        )
+        .with_message_id("write-all")
+        .with_suggestion(
+            Suggestion::new("grant only what the jobs need, like reading the contents")
+                .with_example(EXAMPLE),
+        )
+    }
+}
+
+impl Rule for TokenPermissions {
+    fn name(&self) -> &'static str {
+        "token-permissions"
+    }
+
+    fn description(&self) -> &'static str {
+        "Workflows that leave the token's permissions at the repository default, or grant \
+         `write-all` when nothing writes"
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let workflow = cx.workflow;
+        let jobs = sorted_jobs(workflow);
+        let mut diagnostics = Vec::new();
+
+        let reusable = event_names(cx.document) == ["workflow_call"];
+        if workflow.permissions.is_none() && !reusable {
+            let unset: Vec<&str> = jobs
+                .iter()
+                .filter(|(_, job)| job.permissions.is_none())
+                .map(|&(id, _)| id)
+                .collect();
+            if unset.len() == jobs.len() && !jobs.is_empty() {
+                diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Warning,
+                        "",
+                        "the workflow doesn't set `permissions`, so its jobs get the \
+                         repository's default token permissions, which can include write access",
+                    )
+                    .with_message_id("default")
+                    .with_suggestion(
+                        Suggestion::new("set read-only permissions for the workflow")
+                            .with_example(EXAMPLE),
+                    ),
+                );
+            } else {
+                for job_id in unset {
+                    diagnostics.push(
+                        cx.diagnostic(
+                            self,
+                            Severity::Warning,
+                            &job_path(job_id),
+                            format!(
+                                "job `{}` doesn't set `permissions`, and neither does the \
+                                 workflow, so it gets the repository's default token permissions",
+                                job_id
+                            ),
+                        )
+                        .with_message_id("default-job")
+                        .with_param("job", job_id)
+                        .with_suggestion(
+                            Suggestion::new("set the permissions it needs").with_example(EXAMPLE),
+                        ),
+                    );
+                }
+            }
+        }
+
+        let write_all =
+            |p: &Option<Permissions>| matches!(p, Some(Permissions::All(AllAccess::WriteAll)));
+        if write_all(&workflow.permissions) {
+            let inheriting: Vec<&Job> = jobs
+                .iter()
+                .filter(|(_, job)| job.permissions.is_none())
+                .map(|&(_, job)| job)
+                .collect();
+            if !inheriting.is_empty() && !inheriting.iter().any(|job| writes_with_token(job)) {
+                diagnostics.push(self.write_all(cx, "permissions", "the workflow's `permissions`"));
+            }
+        }
+        for (job_id, job) in &jobs {
+            if write_all(&job.permissions) && !writes_with_token(job) {
+                let node = key_path(&job_path(job_id), "permissions");
+                let scope = format!("job `{}`'s `permissions`", job_id);
+                diagnostics.push(self.write_all(cx, &node, &scope).with_param("job", *job_id));
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,262 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+use crate::workflow::{Event, Extra};
+
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// Keys that aren't part of the workflow syntax as far as this linter
+/// knows. They're kept rather than rejected, since GitHub adds keys faster
+/// than the model here keeps up, but most of them are typos like `run_on`
+/// that GitHub will refuse.
+#[derive(Debug, Clone, Default)]
+pub struct UnknownKey;
+
+// The keys GitHub documents for each object, including the ones the model
+// doesn't have fields for yet and so end up in `extra`.
+const WORKFLOW_KEYS: &[&str] = &[
+    "concurrency",
+    "defaults",
+    "env",
+    "jobs",
+    "name",
+    "on",
+    "permissions",
+    "run-name",
+];
+const JOB_KEYS: &[&str] = &[
+    "concurrency",
+    "container",
+    "continue-on-error",
+    "defaults",
+    "env",
+    "environment",
+    "if",
+    "name",
+    "needs",
+    "outputs",
+    "permissions",
+    "runs-on",
+    "secrets",
+    "services",
+    "steps",
+    "strategy",
+    "timeout-minutes",
+    "uses",
+    "with",
+];
+const STEP_KEYS: &[&str] = &[
+    "continue-on-error",
+    "env",
+    "id",
+    "if",
+    "name",
+    "run",
+    "shell",
+    "timeout-minutes",
+    "uses",
+    "with",
+    "working-directory",
+];
+const CONTAINER_KEYS: &[&str] = &["credentials", "env", "image", "options", "ports", "volumes"];
+const STRATEGY_KEYS: &[&str] = &["fail-fast", "matrix", "max-parallel"];
+const ENVIRONMENT_KEYS: &[&str] = &["name", "url"];
+const DEFAULTS_KEYS: &[&str] = &["run"];
+const RUN_DEFAULTS_KEYS: &[&str] = &["shell", "working-directory"];
+const DISPATCH_INPUT_KEYS: &[&str] = &["default", "description", "options", "required", "type"];
+const CALL_INPUT_KEYS: &[&str] = &["default", "description", "required", "type"];
+const CALL_SECRET_KEYS: &[&str] = &["description", "required"];
+const CALL_OUTPUT_KEYS: &[&str] = &["description", "value"];
+
+/// The number of single-character edits between `a` and `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The known key `key` was most likely meant to be.
+pub fn closest<'k>(key: &str, known: &[&'k str]) -> Option<&'k str> {
+    let normalized = key.to_ascii_lowercase().replace('_', "-");
+    known
+        .iter()
+        .map(|&k| (edit_distance(&normalized, k), k))
+        .filter(|&(distance, k)| distance <= 2 && distance < k.len())
+        .min()
+        .map(|(_, k)| k)
+}
+
+impl UnknownKey {
+    fn check_extra(
+        &self,
+        cx: &Context,
+        extra: &Extra,
+        known: &[&str],
+        node: &str,
+        what: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        for key in extra.keys().filter(|key| !known.contains(&key.as_str())) {
+            let path = key_path(node, key);
+            let diagnostic = match closest(key, known) {
+                Some(suggestion) => cx
+                    .diagnostic(
+                        self,
+                        Severity::Info,
+                        &path,
+                        format!(
+                            "`{}` isn't a key of {}; did you mean `{}`?",
+                            key, what, suggestion
+                        ),
+                    )
+                    .with_message_id("typo")
+                    .with_param("suggestion", suggestion),
+                None => cx
+                    .diagnostic(
+                        self,
+                        Severity::Info,
+                        &path,
+                        format!(
+                            "`{}` isn't a key of {} that this linter knows; it's left unchecked",
+                            key, what
+                        ),
+                    )
+                    .with_message_id("unknown"),
+            };
+            diagnostics.push(diagnostic.with_param("key", key).with_param("object", what));
+        }
+    }
+}
+
+impl Rule for UnknownKey {
+    fn name(&self) -> &'static str {
+        "unknown-key"
+    }
+
+    fn description(&self) -> &'static str {
+        "Keys that aren't part of the workflow syntax, often typos"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let d = &mut diagnostics;
+        let workflow = cx.workflow;
+        self.check_extra(cx, &workflow.extra, WORKFLOW_KEYS, "", "a workflow", d);
+        if let Some(defaults) = &workflow.defaults {
+            self.check_extra(
+                cx,
+                &defaults.extra,
+                DEFAULTS_KEYS,
+                "defaults",
+                "`defaults`",
+                d,
+            );
+            let node = "defaults.run";
+            self.check_extra(cx, &defaults.run.extra, RUN_DEFAULTS_KEYS, node, "`run`", d);
+        }
+
+        for (name, event) in &workflow.on {
+            let node = key_path("on", name);
+            if let Some((extra, known)) = event.extra() {
+                let what = format!("the `{}` event", name);
+                self.check_extra(cx, extra, known, &node, &what, d);
+            }
+            match event {
+                Event::WorkflowDispatch(Some(settings)) => {
+                    let inputs = key_path(&node, "inputs");
+                    for (input, settings) in &settings.inputs {
+                        let node = key_path(&inputs, input);
+                        let what = format!("the input `{}`", input);
+                        let extra = &settings.extra;
+                        self.check_extra(cx, extra, DISPATCH_INPUT_KEYS, &node, &what, d);
+                    }
+                }
+                Event::WorkflowCall(Some(settings)) => {
+                    let inputs = key_path(&node, "inputs");
+                    for (input, settings) in &settings.inputs {
+                        let node = key_path(&inputs, input);
+                        let what = format!("the input `{}`", input);
+                        let extra = &settings.extra;
+                        self.check_extra(cx, extra, CALL_INPUT_KEYS, &node, &what, d);
+                    }
+                    let secrets = key_path(&node, "secrets");
+                    for (secret, settings) in &settings.secrets {
+                        if let Some(settings) = settings {
+                            let node = key_path(&secrets, secret);
+                            let what = format!("the secret `{}`", secret);
+                            let extra = &settings.extra;
+                            self.check_extra(cx, extra, CALL_SECRET_KEYS, &node, &what, d);
+                        }
+                    }
+                    let outputs = key_path(&node, "outputs");
+                    for (output, settings) in &settings.outputs {
+                        let node = key_path(&outputs, output);
+                        let what = format!("the output `{}`", output);
+                        let extra = &settings.extra;
+                        self.check_extra(cx, extra, CALL_OUTPUT_KEYS, &node, &what, d);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (job_id, job) in sorted_jobs(workflow) {
+            let path = job_path(job_id);
+            self.check_extra(cx, &job.extra, JOB_KEYS, &path, "a job", d);
+            if let Some(defaults) = &job.defaults {
+                let node = key_path(&path, "defaults");
+                self.check_extra(cx, &defaults.extra, DEFAULTS_KEYS, &node, "`defaults`", d);
+                let node = key_path(&node, "run");
+                self.check_extra(
+                    cx,
+                    &defaults.run.extra,
+                    RUN_DEFAULTS_KEYS,
+                    &node,
+                    "`run`",
+                    d,
+                );
+            }
+            if let Some(strategy) = &job.strategy {
+                let node = key_path(&path, "strategy");
+                self.check_extra(cx, &strategy.extra, STRATEGY_KEYS, &node, "`strategy`", d);
+            }
+            if let Some(environment) = &job.environment {
+                let node = key_path(&path, "environment");
+                let what = "`environment`";
+                self.check_extra(cx, &environment.extra, ENVIRONMENT_KEYS, &node, what, d);
+            }
+            if let Some(container) = &job.container {
+                let node = key_path(&path, "container");
+                self.check_extra(
+                    cx,
+                    &container.extra,
+                    CONTAINER_KEYS,
+                    &node,
+                    "`container`",
+                    d,
+                );
+            }
+            for (name, service) in &job.services {
+                let node = key_path(&key_path(&path, "services"), name);
+                self.check_extra(cx, &service.extra, CONTAINER_KEYS, &node, "a service", d);
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                let node = step_path(job_id, i);
+                self.check_extra(cx, &step.extra, STEP_KEYS, &node, "a step", d);
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,209 @@
+use serde_yaml::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::expr::{self, Token, TokenKind};
+use crate::spans::key_path;
+
+use super::dependabot::secrets;
+use super::{event_names, for_each_string, job_path, sorted_jobs, step_path, Context, Rule};
+
+/// `if:` conditions that look like access control but can be got around:
+///
+/// - comparing `github.actor` guards nothing, since the actor is whoever
+///   caused the run, not who wrote the code; `dependabot[bot]` can be made
+///   to act on anyone's branch
+/// - a label check on `pull_request_target` keeps passing when new commits
+///   are pushed after a maintainer added the label
+/// - `contains('alice,bob', github.actor)` is a substring match, so `ali`
+///   gets in too
+#[derive(Debug, Clone, Default)]
+pub struct UntrustedGate;
+
+/// Contexts that name a user, for the string comparison check.
+const USER_CONTEXTS: &[&[&str]] = &[
+    &["github", "actor"],
+    &["github", "triggering_actor"],
+    &["github", "event", "sender", "login"],
+    &["github", "event", "pull_request", "user", "login"],
+];
+
+fn is_actor(parts: &[String]) -> bool {
+    parts.len() == 2 && parts[0] == "github" && parts[1] == "actor"
+}
+
+/// What's wrong with a condition, if anything.
+fn problems(tokens: &[Token], has_secrets: bool, label_trigger: bool) -> Vec<String> {
+    let mut found = Vec::new();
+    let references = expr::references(tokens);
+
+    if has_secrets {
+        let compared = tokens.windows(3).any(|w| {
+            matches!(w[1].kind, TokenKind::Eq)
+                && (matches!(w[0].kind, TokenKind::String(_))
+                    || matches!(w[2].kind, TokenKind::String(_)))
+        });
+        if compared && references.iter().any(|r| is_actor(&r.parts)) {
+            let bot = tokens
+                .iter()
+                .any(|t| matches!(&t.kind, TokenKind::String(s) if s.ends_with("[bot]")));
+            found.push(if bot {
+                "checks `github.actor` for a bot before using secrets, but the actor is \
+                 whoever caused the run; check `github.event.pull_request.user.login` instead"
+                    .to_string()
+            } else {
+                "checks `github.actor` before using secrets, but the actor is whoever caused \
+                 the run, not who wrote the code being run"
+                    .to_string()
+            });
+        }
+    }
+
+    if label_trigger {
+        let labels = references.iter().any(|r| {
+            r.parts
+                .starts_with(&["github".to_string(), "event".to_string()])
+                && r.parts.iter().any(|p| p == "labels" || p == "label")
+        });
+        if labels {
+            found.push(
+                "a label check on `pull_request_target` keeps passing for commits pushed \
+                 after the label was added; trigger on `types: [labeled]` only and remove \
+                 the label once the run starts"
+                    .to_string(),
+            );
+        }
+    }
+
+    // `contains('a,b', github.actor)`: the string is searched, not split
+    for (i, token) in tokens.iter().enumerate() {
+        let is_contains =
+            matches!(&token.kind, TokenKind::Ident(name) if name.eq_ignore_ascii_case("contains"));
+        let list = match (is_contains, tokens.get(i + 1), tokens.get(i + 2)) {
+            (true, Some(paren), Some(list)) if paren.kind == TokenKind::LParen => list,
+            _ => continue,
+        };
+        let list = match &list.kind {
+            TokenKind::String(list) => list,
+            _ => continue,
+        };
+        let second = match (tokens.get(i + 3), tokens.get(i + 4)) {
+            (Some(comma), Some(second)) if comma.kind == TokenKind::Comma => second.offset,
+            _ => continue,
+        };
+        let user = references
+            .iter()
+            .any(|r| r.offset == second && USER_CONTEXTS.iter().any(|c| r.parts == *c));
+        if user {
+            found.push(format!(
+                "`contains('{}', ...)` looks for a substring, so any user whose name is \
+                 part of it gets through; use `contains(fromJSON('[...]'), ...)`",
+                list
+            ));
+        }
+    }
+    found
+}
+
+/// Whether `pull_request_target` runs for more than `labeled` events.
+fn label_trigger(cx: &Context) -> bool {
+    if !event_names(cx.document).contains(&"pull_request_target") {
+        return false;
+    }
+    let types = cx
+        .document
+        .get("on")
+        .and_then(|on| on.get("pull_request_target"))
+        .and_then(|event| event.get("types"));
+    match types {
+        Some(Value::Sequence(types)) => types.iter().any(|t| t.as_str() != Some("labeled")),
+        Some(Value::String(t)) => t != "labeled",
+        // The default types include `synchronize`
+        _ => true,
+    }
+}
+
+/// Whether anything under `node` reads a secret.
+fn uses_secrets(node: Option<&Value>) -> bool {
+    let mut found = false;
+    if let Some(node) = node {
+        for_each_string(node, "", &mut |_, value| {
+            found |= !secrets(value).is_empty();
+        });
+    }
+    found
+}
+
+impl UntrustedGate {
+    fn check_condition(
+        &self,
+        cx: &Context,
+        condition: &str,
+        path: &str,
+        has_secrets: bool,
+        label_trigger: bool,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        for body in expr::condition_expressions(condition) {
+            let tokens = match expr::tokenize(body) {
+                Ok(tokens) => tokens,
+                Err(_) => continue,
+            };
+            for problem in problems(&tokens, has_secrets, label_trigger) {
+                diagnostics.push(cx.diagnostic(self, Severity::Warning, path, problem));
+            }
+        }
+    }
+}
+
+impl Rule for UntrustedGate {
+    fn name(&self) -> &'static str {
+        "untrusted-gate"
+    }
+
+    fn description(&self) -> &'static str {
+        "`if:` conditions that look like access control but can be got around"
+    }
+
+    fn security(&self) -> bool {
+        true
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let label_trigger = label_trigger(cx);
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let node = cx.job_node(job_id);
+            if let Some(condition) = &job.run_if {
+                let path = key_path(&job_path(job_id), "if");
+                let has_secrets = uses_secrets(node);
+                self.check_condition(
+                    cx,
+                    condition,
+                    &path,
+                    has_secrets,
+                    label_trigger,
+                    &mut diagnostics,
+                );
+            }
+            for (i, step) in job.steps.iter().enumerate() {
+                let condition = match &step.run_if {
+                    Some(condition) => condition,
+                    None => continue,
+                };
+                let step_node = node
+                    .and_then(|job| job.get("steps"))
+                    .and_then(|steps| steps.get(i));
+                let path = key_path(&step_path(job_id, i), "if");
+                self.check_condition(
+                    cx,
+                    condition,
+                    &path,
+                    uses_secrets(step_node),
+                    label_trigger,
+                    &mut diagnostics,
+                );
+            }
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,98 @@
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::spans::key_path;
+
+use super::{job_path, sorted_jobs, step_path, Context, Rule};
+
+/// Steps with both `uses` and `run`, or neither, and jobs with both `uses`
+/// and `runs-on` or `steps`, or neither. A step either runs an action or
+/// runs a script, and a job either runs steps or calls a reusable
+/// workflow; GitHub rejects the workflow otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct UsesOrRun;
+
+impl Rule for UsesOrRun {
+    fn name(&self) -> &'static str {
+        "uses-or-run"
+    }
+
+    fn description(&self) -> &'static str {
+        "Steps that have both `uses` and `run`, or neither, and jobs that both run steps and call a \
+         workflow, or do neither"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (job_id, job) in sorted_jobs(cx.workflow) {
+            let path = job_path(job_id);
+            let runs = match (&job.runs_on, job.steps.is_empty()) {
+                (Some(_), _) => Some("runs-on"),
+                (None, false) => Some("steps"),
+                (None, true) => None,
+            };
+            match (&job.uses, runs) {
+                (Some(_), Some(key)) => diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &key_path(&path, key),
+                        format!(
+                            "job `{}` calls a reusable workflow with `uses`, so it can't have \
+                             `{}`",
+                            job_id, key
+                        ),
+                    )
+                    .with_message_id("job-both")
+                    .with_suggestion(Suggestion::new(
+                        "move the steps to a job of their own, which can `needs` this one",
+                    )),
+                ),
+                (None, None) | (None, Some("steps")) => diagnostics.push(
+                    cx.diagnostic(
+                        self,
+                        Severity::Error,
+                        &path,
+                        format!(
+                            "job `{}` needs either `runs-on`, to run steps, or `uses`, to call \
+                             a reusable workflow",
+                            job_id
+                        ),
+                    )
+                    .with_message_id("job-neither"),
+                ),
+                _ => {}
+            }
+
+            for (i, step) in job.steps.iter().enumerate() {
+                let path = step_path(job_id, i);
+                let diagnostic = match (&step.uses, &step.run) {
+                    (Some(_), Some(_)) => cx
+                        .diagnostic(
+                            self,
+                            Severity::Error,
+                            &key_path(&path, "run"),
+                            "a step can't have both `uses` and `run`",
+                        )
+                        .with_message_id("both")
+                        .with_suggestion(Suggestion::new(
+                            "split it into a step that uses the action and one that runs the script",
+                        )),
+                    (None, None) => cx
+                        .diagnostic(
+                            self,
+                            Severity::Error,
+                            &path,
+                            "a step needs either `uses` or `run`",
+                        )
+                        .with_message_id("neither"),
+                    _ => continue,
+                };
+                diagnostics.push(diagnostic);
+            }
+        }
+        diagnostics
+    }
+}
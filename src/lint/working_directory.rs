@@ -0,0 +1,113 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::key_path;
+use crate::workflow::Job;
+
+use super::{job_path, sorted_jobs, step_path, Analysis, Context, Rule};
+
+/// `working-directory` values that don't exist in the repository, usually
+/// left behind when a folder is renamed. Only checked when linting inside a
+/// checkout, against the files git tracks, since those are what the runner
+/// checks out: a directory that's only in the working tree, like build
+/// output or one that's empty, doesn't count.
+#[derive(Debug, Clone, Default)]
+pub struct WorkingDirectory;
+
+impl WorkingDirectory {
+    fn check_dir(&self, cx: &Context, dir: &str, node: String, diagnostics: &mut Vec<Diagnostic>) {
+        // Expressions and absolute paths aren't about the repository
+        if dir.contains("${{") || dir.starts_with(['/', '~', '$']) || dir.contains(":\\") {
+            return;
+        }
+        let relative = dir.trim_start_matches("./").trim_end_matches('/');
+        if relative.is_empty() || relative.split('/').any(|segment| segment == "..") {
+            return;
+        }
+        let prefix = format!("{}/", relative);
+        if cx.repo_files().iter().any(|file| file.starts_with(&prefix)) {
+            return;
+        }
+        diagnostics.push(
+            cx.diagnostic(
+                self,
+                Severity::Warning,
+                &node,
+                format!(
+                    "working directory `{}` doesn't exist in the repository",
+                    dir
+                ),
+            )
+            .with_param("directory", dir),
+        );
+    }
+}
+
+/// Whether the repository is checked out somewhere other than the workspace
+/// root, in which case paths aren't relative to the repository.
+fn custom_checkout_path(job: &Job) -> bool {
+    job.steps
+        .iter()
+        .any(|step| step.uses_action("actions/checkout") && step.with.contains_key("path"))
+}
+
+impl Rule for WorkingDirectory {
+    fn name(&self) -> &'static str {
+        "working-directory"
+    }
+
+    fn description(&self) -> &'static str {
+        "`working-directory` values that don't exist in the repository"
+    }
+
+    fn requires(&self) -> &'static [Analysis] {
+        &[Analysis::RepoFiles]
+    }
+
+    fn check(&self, cx: &Context) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let jobs = sorted_jobs(cx.workflow);
+
+        let workflow_dir = cx
+            .workflow
+            .defaults
+            .as_ref()
+            .and_then(|d| d.run.working_directory.as_deref());
+        if let Some(dir) = workflow_dir {
+            if !jobs.iter().any(|(_, job)| custom_checkout_path(job)) {
+                let node = "defaults.run.working-directory".to_string();
+                self.check_dir(cx, dir, node, &mut diagnostics);
+            }
+        }
+
+        for (job_id, job) in jobs {
+            if custom_checkout_path(job) {
+                continue;
+            }
+            let job_dir = job
+                .defaults
+                .as_ref()
+                .and_then(|d| d.run.working_directory.as_deref());
+            if let Some(dir) = job_dir {
+                let node = key_path(&job_path(job_id), "defaults.run.working-directory");
+                self.check_dir(cx, dir, node, &mut diagnostics);
+            }
+
+            for (i, step) in job.steps.iter().enumerate() {
+                let dir = match step.working_directory.as_deref() {
+                    Some(dir) => dir,
+                    None => continue,
+                };
+                // Directories made by an earlier step, like a build directory,
+                // won't be in the repository
+                let created = job.steps[..i]
+                    .iter()
+                    .filter_map(|s| s.run.as_deref())
+                    .any(|run| run.contains(dir.trim_start_matches("./")));
+                if !created {
+                    let node = key_path(&step_path(job_id, i), "working-directory");
+                    self.check_dir(cx, dir, node, &mut diagnostics);
+                }
+            }
+        }
+        diagnostics
+    }
+}
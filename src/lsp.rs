@@ -0,0 +1,4 @@
+//! A language server, so editors can show findings as workflows are
+//! edited. Nothing's here yet: the `lsp` feature is where its
+//! dependencies will go, so crates that only need the parser and the rules
+//! won't pull them in.
@@ -1,13 +1,1781 @@
-use std::io::Read;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-mod custom_types;
-mod workflow;
+use clap::{Parser, Subcommand};
+use workflow_linter::action_ref::ActionRef;
+use workflow_linter::attestations;
+use workflow_linter::badge;
+use workflow_linter::budget::Budget;
+use workflow_linter::conditions;
+use workflow_linter::config;
+use workflow_linter::conform;
+use workflow_linter::deprecation;
+use workflow_linter::diagnostics::{self, Diagnostic, Severity};
+use workflow_linter::elements;
+use workflow_linter::eval::Stubs;
+use workflow_linter::export;
+use workflow_linter::extract;
+use workflow_linter::fix;
+use workflow_linter::fragment::{self, Fragment};
+use workflow_linter::github::{self, TokenSource};
+use workflow_linter::glob;
+use workflow_linter::import::{self, Origin};
+use workflow_linter::inputs;
+use workflow_linter::lint::{
+    self, Context, Limits, PinLevel, Pinning, Rule, RuleOptions, Runners, Skipped, StepNames,
+};
+use workflow_linter::matrix;
+use workflow_linter::messages::Catalog;
+use workflow_linter::noise;
+use workflow_linter::output::{self, Format, SortBy, Summary};
+use workflow_linter::pins;
+use workflow_linter::placeholders::{self, Masked, Style};
+use workflow_linter::preprocess;
+use workflow_linter::provenance;
+use workflow_linter::refactor;
+use workflow_linter::repo;
+use workflow_linter::review;
+use workflow_linter::schema::Target;
+use workflow_linter::segment::{self, Segments};
+use workflow_linter::source::Decoded;
+use workflow_linter::spans::SpanMap;
+use workflow_linter::template;
+use workflow_linter::workflow::Workflow;
+
+/// How many fixes `--fix` has applied, across every file, for the summary.
+static FIXED: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether `--timeout` ran out before every rule ran.
+static STOPPED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Validate GitHub workflow files")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The workflows to lint: files, directories of them, or glob patterns
+    /// like `'.github/workflows/release-*.yml'`; `.github/workflows` of the
+    /// checkout if left out
+    #[arg(value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// Where to look for a GitHub token for network rules, in order:
+    /// workflow-linter-token (WORKFLOW_LINTER_TOKEN), github-token
+    /// (GITHUB_TOKEN), gh-token (GH_TOKEN), gh-cli (`gh auth token`)
+    #[arg(
+        long,
+        env = "WORKFLOW_LINTER_TOKEN_SOURCE",
+        value_delimiter = ',',
+        default_values = ["workflow-linter-token", "github-token", "gh-token", "gh-cli"]
+    )]
+    token_source: Vec<TokenSource>,
+
+    /// How to print findings: text; pretty, with the lines they're about;
+    /// github for workflow command annotations; json, for other tools; or
+    /// sarif, for uploading to code scanning
+    #[arg(long, env = "WORKFLOW_LINTER_FORMAT", default_value = "text")]
+    format: Format,
+
+    /// The order to print findings in: location, by file, line, column and
+    /// rule; or severity, the most severe first
+    #[arg(long, env = "WORKFLOW_LINTER_SORT_BY", default_value = "location")]
+    sort_by: SortBy,
+
+    /// Also run these rules, or groups of rules like `terraform`, which are
+    /// off by default
+    #[arg(long, env = "WORKFLOW_LINTER_ENABLE", value_delimiter = ',')]
+    enable: Vec<String>,
+
+    /// Don't run these rules, or groups of rules
+    #[arg(long, env = "WORKFLOW_LINTER_DISABLE", value_delimiter = ',')]
+    disable: Vec<String>,
+
+    /// Apply the available fixes to the file
+    #[arg(long)]
+    fix: bool,
+
+    /// Lint the file as a piece of a workflow: a job, a step, or a list of
+    /// steps
+    #[arg(long, value_name = "KIND", conflicts_with = "fix")]
+    fragment: Option<Fragment>,
+
+    /// Lint the file as a template another tool fills in, masking its
+    /// placeholders: jinja for `{{ x }}` and `{% x %}` (cookiecutter, copier),
+    /// envsubst for `${VAR}`
+    #[arg(long, value_name = "STYLE", value_delimiter = ',')]
+    placeholders: Vec<Style>,
+
+    /// The retry action your organization uses, e.g. nick-fields/retry;
+    /// other ways of retrying are pointed at it
+    #[arg(long, env = "WORKFLOW_LINTER_RETRY_ACTION", value_name = "ACTION")]
+    retry_action: Option<String>,
+
+    /// The shellcheck to check `run` scripts with when `shell-script` is
+    /// enabled; without one, only its built-in checks are done
+    #[arg(long, env = "WORKFLOW_LINTER_SHELLCHECK", value_name = "PROGRAM")]
+    shellcheck: Option<String>,
+
+    /// A YAML or JSON file of context values to treat as known, like
+    /// `github.ref_name` or `vars.*`, when working out what conditions do
+    #[arg(long, value_name = "FILE")]
+    contexts: Option<PathBuf>,
+
+    /// Where the workflows run: github.com, or a GHES release like
+    /// ghes-3.10, whose missing features are reported; github.com if
+    /// neither this nor the config says
+    #[arg(long, env = "WORKFLOW_LINTER_TARGET")]
+    target: Option<Target>,
+
+    /// Show at most this many findings for each file, the most severe first
+    #[arg(
+        long,
+        env = "WORKFLOW_LINTER_MAX_DIAGNOSTICS_PER_FILE",
+        value_name = "N"
+    )]
+    max_diagnostics_per_file: Option<usize>,
+
+    /// Finish with counts of findings by severity, rule and file
+    #[arg(long)]
+    summary: bool,
+
+    /// Also say which rules were skipped for each file, and why
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Say, for each file, which rules ran and what they found, and which
+    /// were disabled or skipped and why
+    #[arg(long)]
+    explain_run: bool,
+
+    /// Stop after this many seconds, skipping the rules and API requests
+    /// that are left
+    #[arg(long, value_name = "SECONDS", value_parser = seconds)]
+    timeout: Option<Duration>,
+
+    /// Drop the findings of a rule that takes longer than this many seconds
+    #[arg(long, value_name = "SECONDS", value_parser = seconds)]
+    rule_timeout: Option<Duration>,
+
+    /// Show messages from this catalog: a locale like `de`, looked up in
+    /// `.github/workflow-linter/locales/`, or the path of a catalog file
+    #[arg(long, env = "WORKFLOW_LINTER_LOCALE", value_name = "LOCALE")]
+    locale: Option<String>,
+
+    /// Write the summary counts to this file as JSON
+    #[arg(long, value_name = "FILE")]
+    summary_json: Option<PathBuf>,
+
+    /// The config file to read; otherwise `.workflow-lint.toml` in the
+    /// directory of the first file linted or the nearest one above it in the
+    /// checkout
+    #[arg(long, env = "WORKFLOW_LINTER_CONFIG", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Don't fetch the configs `extends` points at; use the cached copies
+    #[arg(
+        long,
+        env = "WORKFLOW_LINTER_OFFLINE",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    offline: bool,
+
+    /// Report a rule's findings at this severity instead of its own, like
+    /// step-name=info; wins over the config's
+    #[arg(long = "severity", value_name = "RULE=SEVERITY", value_parser = rule_severity)]
+    severity_overrides: Vec<(String, Severity)>,
+
+    /// Don't lint the workflows this pattern matches the path in the
+    /// checkout of, as well as those the config ignores; files named on the
+    /// command line are always linted
+    #[arg(long, value_name = "PATTERN")]
+    ignore: Vec<String>,
+
+    /// What actions have to be pinned to: version, or sha for a full commit
+    /// SHA; wins over the config's
+    #[arg(
+        long = "pinning",
+        env = "WORKFLOW_LINTER_PINNING",
+        value_name = "LEVEL"
+    )]
+    pin_level: Option<PinLevel>,
+
+    /// Severities the config and `--severity` give rules, instead of their
+    /// own
+    #[arg(skip)]
+    severity: BTreeMap<String, Severity>,
+
+    /// The pinning policy from the config and `--pinning`
+    #[arg(skip)]
+    pinning: Pinning,
+
+    /// The runner policy from the config
+    #[arg(skip)]
+    runners: Runners,
+
+    /// Which steps can go without a name, from the config
+    #[arg(skip)]
+    step_names: StepNames,
+
+    /// Patterns for deprecated workflows from the config
+    #[arg(skip)]
+    deprecated: Vec<String>,
+
+    /// The limits from `--timeout` and `--rule-timeout`
+    #[arg(skip)]
+    budget: Budget,
+}
+
+fn rule_severity(value: &str) -> Result<(String, Severity), String> {
+    let (rule, severity) = value
+        .split_once('=')
+        .ok_or_else(|| "expected RULE=SEVERITY, like step-name=info".to_string())?;
+    Ok((rule.to_string(), severity.parse()?))
+}
+
+fn seconds(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|_| "not a number".to_string())?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| "not a number of seconds".to_string())
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List every `if:` condition with what it depends on and whether it can
+    /// be worked out before the run
+    Conditions {
+        /// The workflow file to report on
+        file: PathBuf,
+    },
+
+    /// Print every combination a job's matrix expands to, after `include`
+    /// and `exclude`, with the `runs-on` each one gets
+    Matrix {
+        /// The workflow file the job is in
+        file: PathBuf,
+
+        /// The id of the job
+        #[arg(long)]
+        job: String,
+    },
+
+    /// Apply the `x-templates` of an extended workflow, writing the plain
+    /// workflow GitHub can run, and lint it
+    Expand {
+        /// The extended workflow
+        file: PathBuf,
+
+        /// Where to write the workflow; printed if left out
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Merge jobs that only differ in a few values into matrix jobs,
+    /// printing the rewritten workflow for review
+    ToMatrix {
+        /// The workflow to rewrite
+        file: PathBuf,
+
+        /// Where to write the workflow; printed if left out
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the workflow as the rules see it, normalized and with GitHub's
+    /// defaults filled in, for policy engines like OPA or CUE
+    Export {
+        /// The workflow file to export
+        file: PathBuf,
+
+        /// json or cue
+        #[arg(long, value_name = "FORMAT", default_value = "json")]
+        format: export::Format,
+
+        /// Add the legs each matrix runs as to its `strategy`
+        #[arg(long)]
+        expand_matrices: bool,
+    },
+
+    /// Make a workflow from another CI system's pipeline, reporting what
+    /// couldn't be carried over as TODOs, and lint it
+    Import {
+        /// The CI system the pipeline is for: gitlab
+        #[arg(long, value_name = "CI")]
+        from: Origin,
+
+        /// The pipeline, like `.gitlab-ci.yml`
+        file: PathBuf,
+
+        /// Where to write the workflow; printed if left out
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Compare the base and head versions of a workflow a pull request
+    /// changes, failing if it broadens permissions, adds a trigger forks can
+    /// cause, exposes secrets or unpins an action
+    Diff {
+        /// The workflow before the change; `/dev/null` for a new workflow
+        base: PathBuf,
+
+        /// The workflow after the change
+        head: PathBuf,
+    },
+
+    /// Move steps of a job into a new reusable workflow, with inputs and
+    /// secrets for what they use, and rewrite the job to call it
+    Extract {
+        /// The workflow the job is in, which is rewritten
+        file: PathBuf,
+
+        /// The id of the job
+        #[arg(long)]
+        job: String,
+
+        /// The steps to move, numbered from 0, like `2..6` or `2..`; all
+        /// of them if left out
+        #[arg(long, value_name = "RANGE", default_value = "0..")]
+        steps: extract::StepRange,
+
+        /// The reusable workflow to write, in .github/workflows
+        #[arg(long, value_name = "FILE")]
+        into: PathBuf,
+    },
+
+    /// Check that a workflow has the jobs, steps and settings of a template
+    /// the organization requires, reporting what's missing or changed
+    Conform {
+        /// The template the workflow has to follow
+        #[arg(long, value_name = "FILE")]
+        template: PathBuf,
+
+        /// The workflow to check
+        file: PathBuf,
+    },
+
+    /// Add up the `--summary-json` files of many runs, to see which rules
+    /// fire most, which files are noisiest and how many fixes are taken up
+    Report {
+        /// The directory the summaries are in, searched for `.json` files
+        #[arg(long, value_name = "DIR")]
+        aggregate: PathBuf,
+
+        /// How many rules and files to list
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the rules, with what they find, how severe their findings can
+    /// be, and whether they're on
+    Rules {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Lint every workflow and sum it up as a badge for a README, or a score
+    /// for a dashboard
+    Badge {
+        /// The directory the workflows are in; `.github/workflows` of the
+        /// checkout if left out
+        dir: Option<PathBuf>,
+
+        /// Write the badge to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// The score as JSON instead of the SVG badge
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the `workflow_dispatch` inputs of every workflow, flagging
+    /// free-form strings scripts use and booleans with a risky default
+    Inputs {
+        /// The directory the workflows are in; `.github/workflows` of the
+        /// checkout if left out
+        dir: Option<PathBuf>,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Ask GitHub which of the repository's workflows are disabled, like
+    /// schedules it turned off after 60 days without activity
+    Schedules {
+        /// The repository, like `owner/name`; the `origin` of the checkout
+        /// if left out
+        #[arg(long, value_name = "OWNER/NAME")]
+        repo: Option<String>,
+    },
+
+    /// Look up the commits actions are pinned to, reporting pins older
+    /// than `--max-age` and pins no tag of the action points at any more
+    Pins {
+        /// The workflows to check: files, directories of them, or glob
+        /// patterns; `.github/workflows` of the checkout if left out
+        #[arg(value_name = "PATH")]
+        paths: Vec<PathBuf>,
+
+        /// How many days old a pinned commit can be
+        #[arg(long, value_name = "DAYS", default_value_t = 365)]
+        max_age: u64,
+    },
+
+    /// Report third-party actions that jobs with secrets use at a release
+    /// that isn't immutable and a commit that isn't signed
+    Attestations {
+        /// The workflows to check: files, directories of them, or glob
+        /// patterns; `.github/workflows` of the checkout if left out
+        #[arg(value_name = "PATH")]
+        paths: Vec<PathBuf>,
+    },
+
+    /// List the id of every job and step, like `jobs.build.steps[3]`, which
+    /// findings about them carry too
+    Ids {
+        /// The workflow file to list
+        file: PathBuf,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// A workflow file, read and parsed every way the rules need it.
+struct Loaded {
+    source: Decoded,
+    workflow: Workflow,
+    document: serde_yaml::Value,
+    spans: SpanMap,
+    repo_root: Option<PathBuf>,
+}
+
+impl Loaded {
+    fn read(path: &Path) -> Result<Loaded, Vec<Diagnostic>> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            vec![Diagnostic::new("io", Severity::Error, e.to_string()).with_path(path)]
+        })?;
+        Loaded::parse(path, Decoded::decode(&bytes))
+    }
+
+    fn parse(path: &Path, source: Decoded) -> Result<Loaded, Vec<Diagnostic>> {
+        let contents = &source.text;
+        let mut workflow = Workflow::parse_str(contents).map_err(|e| {
+            // Tabs are the usual cause, and the YAML error doesn't say so
+            let tabs = tab_diagnostics(path, contents, Severity::Error);
+            if !tabs.is_empty() {
+                return tabs;
+            }
+            let mut diagnostic =
+                Diagnostic::new("parse", Severity::Error, e.to_string()).with_path(path);
+            if let Some(location) = e.location() {
+                diagnostic = diagnostic.at(location.line(), location.column());
+            }
+            vec![diagnostic]
+        })?;
+        let document = serde_yaml::from_str(contents).unwrap();
+        let spans = SpanMap::parse(contents);
+        workflow.locate(&document, &spans);
+        Ok(Loaded {
+            document,
+            spans,
+            repo_root: repo::find_root(path),
+            workflow,
+            source,
+        })
+    }
+
+    fn context<'a>(&'a self, path: &'a Path, stubs: &'a Stubs, budget: &Budget) -> Context<'a> {
+        Context {
+            path: Some(path),
+            source: &self.source.text,
+            workflow: &self.workflow,
+            document: &self.document,
+            spans: &self.spans,
+            repo_root: self.repo_root.as_deref(),
+            stubs,
+            analyses: Default::default(),
+            budget: budget.clone(),
+        }
+    }
+}
+
+/// A diagnostic for each line indented with tabs.
+fn tab_diagnostics(path: &Path, source: &str, severity: Severity) -> Vec<Diagnostic> {
+    lint::tab_indentation(source)
+        .into_iter()
+        .map(|location| {
+            Diagnostic::new(
+                "tab-indentation",
+                severity,
+                "YAML doesn't allow tabs for indentation; use spaces",
+            )
+            .with_path(path)
+            .at(location.line, location.column)
+        })
+        .collect()
+}
+
+/// Tidy findings up for printing: grouped by place, in the catalog's words,
+/// sorted, and cut down to the per-file limit if there is one.
+fn prepare(args: &Args, catalog: &Catalog, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    // Sorted first too, so the finding others are folded into doesn't
+    // depend on the order they were found in
+    let mut diagnostics = diagnostics;
+    output::sort(&mut diagnostics, SortBy::Location);
+    let mut diagnostics = noise::group(diagnostics);
+    for diagnostic in &mut diagnostics {
+        catalog.localize(diagnostic);
+    }
+    output::sort(&mut diagnostics, args.sort_by);
+    match args.max_diagnostics_per_file {
+        Some(max) => noise::limit_per_file(diagnostics, max),
+        None => diagnostics,
+    }
+}
+
+/// Print findings, stopping quietly when whatever reads them, like `head`,
+/// has closed the pipe.
+fn write_diagnostics(out: &mut impl Write, format: Format, diagnostics: &[Diagnostic]) {
+    if let Err(e) = output::write_diagnostics(out, format, diagnostics) {
+        if e.kind() == std::io::ErrorKind::BrokenPipe {
+            std::process::exit(0);
+        }
+        eprintln!("Can't write the findings: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// The rules to run, as set up by the command line.
+fn rules(args: &Args) -> Vec<Box<dyn Rule>> {
+    configured_rules(args)
+        .into_iter()
+        .filter(|rule| disabled(args, rule.as_ref()).is_none())
+        .collect()
+}
+
+/// Every rule, set up with the options in `args`, whether it's on or off.
+fn configured_rules(args: &Args) -> Vec<Box<dyn Rule>> {
+    let options = RuleOptions {
+        retry_action: args.retry_action.clone(),
+        target: args.target.unwrap_or_default(),
+        runners: args.runners.clone(),
+        pinning: args.pinning.clone(),
+        shellcheck: args.shellcheck.clone(),
+        step_names: args.step_names.clone(),
+    };
+    lint::configured_rules(&options)
+}
+
+/// Why `rule` is off, if it is.
+fn disabled(args: &Args, rule: &dyn Rule) -> Option<&'static str> {
+    let named = |names: &[String]| {
+        names
+            .iter()
+            .any(|n| n == rule.name() || Some(n.as_str()) == rule.group())
+    };
+    if named(&args.disable) {
+        Some("--disable or the config turns it off")
+    } else if !rule.enabled_by_default() && !named(&args.enable) {
+        Some("it's off unless --enable or the config turns it on")
+    } else {
+        None
+    }
+}
+
+/// Give findings the severities the config sets for their rules.
+fn override_severities(args: &Args, diagnostics: &mut [Diagnostic]) {
+    for diagnostic in diagnostics {
+        if let Some(&severity) = args.severity.get(&diagnostic.rule) {
+            diagnostic.severity = severity;
+        }
+    }
+}
+
+/// Run the rules against `cx`, saying which were skipped, or what happened
+/// to every rule, if asked to.
+fn run_rules(args: &Args, path: &Path, cx: &Context) -> Vec<Diagnostic> {
+    let (diagnostics, skipped) = lint::lint_reporting_skipped(cx, &rules(args));
+    if let Some(stopped) = args.budget.stopped() {
+        if skipped.iter().any(|s| s.reason == stopped.reason()) {
+            STOPPED.store(true, Ordering::Relaxed);
+        }
+    }
+    if args.explain_run {
+        explain_run(args, path, &diagnostics, &skipped);
+    } else if args.verbose {
+        for skipped in skipped {
+            eprintln!(
+                "{}: skipped {}: {}",
+                path.display(),
+                skipped.rule,
+                skipped.reason
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Say, for `--explain-run`, which rules ran on `path` and what they found,
+/// and which didn't and why, one rule a line.
+fn explain_run(args: &Args, path: &Path, diagnostics: &[Diagnostic], skipped: &[Skipped]) {
+    let path = path.display();
+    for rule in configured_rules(args) {
+        let name = rule.name();
+        if let Some(why) = disabled(args, rule.as_ref()) {
+            eprintln!("{}: disabled {}: {}", path, name, why);
+        } else if let Some(skipped) = skipped.iter().find(|s| s.rule == name) {
+            eprintln!("{}: skipped {}: {}", path, name, skipped.reason);
+        } else {
+            match diagnostics.iter().filter(|d| d.rule == name).count() {
+                0 => eprintln!("{}: ran {}, no findings", path, name),
+                1 => eprintln!("{}: ran {}, 1 finding", path, name),
+                n => eprintln!("{}: ran {}, {} findings", path, name, n),
+            }
+        }
+    }
+}
+
+/// Lint a file that's only a piece of a workflow.
+fn lint_fragment(args: &Args, stubs: &Stubs, path: &Path, kind: Fragment) -> Vec<Diagnostic> {
+    let source = match std::fs::read(path) {
+        Ok(bytes) => Decoded::decode(&bytes),
+        Err(e) => {
+            return vec![Diagnostic::new("io", Severity::Error, e.to_string()).with_path(path)]
+        }
+    };
+    let mut diagnostics = source.diagnostics(path);
+    diagnostics.extend(tab_diagnostics(path, &source.text, Severity::Warning));
+    let wrapped = fragment::wrap(&source.text, kind);
+    let found = match Loaded::parse(path, Decoded::decode(wrapped.text.as_bytes())) {
+        Ok(loaded) => run_rules(args, path, &loaded.context(path, stubs, &args.budget)),
+        Err(errors) => errors,
+    };
+    diagnostics.extend(wrapped.unwrap(found));
+    override_severities(args, &mut diagnostics);
+    diagnostics
+}
+
+/// Lint a file too big to parse in one go a segment at a time, noting that
+/// the analysis is partial.
+fn lint_segments(
+    args: &Args,
+    stubs: &Stubs,
+    path: &Path,
+    source: &Decoded,
+    masked: Option<&Masked>,
+    segments: Segments,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = source.diagnostics(path);
+    diagnostics.extend(tab_diagnostics(path, &source.text, Severity::Warning));
+    diagnostics.push(
+        Diagnostic::new(
+            segment::RULE,
+            Severity::Warning,
+            format!(
+                "this file is {} MiB, too big to lint in one go, so its {} jobs were linted a \
+                 few at a time; checks across jobs, like `needs`, only saw some of them, and \
+                 no fixes are offered",
+                source.text.len() / (1024 * 1024),
+                segments.jobs()
+            ),
+        )
+        .with_path(path)
+        .at(1, 1)
+        .with_message_id("segmented")
+        .with_param("jobs", segments.jobs()),
+    );
+    for segment in segments {
+        let found = match Loaded::parse(path, Decoded::decode(segment.text.as_bytes())) {
+            Ok(loaded) => run_rules(args, path, &loaded.context(path, stubs, &args.budget)),
+            // The error's text has the line in the segment, not the file
+            Err(errors) => errors
+                .into_iter()
+                .map(|mut error| {
+                    if let Some(at) = error.message.rfind(" at line ") {
+                        error.message.truncate(at);
+                    }
+                    error
+                })
+                .collect(),
+        };
+        diagnostics.extend(segment.unwrap(found));
+    }
+    // The first segment's size error has the segment's size
+    for diagnostic in &mut diagnostics {
+        if diagnostic.rule == "workflow-limits"
+            && diagnostic.message_id.as_deref() == Some("file-size")
+        {
+            if let Some(whole) = Limits::file_size(source.text.len()) {
+                diagnostic.message = whole.message;
+            }
+        }
+    }
+    if let Some(masked) = masked {
+        diagnostics = masked.unmask(&source.text, diagnostics);
+    }
+    override_severities(args, &mut diagnostics);
+    diagnostics
+}
+
+/// Lint a workflow printed to stdout rather than written to a file, as `-`,
+/// the way a file's linted.
+fn lint_printed(args: &Args, stubs: &Stubs, text: &str) -> Vec<Diagnostic> {
+    let path = Path::new("-");
+    let mut diagnostics = match Loaded::parse(path, Decoded::decode(text.as_bytes())) {
+        Ok(loaded) => run_rules(args, path, &loaded.context(path, stubs, &args.budget)),
+        Err(errors) => errors,
+    };
+    override_severities(args, &mut diagnostics);
+    diagnostics
+}
+
+/// Lint one workflow file, applying fixes if asked to.
+fn lint_file(args: &Args, stubs: &Stubs, path: &Path) -> Vec<Diagnostic> {
+    if let Some(kind) = args.fragment {
+        return lint_fragment(args, stubs, path, kind);
+    }
+    if let Ok(metadata) = std::fs::metadata(path) {
+        if metadata.len() > segment::MAX_BYTES {
+            let message = format!(
+                "this file is {} MiB, and files over {} MiB aren't read, so it wasn't linted",
+                metadata.len() / (1024 * 1024),
+                segment::MAX_BYTES / (1024 * 1024)
+            );
+            return vec![Diagnostic::new(segment::RULE, Severity::Error, message)
+                .with_path(path)
+                .with_message_id("too-large")];
+        }
+    }
+    let source = match std::fs::read(path) {
+        Ok(bytes) => Decoded::decode(&bytes),
+        Err(e) => {
+            return vec![Diagnostic::new("io", Severity::Error, e.to_string()).with_path(path)]
+        }
+    };
+    // The masked text has the same lines and columns, so only what's parsed
+    // and linted changes
+    let masked = match args.placeholders.is_empty() {
+        true => None,
+        false => Some(placeholders::mask(&source.text, &args.placeholders)),
+    };
+    let parsed = match &masked {
+        Some(masked) => Decoded {
+            text: masked.text.clone(),
+            ..source.clone()
+        },
+        None => source.clone(),
+    };
+    if parsed.text.len() > segment::SEGMENT_THRESHOLD {
+        if let Some(segments) = segment::split(&parsed.text, segment::SEGMENT_BYTES) {
+            return lint_segments(args, stubs, path, &source, masked.as_ref(), segments);
+        }
+    }
+    let mut diagnostics = Vec::new();
+    match Loaded::parse(path, parsed) {
+        Ok(loaded) => {
+            let cx = loaded.context(path, stubs, &args.budget);
+            diagnostics = source.diagnostics(path);
+            diagnostics.extend(tab_diagnostics(path, &source.text, Severity::Warning));
+            diagnostics.extend(run_rules(args, path, &cx));
+            if let Some(masked) = &masked {
+                diagnostics = masked.unmask(&source.text, diagnostics);
+            }
+            override_severities(args, &mut diagnostics);
+
+            if args.fix {
+                let (fixed, applied) = fix::apply_fixes(&source.text, &diagnostics);
+                // Written with the original's line endings and byte order mark
+                if let (true, Some(fixed)) = (applied > 0, source.encode(&fixed)) {
+                    match std::fs::write(path, fixed) {
+                        Ok(()) => {
+                            eprintln!("Applied {} fixes to {}", applied, path.display());
+                            FIXED.fetch_add(applied, Ordering::Relaxed);
+                            diagnostics.retain(|d| d.fix.is_none());
+                        }
+                        Err(e) => diagnostics.push(
+                            Diagnostic::new(
+                                "io",
+                                Severity::Error,
+                                format!("couldn't write the fixes: {}", e),
+                            )
+                            .with_path(path),
+                        ),
+                    }
+                }
+            }
+        }
+        Err(errors) => {
+            if args.explain_run {
+                eprintln!("{}: no rules ran, as it didn't parse", path.display());
+            }
+            diagnostics.extend(errors)
+        }
+    }
+    diagnostics
+}
+
+/// Lint the workflow templates of the repository `path` is in, returning
+/// what was found and how many templates there were.
+fn lint_templates(args: &Args, stubs: &Stubs, path: &Path) -> (Vec<Diagnostic>, usize) {
+    let dir = match repo::find_root(path) {
+        Some(root) => repo::relative_to_cwd(root.join(template::TEMPLATES_DIR)),
+        None => return (Vec::new(), 0),
+    };
+    let mut diagnostics = Vec::new();
+    let templates = template::templates(&dir);
+    for template in &templates {
+        diagnostics.extend(lint_file(args, stubs, template));
+        let properties = template::properties_path(template);
+        match std::fs::read_to_string(&properties) {
+            Ok(source) => diagnostics.extend(template::check_properties(&properties, &source)),
+            Err(_) => diagnostics.extend(template::missing_properties(template)),
+        }
+    }
+    diagnostics.extend(template::orphaned_properties(&dir));
+    (diagnostics, templates.len())
+}
+
+/// The workflow files in `dir`, or in `.github/workflows` of the checkout,
+/// or exit saying why they can't be listed.
+fn workflow_files_or_exit(dir: Option<&Path>) -> Vec<PathBuf> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => match repo::find_root(Path::new(".")) {
+            Some(root) => repo::relative_to_cwd(root.join(".github/workflows")),
+            None => {
+                eprintln!("Not in a git checkout; give the directory the workflows are in");
+                std::process::exit(2);
+            }
+        },
+    };
+    match inputs::workflow_files(&dir) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Can't read {}: {}", dir.display(), e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The workflow files `paths` name, expanding directories and glob
+/// patterns, or those in `.github/workflows` of the checkout if there are
+/// none. Files found in a directory or by a pattern are left out if their
+/// path in the checkout matches `ignore`. Exits if nothing is left to lint.
+fn workflow_paths(paths: &[PathBuf], ignore: &[String]) -> Vec<PathBuf> {
+    let ignore: Vec<glob::Glob> = ignore
+        .iter()
+        .filter_map(|pattern| match glob::Glob::parse(pattern) {
+            Ok(glob) => Some(glob),
+            Err(e) => {
+                eprintln!(
+                    "warning: ignoring the bad ignore pattern {}: {}",
+                    pattern, e
+                );
+                None
+            }
+        })
+        .collect();
+    let found = |files: Vec<PathBuf>| {
+        files
+            .into_iter()
+            .filter(|file| !glob::matches_all(&ignore, &repo::checkout_path(file)))
+            .collect::<Vec<_>>()
+    };
+    let mut files = Vec::new();
+    if paths.is_empty() {
+        files = found(workflow_files_or_exit(None));
+    }
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if path.is_dir() {
+            files.extend(found(workflow_files_or_exit(Some(path))));
+        } else if glob::is_pattern(&pattern) && !path.exists() {
+            match glob::files(&pattern) {
+                Ok(matched) if matched.is_empty() => {
+                    eprintln!("warning: {} matches no files", pattern)
+                }
+                Ok(matched) => files.extend(found(matched)),
+                Err(e) => {
+                    eprintln!("Bad pattern {}: {}", pattern, e);
+                    std::process::exit(2);
+                }
+            }
+        } else {
+            // A file that isn't there is reported when it's linted
+            files.push(path.clone());
+        }
+    }
+    let mut seen = BTreeSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    if files.is_empty() {
+        eprintln!("No workflow files to lint");
+        std::process::exit(2);
+    }
+    files
+}
+
+/// Read and parse `path`, or exit saying why it couldn't be.
+fn load_or_exit(path: &Path) -> Loaded {
+    match Loaded::read(path) {
+        Ok(loaded) => loaded,
+        Err(errors) => {
+            for diagnostic in errors {
+                eprintln!("{}", diagnostic);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Read and parse every one of `files`, or exit saying why one couldn't be.
+fn load_all_or_exit(files: &[PathBuf]) -> Vec<(PathBuf, Loaded)> {
+    files
+        .iter()
+        .map(|file| (file.clone(), load_or_exit(file)))
+        .collect()
+}
+
+fn run_command(args: &Args, command: &Command, stubs: &Stubs, catalog: &Catalog) {
+    let stdout = std::io::stdout();
+    match command {
+        Command::Conditions { file } => {
+            let path = file.as_path();
+            let loaded = load_or_exit(path);
+            let report = conditions::report(&loaded.context(path, stubs, &args.budget));
+            let file = path.display().to_string();
+            conditions::write_report(&mut stdout.lock(), &file, &report).unwrap();
+        }
+        Command::Matrix { file, job } => {
+            let loaded = load_or_exit(file);
+            let job_def = match loaded.workflow.jobs.get(job) {
+                Some(job_def) => job_def,
+                None => {
+                    let mut ids: Vec<_> = loaded.workflow.jobs.keys().map(String::as_str).collect();
+                    ids.sort_unstable();
+                    eprintln!(
+                        "No job `{}` in {}; its jobs are {}",
+                        job,
+                        file.display(),
+                        ids.join(", ")
+                    );
+                    std::process::exit(2);
+                }
+            };
+            let matrix = match job_def.strategy.as_ref().and_then(|s| s.matrix.as_ref()) {
+                Some(matrix) => matrix,
+                None => {
+                    let leg = matrix::Leg { values: Vec::new() };
+                    println!(
+                        "Job `{}` has no matrix; it runs once, on {}",
+                        job,
+                        matrix::runs_on(job_def, &leg)
+                    );
+                    return;
+                }
+            };
+            match matrix::expand(matrix) {
+                Ok(expansion) => {
+                    matrix::write_table(&mut stdout.lock(), job_def, &expansion).unwrap()
+                }
+                Err(e) => {
+                    eprintln!("Can't expand the matrix of `{}`: {}", job, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Expand { file, output } => {
+            let source = match std::fs::read_to_string(file) {
+                Ok(source) => source,
+                Err(e) => {
+                    eprintln!("Can't read {}: {}", file.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            let body = match preprocess::expand(&source) {
+                Ok(body) => body,
+                Err(e) => {
+                    eprintln!("Can't expand {}: {}", file.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            // Recorded relative to the checkout, so the check works anywhere
+            let source_path = repo::find_root(file)
+                .and_then(|root| {
+                    let relative = file
+                        .canonicalize()
+                        .ok()?
+                        .strip_prefix(root)
+                        .ok()?
+                        .to_path_buf();
+                    Some(relative)
+                })
+                .unwrap_or_else(|| file.clone());
+            let source_path = source_path.to_string_lossy().replace('\\', "/");
+            let expanded = format!(
+                "# Generated from {} by `workflow-linter expand`; edit that file instead.\n{}{}",
+                source_path,
+                provenance::marker(&source_path, &source, &body),
+                body
+            );
+
+            // Diagnostics go to stderr when the workflow itself goes to stdout
+            let diagnostics = match output {
+                Some(output) => {
+                    if let Err(e) = std::fs::write(output, &expanded) {
+                        let diagnostic = Diagnostic::new(
+                            "io",
+                            Severity::Error,
+                            format!("couldn't write the expanded workflow: {}", e),
+                        )
+                        .with_path(output);
+                        let shown = prepare(args, catalog, vec![diagnostic]);
+                        write_diagnostics(&mut stdout.lock(), args.format, &shown);
+                        std::process::exit(1);
+                    }
+                    let diagnostics = lint_file(args, stubs, output);
+                    let shown = prepare(args, catalog, diagnostics.clone());
+                    write_diagnostics(&mut stdout.lock(), args.format, &shown);
+                    diagnostics
+                }
+                None => {
+                    print!("{}", expanded);
+                    let diagnostics = lint_printed(args, stubs, &expanded);
+                    let stderr = std::io::stderr();
+                    let shown = prepare(args, catalog, diagnostics.clone());
+                    write_diagnostics(&mut stderr.lock(), args.format, &shown);
+                    diagnostics
+                }
+            };
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                std::process::exit(1);
+            }
+        }
+        Command::ToMatrix { file, output } => {
+            let loaded = load_or_exit(file);
+            let (rewritten, groups) = match refactor::to_matrix(&loaded.source.text) {
+                Ok(rewritten) => rewritten,
+                Err(e) => {
+                    eprintln!("Can't rewrite {}: {}", file.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            if groups.is_empty() {
+                eprintln!("No jobs in {} can be merged into a matrix", file.display());
+                return;
+            }
+            for group in &groups {
+                let axes: Vec<&str> = group.axes.iter().map(|a| a.name.as_str()).collect();
+                eprintln!(
+                    "Merged {} into `{}`, with a matrix of {}",
+                    group.jobs.join(", "),
+                    group.id,
+                    axes.join(", ")
+                );
+            }
+            match output {
+                Some(output) => {
+                    let encoded = loaded.source.encode(&rewritten).unwrap_or(rewritten);
+                    if let Err(e) = std::fs::write(output, encoded) {
+                        eprintln!("Can't write {}: {}", output.display(), e);
+                        std::process::exit(1);
+                    }
+                }
+                None => print!("{}", rewritten),
+            }
+        }
+        Command::Export {
+            file,
+            format,
+            expand_matrices,
+        } => {
+            let loaded = load_or_exit(file);
+            let model = export::model(&loaded.workflow, &loaded.document, *expand_matrices);
+            print!("{}", export::render(&model, *format));
+        }
+        Command::Import { from, file, output } => {
+            let source = match std::fs::read(file) {
+                Ok(bytes) => Decoded::decode(&bytes).text,
+                Err(e) => {
+                    eprintln!("Can't read {}: {}", file.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            let imported = match import::import(*from, &source, file) {
+                Ok(imported) => imported,
+                Err(e) => {
+                    eprintln!("Can't import {}: {}", file.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            let mut diagnostics = imported.todos;
+
+            // Diagnostics go to stderr when the workflow itself goes to stdout
+            match output {
+                Some(output) => {
+                    if let Err(e) = std::fs::write(output, &imported.workflow) {
+                        eprintln!("Can't write {}: {}", output.display(), e);
+                        std::process::exit(1);
+                    }
+                    diagnostics.extend(lint_file(args, stubs, output));
+                    let shown = prepare(args, catalog, diagnostics.clone());
+                    write_diagnostics(&mut stdout.lock(), args.format, &shown);
+                }
+                None => {
+                    print!("{}", imported.workflow);
+                    diagnostics.extend(lint_printed(args, stubs, &imported.workflow));
+                    let stderr = std::io::stderr();
+                    let shown = prepare(args, catalog, diagnostics.clone());
+                    write_diagnostics(&mut stderr.lock(), args.format, &shown);
+                }
+            }
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                std::process::exit(1);
+            }
+        }
+        Command::Diff { base, head } => {
+            let base_source = match std::fs::read(base).map(|bytes| Decoded::decode(&bytes).text) {
+                Ok(source) => source,
+                Err(e) => {
+                    eprintln!("Can't read {}: {}", base.display(), e);
+                    std::process::exit(2);
+                }
+            };
+            let base_document = match base_source.trim().is_empty() {
+                true => serde_yaml::Value::Null,
+                false => match serde_yaml::from_str(&base_source) {
+                    Ok(document) => document,
+                    Err(e) => {
+                        eprintln!("Can't parse {}: {}", base.display(), e);
+                        std::process::exit(2);
+                    }
+                },
+            };
+            let loaded = load_or_exit(head);
+            let mut diagnostics =
+                review::compare(&base_document, &loaded.document, &loaded.source.text, head);
+            // Marking a workflow deprecated isn't an edit to a deprecated one
+            if let Some(deprecation) = deprecation::find(&base_source, head, &args.deprecated) {
+                diagnostics.extend(deprecation::check_edit(
+                    &deprecation,
+                    &base_document,
+                    &loaded.document,
+                    head,
+                ));
+            }
+            let shown = prepare(args, catalog, diagnostics.clone());
+            write_diagnostics(&mut stdout.lock(), args.format, &shown);
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Conform { template, file } => {
+            let template_document =
+                match std::fs::read(template).map(|bytes| Decoded::decode(&bytes).text) {
+                    Ok(source) => match serde_yaml::from_str(&source) {
+                        Ok(document) => document,
+                        Err(e) => {
+                            eprintln!("Can't parse {}: {}", template.display(), e);
+                            std::process::exit(2);
+                        }
+                    },
+                    Err(e) => {
+                        eprintln!("Can't read {}: {}", template.display(), e);
+                        std::process::exit(2);
+                    }
+                };
+            let loaded = load_or_exit(file);
+            let diagnostics = conform::conform(
+                &template_document,
+                &loaded.document,
+                &loaded.source.text,
+                file,
+            );
+            let shown = prepare(args, catalog, diagnostics.clone());
+            write_diagnostics(&mut stdout.lock(), args.format, &shown);
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Report {
+            aggregate,
+            top,
+            json,
+        } => {
+            let (aggregate, skipped) = match output::aggregate(aggregate) {
+                Ok(found) => found,
+                Err(e) => {
+                    eprintln!("Can't read {}: {}", aggregate.display(), e);
+                    std::process::exit(2);
+                }
+            };
+            for (file, e) in skipped {
+                eprintln!("Skipped {}, which isn't a summary: {}", file.display(), e);
+            }
+            let mut out = stdout.lock();
+            if *json {
+                let json = serde_json::to_string_pretty(&aggregate).unwrap();
+                writeln!(out, "{}", json).unwrap();
+            } else {
+                output::write_aggregate(&mut out, &aggregate, *top).unwrap();
+            }
+        }
+        Command::Extract {
+            file,
+            job,
+            steps,
+            into,
+        } => {
+            let loaded = load_or_exit(file);
+            if into.exists() {
+                eprintln!("{} already exists", into.display());
+                std::process::exit(1);
+            }
+            // `uses` needs the path from the root of the repository
+            let uses = loaded
+                .repo_root
+                .as_ref()
+                .and_then(|root| {
+                    let parent = into.parent()?.canonicalize().ok()?;
+                    let name = into.file_name()?;
+                    Some(parent.join(name).strip_prefix(root).ok()?.to_path_buf())
+                })
+                .unwrap_or_else(|| into.clone());
+            let uses = uses.to_string_lossy().replace('\\', "/");
+            let extraction = match extract::extract(&loaded.source.text, job, *steps, &uses) {
+                Ok(extraction) => extraction,
+                Err(e) => {
+                    eprintln!("Can't extract steps from `{}`: {}", job, e);
+                    std::process::exit(1);
+                }
+            };
+            let caller = loaded
+                .source
+                .encode(&extraction.caller)
+                .unwrap_or(extraction.caller);
+            for (path, contents) in [(into, &extraction.workflow), (file, &caller)].iter() {
+                if let Err(e) = std::fs::write(path, contents) {
+                    eprintln!("Can't write {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+            let inputs: Vec<&str> = extraction.inputs.iter().map(|i| i.name.as_str()).collect();
+            eprintln!(
+                "Moved the steps into {}, called by `{}`",
+                into.display(),
+                extraction.call_job
+            );
+            if !inputs.is_empty() {
+                eprintln!("  inputs: {}", inputs.join(", "));
+            }
+            if extraction.inherit_secrets {
+                eprintln!("  secrets: all, inherited");
+            } else if !extraction.secrets.is_empty() {
+                eprintln!("  secrets: {}", extraction.secrets.join(", "));
+            }
+            for note in &extraction.notes {
+                eprintln!("note: {}", note);
+            }
+            eprintln!(
+                "note: {} was rewritten from the parsed YAML, so its comments are gone",
+                file.display()
+            );
+        }
+        Command::Rules { json } => {
+            let rules = configured_rules(args);
+            if *json {
+                let listed: Vec<serde_json::Value> = rules
+                    .iter()
+                    .map(|rule| {
+                        serde_json::json!({
+                            "name": rule.name(),
+                            "code": diagnostics::code(rule.name()),
+                            "description": rule.description(),
+                            "severity": rule.severity().as_str(),
+                            "enabled": disabled(args, rule.as_ref()).is_none(),
+                            "group": rule.group(),
+                            "security": rule.security(),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&listed).unwrap());
+                return;
+            }
+            let width = rules
+                .iter()
+                .map(|rule| rule.name().len())
+                .max()
+                .unwrap_or(0);
+            for rule in &rules {
+                let mut notes = Vec::new();
+                if disabled(args, rule.as_ref()).is_some() {
+                    notes.push("off".to_string());
+                }
+                if let Some(group) = rule.group() {
+                    notes.push(format!("in {}", group));
+                }
+                if rule.security() {
+                    notes.push("security".to_string());
+                }
+                let notes = match notes.is_empty() {
+                    true => String::new(),
+                    false => format!(" ({})", notes.join(", ")),
+                };
+                println!(
+                    "{}  {:width$}  {:7}  {}{}",
+                    diagnostics::code(rule.name()).unwrap_or("     "),
+                    rule.name(),
+                    rule.severity().as_str(),
+                    rule.description(),
+                    notes,
+                    width = width
+                );
+            }
+        }
+        Command::Badge { dir, output, json } => {
+            let files = workflow_files_or_exit(dir.as_deref());
+            let mut diagnostics = Vec::new();
+            for file in &files {
+                diagnostics.extend(lint_file(args, stubs, file));
+            }
+            let security: Vec<&str> = rules(args)
+                .iter()
+                .filter(|rule| rule.security())
+                .map(|rule| rule.name())
+                .collect();
+            let score = badge::Score::new(files.len(), &diagnostics, &security);
+            let text = match json {
+                true => serde_json::to_string_pretty(&score).unwrap() + "\n",
+                false => badge::svg(&score),
+            };
+            match output {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(path, text) {
+                        eprintln!("Couldn't write {}: {}", path.display(), e);
+                        std::process::exit(2);
+                    }
+                }
+                None => print!("{}", text),
+            }
+        }
+        Command::Inputs { dir, json } => {
+            let files = workflow_files_or_exit(dir.as_deref());
+            let mut found = Vec::new();
+            for file in files {
+                let source = match std::fs::read(&file) {
+                    Ok(bytes) => Decoded::decode(&bytes).text,
+                    Err(e) => {
+                        eprintln!("warning: can't read {}: {}", file.display(), e);
+                        continue;
+                    }
+                };
+                match serde_yaml::from_str(&source) {
+                    Ok(document) => found.extend(inputs::inputs(&document, &source, &file)),
+                    Err(e) => eprintln!("warning: can't parse {}: {}", file.display(), e),
+                }
+            }
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&found).unwrap());
+            } else {
+                inputs::write_report(&mut stdout.lock(), &found).unwrap();
+            }
+        }
+        Command::Schedules { repo: name } => {
+            let remote = match name {
+                Some(name) => name
+                    .split_once('/')
+                    .map(|(owner, name)| (owner.to_string(), name.to_string())),
+                None => repo::find_root(Path::new(".")).and_then(|root| repo::github_remote(&root)),
+            };
+            let (owner, name) = match remote {
+                Some(remote) => remote,
+                None => {
+                    eprintln!("Can't tell which repository to ask about; pass --repo owner/name");
+                    std::process::exit(2);
+                }
+            };
+            let mut scheduler = scheduler_or_exit(args);
+            // GitHub doesn't run the workflows of an archived repository,
+            // whatever their state. Without a token the lookup would be
+            // REST calls of its own, so it's skipped
+            if scheduler.client().has_token() {
+                let repository = github::RepositoryName::new(owner.as_str(), name.as_str());
+                let resolved = scheduler.resolve_repositories(vec![repository]);
+                if let Some((_, github::Response::Found(found))) = resolved.first() {
+                    if found.archived {
+                        println!(
+                            "{}/{} is archived, so none of its workflows run",
+                            owner, name
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            }
+            let path = format!("/repos/{}/{}/actions/workflows?per_page=100", owner, name);
+            let workflows = match scheduler.get(&path) {
+                github::Response::Found(found) => found,
+                github::Response::NotFound => {
+                    eprintln!(
+                        "GitHub doesn't know {}/{}, or the token can't see it",
+                        owner, name
+                    );
+                    std::process::exit(2);
+                }
+                github::Response::Unavailable => {
+                    for warning in scheduler.take_warnings() {
+                        eprintln!("warning: {}", warning);
+                    }
+                    std::process::exit(2);
+                }
+            };
+            let mut inactive = false;
+            for workflow in workflows["workflows"].as_array().into_iter().flatten() {
+                let state = workflow["state"].as_str().unwrap_or_default();
+                let why = match state {
+                    "disabled_inactivity" => {
+                        inactive = true;
+                        "disabled by GitHub after 60 days without activity; re-enable it \
+                         and add `workflow_dispatch` or a keepalive step"
+                    }
+                    "disabled_manually" => "disabled by hand",
+                    "disabled_fork" => "disabled because the repository is a fork",
+                    _ => continue,
+                };
+                println!("{}: {}", workflow["path"].as_str().unwrap_or_default(), why);
+            }
+            if inactive {
+                std::process::exit(1);
+            }
+        }
+        Command::Pins { paths, max_age } => {
+            let mut scheduler = scheduler_or_exit(args);
+            let loaded = load_all_or_exit(&workflow_paths(paths, &args.ignore));
+            let actions: Vec<ActionRef> = loaded
+                .iter()
+                .flat_map(|(_, loaded)| pins::pinned(&loaded.document))
+                .map(|(_, action)| action)
+                .collect();
+            prefetch(&mut scheduler, &actions);
+            let mut diagnostics = Vec::new();
+            for (file, loaded) in &loaded {
+                diagnostics.extend(pins::check(
+                    &mut scheduler,
+                    &loaded.document,
+                    &loaded.source.text,
+                    file,
+                    *max_age,
+                ));
+            }
+            for warning in scheduler.take_warnings() {
+                eprintln!("warning: {}", warning);
+            }
+            let shown = prepare(args, catalog, diagnostics.clone());
+            write_diagnostics(&mut stdout.lock(), args.format, &shown);
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Attestations { paths } => {
+            let mut scheduler = scheduler_or_exit(args);
+            let loaded = load_all_or_exit(&workflow_paths(paths, &args.ignore));
+            let actions: Vec<ActionRef> = loaded
+                .iter()
+                .flat_map(|(_, loaded)| attestations::checked(&loaded.document))
+                .map(|(_, _, action)| action)
+                .collect();
+            prefetch(&mut scheduler, &actions);
+            let mut diagnostics = Vec::new();
+            for (file, loaded) in &loaded {
+                diagnostics.extend(attestations::check(
+                    &mut scheduler,
+                    &loaded.document,
+                    &loaded.source.text,
+                    file,
+                ));
+            }
+            for warning in scheduler.take_warnings() {
+                eprintln!("warning: {}", warning);
+            }
+            let shown = prepare(args, catalog, diagnostics.clone());
+            write_diagnostics(&mut stdout.lock(), args.format, &shown);
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Ids { file, json } => {
+            let loaded = load_or_exit(file);
+            let elements = elements::elements(&loaded.workflow, &loaded.spans);
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&elements).unwrap());
+            } else {
+                elements::write_elements(&mut stdout.lock(), &elements).unwrap();
+            }
+        }
+    }
+}
+
+/// The GitHub token from the first of `sources` that has one. It's looked
+/// for the first time something needs the API, so a run that doesn't never
+/// asks `gh` for one or says it's using one.
+fn token(sources: &[TokenSource]) -> Option<&'static github::Token> {
+    static TOKEN: OnceLock<Option<github::Token>> = OnceLock::new();
+    TOKEN
+        .get_or_init(|| {
+            let token = github::discover_token(sources);
+            if let Some(token) = &token {
+                eprintln!("Using GitHub token from {}", token.source);
+            }
+            token
+        })
+        .as_ref()
+}
+
+/// A client for the GitHub API, authenticated with `token` if there is one.
+fn github_client(token: Option<&github::Token>) -> Option<github::Client> {
+    github::Client::new(&github::HttpConfig::from_env())
+        .ok()
+        .map(|client| match token {
+            Some(token) => client.with_token(token.value.clone()),
+            None => client,
+        })
+}
+
+/// Look up the repositories and commits of `actions`, from every workflow
+/// being checked, before checking any of them, so that an action many
+/// workflows use costs one lookup.
+fn prefetch(scheduler: &mut github::Scheduler, actions: &[ActionRef]) {
+    pins::resolve_repositories(scheduler, actions);
+    let commits = actions.iter().filter_map(|action| match action {
+        ActionRef::Repository {
+            owner,
+            repo,
+            git_ref,
+            ..
+        } => {
+            // As the checks ask for them, with pins in lower case
+            let git_ref = match action.is_pinned() {
+                true => git_ref.to_lowercase(),
+                false => git_ref.clone(),
+            };
+            Some(format!("/repos/{}/{}/commits/{}", owner, repo, git_ref))
+        }
+        _ => None,
+    });
+    scheduler.prefetch(commits);
+}
+
+/// A scheduler for commands that can't do without the GitHub API.
+fn scheduler_or_exit(args: &Args) -> github::Scheduler {
+    if args.offline {
+        eprintln!("This command needs the GitHub API, which --offline turns off");
+        std::process::exit(2);
+    }
+    match github_client(token(&args.token_source)) {
+        Some(client) => github::Scheduler::new(client).with_budget(args.budget.clone()),
+        None => {
+            eprintln!("Couldn't set up a GitHub API client; check the proxy and CA bundle");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Read the config file, if there is one, and fill in what the command line
+/// left out. Rules the command line enables aren't disabled by the config.
+fn apply_config(args: &mut Args) {
+    let path = match &args.config {
+        Some(path) => Some(path.clone()),
+        None => {
+            let start = args
+                .paths
+                .first()
+                .filter(|path| path.exists())
+                .map_or(Path::new("."), PathBuf::as_path);
+            config::discover(start).map(repo::relative_to_cwd)
+        }
+    };
+    let loaded = match path {
+        Some(path) => load_config(args, &path),
+        None => config::Config::default(),
+    };
+
+    let enabled = args.enable.clone();
+    args.enable.extend(loaded.enable);
+    args.disable.extend(
+        loaded
+            .disable
+            .into_iter()
+            .filter(|name| !enabled.contains(name)),
+    );
+    // Already checked when the config was read
+    let target = loaded.target.as_deref().and_then(|t| t.parse().ok());
+    args.target = args.target.or(target);
+    args.severity = loaded.severity;
+    args.severity
+        .extend(args.severity_overrides.iter().cloned());
+    args.runners = loaded.runners;
+    args.step_names = loaded.step_name;
+    args.deprecated = loaded.deprecated;
+    let ignore = std::mem::take(&mut args.ignore);
+    args.ignore = loaded.ignore;
+    args.ignore.extend(ignore);
+    args.pinning = loaded.pinning;
+    args.pinning.require = args.pin_level.or(args.pinning.require);
+    args.retry_action = args.retry_action.take().or(loaded.retry_action);
+    args.shellcheck = args.shellcheck.take().or(loaded.shellcheck);
+    args.locale = args.locale.take().or(loaded.locale);
+    args.max_diagnostics_per_file = args
+        .max_diagnostics_per_file
+        .or(loaded.max_diagnostics_per_file);
+}
+
+/// Read the config at `path`, with what it extends, or exit saying why it
+/// can't be.
+fn load_config(args: &Args, path: &Path) -> config::Config {
+    let sources = args.token_source.clone();
+    let mut loader = config::Loader::new().with_connect(move || github_client(token(&sources)));
+    loader.offline = args.offline;
+    for rule in lint::builtin_rules() {
+        loader.rules.push(rule.name().to_string());
+        if let Some(group) = rule.group() {
+            loader.rules.push(group.to_string());
+        }
+    }
+    let loaded = loader.load(path);
+    for warning in &loader.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    match loaded {
+        Ok(loaded) => loaded,
+        Err(errors) => {
+            eprintln!("Couldn't read the config:");
+            for e in errors {
+                eprintln!("  {}", e);
+            }
+            std::process::exit(2);
+        }
+    }
+}
 
 fn main() {
-    println!("Imagine having two write this with marshmallow.py");
-    let mut file = std::fs::File::open("./test_input/example_issue.yaml").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    let workflow: workflow::Workflow = serde_yaml::from_str(&contents).unwrap();
-    print!("{:?}", workflow);
+    let started = Instant::now();
+    let mut args = Args::parse();
+    let mut budget = Budget::default();
+    if let Some(timeout) = args.timeout {
+        budget = budget.with_timeout(timeout);
+    }
+    if let Some(timeout) = args.rule_timeout {
+        budget = budget.with_rule_timeout(timeout);
+    }
+    args.budget = budget;
+    // The config's names are checked as it's read
+    let rules = lint::builtin_rules();
+    for name in args.enable.iter().chain(&args.disable) {
+        if !rules
+            .iter()
+            .any(|rule| rule.name() == name || rule.group() == Some(name.as_str()))
+        {
+            eprintln!(
+                "warning: there's no rule or group of rules named `{}`",
+                name
+            );
+        }
+    }
+    for (name, _) in &args.severity_overrides {
+        if !rules.iter().any(|rule| rule.name() == name) {
+            eprintln!("warning: there's no rule named `{}`", name);
+        }
+    }
+    apply_config(&mut args);
+
+    let stubs = match &args.contexts {
+        Some(path) => match std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| Stubs::parse(&source).map_err(|e| e.to_string()))
+        {
+            Ok(stubs) => stubs,
+            Err(e) => {
+                eprintln!("Couldn't read contexts from {}: {}", path.display(), e);
+                std::process::exit(2);
+            }
+        },
+        None => Stubs::default(),
+    };
+
+    let catalog = match &args.locale {
+        Some(locale) => match Catalog::load(locale, repo::find_root(Path::new(".")).as_deref()) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                eprintln!("Couldn't load messages for {}: {}", locale, e);
+                std::process::exit(2);
+            }
+        },
+        None => Catalog::default(),
+    };
+
+    if let Some(command) = &args.command {
+        run_command(&args, command, &stubs, &catalog);
+        return;
+    }
+
+    let files = workflow_paths(&args.paths, &args.ignore);
+    let mut diagnostics = Vec::new();
+    for file in &files {
+        diagnostics.extend(lint_file(&args, &stubs, file));
+    }
+    // A fragment isn't part of the checkout's workflows
+    let mut templates = 0;
+    if args.fragment.is_none() {
+        let roots: BTreeSet<PathBuf> = files.iter().filter_map(|f| repo::find_root(f)).collect();
+        for root in roots {
+            let (found, count) = lint_templates(&args, &stubs, &root);
+            diagnostics.extend(found);
+            templates += count;
+        }
+    }
+
+    // The exit status counts everything, including findings left unshown
+    let stdout = std::io::stdout();
+    let shown = prepare(&args, &catalog, diagnostics.clone());
+    write_diagnostics(&mut stdout.lock(), args.format, &shown);
+    if args.summary || args.summary_json.is_some() {
+        let grouped = noise::group(diagnostics.clone());
+        let mut summary = Summary::new(files.len() + templates, started.elapsed(), &grouped);
+        summary.fixed = FIXED.load(Ordering::Relaxed);
+        for input in &files {
+            if let Ok(bytes) = std::fs::read(input) {
+                let source = Decoded::decode(&bytes).text;
+                if let Some(deprecation) = deprecation::find(&source, input, &args.deprecated) {
+                    summary
+                        .deprecated
+                        .insert(input.display().to_string(), deprecation);
+                }
+            }
+        }
+        if args.summary {
+            output::write_summary(&mut stdout.lock(), &summary).unwrap();
+        }
+        if let Some(path) = &args.summary_json {
+            let json = serde_json::to_string_pretty(&summary).unwrap();
+            if let Err(e) = std::fs::write(path, json + "\n") {
+                eprintln!("Couldn't write the summary to {}: {}", path.display(), e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if STOPPED.load(Ordering::Relaxed) {
+        eprintln!("warning: the run's time limit ran out, so not every rule ran");
+    }
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,291 @@
+//! Expanding a job's `strategy.matrix` into the legs GitHub runs.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use serde_json::{Map, Value};
+
+use crate::eval;
+use crate::expr;
+use crate::workflow::{Axis, Job, Matrix, MatrixEntries};
+
+/// GitHub refuses matrices with more legs than this.
+pub const MAX_LEGS: usize = 256;
+
+/// One way a matrix job runs: a value for each key, in the order the keys
+/// were first seen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub values: Vec<(String, Value)>,
+}
+
+impl Leg {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn set(&mut self, key: &str, value: Value) {
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.values.push((key.to_string(), value)),
+        }
+    }
+
+    /// The leg as the `matrix` context.
+    pub fn context(&self) -> Value {
+        Value::Object(self.values.iter().cloned().collect::<Map<_, _>>())
+    }
+}
+
+/// A matrix worked out leg by leg.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    /// Every key any leg has, axes first, then keys added by `include`.
+    pub keys: Vec<String>,
+    pub legs: Vec<Leg>,
+    /// Combinations removed by `exclude`.
+    pub excluded: usize,
+    /// Legs that `include` added rather than extended.
+    pub added: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixError {
+    pub message: String,
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+fn error(message: impl Into<String>) -> MatrixError {
+    MatrixError {
+        message: message.into(),
+    }
+}
+
+/// Whether every key of `entry` that's also in `leg` has the same value.
+fn matches(leg: &Leg, entry: &Map<String, Value>, keys: &[String]) -> bool {
+    entry
+        .iter()
+        .filter(|(key, _)| keys.contains(key))
+        .all(|(key, value)| leg.get(key) == Some(value))
+}
+
+fn json(value: &serde_yaml::Value) -> Result<Value, MatrixError> {
+    serde_json::to_value(value).map_err(|e| error(e.to_string()))
+}
+
+fn only_at_run(key: &str) -> MatrixError {
+    error(format!(
+        "`{}` is an expression, so the matrix is only known during the run",
+        key
+    ))
+}
+
+fn entries(
+    entries: &Option<MatrixEntries>,
+    key: &str,
+) -> Result<Vec<Map<String, Value>>, MatrixError> {
+    match entries {
+        None => Ok(Vec::new()),
+        Some(MatrixEntries::Expression(s)) if s.contains("${{") => Err(only_at_run(key)),
+        Some(MatrixEntries::Entries(items)) => items
+            .iter()
+            .map(|item| {
+                item.iter()
+                    .map(|(k, v)| Ok((k.clone(), json(v)?)))
+                    .collect()
+            })
+            .collect(),
+        Some(MatrixEntries::Invalid(serde_yaml::Value::Sequence(_))) => {
+            Err(error(format!("`{}` entries must be maps", key)))
+        }
+        Some(_) => Err(error(format!("`{}` must be a list of maps", key))),
+    }
+}
+
+/// Expand a matrix the way GitHub does: every combination of the axes,
+/// less the ones matching an `exclude`, then each `include` added to the
+/// legs it doesn't contradict, or as a leg of its own if it contradicts all
+/// of them.
+pub fn expand(matrix: &Matrix) -> Result<Expansion, MatrixError> {
+    let matrix = match matrix {
+        Matrix::Axes(axes) => axes,
+        Matrix::Expression(s) if s.contains("${{") => {
+            return Err(error(
+                "the matrix is an expression, so it's only known during the run",
+            ))
+        }
+        _ => return Err(error("the matrix must be a map of keys to lists of values")),
+    };
+
+    let mut keys = Vec::new();
+    let mut legs = vec![Leg { values: Vec::new() }];
+    for (key, axis) in &matrix.axes {
+        let values = match axis {
+            Axis::Values(values) => values.iter().map(json).collect::<Result<Vec<_>, _>>()?,
+            Axis::Expression(s) if s.contains("${{") => return Err(only_at_run(key)),
+            _ => return Err(error(format!("`{}` must be a list of values", key))),
+        };
+        legs = legs
+            .iter()
+            .flat_map(|leg| {
+                values.iter().map(move |value| {
+                    let mut leg = leg.clone();
+                    leg.set(key, value.clone());
+                    leg
+                })
+            })
+            .collect();
+        keys.push(key.to_string());
+    }
+    // No axes at all means no combinations, only what `include` adds
+    if keys.is_empty() {
+        legs.clear();
+    }
+
+    let before = legs.len();
+    for exclude in entries(&matrix.exclude, "exclude")? {
+        legs.retain(|leg| {
+            !exclude
+                .iter()
+                .all(|(key, value)| leg.get(key) == Some(value))
+        });
+    }
+    let excluded = before - legs.len();
+
+    let axes = keys.clone();
+    let mut added = 0;
+    for include in &entries(&matrix.include, "include")? {
+        let mut extended = false;
+        for leg in legs.iter_mut().take(before - excluded) {
+            if matches(leg, include, &axes) {
+                for (key, value) in include {
+                    leg.set(key, value.clone());
+                }
+                extended = true;
+            }
+        }
+        if !extended {
+            legs.push(Leg {
+                values: include
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            });
+            added += 1;
+        }
+        for key in include.keys() {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+    }
+
+    Ok(Expansion {
+        keys,
+        legs,
+        excluded,
+        added,
+    })
+}
+
+/// How a value is shown in the table: scalars as they'd be interpolated,
+/// maps and lists as JSON.
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::Array(_) | Value::Object(_) => value.to_string(),
+        _ => eval::to_string(value),
+    }
+}
+
+/// `s` with the expressions in it that only read the leg's values filled
+/// in.
+fn interpolate(s: &str, leg: &Leg) -> String {
+    let mut contexts = Map::new();
+    contexts.insert("matrix".to_string(), leg.context());
+    let contexts = Value::Object(contexts);
+    let mut out = String::new();
+    let mut rest = 0;
+    for embedded in expr::find_expressions(s) {
+        let value = expr::parse(embedded.body)
+            .ok()
+            .and_then(|e| eval::evaluate(&e, &contexts));
+        if let Some(value) = value {
+            out.push_str(&s[rest..embedded.offset]);
+            out.push_str(&display(&value));
+            rest = (embedded.body_offset + embedded.body.len() + 2).min(s.len());
+        }
+    }
+    out.push_str(&s[rest..]);
+    out
+}
+
+/// The `runs-on` labels a leg of `job` ends up with.
+pub fn runs_on(job: &Job, leg: &Leg) -> String {
+    job.labels()
+        .iter()
+        .map(|label| interpolate(label, leg))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Print the legs as a table, one row per leg with its `runs-on`, followed
+/// by a summary.
+pub fn write_table(out: &mut impl Write, job: &Job, expansion: &Expansion) -> io::Result<()> {
+    let mut header = vec!["#".to_string()];
+    header.extend(expansion.keys.iter().cloned());
+    header.push("runs-on".to_string());
+    let rows: Vec<Vec<String>> = expansion
+        .legs
+        .iter()
+        .enumerate()
+        .map(|(i, leg)| {
+            let mut row = vec![(i + 1).to_string()];
+            row.extend(
+                expansion
+                    .keys
+                    .iter()
+                    .map(|key| leg.get(key).map(display).unwrap_or_default()),
+            );
+            row.push(runs_on(job, leg));
+            row
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+            .collect();
+        writeln!(out, "{}", cells.join("  ").trim_end())?;
+    }
+
+    writeln!(
+        out,
+        "{} legs ({} excluded, {} added by include)",
+        expansion.legs.len(),
+        expansion.excluded,
+        expansion.added
+    )?;
+    if expansion.legs.len() > MAX_LEGS {
+        writeln!(
+            out,
+            "GitHub won't run this: a matrix can have at most {} legs",
+            MAX_LEGS
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Translated and reworded diagnostic messages.
+//!
+//! Rules write their messages in English. A catalog replaces them with its
+//! own text, looked up by rule name: `<rule>.<id>` for the rule's message
+//! with that id, falling back to `<rule>` for all of the rule's messages.
+//! Catalogs are YAML mappings from those keys to templates, in which
+//! `{name}` is replaced with the diagnostic's parameter of that name and
+//! `{{` and `}}` stand for braces. Every message has the parameters `rule`,
+//! `severity` and `message`, the English text, so a catalog can reword the
+//! messages it hasn't translated around it.
+//!
+//! ```yaml
+//! step-name: "Schritt ohne `name`"
+//! unknown-key.typo: "`{key}` ist kein Schlüssel von {object}; gemeint war wohl `{suggestion}`"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::Diagnostic;
+
+/// Where a repository keeps its catalogs, named `<locale>.yaml`.
+pub const LOCALES_DIR: &str = ".github/workflow-linter/locales";
+
+/// Why a catalog couldn't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessagesError {
+    pub message: String,
+}
+
+impl fmt::Display for MessagesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for MessagesError {}
+
+fn error(message: impl Into<String>) -> MessagesError {
+    MessagesError {
+        message: message.into(),
+    }
+}
+
+/// Message templates keyed by rule and message id. The default catalog is
+/// empty, which leaves every message in English.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    messages: BTreeMap<String, String>,
+}
+
+impl Catalog {
+    pub fn parse(source: &str) -> Result<Catalog, MessagesError> {
+        let messages: BTreeMap<String, String> = serde_yaml::from_str(source)
+            .map_err(|e| error(format!("a catalog maps message keys to text: {}", e)))?;
+        for (key, template) in &messages {
+            if let Err(e) = check_template(template) {
+                return Err(error(format!("`{}`: {}", key, e)));
+            }
+        }
+        Ok(Catalog { messages })
+    }
+
+    /// The file `locale` names: a path if it looks like one, otherwise
+    /// `<locale>.yaml` in the repository's `LOCALES_DIR`.
+    pub fn path(locale: &str, repo_root: Option<&Path>) -> PathBuf {
+        if locale.ends_with(".yaml") || locale.ends_with(".yml") || locale.contains('/') {
+            return PathBuf::from(locale);
+        }
+        let file = Path::new(LOCALES_DIR).join(format!("{}.yaml", locale));
+        match repo_root {
+            Some(root) => root.join(file),
+            None => file,
+        }
+    }
+
+    /// Load the catalog for `locale`. English needs none.
+    pub fn load(locale: &str, repo_root: Option<&Path>) -> Result<Catalog, MessagesError> {
+        if locale == "en" {
+            return Ok(Catalog::default());
+        }
+        let path = Catalog::path(locale, repo_root);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| error(format!("couldn't read {}: {}", path.display(), e)))?;
+        Catalog::parse(&source).map_err(|e| error(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The template for `diagnostic`, if the catalog has one.
+    pub fn template(&self, diagnostic: &Diagnostic) -> Option<&str> {
+        let specific = diagnostic
+            .message_id
+            .as_ref()
+            .and_then(|id| self.messages.get(&format!("{}.{}", diagnostic.rule, id)));
+        specific
+            .or_else(|| self.messages.get(&diagnostic.rule))
+            .map(String::as_str)
+    }
+
+    /// Replace the messages of `diagnostic`, and those grouped under it,
+    /// with the catalog's.
+    pub fn localize(&self, diagnostic: &mut Diagnostic) {
+        if let Some(template) = self.template(diagnostic) {
+            diagnostic.message = interpolate(template, diagnostic);
+        }
+        for related in &mut diagnostic.related {
+            self.localize(related);
+        }
+    }
+}
+
+/// Check that the braces in `template` pair up.
+fn check_template(template: &str) -> Result<(), String> {
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+            }
+            '{' => {
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                if name.is_empty() || name.contains('{') {
+                    return Err("`{` has to start a `{name}` or be written `{{`".to_string());
+                }
+            }
+            '}' => return Err("`}` has to end a `{name}` or be written `}}`".to_string()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// `template` with the parameters of `diagnostic` filled in. Names it
+/// doesn't have are left as they are.
+fn interpolate(template: &str, diagnostic: &Diagnostic) -> String {
+    let param = |name: &str| -> Option<String> {
+        match name {
+            "rule" => Some(diagnostic.rule.clone()),
+            "severity" => Some(diagnostic.severity.to_string()),
+            "message" => Some(diagnostic.message.clone()),
+            _ => diagnostic
+                .params
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone()),
+        }
+    };
+
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if after.starts_with(&rest[i..=i]) {
+            out.push_str(&rest[i..=i]);
+            rest = &after[1..];
+            continue;
+        }
+        match after.find('}') {
+            Some(end) if rest.as_bytes()[i] == b'{' => {
+                let name = &after[..end];
+                match param(name) {
+                    Some(value) => out.push_str(&value),
+                    None => out.push_str(&rest[i..i + end + 2]),
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push_str(&rest[i..=i]);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,97 @@
+//! Keeping output readable when there's a lot of it.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::diagnostics::{Diagnostic, Location, Severity};
+
+/// The rule name of the note left where findings were cut off.
+pub const TRUNCATED: &str = "max-diagnostics";
+
+/// Drop repeated findings, and fold findings at the same place into the
+/// most severe of them so a line flagged by several rules is shown once.
+/// Findings without a location are left alone.
+pub fn group(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut grouped: Vec<Diagnostic> = Vec::new();
+    let mut at: BTreeMap<(Option<PathBuf>, Location), usize> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        let location = match diagnostic.location {
+            Some(location) => location,
+            None => {
+                if !grouped.contains(&diagnostic) {
+                    grouped.push(diagnostic);
+                }
+                continue;
+            }
+        };
+        let key = (diagnostic.path.clone(), location);
+        let primary = match at.get(&key) {
+            Some(&i) => &mut grouped[i],
+            None => {
+                at.insert(key, grouped.len());
+                grouped.push(diagnostic);
+                continue;
+            }
+        };
+        let same = |d: &Diagnostic| {
+            d.rule == diagnostic.rule
+                && d.severity == diagnostic.severity
+                && d.message == diagnostic.message
+        };
+        if same(primary) || primary.related.iter().any(same) {
+            continue;
+        }
+        if diagnostic.severity > primary.severity {
+            let mut diagnostic = diagnostic;
+            let mut previous = std::mem::take(&mut primary.related);
+            std::mem::swap(primary, &mut diagnostic);
+            previous.insert(0, diagnostic);
+            primary.related = previous;
+        } else {
+            primary.related.push(diagnostic);
+        }
+    }
+    grouped
+}
+
+/// Keep at most `max` findings for each file, the most severe first, with
+/// a note saying how many more there were.
+pub fn limit_per_file(diagnostics: Vec<Diagnostic>, max: usize) -> Vec<Diagnostic> {
+    let mut by_file: BTreeMap<Option<PathBuf>, Vec<usize>> = BTreeMap::new();
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        by_file.entry(diagnostic.path.clone()).or_default().push(i);
+    }
+
+    let mut keep = vec![true; diagnostics.len()];
+    let mut notes = Vec::new();
+    for (path, mut indexes) in by_file {
+        if indexes.len() <= max {
+            continue;
+        }
+        // Stable, so findings of the same severity keep their order
+        indexes.sort_by_key(|&i| std::cmp::Reverse(diagnostics[i].severity));
+        let dropped = &indexes[max..];
+        for &i in dropped {
+            keep[i] = false;
+        }
+        let mut note = Diagnostic::new(
+            TRUNCATED,
+            Severity::Info,
+            format!(
+                "{} more findings in this file aren't shown; raise \
+                 --max-diagnostics-per-file to see them",
+                dropped.len()
+            ),
+        );
+        note.path = path;
+        notes.push(note);
+    }
+
+    let mut limited: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(diagnostic, keep)| if keep { Some(diagnostic) } else { None })
+        .collect();
+    limited.extend(notes);
+    limited
+}
@@ -0,0 +1,169 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::deprecation::Deprecation;
+
+use super::summary::{plural, write_deprecated};
+use super::Summary;
+
+/// How often a rule fired across the runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RuleCount {
+    pub findings: usize,
+    /// How many of the runs it fired in.
+    pub runs: usize,
+}
+
+/// The summaries of many runs, from many repositories or many days, added
+/// up to see which rules are worth enforcing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Aggregate {
+    pub runs: usize,
+    pub files_scanned: usize,
+    pub total: usize,
+    pub by_severity: BTreeMap<String, usize>,
+    pub by_rule: BTreeMap<String, RuleCount>,
+    /// Keyed by the summary's path under the directory, then the file's,
+    /// since runs in different repositories have files with the same path.
+    pub by_file: BTreeMap<String, usize>,
+    pub fixable: usize,
+    pub fixed: usize,
+    /// Keyed like `by_file`.
+    pub deprecated: BTreeMap<String, Deprecation>,
+}
+
+impl Aggregate {
+    /// Add the summary of a run, read from `source`.
+    pub fn add(&mut self, source: &str, summary: &Summary) {
+        self.runs += 1;
+        self.files_scanned += summary.files_scanned;
+        self.total += summary.total;
+        for (severity, count) in &summary.by_severity {
+            *self.by_severity.entry(severity.clone()).or_default() += count;
+        }
+        for (rule, &count) in &summary.by_rule {
+            let entry = self.by_rule.entry(rule.clone()).or_default();
+            entry.findings += count;
+            entry.runs += 1;
+        }
+        for (file, count) in &summary.by_file {
+            *self
+                .by_file
+                .entry(format!("{}: {}", source, file))
+                .or_default() += count;
+        }
+        self.fixable += summary.fixable;
+        self.fixed += summary.fixed;
+        for (file, deprecation) in &summary.deprecated {
+            self.deprecated
+                .insert(format!("{}: {}", source, file), deprecation.clone());
+        }
+    }
+
+    /// The share of findings with a fix that were fixed, if any had one.
+    pub fn fix_adoption(&self) -> Option<f64> {
+        let offered = self.fixed + self.fixable;
+        (offered > 0).then(|| self.fixed as f64 / offered as f64)
+    }
+}
+
+/// Every `.json` file under `dir`, in path order.
+fn json_files(dir: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            json_files(&path, found)?;
+        } else if path.extension().is_some_and(|e| e == "json") {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Add up the `--summary-json` files under `dir`. Files that aren't
+/// summaries are left out, and returned with why.
+pub fn aggregate(dir: &Path) -> io::Result<(Aggregate, Vec<(PathBuf, String)>)> {
+    let mut files = Vec::new();
+    json_files(dir, &mut files)?;
+    files.sort();
+    let mut aggregate = Aggregate::default();
+    let mut skipped = Vec::new();
+    for file in files {
+        let summary = std::fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|source| serde_json::from_str::<Summary>(&source).map_err(|e| e.to_string()));
+        match summary {
+            Ok(summary) => {
+                let source = file.strip_prefix(dir).unwrap_or(&file).with_extension("");
+                aggregate.add(&source.display().to_string(), &summary);
+            }
+            Err(e) => skipped.push((file, e)),
+        }
+    }
+    Ok((aggregate, skipped))
+}
+
+/// Write the most violated rules, the noisiest files and how many fixes
+/// were taken up, `top` of each list.
+pub fn write_aggregate(out: &mut impl Write, aggregate: &Aggregate, top: usize) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} findings in {} files over {} runs",
+        aggregate.total, aggregate.files_scanned, aggregate.runs
+    )?;
+    let severities: Vec<String> = [
+        ("error", "errors"),
+        ("warning", "warnings"),
+        ("info", "info"),
+    ]
+    .iter()
+    .filter_map(|&(one, many)| Some(plural(*aggregate.by_severity.get(one)?, one, many)))
+    .collect();
+    if !severities.is_empty() {
+        writeln!(out, "  {}", severities.join(", "))?;
+    }
+
+    let mut rules: Vec<(&String, &RuleCount)> = aggregate.by_rule.iter().collect();
+    rules.sort_by_key(|&(name, count)| (Reverse(count.findings), name));
+    if !rules.is_empty() {
+        let width = rules.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        writeln!(out, "Most violated rules:")?;
+        for (name, count) in rules.into_iter().take(top) {
+            writeln!(
+                out,
+                "  {:width$}  {} in {} of {} runs",
+                name,
+                count.findings,
+                count.runs,
+                aggregate.runs,
+                width = width
+            )?;
+        }
+    }
+
+    let mut files: Vec<(&String, &usize)> = aggregate.by_file.iter().collect();
+    files.sort_by_key(|&(name, count)| (Reverse(*count), name));
+    if !files.is_empty() {
+        let width = files.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        writeln!(out, "Noisiest files:")?;
+        for (name, count) in files.into_iter().take(top) {
+            writeln!(out, "  {:width$}  {}", name, count, width = width)?;
+        }
+    }
+
+    match aggregate.fix_adoption() {
+        Some(adoption) => writeln!(
+            out,
+            "Fixes: {} applied with --fix, {} left, {:.0}% taken up",
+            aggregate.fixed,
+            aggregate.fixable,
+            adoption * 100.0
+        )?,
+        None => writeln!(out, "Fixes: none offered")?,
+    }
+    write_deprecated(out, &aggregate.deprecated)
+}
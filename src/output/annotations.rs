@@ -0,0 +1,147 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::diagnostics::{Diagnostic, Severity};
+
+/// GitHub only shows the first ten annotations from a step and silently
+/// drops the rest.
+pub const MAX_ANNOTATIONS: usize = 10;
+
+/// Print diagnostics as workflow commands.
+///
+/// When there are more than GitHub will show, the most severe ones are
+/// annotated and the rest are printed as plain log lines, followed by a
+/// summary annotation with the counts so nobody mistakes the annotations for
+/// the full list.
+pub fn write_annotations(out: &mut impl Write, diagnostics: &[Diagnostic]) -> io::Result<()> {
+    if diagnostics.len() <= MAX_ANNOTATIONS {
+        for diagnostic in diagnostics {
+            write_annotation(out, diagnostic)?;
+        }
+        return Ok(());
+    }
+
+    // The sort is stable, so within a severity findings stay in file order
+    let mut by_priority: Vec<&Diagnostic> = diagnostics.iter().collect();
+    by_priority.sort_by_key(|d| Reverse(d.severity));
+    let (annotated, rest) = by_priority.split_at(MAX_ANNOTATIONS - 1);
+
+    for diagnostic in annotated {
+        write_annotation(out, diagnostic)?;
+    }
+    for diagnostic in rest {
+        writeln!(out, "{}", diagnostic)?;
+    }
+    write_summary(out, diagnostics, rest)
+}
+
+fn write_summary(
+    out: &mut impl Write,
+    diagnostics: &[Diagnostic],
+    rest: &[&Diagnostic],
+) -> io::Result<()> {
+    let mut by_rule: BTreeMap<&str, usize> = BTreeMap::new();
+    for diagnostic in rest {
+        *by_rule.entry(&diagnostic.rule).or_default() += 1;
+    }
+    let mut by_rule: Vec<(&str, usize)> = by_rule.into_iter().collect();
+    by_rule.sort_by_key(|&(_, count)| Reverse(count));
+    let by_rule: Vec<String> = by_rule
+        .into_iter()
+        .map(|(rule, count)| format!("{} ({})", rule, count))
+        .collect();
+
+    let level = rest
+        .iter()
+        .map(|d| d.severity)
+        .max()
+        .unwrap_or(Severity::Info);
+    let message = format!(
+        "{} findings in total ({}); {} more were not annotated and are listed in the log: {}",
+        diagnostics.len(),
+        count_by_severity(diagnostics.iter()),
+        rest.len(),
+        by_rule.join(", "),
+    );
+    writeln!(
+        out,
+        "::{} title=workflow-linter summary::{}",
+        command(level),
+        escape_data(&message)
+    )
+}
+
+fn count_by_severity<'a>(diagnostics: impl Iterator<Item = &'a Diagnostic>) -> String {
+    let mut counts: BTreeMap<Reverse<Severity>, usize> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        *counts.entry(Reverse(diagnostic.severity)).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(Reverse(severity), count)| match severity {
+            Severity::Info => format!("{} info", count),
+            _ if count == 1 => format!("1 {}", severity),
+            _ => format!("{} {}s", count, severity),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn write_annotation(out: &mut impl Write, diagnostic: &Diagnostic) -> io::Result<()> {
+    let mut properties = Vec::new();
+    if let Some(path) = &diagnostic.path {
+        properties.push(format!(
+            "file={}",
+            escape_property(&path.display().to_string())
+        ));
+    }
+    if let Some(location) = diagnostic.location {
+        properties.push(format!("line={}", location.line));
+        properties.push(format!("col={}", location.column));
+    }
+    properties.push(format!("title={}", escape_property(&diagnostic.rule)));
+
+    let mut message = diagnostic.message.clone();
+    push_suggestions(&mut message, diagnostic);
+    for related in &diagnostic.related {
+        message.push_str(&format!("\nAlso [{}]: {}", related.rule, related.message));
+        push_suggestions(&mut message, related);
+    }
+    writeln!(
+        out,
+        "::{} {}::{}",
+        command(diagnostic.severity),
+        properties.join(","),
+        escape_data(&message)
+    )
+}
+
+/// Suggestions go under the message they're for, with their examples set
+/// apart by blank lines.
+fn push_suggestions(message: &mut String, diagnostic: &Diagnostic) {
+    for suggestion in &diagnostic.suggestions {
+        message.push_str(&format!("\nSuggestion: {}", suggestion.message));
+        if let Some(example) = &suggestion.example {
+            message.push_str(&format!("\n\n{}\n", example));
+        }
+    }
+}
+
+fn command(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "notice",
+    }
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
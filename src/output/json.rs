@@ -0,0 +1,73 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::diagnostics::{Diagnostic, Suggestion};
+
+/// A diagnostic as `--format json` prints it. Positions are 1-based, with
+/// columns counted in characters; the ends are just past the last
+/// character.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsonDiagnostic<'d> {
+    pub rule: &'d str,
+    pub code: Option<&'static str>,
+    pub severity: &'static str,
+    pub message: &'d str,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub end_line: Option<usize>,
+    pub end_column: Option<usize>,
+    pub node: Option<&'d str>,
+    pub message_id: Option<&'d str>,
+    /// Whether `--fix` can resolve it.
+    pub fixable: bool,
+    pub suggestions: Vec<JsonSuggestion<'d>>,
+    pub related: Vec<JsonDiagnostic<'d>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsonSuggestion<'d> {
+    pub message: &'d str,
+    pub example: Option<&'d str>,
+}
+
+impl<'d> From<&'d Suggestion> for JsonSuggestion<'d> {
+    fn from(suggestion: &'d Suggestion) -> Self {
+        JsonSuggestion {
+            message: &suggestion.message,
+            example: suggestion.example.as_deref(),
+        }
+    }
+}
+
+impl<'d> From<&'d Diagnostic> for JsonDiagnostic<'d> {
+    fn from(diagnostic: &'d Diagnostic) -> Self {
+        JsonDiagnostic {
+            rule: &diagnostic.rule,
+            code: diagnostic.code(),
+            severity: diagnostic.severity.as_str(),
+            message: &diagnostic.message,
+            file: diagnostic
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            line: diagnostic.location.map(|l| l.line),
+            column: diagnostic.location.map(|l| l.column),
+            end_line: diagnostic.span.map(|s| s.end.line),
+            end_column: diagnostic.span.map(|s| s.end.column),
+            node: diagnostic.node.as_deref(),
+            message_id: diagnostic.message_id.as_deref(),
+            fixable: diagnostic.fix.is_some(),
+            suggestions: diagnostic.suggestions.iter().map(Into::into).collect(),
+            related: diagnostic.related.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Print diagnostics as a JSON array, in the order they're given.
+pub fn write_json(out: &mut impl Write, diagnostics: &[Diagnostic]) -> io::Result<()> {
+    let diagnostics: Vec<JsonDiagnostic> = diagnostics.iter().map(Into::into).collect();
+    serde_json::to_writer_pretty(&mut *out, &diagnostics)?;
+    writeln!(out)
+}
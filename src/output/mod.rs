@@ -0,0 +1,143 @@
+//! Rendering diagnostics for people and for other tools.
+
+use std::cmp::{Ordering, Reverse};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::diagnostics::Diagnostic;
+use crate::report::{self, Style};
+
+mod aggregate;
+mod annotations;
+mod json;
+#[cfg(feature = "formats-sarif")]
+mod sarif;
+mod summary;
+
+pub use aggregate::{aggregate, write_aggregate, Aggregate, RuleCount};
+pub use annotations::{write_annotations, MAX_ANNOTATIONS};
+pub use json::{write_json, JsonDiagnostic, JsonSuggestion};
+#[cfg(feature = "formats-sarif")]
+pub use sarif::write_sarif;
+pub use summary::{write_summary, Summary};
+
+/// The ways diagnostics can be printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One line per diagnostic.
+    Text,
+    /// GitHub Actions workflow commands, which show up as annotations on the
+    /// run and the pull request.
+    Github,
+    /// Each finding with the lines it's about underlined, colored when
+    /// printing to a terminal.
+    Pretty,
+    /// A JSON array of the diagnostics, for other tools to read.
+    Json,
+    /// A SARIF log, for uploading to GitHub code scanning.
+    #[cfg(feature = "formats-sarif")]
+    Sarif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "github" => Ok(Format::Github),
+            "json" => Ok(Format::Json),
+            "pretty" => Ok(Format::Pretty),
+            #[cfg(feature = "formats-sarif")]
+            "sarif" => Ok(Format::Sarif),
+            #[cfg(feature = "formats-sarif")]
+            _ => Err(format!(
+                "unknown format `{}`, expected text, pretty, github, json or sarif",
+                s
+            )),
+            #[cfg(not(feature = "formats-sarif"))]
+            _ => Err(format!(
+                "unknown format `{}`, expected text, pretty, github or json",
+                s
+            )),
+        }
+    }
+}
+
+/// The order diagnostics are printed in. Either way it only depends on the
+/// findings, not on the order rules or files were checked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortBy {
+    /// By file, line, column and rule.
+    #[default]
+    Location,
+    /// The most severe first, then by location.
+    Severity,
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "location" => Ok(SortBy::Location),
+            "severity" => Ok(SortBy::Severity),
+            _ => Err(format!(
+                "unknown order `{}`, expected location or severity",
+                s
+            )),
+        }
+    }
+}
+
+/// File, line, column and rule, with the message and severity to settle
+/// ties. Findings without a location come first in their file.
+fn by_location(a: &Diagnostic, b: &Diagnostic) -> Ordering {
+    let key = |d: &Diagnostic| {
+        (
+            d.path.clone(),
+            d.location,
+            d.rule.clone(),
+            d.message.clone(),
+            Reverse(d.severity),
+        )
+    };
+    key(a).cmp(&key(b))
+}
+
+/// Put `diagnostics`, and those folded into each, in the order `by` asks for.
+pub fn sort(diagnostics: &mut [Diagnostic], by: SortBy) {
+    for diagnostic in diagnostics.iter_mut() {
+        diagnostic
+            .related
+            .sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| by_location(a, b)));
+    }
+    match by {
+        SortBy::Location => diagnostics.sort_by(by_location),
+        SortBy::Severity => {
+            diagnostics.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| by_location(a, b)))
+        }
+    }
+}
+
+pub fn write_diagnostics(
+    out: &mut impl Write,
+    format: Format,
+    diagnostics: &[Diagnostic],
+) -> io::Result<()> {
+    match format {
+        Format::Text => write_text(out, diagnostics),
+        Format::Github => write_annotations(out, diagnostics),
+        Format::Json => write_json(out, diagnostics),
+        Format::Pretty => report::write_report(out, diagnostics, Style::detect()),
+        #[cfg(feature = "formats-sarif")]
+        Format::Sarif => write_sarif(out, diagnostics),
+    }
+}
+
+pub fn write_text(out: &mut impl Write, diagnostics: &[Diagnostic]) -> io::Result<()> {
+    for diagnostic in diagnostics {
+        writeln!(out, "{}", diagnostic)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+//! SARIF 2.1.0, the format GitHub code scanning and other tools read
+//! static analysis results in.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::diagnostics::{Diagnostic, Severity, CODES};
+use crate::lint;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/aThorp96/workflow-linter";
+
+/// A rule as SARIF describes it.
+struct Descriptor {
+    name: String,
+    description: String,
+    severity: Severity,
+    security: bool,
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+/// A rule's id is its code, or its name when it doesn't have one.
+fn rule_id(rule: &str) -> String {
+    crate::diagnostics::code(rule)
+        .map(str::to_string)
+        .unwrap_or_else(|| rule.to_string())
+}
+
+/// `path` as a URI, relative to the root of the checkout when it's
+/// relative, which is how code scanning matches results to files.
+fn artifact_location(path: &Path) -> Value {
+    let uri: String = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .trim_start_matches("./")
+        .chars()
+        .map(|c| match c {
+            ' ' => "%20".to_string(),
+            '%' => "%25".to_string(),
+            '#' => "%23".to_string(),
+            '?' => "%3F".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+    match path.is_absolute() {
+        true if uri.starts_with('/') => json!({ "uri": format!("file://{}", uri) }),
+        true => json!({ "uri": format!("file:///{}", uri) }),
+        false => json!({ "uri": uri, "uriBaseId": "%SRCROOT%" }),
+    }
+}
+
+/// The message with its suggestions under it, both as plain text and as
+/// Markdown with the examples as code blocks.
+fn message(diagnostic: &Diagnostic) -> Value {
+    let mut text = diagnostic.message.clone();
+    let mut markdown = diagnostic.message.clone();
+    for suggestion in &diagnostic.suggestions {
+        text.push_str(&format!("\nSuggestion: {}", suggestion.message));
+        markdown.push_str(&format!("\n\n**Suggestion:** {}", suggestion.message));
+        if let Some(example) = &suggestion.example {
+            text.push_str(&format!("\n\n{}\n", example));
+            markdown.push_str(&format!("\n\n```yaml\n{}\n```", example));
+        }
+    }
+    json!({ "text": text, "markdown": markdown })
+}
+
+fn result(diagnostic: &Diagnostic, rule_index: usize) -> Value {
+    let mut result = json!({
+        "ruleId": rule_id(&diagnostic.rule),
+        "ruleIndex": rule_index,
+        "level": level(diagnostic.severity),
+        "message": message(diagnostic),
+    });
+    if let Some(path) = &diagnostic.path {
+        let mut physical = json!({ "artifactLocation": artifact_location(path) });
+        if let Some(location) = diagnostic.location {
+            let mut region = json!({
+                "startLine": location.line,
+                "startColumn": location.column,
+            });
+            if let Some(span) = diagnostic.span {
+                region["endLine"] = json!(span.end.line);
+                region["endColumn"] = json!(span.end.column);
+            }
+            physical["region"] = region;
+        }
+        result["locations"] = json!([{ "physicalLocation": physical }]);
+    }
+    if let Some(node) = &diagnostic.node {
+        result["properties"] = json!({ "node": node });
+    }
+    result
+}
+
+fn descriptor(id: &str, descriptor: &Descriptor) -> Value {
+    let mut value = json!({
+        "id": id,
+        "name": descriptor.name,
+        "shortDescription": { "text": descriptor.description },
+        "defaultConfiguration": { "level": level(descriptor.severity) },
+    });
+    if descriptor.security {
+        value["properties"] = json!({ "tags": ["security"] });
+    }
+    value
+}
+
+/// Print diagnostics as a SARIF log with a single run.
+///
+/// Every built-in rule is listed as a rule descriptor, whether it found
+/// anything or not, so code scanning can tell a fixed alert from a rule
+/// that stopped running. Findings folded into another are results of their
+/// own, since each is for its own rule.
+pub fn write_sarif(out: &mut impl Write, diagnostics: &[Diagnostic]) -> io::Result<()> {
+    let mut rules: BTreeMap<String, Descriptor> = lint::builtin_rules()
+        .iter()
+        .map(|rule| {
+            (
+                rule_id(rule.name()),
+                Descriptor {
+                    name: rule.name().to_string(),
+                    description: rule.description().to_string(),
+                    severity: rule.severity(),
+                    security: rule.security(),
+                },
+            )
+        })
+        .collect();
+    let flattened: Vec<&Diagnostic> = diagnostics
+        .iter()
+        .flat_map(|d| std::iter::once(d).chain(d.related.iter()))
+        .collect();
+    // Findings about the file rather than from a rule, like `parse`
+    for diagnostic in &flattened {
+        rules
+            .entry(rule_id(&diagnostic.rule))
+            .or_insert_with(|| Descriptor {
+                name: diagnostic.rule.clone(),
+                description: diagnostic.rule.clone(),
+                severity: Severity::Error,
+                security: false,
+            });
+    }
+    // Codes first, in their order, then rules without one by name
+    let mut ids: Vec<&String> = rules.keys().collect();
+    ids.sort_by_key(|id| {
+        let position = CODES.iter().position(|&(_, code)| code == id.as_str());
+        (position.is_none(), position)
+    });
+
+    let results: Vec<Value> = flattened
+        .iter()
+        .map(|d| {
+            let id = rule_id(&d.rule);
+            let index = ids.iter().position(|&i| *i == id).unwrap_or_default();
+            result(d, index)
+        })
+        .collect();
+    let descriptors: Vec<Value> = ids.iter().map(|id| descriptor(id, &rules[*id])).collect();
+
+    let log = json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "workflow-linter",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": descriptors,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    });
+    serde_json::to_writer_pretty(&mut *out, &log)?;
+    writeln!(out)
+}
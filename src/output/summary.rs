@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::deprecation::Deprecation;
+use crate::diagnostics::Diagnostic;
+
+/// Counts of what a run found, for a line at the end of a CI log or for a
+/// dashboard to collect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub files_scanned: usize,
+    pub elapsed_seconds: f64,
+    pub total: usize,
+    /// Keyed by severity name; severities with nothing found are left out.
+    pub by_severity: BTreeMap<String, usize>,
+    pub by_rule: BTreeMap<String, usize>,
+    /// Keyed by path, with findings that aren't about a file under `-`.
+    pub by_file: BTreeMap<String, usize>,
+    /// Findings that came with a fix `--fix` didn't apply.
+    #[serde(default)]
+    pub fixable: usize,
+    /// Fixes `--fix` applied, whose findings aren't counted otherwise.
+    #[serde(default)]
+    pub fixed: usize,
+    /// The deprecated workflows scanned, by path, to track how far a
+    /// migration off them has got.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub deprecated: BTreeMap<String, Deprecation>,
+}
+
+impl Summary {
+    /// Count `diagnostics`, including the ones grouped under others.
+    pub fn new(files_scanned: usize, elapsed: Duration, diagnostics: &[Diagnostic]) -> Summary {
+        let mut summary = Summary {
+            files_scanned,
+            elapsed_seconds: elapsed.as_secs_f64(),
+            total: 0,
+            by_severity: BTreeMap::new(),
+            by_rule: BTreeMap::new(),
+            by_file: BTreeMap::new(),
+            fixable: 0,
+            fixed: 0,
+            deprecated: BTreeMap::new(),
+        };
+        let all = diagnostics
+            .iter()
+            .flat_map(|d| std::iter::once(d).chain(&d.related));
+        for diagnostic in all {
+            summary.total += 1;
+            if diagnostic.fix.is_some() {
+                summary.fixable += 1;
+            }
+            *summary
+                .by_severity
+                .entry(diagnostic.severity.to_string())
+                .or_default() += 1;
+            *summary.by_rule.entry(diagnostic.rule.clone()).or_default() += 1;
+            let file = match &diagnostic.path {
+                Some(path) => path.display().to_string(),
+                None => "-".to_string(),
+            };
+            *summary.by_file.entry(file).or_default() += 1;
+        }
+        summary
+    }
+}
+
+pub(super) fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+/// Write the counts as a few lines of text, the busiest rules and files
+/// first.
+pub fn write_summary(out: &mut impl Write, summary: &Summary) -> io::Result<()> {
+    let severities: Vec<String> = [
+        ("error", "errors"),
+        ("warning", "warnings"),
+        ("info", "info"),
+    ]
+    .iter()
+    .filter_map(|&(one, many)| Some(plural(*summary.by_severity.get(one)?, one, many)))
+    .collect();
+    write!(
+        out,
+        "\n{} in {} scanned in {:.2}s",
+        plural(summary.total, "finding", "findings"),
+        plural(summary.files_scanned, "file", "files"),
+        summary.elapsed_seconds
+    )?;
+    if severities.is_empty() {
+        writeln!(out)?;
+    } else {
+        writeln!(out, ": {}", severities.join(", "))?;
+    }
+
+    for (title, counts) in [("rule", &summary.by_rule), ("file", &summary.by_file)] {
+        if counts.is_empty() {
+            continue;
+        }
+        let mut counts: Vec<(&String, &usize)> = counts.iter().collect();
+        counts.sort_by_key(|&(_, count)| std::cmp::Reverse(*count));
+        let width = counts.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        writeln!(out, "By {}:", title)?;
+        for (name, count) in counts {
+            writeln!(out, "  {:width$}  {}", name, count, width = width)?;
+        }
+    }
+    write_deprecated(out, &summary.deprecated)
+}
+
+/// The deprecated workflows and what replaces them.
+pub(super) fn write_deprecated(
+    out: &mut impl Write,
+    deprecated: &BTreeMap<String, Deprecation>,
+) -> io::Result<()> {
+    if deprecated.is_empty() {
+        return Ok(());
+    }
+    let width = deprecated.keys().map(String::len).max().unwrap_or(0);
+    writeln!(
+        out,
+        "{} still to migrate:",
+        plural(
+            deprecated.len(),
+            "deprecated workflow",
+            "deprecated workflows"
+        )
+    )?;
+    for (file, deprecation) in deprecated {
+        match deprecation.describe() {
+            why if why.is_empty() => writeln!(out, "  {}", file)?,
+            why => writeln!(out, "  {:width$}  {}", file, why, width = width)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,331 @@
+//! Checking the commits actions are pinned to against GitHub, to find pins
+//! that have gone stale.
+//!
+//! Pinning an action to a commit SHA stops it changing under the workflow,
+//! but also stops it getting fixes. A pin is reported when its commit is
+//! older than the age allowed, when no tag of the action points at it any
+//! more (the tag was moved or force-pushed, or the commit was never
+//! released), or when the repository doesn't have the commit at all.
+//!
+//! A pin with its version in a comment after it, like
+//! `actions/checkout@<sha> # v4.1.0`, has to be the commit that tag is at.
+//! One that isn't usually means a botched manual bump, or a pin changed to
+//! look like the release it replaced.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value as Json;
+use serde_yaml::Value;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity, Suggestion};
+use crate::github::{RepositoryName, Response, Scheduler};
+use crate::review::{jobs, uses};
+use crate::spans::SpanMap;
+
+/// The rule name of every finding here.
+pub const RULE: &str = "stale-pin";
+
+/// How many pages of 100 tags are read looking for the one a pin is at.
+const MAX_TAG_PAGES: usize = 10;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// What GitHub knows about a pinned commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    /// When it was committed, as a Unix timestamp.
+    pub date: Option<u64>,
+    /// The tags pointing at it.
+    pub tags: Vec<String>,
+    /// Whether the repository has tags and they were all read, since a pin
+    /// to an action that never tags its releases can't have lost its tag.
+    pub tagged: bool,
+}
+
+/// Seconds since the Unix epoch of an ISO 8601 time like
+/// `2024-01-31T12:00:00Z`, as the API gives them.
+fn timestamp(date: &str) -> Option<u64> {
+    let number = |range: std::ops::Range<usize>| date.get(range)?.parse::<u64>().ok();
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || year < 1970 {
+        return None;
+    }
+    // Days from the epoch to the date, counting years from March so the
+    // leap day comes last
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y % 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+    Some(days * DAY + hour * 3600 + minute * 60 + second)
+}
+
+/// The date of a Unix timestamp, like `2024-01-31`.
+fn date(timestamp: u64) -> String {
+    let days = timestamp / DAY + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let m = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * m + 2) / 5 + 1;
+    let month = if m < 10 { m + 3 } else { m - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Look up the commit `sha` of `owner/repo` and the tags pointing at it.
+pub fn resolve(scheduler: &mut Scheduler, owner: &str, repo: &str, sha: &str) -> Response<Commit> {
+    let base = format!("/repos/{}/{}", owner, repo);
+    let commit = match scheduler.get(&format!("{}/commits/{}", base, sha)) {
+        Response::Found(commit) => commit,
+        Response::NotFound => return Response::NotFound,
+        Response::Unavailable => return Response::Unavailable,
+    };
+    let date = commit["commit"]["committer"]["date"]
+        .as_str()
+        .and_then(timestamp);
+
+    let mut tags = Vec::new();
+    let mut tagged = false;
+    let mut read_all = false;
+    for page in 1..=MAX_TAG_PAGES {
+        let listed = match scheduler.get(&format!("{}/tags?per_page=100&page={}", base, page)) {
+            Response::Found(Json::Array(listed)) => listed,
+            Response::Found(_) | Response::NotFound => break,
+            // Without all the tags, a missing one can't be told from one
+            // that wasn't read
+            Response::Unavailable => return Response::Unavailable,
+        };
+        tagged |= !listed.is_empty();
+        tags.extend(
+            listed
+                .iter()
+                .filter(|tag| tag["commit"]["sha"].as_str() == Some(sha))
+                .filter_map(|tag| tag["name"].as_str().map(String::from)),
+        );
+        if listed.len() < 100 || !tags.is_empty() {
+            read_all = true;
+            break;
+        }
+    }
+    Response::Found(Commit {
+        date,
+        tags,
+        tagged: tagged && read_all,
+    })
+}
+
+/// Look up the repositories of `actions` together before checking each, so
+/// that the ones that don't exist cost no more requests. It's a GraphQL
+/// batch, which needs a token; without one it'd be REST calls of its own,
+/// more than it saves.
+pub fn resolve_repositories<'a, I>(scheduler: &mut Scheduler, actions: I)
+where
+    I: IntoIterator<Item = &'a ActionRef>,
+{
+    if !scheduler.client().has_token() {
+        return;
+    }
+    let names: Vec<RepositoryName> = actions
+        .into_iter()
+        .filter_map(|action| match action {
+            ActionRef::Repository { owner, repo, .. } => {
+                Some(RepositoryName::new(owner.as_str(), repo.as_str()))
+            }
+            _ => None,
+        })
+        .collect();
+    scheduler.resolve_repositories(names);
+}
+
+/// The version a comment after `uses` at `node` says the pin is, like
+/// `v4.1.0` from `actions/checkout@<sha> # v4.1.0`. Comments written by
+/// tools, like `# tag=v4.1.0` or `# pin@v4.1.0`, are understood too.
+fn version_comment(source: &str, spans: &SpanMap, node: &str) -> Option<String> {
+    let start = spans.get(node)?.value.offset;
+    let line = source[start..].lines().next()?;
+    let comment = &line[line.find(" #")? + 2..];
+    let word = comment.split_whitespace().next()?;
+    let version = word
+        .trim_start_matches("tag=")
+        .trim_start_matches("pin@")
+        .trim_end_matches(',');
+    let number = version.strip_prefix('v').unwrap_or(version);
+    if number.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(version.to_string())
+    } else {
+        None
+    }
+}
+
+/// Whether the pin `sha`, whose commit is `commit`, is at the tag
+/// `version`, and the commit the tag is at. A major version tag like `v4`
+/// that has moved on since still matches a pin at a `v4.x.y` tag.
+fn version_matches(
+    scheduler: &mut Scheduler,
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    commit: &Commit,
+    version: &str,
+) -> Response<(bool, String)> {
+    let prefix = format!("{}.", version);
+    if commit
+        .tags
+        .iter()
+        .any(|tag| tag == version || tag.starts_with(&prefix))
+    {
+        return Response::Found((true, sha.to_string()));
+    }
+    // Annotated tags resolve to the commit they're on too
+    let path = format!("/repos/{}/{}/commits/{}", owner, repo, version);
+    match scheduler.get(&path) {
+        Response::Found(tagged) => {
+            let tagged = tagged["sha"].as_str().unwrap_or_default().to_lowercase();
+            Response::Found((tagged == sha, tagged))
+        }
+        Response::NotFound => Response::Found((false, "a tag the action doesn't have".to_string())),
+        Response::Unavailable => Response::Unavailable,
+    }
+}
+
+/// The actions of `workflow` pinned to a commit, by node path, which are
+/// the ones [`check`] looks up.
+pub fn pinned(workflow: &Value) -> Vec<(String, ActionRef)> {
+    jobs(workflow)
+        .into_iter()
+        .flat_map(|(job_id, job)| uses(job_id, job))
+        .filter(|(_, action)| action.is_pinned())
+        .collect()
+}
+
+/// The findings for the pinned actions of `workflow`, from `source` at
+/// `path`. Pins with a commit more than `max_age` days old are stale.
+pub fn check(
+    scheduler: &mut Scheduler,
+    workflow: &Value,
+    source: &str,
+    path: &Path,
+    max_age: u64,
+) -> Vec<Diagnostic> {
+    let spans = SpanMap::parse(source);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut diagnostics = Vec::new();
+    let mut report = |node: &str, severity, id: &str, message: String, suggestion: &str| {
+        let mut diagnostic = Diagnostic::new(RULE, severity, message)
+            .with_path(path)
+            .with_location(spans.locate(node))
+            .with_message_id(id)
+            .with_suggestion(Suggestion::new(suggestion));
+        diagnostic.node = Some(node.to_string());
+        diagnostics.push(diagnostic);
+    };
+
+    let pinned = pinned(workflow);
+    resolve_repositories(scheduler, pinned.iter().map(|(_, action)| action));
+
+    for (node, action) in pinned {
+        let (owner, repo, sha) = match &action {
+            ActionRef::Repository {
+                owner,
+                repo,
+                git_ref,
+                ..
+            } => (owner, repo, git_ref.to_lowercase()),
+            _ => continue,
+        };
+        let name = action.name();
+        let commit = match resolve(scheduler, owner, repo, &sha) {
+            Response::Found(commit) => commit,
+            Response::NotFound => {
+                report(
+                    &node,
+                    Severity::Warning,
+                    "missing",
+                    format!(
+                        "`{}` is pinned to {}, which {}/{} doesn't have; it may have \
+                             been force-pushed away",
+                        name, sha, owner, repo
+                    ),
+                    "pin a commit of a release of the action",
+                );
+                continue;
+            }
+            Response::Unavailable => continue,
+        };
+        if let Some(version) = version_comment(source, &spans, &node) {
+            let matches = version_matches(scheduler, owner, repo, &sha, &commit, &version);
+            if let Response::Found((false, tagged)) = matches {
+                let actual = if commit.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!("; the pin is {}", commit.tags.join(", "))
+                };
+                report(
+                    &node,
+                    Severity::Error,
+                    "version-mismatch",
+                    format!(
+                        "`{}` is pinned to {}, but the comment says {}, which is {}{}",
+                        name, sha, version, tagged, actual
+                    ),
+                    "pin the commit the tag is at, or fix the comment; a pin that \
+                         doesn't match its comment may have been tampered with",
+                );
+            }
+        }
+        if commit.tagged && commit.tags.is_empty() {
+            report(
+                &node,
+                Severity::Warning,
+                "untagged",
+                format!(
+                    "`{}` is pinned to {}, which no tag of {}/{} points at; the tag \
+                         it was may have been moved",
+                    name, sha, owner, repo
+                ),
+                "check the pin is a commit the action released",
+            );
+        }
+        let committed = match commit.date {
+            Some(committed) => committed,
+            None => continue,
+        };
+        let age = now.saturating_sub(committed) / DAY;
+        if age > max_age {
+            let tag = match commit.tags.first() {
+                Some(tag) => format!(" ({})", tag),
+                None => String::new(),
+            };
+            report(
+                &node,
+                Severity::Warning,
+                "stale",
+                format!(
+                    "`{}` is pinned to a commit from {}{}, {} days ago, more than the \
+                         {} allowed",
+                    name,
+                    date(committed),
+                    tag,
+                    age,
+                    max_age
+                ),
+                "review what changed since, and pin a newer release",
+            );
+        }
+    }
+    diagnostics
+}
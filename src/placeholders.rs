@@ -0,0 +1,255 @@
+//! Linting workflow templates that are filled in by another tool before
+//! they're workflows, like cookiecutter's `{{ cookiecutter.name }}` or
+//! envsubst's `${VAR}`.
+//!
+//! Each placeholder is masked with a stand-in of the same length, so YAML
+//! can parse the template and findings keep their lines and columns. Values
+//! become `x` and a number that's different for each placeholder, padded
+//! with spaces; the values of keys that take a number or a boolean, like
+//! `timeout-minutes`, become one. A line of Jinja tags like `{% if x %}`
+//! becomes a comment. Findings about a stand-in are left out, since they'd
+//! be about it rather than what fills the placeholder in. GitHub's own `${{ }}`
+//! expressions aren't placeholders and are left alone.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::diagnostics::{Diagnostic, Fix, Location};
+
+/// A kind of placeholder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Jinja and the tools built on it, like cookiecutter and copier:
+    /// `{{ value }}`, `{% tag %}` and `{# comment #}`.
+    Jinja,
+    /// `${VAR}`, as envsubst and many generators fill in.
+    Envsubst,
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jinja" | "cookiecutter" => Ok(Style::Jinja),
+            "envsubst" => Ok(Style::Envsubst),
+            _ => Err(format!(
+                "unknown placeholder style `{}`, expected jinja or envsubst",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Style::Jinja => "jinja",
+            Style::Envsubst => "envsubst",
+        })
+    }
+}
+
+/// A placeholder that was masked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// Where it starts.
+    pub location: Location,
+    pub text: String,
+    /// What it was replaced with.
+    pub stand_in: String,
+}
+
+/// The most characters a stand-in has before its padding, which keeps
+/// numbers an `i32`.
+const MAX_DIGITS: usize = 9;
+
+/// Keys whose values are numbers, and booleans.
+const NUMBER_KEYS: &[&str] = &["max-parallel", "timeout-minutes"];
+const BOOLEAN_KEYS: &[&str] = &["cancel-in-progress", "continue-on-error", "fail-fast"];
+
+/// What goes first in the stand-in for the placeholder after `before` on
+/// its line: `9` where a number is wanted and `x` elsewhere, or `true`.
+fn stand_in_start(before: &str) -> &'static str {
+    let key = before.trim();
+    let key = key.strip_prefix("- ").unwrap_or(key).trim_start();
+    match key.strip_suffix(':') {
+        Some(key) if NUMBER_KEYS.contains(&key.trim()) => "9",
+        Some(key) if BOOLEAN_KEYS.contains(&key.trim()) => "true",
+        _ => "x",
+    }
+}
+
+impl Placeholder {
+    /// Whether `location` is inside the placeholder.
+    fn contains(&self, location: Location) -> bool {
+        let mut line = self.location.line;
+        let mut column = self.location.column;
+        for c in self.text.chars() {
+            if line == location.line && column == location.column {
+                return true;
+            }
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        false
+    }
+}
+
+/// A template with its placeholders masked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Masked {
+    /// The template as YAML can read it.
+    pub text: String,
+    pub placeholders: Vec<Placeholder>,
+}
+
+/// Where a placeholder of one of `styles` starts at `rest`, and how long it
+/// is in bytes; `None` if it's unterminated.
+fn placeholder_at(rest: &str, before: Option<char>, styles: &[Style]) -> Option<usize> {
+    let closing = |open: &str, close: &str| {
+        if !rest.starts_with(open) {
+            return None;
+        }
+        rest[open.len()..]
+            .find(close)
+            .map(|end| open.len() + end + close.len())
+    };
+    if styles.contains(&Style::Jinja) && before != Some('$') {
+        if let Some(len) = closing("{{", "}}")
+            .or_else(|| closing("{%", "%}"))
+            .or_else(|| closing("{#", "#}"))
+        {
+            return Some(len);
+        }
+    }
+    if styles.contains(&Style::Envsubst) && !rest.starts_with("${{") && before != Some('$') {
+        let len = closing("${", "}")?;
+        let name = &rest[2..len - 1];
+        let is_name =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_name {
+            return Some(len);
+        }
+    }
+    None
+}
+
+/// Mask the placeholders of `styles` in `source`.
+pub fn mask(source: &str, styles: &[Style]) -> Masked {
+    let mut text = String::with_capacity(source.len());
+    let mut placeholders = Vec::new();
+    let (mut line, mut column) = (1, 1);
+    let mut line_start = 0;
+    let mut i = 0;
+    let mut before = None;
+    while i < source.len() {
+        let rest = &source[i..];
+        if let Some(len) = placeholder_at(rest, before, styles) {
+            let placeholder = &rest[..len];
+            // A line of nothing but tags becomes a comment
+            let alone = source[line_start..i].trim().is_empty()
+                && rest[len..]
+                    .split('\n')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .is_empty()
+                && !placeholder.starts_with("{{")
+                && !placeholder.starts_with("${");
+            // The start and the placeholder's number, as many digits as fit
+            let start = stand_in_start(&source[line_start..i]);
+            let width = placeholder.chars().count().min(MAX_DIGITS);
+            let mut value = start.to_string();
+            if start != "true" {
+                let number = format!("{:0>1$}", placeholders.len() + 1, width - 1);
+                value.push_str(&number[number.len() - (width - 1)..]);
+            }
+            let mut value = value.chars();
+            let mut stand_in = String::new();
+            for (n, c) in placeholder.chars().enumerate() {
+                stand_in.push(match c {
+                    '\n' => '\n',
+                    _ if alone && n == 0 => '#',
+                    _ if alone => ' ',
+                    _ => value.next().unwrap_or(' '),
+                });
+            }
+            text.push_str(&stand_in);
+            placeholders.push(Placeholder {
+                location: Location { line, column },
+                text: placeholder.to_string(),
+                stand_in,
+            });
+            for c in placeholder.chars() {
+                if c == '\n' {
+                    line += 1;
+                    column = 1;
+                    line_start = i + 1;
+                } else {
+                    column += 1;
+                }
+                i += c.len_utf8();
+            }
+            before = placeholder.chars().last();
+            continue;
+        }
+        let c = rest.chars().next().unwrap();
+        text.push(c);
+        if c == '\n' {
+            line += 1;
+            column = 1;
+            line_start = i + 1;
+        } else {
+            column += 1;
+        }
+        i += c.len_utf8();
+        before = Some(c);
+    }
+    Masked { text, placeholders }
+}
+
+impl Masked {
+    /// Whether a finding is about a stand-in: it's at one, or quotes one.
+    fn about_stand_in(&self, diagnostic: &Diagnostic) -> bool {
+        self.placeholders.iter().any(|p| {
+            let value = p.stand_in.trim_end();
+            let quotes = !value.starts_with('#')
+                && value != "true"
+                && (diagnostic.message.contains(value)
+                    || diagnostic.params.iter().any(|(_, v)| v.contains(value)));
+            quotes || diagnostic.location.is_some_and(|l| p.contains(l))
+        })
+    }
+
+    /// `diagnostics` of the masked text, without those about stand-ins.
+    /// Fixes that touch a placeholder are dropped, as are all of them when
+    /// masking changed the length of the text, since their offsets would be
+    /// off.
+    pub fn unmask(&self, source: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let same_length = self.text.len() == source.len();
+        diagnostics
+            .into_iter()
+            .filter(|d| d.node.is_none() || !self.about_stand_in(d))
+            .map(|mut diagnostic| {
+                let touches = |fix: &Fix| {
+                    fix.edits.iter().any(|edit| {
+                        let range = edit.offset..edit.offset + edit.len;
+                        self.text.get(range.clone()) != source.get(range)
+                    })
+                };
+                if !same_length || diagnostic.fix.as_ref().is_some_and(touches) {
+                    diagnostic.fix = None;
+                }
+                if !same_length {
+                    diagnostic.span = None;
+                }
+                diagnostic
+            })
+            .collect()
+    }
+}
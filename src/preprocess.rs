@@ -0,0 +1,171 @@
+//! Job templates for workflows, which GitHub doesn't have.
+//!
+//! GitHub rejects YAML anchors, so jobs that differ in a line or two get
+//! copied whole. An extended workflow can instead declare templates under
+//! `x-templates:` and have jobs pull them in with `extends:`:
+//!
+//! ```yaml
+//! x-templates:
+//!   node:
+//!     runs-on: ubuntu-latest
+//!     steps:
+//!       - uses: actions/checkout@v4
+//!       - uses: actions/setup-node@v4
+//!
+//! jobs:
+//!   test:
+//!     extends: node
+//!     timeout-minutes: 10
+//! ```
+//!
+//! [`expand`] turns that into a plain workflow. A job's own keys win over
+//! its templates', maps are merged key by key, and lists and other values
+//! are replaced whole. Templates can extend other templates, and a list of
+//! templates is applied in order.
+
+use std::fmt;
+
+use serde_yaml::{Mapping, Value};
+
+/// The top-level key holding the templates.
+pub const TEMPLATES_KEY: &str = "x-templates";
+
+/// The key a job or template names its templates with.
+pub const EXTENDS_KEY: &str = "extends";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessError {
+    pub message: String,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+fn error(message: impl Into<String>) -> PreprocessError {
+    PreprocessError {
+        message: message.into(),
+    }
+}
+
+/// Put `overlay` on top of `base`.
+pub(crate) fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// The template names in an `extends:`.
+fn extends(node: &Value, what: &str) -> Result<Vec<String>, PreprocessError> {
+    let names = match node.get(EXTENDS_KEY) {
+        None => return Ok(Vec::new()),
+        Some(Value::String(name)) => return Ok(vec![name.clone()]),
+        Some(Value::Sequence(names)) => names,
+        Some(_) => {
+            return Err(error(format!(
+                "`extends` in {} must be a template name or a list of them",
+                what
+            )))
+        }
+    };
+    names
+        .iter()
+        .map(|name| {
+            name.as_str().map(str::to_string).ok_or_else(|| {
+                error(format!(
+                    "`extends` in {} must only list template names",
+                    what
+                ))
+            })
+        })
+        .collect()
+}
+
+/// `node` with its templates applied, where `stack` is the templates being
+/// resolved on the way here.
+fn resolve(
+    templates: &Mapping,
+    node: &Value,
+    what: &str,
+    stack: &mut Vec<String>,
+) -> Result<Value, PreprocessError> {
+    let mut resolved = Value::Mapping(Mapping::new());
+    for name in extends(node, what)? {
+        if stack.contains(&name) {
+            stack.push(name);
+            return Err(error(format!(
+                "templates extend each other in a loop: {}",
+                stack.join(" -> ")
+            )));
+        }
+        let template = templates.get(&Value::String(name.clone())).ok_or_else(|| {
+            error(format!(
+                "{} extends `{}`, which isn't in `{}`",
+                what, name, TEMPLATES_KEY
+            ))
+        })?;
+        if !template.is_mapping() {
+            return Err(error(format!("template `{}` must be a map", name)));
+        }
+        stack.push(name.clone());
+        let template = resolve(templates, template, &format!("template `{}`", name), stack)?;
+        stack.pop();
+        merge(&mut resolved, template);
+    }
+
+    let mut own = node.clone();
+    if let Value::Mapping(own) = &mut own {
+        own.remove(&Value::String(EXTENDS_KEY.to_string()));
+    }
+    merge(&mut resolved, own);
+    Ok(resolved)
+}
+
+/// Apply the templates of an extended workflow, leaving a plain one.
+pub fn expand_document(document: &Value) -> Result<Value, PreprocessError> {
+    let mut document = match document {
+        Value::Mapping(document) => document.clone(),
+        _ => return Err(error("expected the workflow to be a map")),
+    };
+    let templates = match document.remove(&Value::String(TEMPLATES_KEY.to_string())) {
+        None | Some(Value::Null) => Mapping::new(),
+        Some(Value::Mapping(templates)) => templates,
+        Some(_) => {
+            return Err(error(format!(
+                "`{}` must be a map of template names to jobs",
+                TEMPLATES_KEY
+            )))
+        }
+    };
+
+    if let Some(Value::Mapping(jobs)) = document.get_mut(&Value::String("jobs".to_string())) {
+        for (id, job) in jobs.iter_mut() {
+            let what = format!("job `{}`", id.as_str().unwrap_or_default());
+            *job = resolve(&templates, job, &what, &mut Vec::new())?;
+        }
+    }
+    Ok(Value::Mapping(document))
+}
+
+/// Expand the source of an extended workflow into plain workflow YAML.
+/// Comments don't survive, so the source stays the file to edit.
+pub fn expand(source: &str) -> Result<String, PreprocessError> {
+    let document: Value = serde_yaml::from_str(source).map_err(|e| error(e.to_string()))?;
+    let expanded = expand_document(&document)?;
+    let yaml = serde_yaml::to_string(&expanded).map_err(|e| error(e.to_string()))?;
+    Ok(format!("{}\n", yaml.trim_start_matches("---\n").trim_end()))
+}
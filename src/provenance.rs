@@ -0,0 +1,82 @@
+//! Marking generated workflows with where they came from, so edits made to
+//! the generated file instead of its source can be caught.
+//!
+//! A generated file has a line like this above its contents:
+//!
+//! ```yaml
+//! # @generated source=workflows-src/ci.yml source-hash=4f0e3c1a9b2d7e65 hash=b81c07aa3d5e9f12
+//! ```
+//!
+//! `hash` covers everything after that line and `source-hash` the source
+//! file, both with [`content_hash`]. `workflow-linter expand` writes it, and
+//! other generators can too. `source` is relative to the root of the
+//! checkout.
+
+/// What starts the marker line.
+pub const MARKER: &str = "# @generated";
+
+/// Where a generated file came from, as recorded in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub source: String,
+    pub source_hash: String,
+    pub hash: String,
+    /// 1-based line of the marker.
+    pub line: usize,
+    /// Byte offset of what comes after the marker line, which `hash` covers.
+    pub body_offset: usize,
+}
+
+/// A 64-bit FNV-1a hash of `text` with CRLF line endings read as LF, in hex.
+/// This is for noticing changes, not for security.
+pub fn content_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in text.replace("\r\n", "\n").as_bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// The marker for a file generated from `source`, found at `source_path`,
+/// with contents `body`.
+pub fn marker(source_path: &str, source: &str, body: &str) -> String {
+    format!(
+        "{} source={} source-hash={} hash={}\n",
+        MARKER,
+        source_path,
+        content_hash(source),
+        content_hash(body)
+    )
+}
+
+/// Find the marker in a generated file. It has to be in the comments at the
+/// top of the file.
+pub fn parse(text: &str) -> Option<Provenance> {
+    let mut offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        offset += line.len();
+        if !trimmed.starts_with('#') {
+            return None;
+        }
+        let fields = match trimmed.strip_prefix(MARKER) {
+            Some(fields) => fields,
+            None => continue,
+        };
+        let field = |name: &str| {
+            fields
+                .split_whitespace()
+                .find_map(|f| f.strip_prefix(name)?.strip_prefix('='))
+                .map(str::to_string)
+        };
+        return Some(Provenance {
+            source: field("source")?,
+            source_hash: field("source-hash")?,
+            hash: field("hash")?,
+            line: i + 1,
+            body_offset: offset,
+        });
+    }
+    None
+}
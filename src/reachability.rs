@@ -0,0 +1,128 @@
+//! Jobs and steps that can never run.
+//!
+//! A finding about code that never runs is rarely worth fixing first, and
+//! on large legacy workflows it buries the ones that are. Jobs whose `if`
+//! is false for every scenario, and steps whose `if` is also false in every
+//! leg of the job's matrix, are found here so that findings inside them can
+//! be demoted.
+
+use serde_json::Value;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::eval;
+use crate::lint::{job_path, sorted_jobs, step_path, Context};
+use crate::matrix;
+use crate::workflow::Matrix;
+
+/// A job or step that never runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unreachable {
+    /// The path of the job or step, like `jobs.build.steps[2]`.
+    pub node: String,
+    /// Why it never runs, to finish "never runs, since ...".
+    pub reason: String,
+}
+
+impl Unreachable {
+    /// Whether the node at `node` is inside this one.
+    pub fn contains(&self, node: &str) -> bool {
+        match node.strip_prefix(&self.node) {
+            Some(rest) => rest.is_empty() || rest.starts_with(['.', '[']),
+            None => false,
+        }
+    }
+}
+
+/// Whether `condition` is false in every one of `scenarios`.
+fn never_true(condition: &str, scenarios: &[Value]) -> bool {
+    eval::parse_condition(condition)
+        .is_some_and(|expr| eval::condition_outcome(&expr, scenarios) == Some(false))
+}
+
+/// The scenarios for the steps of a job: each of `scenarios` in each leg of
+/// the job's matrix, when it can be worked out.
+fn with_matrix(scenarios: &[Value], matrix: Option<&Matrix>) -> Vec<Value> {
+    let legs = match matrix.map(matrix::expand) {
+        Some(Ok(expansion)) if !expansion.legs.is_empty() => expansion.legs,
+        _ => return scenarios.to_vec(),
+    };
+    let mut combined = Vec::new();
+    for scenario in scenarios {
+        for leg in &legs {
+            let mut scenario = scenario.clone();
+            if let Value::Object(contexts) = &mut scenario {
+                contexts.insert("matrix".to_string(), leg.context());
+            }
+            combined.push(scenario);
+        }
+    }
+    combined
+}
+
+/// The jobs and steps of the workflow that never run. The steps of a job
+/// that never runs aren't listed separately.
+pub fn unreachable(cx: &Context) -> Vec<Unreachable> {
+    let scenarios = cx.scenarios();
+    let mut found = Vec::new();
+    for (job_id, job) in sorted_jobs(cx.workflow) {
+        if let Some(condition) = &job.run_if {
+            if never_true(condition, &scenarios) {
+                found.push(Unreachable {
+                    node: job_path(job_id),
+                    reason: "its `if` is never true".to_string(),
+                });
+                continue;
+            }
+        }
+        let matrix = job.strategy.as_ref().and_then(|s| s.matrix.as_ref());
+        let mut step_scenarios = None;
+        for (i, step) in job.steps.iter().enumerate() {
+            let condition = match &step.run_if {
+                Some(condition) => condition,
+                None => continue,
+            };
+            let scenarios = step_scenarios.get_or_insert_with(|| with_matrix(&scenarios, matrix));
+            if never_true(condition, scenarios) {
+                let reason = if matrix.is_some() {
+                    "its `if` is false in every leg of the matrix"
+                } else {
+                    "its `if` is never true"
+                };
+                found.push(Unreachable {
+                    node: step_path(job_id, i),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Demote `diagnostic` to info if it's inside a job or step that never runs.
+/// Findings about the `if` itself are left alone, since they're the ones
+/// saying so.
+pub fn demote(diagnostic: &mut Diagnostic, unreachable: &[Unreachable]) {
+    let node = match &diagnostic.node {
+        Some(node) if !node.ends_with(".if") => node,
+        _ => return,
+    };
+    let found = match unreachable.iter().find(|u| u.contains(node)) {
+        Some(found) => found,
+        None => return,
+    };
+    if diagnostic.severity > Severity::Info {
+        diagnostic.severity = Severity::Info;
+    }
+    let what = if found.node.contains(".steps[") {
+        "step"
+    } else {
+        "job"
+    };
+    diagnostic.message.push_str(&format!(
+        " (demoted: this {} never runs, since {})",
+        what, found.reason
+    ));
+    diagnostic
+        .params
+        .push(("unreachable".to_string(), found.node.clone()));
+}
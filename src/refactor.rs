@@ -0,0 +1,474 @@
+//! Merging copy-pasted jobs into one matrix job.
+//!
+//! Workflows often have a job per OS or per version that are the same
+//! apart from a value or two:
+//!
+//! ```yaml
+//! test-linux:
+//!   runs-on: ubuntu-latest
+//!   steps: [...]
+//! test-macos:
+//!   runs-on: macos-latest
+//!   steps: [...]
+//! ```
+//!
+//! [`find_groups`] finds them, and [`to_matrix`] rewrites each group into a
+//! single job with a `strategy.matrix.include` entry for each of the
+//! original jobs and `${{ matrix.<key> }}` where they differed. The
+//! matrix has `fail-fast: false`, so a failing leg doesn't cancel the others
+//! any more than a failing job did.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::lint::{for_each_string, job_path};
+use crate::spans::{index_path, key_path};
+
+/// Jobs that differ in more values than this are left separate; past a few,
+/// the matrix is harder to read than the copies.
+pub const MAX_AXES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefactorError {
+    pub message: String,
+}
+
+impl fmt::Display for RefactorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RefactorError {}
+
+fn error(message: impl Into<String>) -> RefactorError {
+    RefactorError {
+        message: message.into(),
+    }
+}
+
+/// A step from a job down to one of its values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Segment {
+    Key(Value),
+    Index(usize),
+}
+
+/// A scalar in a job and the path to it.
+type Leaf = (Vec<Segment>, Value);
+
+/// A leaf's path, and its value if the value has to match to be merged.
+type Likeness<'a> = (&'a [Segment], Option<&'a Value>);
+
+/// A value the jobs of a group differ in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Axis {
+    /// The matrix key, like `os` or `node-version`.
+    pub name: String,
+    /// Where the value is in each job, like `steps[1].with.node-version`.
+    pub node: String,
+    path: Vec<Segment>,
+    /// Each job's value, in the order of [`Group::jobs`].
+    pub values: Vec<Value>,
+}
+
+/// Jobs that can be merged into one matrix job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    /// The id for the merged job.
+    pub id: String,
+    /// The ids of the jobs to merge, in workflow order.
+    pub jobs: Vec<String>,
+    pub axes: Vec<Axis>,
+}
+
+fn leaves(value: &Value, prefix: &mut Vec<Segment>, out: &mut Vec<Leaf>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, value) in map {
+                prefix.push(Segment::Key(key.clone()));
+                leaves(value, prefix, out);
+                prefix.pop();
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                prefix.push(Segment::Index(i));
+                leaves(item, prefix, out);
+                prefix.pop();
+            }
+        }
+        _ => out.push((prefix.clone(), value.clone())),
+    }
+}
+
+fn node(path: &[Segment]) -> String {
+    path.iter()
+        .fold(String::new(), |node, segment| match segment {
+            Segment::Key(key) => key_path(&node, key.as_str().unwrap_or("?")),
+            Segment::Index(i) => index_path(&node, *i),
+        })
+}
+
+/// Whether a value at `path` can be replaced with a matrix expression.
+/// Job-level `if` and `needs` are worked out before the matrix is, and
+/// `uses` and step ids can't be expressions at all.
+fn can_vary(path: &[Segment]) -> bool {
+    let key = |segment: &Segment| match segment {
+        Segment::Key(key) => key.as_str().map(str::to_string),
+        Segment::Index(_) => None,
+    };
+    let first = path.first().and_then(key);
+    let last = path.iter().rev().find_map(key);
+    !matches!(
+        first.as_deref(),
+        Some("needs") | Some("if") | Some("strategy")
+    ) && !matches!(last.as_deref(), Some("uses") | Some("id"))
+}
+
+/// The indexes of the leaves where `a` and `b` differ, if they're the same
+/// shape and differ only where a matrix value can go, in no more than
+/// [`MAX_AXES`] places.
+fn differences(a: &[Leaf], b: &[Leaf]) -> Option<Vec<usize>> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let mut differ = Vec::new();
+    for (i, ((path_a, value_a), (path_b, value_b))) in a.iter().zip(b).enumerate() {
+        if path_a != path_b {
+            return None;
+        }
+        if value_a != value_b {
+            if !can_vary(path_a) {
+                return None;
+            }
+            differ.push(i);
+            if differ.len() > MAX_AXES {
+                return None;
+            }
+        }
+    }
+    Some(differ)
+}
+
+/// The matrix key for the value at `path`, unique among `taken`.
+fn axis_name(path: &[Segment], taken: &[String]) -> String {
+    let keys: Vec<&str> = path
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Key(key) => key.as_str(),
+            Segment::Index(_) => None,
+        })
+        .collect();
+    let mut candidates = Vec::new();
+    match keys.as_slice() {
+        ["runs-on", ..] => candidates.push("os".to_string()),
+        [.., parent, last] => {
+            candidates.push(last.to_string());
+            candidates.push(format!("{}-{}", parent, last));
+        }
+        [last] => candidates.push(last.to_string()),
+        [] => {}
+    }
+    if let Some(name) = candidates.iter().find(|c| !taken.contains(c)) {
+        return name.clone();
+    }
+    let base = candidates.pop().unwrap_or_else(|| "value".to_string());
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|c| !taken.contains(c))
+        .unwrap_or(base)
+}
+
+/// An id for merging `ids`: what they have in common at the start, or at
+/// the end, if that's free.
+fn merged_id(ids: &[String], taken: &[&str]) -> String {
+    let trim: &[char] = &['-', '_', '.'];
+    let first = &ids[0];
+    let prefix_len = ids.iter().skip(1).fold(first.len(), |len, id| {
+        first
+            .bytes()
+            .zip(id.bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    let suffix_len = ids.iter().skip(1).fold(first.len(), |len, id| {
+        first
+            .bytes()
+            .rev()
+            .zip(id.bytes().rev())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    let prefix = first[..prefix_len].trim_end_matches(trim);
+    let suffix = first[first.len() - suffix_len..].trim_start_matches(trim);
+    let fallback = format!("{}-matrix", first);
+    [prefix, suffix]
+        .iter()
+        .find(|id| !id.is_empty() && !taken.contains(id))
+        .map(|id| id.to_string())
+        .unwrap_or(fallback)
+}
+
+/// The jobs of `document` that could be one matrix job.
+pub fn find_groups(document: &Value) -> Vec<Group> {
+    let jobs = match document.get("jobs").and_then(Value::as_mapping) {
+        Some(jobs) => jobs,
+        None => return Vec::new(),
+    };
+    // Outputs of a matrix job come from whichever leg finishes last
+    let mut referenced = HashSet::new();
+    for_each_string(document, "", &mut |_, value| {
+        for (at, _) in value.match_indices("needs.") {
+            let rest = &value[at + "needs.".len()..];
+            let id_len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            if rest[id_len..].starts_with('.') {
+                referenced.insert(rest[..id_len].to_string());
+            }
+        }
+    });
+
+    let candidates: Vec<(String, Vec<Leaf>)> = jobs
+        .iter()
+        .filter_map(|(id, job)| {
+            let id = id.as_str()?;
+            if job.get("strategy").is_some() || referenced.contains(id) {
+                return None;
+            }
+            let mut found = Vec::new();
+            leaves(job, &mut Vec::new(), &mut found);
+            Some((id.to_string(), found))
+        })
+        .collect();
+    let all_ids: Vec<&str> = jobs.iter().filter_map(|(id, _)| id.as_str()).collect();
+
+    // Only jobs of the same shape, alike where a matrix value can't go, can
+    // be merged, so jobs are only compared with the others like that
+    let mut alike: HashMap<Vec<Likeness>, Vec<usize>> = HashMap::new();
+    for (i, (_, found)) in candidates.iter().enumerate() {
+        let key = found
+            .iter()
+            .map(|(path, value)| (path.as_slice(), Some(value).filter(|_| !can_vary(path))))
+            .collect();
+        alike.entry(key).or_default().push(i);
+    }
+    let mut others: Vec<&[usize]> = vec![&[]; candidates.len()];
+    for members in alike.values() {
+        for &i in members {
+            others[i] = members;
+        }
+    }
+
+    let mut grouped = vec![false; candidates.len()];
+    let mut groups: Vec<Group> = Vec::new();
+    for i in 0..candidates.len() {
+        if grouped[i] {
+            continue;
+        }
+        let first = &candidates[i].1;
+        let mut members = vec![i];
+        let mut differ: Vec<usize> = Vec::new();
+        for &j in others[i].iter().filter(|&&j| j > i) {
+            let other = &candidates[j].1;
+            if grouped[j] {
+                continue;
+            }
+            let found = match differences(first, other) {
+                Some(found) if !found.is_empty() => found,
+                _ => continue,
+            };
+            let mut union = differ.clone();
+            union.extend(found.into_iter().filter(|d| !differ.contains(d)));
+            if union.len() > MAX_AXES {
+                continue;
+            }
+            differ = union;
+            members.push(j);
+        }
+        if members.len() < 2 {
+            continue;
+        }
+        for &m in &members {
+            grouped[m] = true;
+        }
+        differ.sort_unstable();
+
+        let ids: Vec<String> = members.iter().map(|&m| candidates[m].0.clone()).collect();
+        let mut taken: Vec<&str> = all_ids
+            .iter()
+            .copied()
+            .filter(|id| !ids.iter().any(|i| i == id))
+            .collect();
+        taken.extend(groups.iter().map(|g| g.id.as_str()));
+        let id = merged_id(&ids, &taken);
+
+        let mut axes: Vec<Axis> = Vec::new();
+        for &d in &differ {
+            let path = first[d].0.clone();
+            let taken: Vec<String> = axes.iter().map(|a| a.name.clone()).collect();
+            axes.push(Axis {
+                name: axis_name(&path, &taken),
+                node: node(&path),
+                values: members
+                    .iter()
+                    .map(|&m| candidates[m].1[d].1.clone())
+                    .collect(),
+                path,
+            });
+        }
+        groups.push(Group {
+            id,
+            jobs: ids,
+            axes,
+        });
+    }
+    groups
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+/// Set the value at `path` under `value`.
+fn set(value: &mut Value, path: &[Segment], new: Value) {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *value = new;
+            return;
+        }
+    };
+    let child = match (segment, value) {
+        (Segment::Key(key), Value::Mapping(map)) => map.get_mut(key),
+        (Segment::Index(i), Value::Sequence(items)) => items.get_mut(*i),
+        _ => None,
+    };
+    if let Some(child) = child {
+        set(child, rest, new);
+    }
+}
+
+/// The merged job for `group`, built from the first of its jobs.
+fn merged_job(group: &Group, first: &Value) -> Value {
+    let mut job = first.clone();
+    for axis in &group.axes {
+        set(
+            &mut job,
+            &axis.path,
+            string(&format!("${{{{ matrix.{} }}}}", axis.name)),
+        );
+    }
+
+    let include: Vec<Value> = (0..group.jobs.len())
+        .map(|leg| {
+            let mut values = Mapping::new();
+            for axis in &group.axes {
+                values.insert(string(&axis.name), axis.values[leg].clone());
+            }
+            Value::Mapping(values)
+        })
+        .collect();
+    let mut matrix = Mapping::new();
+    matrix.insert(string("include"), Value::Sequence(include));
+    let mut strategy = Mapping::new();
+    strategy.insert(string("fail-fast"), Value::Bool(false));
+    strategy.insert(string("matrix"), Value::Mapping(matrix));
+
+    // `strategy` goes before `steps`, where people expect it
+    let mut with_strategy = Mapping::new();
+    let mut placed = false;
+    if let Value::Mapping(keys) = job {
+        for (key, value) in keys {
+            if key.as_str() == Some("steps") && !placed {
+                with_strategy.insert(string("strategy"), Value::Mapping(strategy.clone()));
+                placed = true;
+            }
+            with_strategy.insert(key, value);
+        }
+    }
+    if !placed {
+        with_strategy.insert(string("strategy"), Value::Mapping(strategy));
+    }
+    Value::Mapping(with_strategy)
+}
+
+/// Rename the merged jobs in a `needs`.
+fn rewrite_needs(needs: &mut Value, group: &Group) {
+    let rename = |id: &Value| match id.as_str() {
+        Some(id) if group.jobs.iter().any(|j| j == id) => string(&group.id),
+        _ => id.clone(),
+    };
+    match needs {
+        Value::Sequence(ids) => {
+            let mut renamed: Vec<Value> = Vec::new();
+            for id in ids.iter().map(rename) {
+                if !renamed.contains(&id) {
+                    renamed.push(id);
+                }
+            }
+            *ids = renamed;
+        }
+        Value::String(_) => *needs = rename(needs),
+        _ => {}
+    }
+}
+
+/// `document` with each of `groups` merged into a matrix job.
+pub fn apply(document: &Value, groups: &[Group]) -> Value {
+    let mut document = document.clone();
+    let jobs = match document.get_mut("jobs") {
+        Some(Value::Mapping(jobs)) => jobs,
+        _ => return document,
+    };
+    for group in groups {
+        let mut rebuilt = Mapping::new();
+        for (id, job) in std::mem::take(jobs) {
+            match id.as_str() {
+                Some(id) if id == group.jobs[0] => {
+                    rebuilt.insert(string(&group.id), merged_job(group, &job));
+                }
+                Some(id) if group.jobs.iter().any(|j| j == id) => {}
+                _ => {
+                    rebuilt.insert(id, job);
+                }
+            }
+        }
+        for (_, job) in rebuilt.iter_mut() {
+            if let Some(needs) = job.get_mut("needs") {
+                rewrite_needs(needs, group);
+            }
+        }
+        *jobs = rebuilt;
+    }
+    document
+}
+
+/// Rewrite the workflow `source`, merging every group of jobs that could be
+/// one matrix job. Also returns the groups, which are empty if nothing
+/// changed.
+pub fn to_matrix(source: &str) -> Result<(String, Vec<Group>), RefactorError> {
+    let document: Value = serde_yaml::from_str(source).map_err(|e| error(e.to_string()))?;
+    let groups = find_groups(&document);
+    if groups.is_empty() {
+        return Ok((source.to_string(), groups));
+    }
+    let rewritten = apply(&document, &groups);
+    let yaml = serde_yaml::to_string(&rewritten).map_err(|e| error(e.to_string()))?;
+    Ok((
+        format!("{}\n", yaml.trim_start_matches("---\n").trim_end()),
+        groups,
+    ))
+}
+
+impl Group {
+    /// The node of the first job, where findings about the group go.
+    pub fn node(&self) -> String {
+        job_path(&self.jobs[0])
+    }
+}
@@ -0,0 +1,124 @@
+//! The repository a workflow belongs to, for rules that check the workflow
+//! against the files next to it.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Find the root of the git checkout containing `path`, if there is one.
+pub fn find_root(path: &Path) -> Option<PathBuf> {
+    let start = path.canonicalize().ok()?;
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// `path` relative to the current directory when it's under it, for
+/// showing paths found from the (absolute) root the way the user typed
+/// theirs.
+pub fn relative_to_cwd(path: PathBuf) -> PathBuf {
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(PathBuf::from))
+        .unwrap_or(path)
+}
+
+/// `path` relative to the root of its checkout and `/` separated, for
+/// matching against the config's patterns.
+pub fn checkout_path(path: &Path) -> String {
+    let relative = find_root(path).and_then(|root| {
+        let path = path.canonicalize().ok()?;
+        Some(path.strip_prefix(root).ok()?.to_path_buf())
+    });
+    relative
+        .unwrap_or_else(|| path.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Every file in the checkout at `root`, relative and `/` separated.
+///
+/// Asks git for the tracked files when it can, so build output and other
+/// untracked files don't count; otherwise walks the directory.
+pub fn files(root: &Path) -> Vec<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["ls-files", "-z"])
+        .output();
+    if let Ok(output) = output {
+        if output.status.success() {
+            return String::from_utf8_lossy(&output.stdout)
+                .split('\0')
+                .filter(|f| !f.is_empty())
+                .map(String::from)
+                .collect();
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(root, "", &mut files);
+    files
+}
+
+/// When the last commit of the checkout at `root` was made, as a Unix
+/// timestamp.
+pub fn last_commit(root: &Path) -> Option<u64> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "-1", "--format=%ct"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// The owner and name of the GitHub repository the checkout at `root` was
+/// cloned from, read from its `origin` remote.
+pub fn github_remote(root: &Path) -> Option<(String, String)> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    // `https://github.com/owner/repo.git` or `git@github.com:owner/repo.git`
+    let path = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.split_once("github.com/").map(|(_, path)| path))?;
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+fn walk(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == ".git" {
+            continue;
+        }
+        let relative = format!("{}{}", prefix, name);
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => walk(&entry.path(), &format!("{}/", relative), files),
+            Ok(_) => files.push(relative),
+            Err(_) => {}
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Findings rendered for a terminal: each with the lines of the workflow
+//! it's about, the stretch it's about underlined, and colored by severity.
+//!
+//! ```text
+//! warning[WF151 unpinned-action]: `actions/checkout` is used at `main`, ...
+//!  --> .github/workflows/ci.yml:6:9
+//!   |
+//! 6 |       - uses: actions/checkout@main
+//!   |         ^^^^^^^^^^^^^^^^^^^^^^^^^^^
+//!   = help: use a release tag, or the full commit SHA of one
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::{Diagnostic, Location, Severity, Suggestion};
+use crate::source::Decoded;
+
+/// The most lines of a span shown; the middle of longer ones is left out.
+const MAX_LINES: usize = 6;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const GUTTER: &str = "\x1b[1;34m";
+
+/// Whether to color the report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub color: bool,
+}
+
+impl Style {
+    /// Color when stdout is a terminal, unless `NO_COLOR` is set, or always
+    /// when `CLICOLOR_FORCE` is, following the usual conventions.
+    pub fn detect() -> Self {
+        let set = |name| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+        let color = match (set("CLICOLOR_FORCE"), set("NO_COLOR")) {
+            (true, _) => true,
+            (false, true) => false,
+            (false, false) => io::stdout().is_terminal(),
+        };
+        Style { color }
+    }
+
+    fn paint(self, code: &str, text: &str) -> String {
+        match self.color {
+            true => format!("{}{}{}", code, text, RESET),
+            false => text.to_string(),
+        }
+    }
+
+    fn severity(self, severity: Severity, text: &str) -> String {
+        let code = match severity {
+            Severity::Error => "\x1b[1;31m",
+            Severity::Warning => "\x1b[1;33m",
+            Severity::Info => "\x1b[1;36m",
+        };
+        self.paint(code, text)
+    }
+}
+
+/// The text of each file findings are in, read once. Files that can't be
+/// read are rendered without their lines.
+#[derive(Debug, Default)]
+struct Sources {
+    lines: BTreeMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl Sources {
+    fn lines(&mut self, path: &Path) -> Option<&[String]> {
+        self.lines
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let bytes = std::fs::read(path).ok()?;
+                let text = Decoded::decode(&bytes).text;
+                Some(text.lines().map(str::to_string).collect())
+            })
+            .as_deref()
+    }
+}
+
+/// The characters of `line` before `column`, with tabs kept so what comes
+/// after lines up with the line above.
+fn padding(line: &str, column: usize) -> String {
+    line.chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+/// Write the report for `diagnostics`, a blank line between each.
+pub fn write_report(
+    out: &mut impl Write,
+    diagnostics: &[Diagnostic],
+    style: Style,
+) -> io::Result<()> {
+    let mut sources = Sources::default();
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        write_diagnostic(out, diagnostic, style, &mut sources)?;
+    }
+    Ok(())
+}
+
+fn write_diagnostic(
+    out: &mut impl Write,
+    diagnostic: &Diagnostic,
+    style: Style,
+    sources: &mut Sources,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}{}",
+        style.severity(diagnostic.severity, &diagnostic.label()),
+        style.paint(BOLD, &format!(": {}", diagnostic.message))
+    )?;
+
+    let snippet = match (&diagnostic.path, diagnostic.location) {
+        (Some(path), Some(location)) => sources.lines(path).and_then(|lines| {
+            let end = diagnostic.span.map(|s| s.end);
+            snippet(lines, location, end)
+        }),
+        _ => None,
+    };
+    let width = snippet
+        .as_ref()
+        .and_then(|lines| lines.iter().filter_map(|line| line.number).max())
+        .map_or(1, |number| number.to_string().len());
+    let gutter = |number: Option<usize>| {
+        let number = number.map(|n| n.to_string()).unwrap_or_default();
+        style.paint(GUTTER, &format!("{:>width$} |", number, width = width))
+    };
+
+    if let Some(path) = &diagnostic.path {
+        let at = match diagnostic.location {
+            Some(location) => format!("{}:{}:{}", path.display(), location.line, location.column),
+            None => path.display().to_string(),
+        };
+        writeln!(
+            out,
+            "{}{} {}",
+            " ".repeat(width),
+            style.paint(GUTTER, "-->"),
+            at
+        )?;
+    }
+    if let Some(lines) = &snippet {
+        writeln!(out, "{}", gutter(None))?;
+        for line in lines {
+            match line.number {
+                Some(number) => writeln!(out, "{} {}", gutter(Some(number)), line.text)?,
+                None => writeln!(out, "{}", style.paint(GUTTER, "..."))?,
+            }
+            if let Some((start, len)) = line.underline {
+                writeln!(
+                    out,
+                    "{} {}{}",
+                    gutter(None),
+                    padding(&line.text, start),
+                    style.severity(diagnostic.severity, &"^".repeat(len.max(1)))
+                )?;
+            }
+        }
+    }
+
+    write_suggestions(out, &diagnostic.suggestions, style, width)?;
+    for related in &diagnostic.related {
+        let also = format!(
+            "also {}: {}",
+            style.severity(related.severity, &related.label()),
+            related.message
+        );
+        write_note(out, &also, style, width)?;
+        write_suggestions(out, &related.suggestions, style, width)?;
+    }
+    Ok(())
+}
+
+/// A line under the snippet, like `= help: ...`, lined up with its gutter.
+fn write_note(out: &mut impl Write, text: &str, style: Style, width: usize) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} {} {}",
+        " ".repeat(width),
+        style.paint(GUTTER, "="),
+        text
+    )
+}
+
+fn write_suggestions(
+    out: &mut impl Write,
+    suggestions: &[Suggestion],
+    style: Style,
+    width: usize,
+) -> io::Result<()> {
+    for suggestion in suggestions {
+        let help = format!("{}: {}", style.paint(BOLD, "help"), suggestion.message);
+        write_note(out, &help, style, width)?;
+        for line in suggestion.example.iter().flat_map(|e| e.lines()) {
+            writeln!(out, "{}    | {}", " ".repeat(width + 3), line)?;
+        }
+    }
+    Ok(())
+}
+
+/// A line of a snippet: its number and text, or `None` for lines left out,
+/// and the column and length of its underline.
+struct SnippetLine {
+    number: Option<usize>,
+    text: String,
+    underline: Option<(usize, usize)>,
+}
+
+/// The lines from `start` to `end`, just past the end of the span, each
+/// underlined where the span covers it. Without an end, the start is
+/// marked with a single `^`.
+fn snippet(lines: &[String], start: Location, end: Option<Location>) -> Option<Vec<SnippetLine>> {
+    let first = lines.get(start.line.checked_sub(1)?)?;
+    let end = match end {
+        // A span ending at the start of a line ends with the line before
+        Some(end) if end.line > start.line && end.column == 1 => lines
+            .get(end.line - 2)
+            .map(|line| Location {
+                line: end.line - 1,
+                column: line.chars().count() + 1,
+            })
+            .unwrap_or(start),
+        Some(end) if end >= start && end.line <= lines.len() => end,
+        _ => start,
+    };
+    if end.line == start.line {
+        return Some(vec![SnippetLine {
+            number: Some(start.line),
+            text: first.clone(),
+            underline: Some((start.column, end.column.saturating_sub(start.column))),
+        }]);
+    }
+
+    let mut shown: Vec<SnippetLine> = Vec::new();
+    let count = end.line - start.line + 1;
+    for number in start.line..=end.line {
+        let skipped = count > MAX_LINES
+            && number >= start.line + MAX_LINES / 2
+            && number <= end.line - (MAX_LINES - MAX_LINES / 2 - 1);
+        if skipped {
+            if shown.last().is_some_and(|line| line.number.is_some()) {
+                shown.push(SnippetLine {
+                    number: None,
+                    text: String::new(),
+                    underline: None,
+                });
+            }
+            continue;
+        }
+        let text = &lines[number - 1];
+        let chars = text.chars().count();
+        let from = match number == start.line {
+            true => start.column,
+            false => text.chars().take_while(|c| c.is_whitespace()).count() + 1,
+        };
+        let to = match number == end.line {
+            true => end.column,
+            false => chars + 1,
+        };
+        shown.push(SnippetLine {
+            number: Some(number),
+            text: text.clone(),
+            underline: (to > from).then(|| (from, to - from)),
+        });
+    }
+    Some(shown)
+}
@@ -0,0 +1,279 @@
+//! Comparing the base and head versions of a workflow a pull request
+//! changes.
+//!
+//! Most of what makes a workflow dangerous is a small edit: a `write` where
+//! there was a `read`, a new `pull_request_target` trigger, a secret in a
+//! job that forks can reach, or a SHA pin swapped for a tag. Each of those
+//! is reported as a `workflow-diff` error, so a reviewer sees it even when
+//! the rest of the change is large.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde_yaml::Value;
+
+use crate::action_ref::ActionRef;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::lint::{event_names, for_each_string, job_path, secrets, step_path, EXPOSED_EVENTS};
+use crate::spans::{key_path, SpanMap};
+
+/// The rule name of every finding here.
+pub const RULE: &str = "workflow-diff";
+
+/// The scopes of the `GITHUB_TOKEN`, for `read-all` and `write-all`.
+pub(crate) const SCOPES: &[&str] = &[
+    "actions",
+    "attestations",
+    "checks",
+    "contents",
+    "deployments",
+    "discussions",
+    "id-token",
+    "issues",
+    "models",
+    "packages",
+    "pages",
+    "pull-requests",
+    "repository-projects",
+    "security-events",
+    "statuses",
+];
+
+/// How much of the token a `permissions` value grants. `None` when there's
+/// no `permissions`, which means the repository's default.
+pub(crate) fn grants(permissions: Option<&Value>) -> Option<BTreeMap<&'static str, u8>> {
+    let level = |access: &str| match access {
+        "write" => 2,
+        "read" => 1,
+        _ => 0,
+    };
+    let all = |access| SCOPES.iter().map(|&scope| (scope, access)).collect();
+    Some(match permissions? {
+        Value::String(s) if s == "write-all" => all(2),
+        Value::String(s) if s == "read-all" => all(1),
+        Value::Mapping(scopes) => SCOPES
+            .iter()
+            .map(|&scope| {
+                let access = scopes
+                    .get(&Value::String(scope.to_string()))
+                    .and_then(Value::as_str)
+                    .unwrap_or("none");
+                (scope, level(access))
+            })
+            .collect(),
+        _ => all(0),
+    })
+}
+
+pub(crate) fn level_name(level: u8) -> &'static str {
+    match level {
+        2 => "write",
+        1 => "read",
+        _ => "none",
+    }
+}
+
+/// The jobs of `document`, by id.
+pub(crate) fn jobs(document: &Value) -> BTreeMap<&str, &Value> {
+    document
+        .get("jobs")
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, job)| Some((id.as_str()?, job)))
+        .collect()
+}
+
+/// The `uses` of a job and its steps, by node path.
+pub(crate) fn uses(job_id: &str, job: &Value) -> Vec<(String, ActionRef)> {
+    let mut found = Vec::new();
+    let parse = |uses: Option<&Value>| uses?.as_str()?.parse::<ActionRef>().ok();
+    if let Some(action) = parse(job.get("uses")) {
+        found.push((key_path(&job_path(job_id), "uses"), action));
+    }
+    let steps = job.get("steps").and_then(Value::as_sequence);
+    for (i, step) in steps.into_iter().flatten().enumerate() {
+        if let Some(action) = parse(step.get("uses")) {
+            found.push((key_path(&step_path(job_id, i), "uses"), action));
+        }
+    }
+    found
+}
+
+/// The secrets a job uses.
+pub(crate) fn job_secrets(job: &Value) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    for_each_string(job, "", &mut |_, value| found.extend(secrets(value)));
+    found
+}
+
+struct Compare<'a> {
+    head_path: &'a Path,
+    spans: SpanMap,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Compare<'_> {
+    fn report(&mut self, node: &str, id: &str, message: String) {
+        let mut diagnostic = Diagnostic::new(RULE, Severity::Error, message)
+            .with_path(self.head_path)
+            .with_location(self.spans.locate(node))
+            .with_message_id(id);
+        diagnostic.node = Some(node.to_string()).filter(|n| !n.is_empty());
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn permissions(&mut self, node: &str, what: &str, base: Option<&Value>, head: Option<&Value>) {
+        let (base, head) = match (grants(base), grants(head)) {
+            (_, None) if base.is_none() => return,
+            (Some(_), None) => {
+                self.report(
+                    node,
+                    "dropped-permissions",
+                    format!(
+                        "this pull request removes the `permissions` of {}, so it gets the \
+                         repository's default token permissions, which can be write-all",
+                        what
+                    ),
+                );
+                return;
+            }
+            (Some(base), Some(head)) => (base, head),
+            // Going from the default to explicit permissions narrows them
+            (None, _) => return,
+        };
+        let broadened: Vec<String> = SCOPES
+            .iter()
+            .filter(|scope| head[*scope] > base[*scope])
+            .map(|scope| {
+                format!(
+                    "`{}` from {} to {}",
+                    scope,
+                    level_name(base[scope]),
+                    level_name(head[scope])
+                )
+            })
+            .collect();
+        if !broadened.is_empty() {
+            self.report(
+                node,
+                "broadened-permissions",
+                format!(
+                    "this pull request broadens the token permissions of {}: {}",
+                    what,
+                    broadened.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+/// The findings for a pull request changing the workflow `base` into
+/// `head`, located in `head`, which is at `head_path`. A new workflow has
+/// an empty `base`.
+pub fn compare(base: &Value, head: &Value, head_source: &str, head_path: &Path) -> Vec<Diagnostic> {
+    let mut cx = Compare {
+        head_path,
+        spans: SpanMap::parse(head_source),
+        diagnostics: Vec::new(),
+    };
+
+    let base_events = event_names(base);
+    let head_events = event_names(head);
+    for event in EXPOSED_EVENTS {
+        if head_events.contains(event) && !base_events.contains(event) {
+            cx.report(
+                &key_path("on", event),
+                "exposed-trigger",
+                format!(
+                    "this pull request adds the `{}` trigger, which people outside the \
+                     repository can cause and which runs with its secrets",
+                    event
+                ),
+            );
+        }
+    }
+    let exposed = EXPOSED_EVENTS.iter().any(|e| head_events.contains(e));
+
+    let new_workflow = base.as_mapping().is_none_or(|m| m.is_empty());
+    if !new_workflow {
+        cx.permissions(
+            "permissions",
+            "the workflow",
+            base.get("permissions"),
+            head.get("permissions"),
+        );
+    }
+
+    let base_jobs = jobs(base);
+    for (job_id, job) in jobs(head) {
+        let path = job_path(job_id);
+        let base_job = base_jobs.get(job_id).copied();
+
+        if let Some(base_job) = base_job {
+            // A job without `permissions` has the workflow's
+            let effective = |document: &Value, job: &Value| {
+                job.get("permissions")
+                    .or_else(|| document.get("permissions"))
+                    .cloned()
+            };
+            let (before, after) = (effective(base, base_job), effective(head, job));
+            let node = if job.get("permissions").is_some() {
+                key_path(&path, "permissions")
+            } else {
+                path.clone()
+            };
+            // The workflow-level change is already reported
+            if job.get("permissions").is_some() || base_job.get("permissions").is_some() {
+                let what = format!("job `{}`", job_id);
+                cx.permissions(&node, &what, before.as_ref(), after.as_ref());
+            }
+        }
+
+        if exposed {
+            // Secrets the job had before were only reachable if the trigger was too
+            let before = match base_job {
+                Some(base_job) if EXPOSED_EVENTS.iter().any(|e| base_events.contains(e)) => {
+                    job_secrets(base_job)
+                }
+                _ => BTreeSet::new(),
+            };
+            let added: Vec<String> = job_secrets(job).difference(&before).cloned().collect();
+            if !added.is_empty() {
+                let names: Vec<String> = added.iter().map(|s| format!("`{}`", s)).collect();
+                cx.report(
+                    &path,
+                    "exposed-secret",
+                    format!(
+                        "this pull request gives job `{}` the secrets {} in a workflow that \
+                         forks can trigger",
+                        job_id,
+                        names.join(", ")
+                    ),
+                );
+            }
+        }
+
+        let pinned: BTreeSet<String> = base_job
+            .map(|base_job| uses(job_id, base_job))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, action)| action.is_pinned())
+            .map(|(_, action)| action.name())
+            .collect();
+        for (node, action) in uses(job_id, job) {
+            if !action.is_pinned() && pinned.contains(&action.name()) {
+                cx.report(
+                    &node,
+                    "unpinned-action",
+                    format!(
+                        "this pull request replaces the pinned commit of `{}` with a ref that \
+                         can move",
+                        action.name()
+                    ),
+                );
+            }
+        }
+    }
+    cx.diagnostics
+}
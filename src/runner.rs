@@ -0,0 +1,233 @@
+//! What a job's `runs-on` picks: one of GitHub's hosted images, the
+//! repository's self-hosted runners, or a runner group, going by GitHub's
+//! list of images in `actions/runner-images`.
+
+use crate::workflow::RunsOn;
+
+/// The operating system a hosted image runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Os {
+    Linux,
+    Windows,
+    MacOs,
+}
+
+/// Whether GitHub still runs jobs on an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Current,
+    /// Announced for removal on the date, with brownouts before it.
+    Deprecated {
+        removal: &'static str,
+    },
+    /// Removed, so jobs asking for it fail without starting.
+    Retired {
+        since: &'static str,
+    },
+}
+
+/// A GitHub-hosted runner image and the label that picks it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Image {
+    pub label: &'static str,
+    pub os: Os,
+    pub status: Status,
+    /// The label to move to when it's deprecated or retired.
+    pub replacement: &'static str,
+}
+
+const fn current(label: &'static str, os: Os) -> Image {
+    Image {
+        label,
+        os,
+        status: Status::Current,
+        replacement: "",
+    }
+}
+
+const fn retired(
+    label: &'static str,
+    os: Os,
+    since: &'static str,
+    replacement: &'static str,
+) -> Image {
+    Image {
+        label,
+        os,
+        status: Status::Retired { since },
+        replacement,
+    }
+}
+
+/// The labels of GitHub's hosted images, including the larger and arm64
+/// ones any repository can use.
+pub const IMAGES: &[Image] = &[
+    current("ubuntu-latest", Os::Linux),
+    current("ubuntu-26.04", Os::Linux),
+    current("ubuntu-24.04", Os::Linux),
+    current("ubuntu-22.04", Os::Linux),
+    current("ubuntu-24.04-arm", Os::Linux),
+    current("ubuntu-22.04-arm", Os::Linux),
+    current("ubuntu-slim", Os::Linux),
+    retired("ubuntu-20.04", Os::Linux, "April 2025", "ubuntu-24.04"),
+    retired("ubuntu-18.04", Os::Linux, "April 2023", "ubuntu-24.04"),
+    retired("ubuntu-16.04", Os::Linux, "September 2021", "ubuntu-24.04"),
+    current("windows-latest", Os::Windows),
+    current("windows-2025", Os::Windows),
+    current("windows-2022", Os::Windows),
+    current("windows-11-arm", Os::Windows),
+    retired("windows-2019", Os::Windows, "June 2025", "windows-2022"),
+    retired("windows-2016", Os::Windows, "March 2022", "windows-2022"),
+    current("macos-latest", Os::MacOs),
+    current("macos-latest-large", Os::MacOs),
+    current("macos-latest-xlarge", Os::MacOs),
+    current("macos-26", Os::MacOs),
+    current("macos-26-intel", Os::MacOs),
+    current("macos-15", Os::MacOs),
+    current("macos-15-intel", Os::MacOs),
+    current("macos-15-large", Os::MacOs),
+    current("macos-15-xlarge", Os::MacOs),
+    current("macos-14", Os::MacOs),
+    current("macos-14-large", Os::MacOs),
+    current("macos-14-xlarge", Os::MacOs),
+    retired("macos-13", Os::MacOs, "December 2025", "macos-15-intel"),
+    retired(
+        "macos-13-large",
+        Os::MacOs,
+        "December 2025",
+        "macos-15-large",
+    ),
+    retired(
+        "macos-13-xlarge",
+        Os::MacOs,
+        "December 2025",
+        "macos-15-xlarge",
+    ),
+    retired("macos-12", Os::MacOs, "December 2024", "macos-15"),
+    retired("macos-11", Os::MacOs, "June 2024", "macos-15"),
+    retired("macos-10.15", Os::MacOs, "August 2022", "macos-15"),
+];
+
+/// The labels every self-hosted runner gets, for its OS and architecture.
+pub const SELF_HOSTED_LABELS: &[&str] = &[
+    "self-hosted",
+    "linux",
+    "windows",
+    "macos",
+    "x64",
+    "arm",
+    "arm64",
+];
+
+/// The hosted image `label` picks, ignoring case as GitHub does.
+pub fn image_for(label: &str) -> Option<&'static Image> {
+    IMAGES
+        .iter()
+        .find(|image| image.label.eq_ignore_ascii_case(label))
+}
+
+/// The words after the version in hosted image labels, like `arm` in
+/// `ubuntu-24.04-arm`.
+const VARIANTS: &[&str] = &["arm", "intel", "large", "xlarge"];
+
+/// How hosted image labels start, for each OS.
+const PREFIXES: &[(&str, Os)] = &[
+    ("ubuntu-", Os::Linux),
+    ("windows-", Os::Windows),
+    ("macos-", Os::MacOs),
+];
+
+/// Whether `label` starts like a hosted image's, as a misspelt one would,
+/// like `windows-lastest`.
+pub fn looks_hosted(label: &str) -> bool {
+    let label = label.to_ascii_lowercase();
+    PREFIXES.iter().any(|(prefix, _)| label.starts_with(prefix))
+}
+
+impl Os {
+    /// The OS a runner label says a job runs on: a hosted image's; that of
+    /// the images a label that starts like theirs is named after, like
+    /// `ubuntu-28.04` or an organization's `windows-latest-8-cores`; or the
+    /// one a self-hosted runner's OS label, like `windows`, names.
+    pub fn of_label(label: &str) -> Option<Os> {
+        if let Some(image) = image_for(label) {
+            return Some(image.os);
+        }
+        let label = label.to_ascii_lowercase();
+        PREFIXES
+            .iter()
+            .find(|(prefix, _)| label.starts_with(prefix) || label == prefix.trim_end_matches('-'))
+            .map(|&(_, os)| os)
+            .or_else(|| (label == "linux").then_some(Os::Linux))
+    }
+}
+
+/// Whether `label` is written the way a hosted image's is, with a version
+/// number, like `ubuntu-28.04` or `macos-27-xlarge`. GitHub adds images
+/// faster than a list can keep up, so one like that may be real.
+pub fn is_versioned(label: &str) -> bool {
+    let label = label.to_ascii_lowercase();
+    let mut parts = label.split('-').skip(1);
+    let version = parts.next().unwrap_or_default();
+    !version.is_empty()
+        && version.starts_with(|c: char| c.is_ascii_digit())
+        && version.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && parts.all(|part| VARIANTS.contains(&part))
+}
+
+/// What a job's `runs-on` asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Runner<'w> {
+    /// One of GitHub's hosted images.
+    Hosted(&'static Image),
+    /// The repository's or organization's self-hosted runners with all of
+    /// these labels, `self-hosted` included.
+    SelfHosted(Vec<&'w str>),
+    /// A runner group, and the labels picking runners in it.
+    Group {
+        group: Option<&'w str>,
+        labels: Vec<&'w str>,
+    },
+    /// Labels with an expression in them, which are only known when the
+    /// workflow runs.
+    Expression(Vec<&'w str>),
+    /// Labels that aren't a hosted image and don't include `self-hosted`,
+    /// like an organization's larger runners, or a misspelt image.
+    Custom(Vec<&'w str>),
+}
+
+impl<'w> Runner<'w> {
+    pub fn new(runs_on: &'w RunsOn) -> Self {
+        let labels: Vec<&str> = runs_on.labels().iter().map(String::as_str).collect();
+        if let RunsOn::Group { group, .. } = runs_on {
+            return Runner::Group {
+                group: group.as_deref(),
+                labels,
+            };
+        }
+        if labels.iter().any(|label| label.contains("${{")) {
+            return Runner::Expression(labels);
+        }
+        if labels
+            .iter()
+            .any(|label| label.eq_ignore_ascii_case("self-hosted"))
+        {
+            return Runner::SelfHosted(labels);
+        }
+        match labels.as_slice() {
+            [label] => match image_for(label) {
+                Some(image) => Runner::Hosted(image),
+                None => Runner::Custom(labels),
+            },
+            _ => Runner::Custom(labels),
+        }
+    }
+
+    /// The hosted image, if that's what it is.
+    pub fn image(&self) -> Option<&'static Image> {
+        match self {
+            Runner::Hosted(image) => Some(image),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Which version of the workflow syntax a workflow needs.
+//!
+//! GitHub keeps adding keys, events and contexts, and GitHub Enterprise
+//! Server gets them releases later, if at all. Each [`Feature`] records the
+//! first GHES release that has it, so a workflow can be checked against the
+//! server it will run on.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_yaml::Value;
+
+use crate::expr;
+use crate::lint::{event_names, for_each_string, job_path, step_path};
+use crate::spans::key_path;
+
+/// Where workflows will run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Target {
+    /// github.com, which has every feature.
+    #[default]
+    Dotcom,
+    /// A GitHub Enterprise Server release, like 3.10.
+    Ghes(u32, u32),
+}
+
+impl Target {
+    pub fn is_ghes(self) -> bool {
+        matches!(self, Target::Ghes(..))
+    }
+
+    /// Whether workflows here can use `feature`.
+    pub fn supports(self, feature: &Feature) -> bool {
+        match (self, feature.ghes) {
+            (Target::Dotcom, _) => true,
+            (Target::Ghes(..), None) => false,
+            (Target::Ghes(major, minor), Some(since)) => (major, minor) >= since,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Dotcom => f.write_str("github.com"),
+            Target::Ghes(major, minor) => write!(f, "GHES {}.{}", major, minor),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "github.com" || s == "dotcom" {
+            return Ok(Target::Dotcom);
+        }
+        let version = s
+            .strip_prefix("ghes-")
+            .and_then(|v| v.split_once('.'))
+            .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+        match version {
+            Some((major, minor)) => Ok(Target::Ghes(major, minor)),
+            None => Err(format!(
+                "unknown target `{}`, expected github.com or ghes-<major>.<minor>, like ghes-3.10",
+                s
+            )),
+        }
+    }
+}
+
+/// Something in the workflow syntax that not every server has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    pub name: &'static str,
+    /// The first GHES release with it, or `None` if GHES doesn't have it.
+    pub ghes: Option<(u32, u32)>,
+}
+
+pub const CONCURRENCY: Feature = Feature {
+    name: "`concurrency`",
+    ghes: Some((3, 1)),
+};
+pub const PERMISSIONS: Feature = Feature {
+    name: "`permissions`",
+    ghes: Some((3, 2)),
+};
+pub const WORKFLOW_CALL: Feature = Feature {
+    name: "reusable workflows",
+    ghes: Some((3, 4)),
+};
+pub const ID_TOKEN: Feature = Feature {
+    name: "the `id-token` permission for OIDC",
+    ghes: Some((3, 5)),
+};
+pub const SECRETS_INHERIT: Feature = Feature {
+    name: "`secrets: inherit`",
+    ghes: Some((3, 6)),
+};
+pub const RUN_NAME: Feature = Feature {
+    name: "`run-name`",
+    ghes: Some((3, 8)),
+};
+pub const VARS: Feature = Feature {
+    name: "the `vars` context",
+    ghes: Some((3, 8)),
+};
+pub const MERGE_GROUP: Feature = Feature {
+    name: "the `merge_group` event",
+    ghes: Some((3, 12)),
+};
+pub const ARTIFACT_V4: Feature = Feature {
+    name: "v4 of upload-artifact and download-artifact",
+    ghes: None,
+};
+
+/// A feature a workflow uses, and the node using it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Use {
+    pub feature: Feature,
+    pub node: String,
+}
+
+/// Whether `uses` is one of the artifact actions at v4 or later.
+fn artifact_v4(uses: &str) -> bool {
+    let (action, git_ref) = match uses.split_once('@') {
+        Some(split) => split,
+        None => return false,
+    };
+    let artifact = ["actions/upload-artifact", "actions/download-artifact"]
+        .iter()
+        .any(|a| action.eq_ignore_ascii_case(a));
+    let major: String = git_ref
+        .strip_prefix('v')
+        .unwrap_or_default()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    artifact && major.parse::<u32>().is_ok_and(|major| major >= 4)
+}
+
+/// Whether a `permissions` value grants `id-token`.
+fn grants_id_token(permissions: &Value) -> bool {
+    match permissions {
+        Value::Mapping(scopes) => scopes
+            .get(&Value::String("id-token".to_string()))
+            .is_some_and(|access| access.as_str() == Some("write")),
+        Value::String(all) => all == "write-all",
+        _ => false,
+    }
+}
+
+/// The versioned features `document` uses, each at the first place it's
+/// used.
+pub fn features_used(document: &Value) -> Vec<Use> {
+    let mut uses: Vec<Use> = Vec::new();
+    let mut found = |feature: Feature, node: String| {
+        if !uses.iter().any(|u| u.feature == feature) {
+            uses.push(Use { feature, node });
+        }
+    };
+
+    if document.get("run-name").is_some() {
+        found(RUN_NAME, "run-name".to_string());
+    }
+    for event in event_names(document) {
+        let node = key_path("on", event);
+        match event {
+            "merge_group" => found(MERGE_GROUP, node),
+            "workflow_call" => found(WORKFLOW_CALL, node),
+            _ => {}
+        }
+    }
+    if document.get("concurrency").is_some() {
+        found(CONCURRENCY, "concurrency".to_string());
+    }
+    if let Some(permissions) = document.get("permissions") {
+        found(PERMISSIONS, "permissions".to_string());
+        if grants_id_token(permissions) {
+            found(ID_TOKEN, "permissions".to_string());
+        }
+    }
+
+    let mut jobs: Vec<(&str, &Value)> = document
+        .get("jobs")
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flat_map(|jobs| {
+            jobs.iter()
+                .filter_map(|(id, job)| Some((id.as_str()?, job)))
+        })
+        .collect();
+    jobs.sort_by_key(|&(id, _)| id);
+    for (id, job) in jobs {
+        let path = job_path(id);
+        if job.get("concurrency").is_some() {
+            found(CONCURRENCY, key_path(&path, "concurrency"));
+        }
+        if let Some(permissions) = job.get("permissions") {
+            found(PERMISSIONS, key_path(&path, "permissions"));
+            if grants_id_token(permissions) {
+                found(ID_TOKEN, key_path(&path, "permissions"));
+            }
+        }
+        if job.get("uses").is_some() {
+            found(WORKFLOW_CALL, key_path(&path, "uses"));
+        }
+        if job.get("secrets").and_then(Value::as_str) == Some("inherit") {
+            found(SECRETS_INHERIT, key_path(&path, "secrets"));
+        }
+        let steps = job.get("steps").and_then(Value::as_sequence);
+        for (i, step) in steps.into_iter().flatten().enumerate() {
+            if step
+                .get("uses")
+                .and_then(Value::as_str)
+                .is_some_and(artifact_v4)
+            {
+                found(ARTIFACT_V4, key_path(&step_path(id, i), "uses"));
+            }
+        }
+    }
+
+    for_each_string(document, "", &mut |path, s| {
+        for embedded in expr::find_expressions(s) {
+            let tokens = match expr::tokenize(embedded.body) {
+                Ok(tokens) => tokens,
+                Err(_) => continue,
+            };
+            if expr::references(&tokens)
+                .iter()
+                .any(|r| r.parts.first().is_some_and(|c| c == "vars"))
+            {
+                found(VARS, path.to_string());
+            }
+        }
+    });
+    uses
+}
@@ -0,0 +1,217 @@
+//! Linting workflows too big to parse in one go, like the tens of megabytes
+//! with thousands of jobs some generators write.
+//!
+//! Parsing a file builds its YAML, its typed workflow and its spans, which
+//! together take many times the file's size. A file over
+//! [`SEGMENT_THRESHOLD`] is split at its jobs instead: each segment is
+//! everything outside `jobs:`, the header, with as many whole jobs as fit in
+//! [`SEGMENT_BYTES`], so only one segment is parsed at a time. Findings are
+//! moved back to the lines they came from, and those about the header are
+//! kept from the first segment only.
+//!
+//! It's a partial analysis: rules that look across jobs, like those
+//! following `needs`, only see the jobs in the same segment.
+
+use std::ops::Range;
+
+use crate::diagnostics::{Diagnostic, Location};
+
+/// The findings saying a file was too big to lint in one go.
+pub const RULE: &str = "partial-analysis";
+
+/// Files bigger than this are linted a segment at a time.
+pub const SEGMENT_THRESHOLD: usize = 8 * 1024 * 1024;
+
+/// How many bytes of jobs go in a segment, unless one job is bigger.
+pub const SEGMENT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Files bigger than this aren't read at all.
+pub const MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// A piece of a workflow that parses as one, and how to map findings back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub text: String,
+    /// The line in the file of each line of the segment, 1-based.
+    lines: Vec<usize>,
+    /// The segment's lines that hold its jobs, from 0.
+    jobs: Range<usize>,
+    /// Whether this is the first segment, which keeps findings about the
+    /// header.
+    first: bool,
+}
+
+/// Where a file's jobs are, by line from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segments<'a> {
+    lines: Vec<&'a str>,
+    /// The `jobs:` line.
+    jobs_line: usize,
+    /// The lines each job takes, in order.
+    job_ranges: Vec<Range<usize>>,
+    /// The line after the last job.
+    end: usize,
+    max_bytes: usize,
+    next: usize,
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+/// Split `source` into segments of about `max_bytes` of jobs each. `None`
+/// if its jobs can't be found line by line, like `jobs: {...}` written in
+/// flow style.
+pub fn split(source: &str, max_bytes: usize) -> Option<Segments<'_>> {
+    let lines: Vec<&str> = source.lines().collect();
+    let jobs_line = lines.iter().position(|line| {
+        line.strip_prefix("jobs:")
+            .is_some_and(|rest| !is_content(rest))
+    })?;
+    let end = (jobs_line + 1..lines.len())
+        .find(|&i| is_content(lines[i]) && indent(lines[i]) == 0)
+        .unwrap_or(lines.len());
+    let job_indent = (jobs_line + 1..end)
+        .find(|&i| is_content(lines[i]))
+        .map(|i| indent(lines[i]))?;
+
+    let mut job_ranges: Vec<Range<usize>> = Vec::new();
+    for (i, &line) in lines.iter().enumerate().take(end).skip(jobs_line + 1) {
+        if !is_content(line) {
+            continue;
+        }
+        match indent(line) {
+            n if n == job_indent => {
+                if let Some(last) = job_ranges.last_mut() {
+                    last.end = i;
+                }
+                job_ranges.push(i..end);
+            }
+            // Something at the level of `jobs:`'s keys that isn't a job
+            n if n < job_indent => return None,
+            _ if job_ranges.is_empty() => return None,
+            _ => {}
+        }
+    }
+    Some(Segments {
+        lines,
+        jobs_line,
+        job_ranges,
+        end,
+        max_bytes,
+        next: 0,
+    })
+}
+
+impl Segments<'_> {
+    /// How many jobs there are.
+    pub fn jobs(&self) -> usize {
+        self.job_ranges.len()
+    }
+
+    fn bytes(&self, range: Range<usize>) -> usize {
+        self.lines[range].iter().map(|line| line.len() + 1).sum()
+    }
+}
+
+impl Iterator for Segments<'_> {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Segment> {
+        let first = self.next == 0;
+        if !first && self.next >= self.job_ranges.len() {
+            return None;
+        }
+        // Whole jobs until they'd go over, and always at least one
+        let start = self.next;
+        let mut bytes = 0;
+        while self.next < self.job_ranges.len() {
+            let size = self.bytes(self.job_ranges[self.next].clone());
+            if self.next > start && bytes + size > self.max_bytes {
+                break;
+            }
+            bytes += size;
+            self.next += 1;
+        }
+        let job_lines = match (
+            self.job_ranges.get(start),
+            self.job_ranges.get(self.next - 1),
+        ) {
+            (Some(from), Some(to)) if self.next > start => from.start..to.end,
+            _ => self.jobs_line + 1..self.jobs_line + 1,
+        };
+
+        let header_before = 0..self.jobs_line + 1;
+        let header_after = self.end..self.lines.len();
+        let mut text = String::new();
+        let mut lines = Vec::new();
+        let mut jobs = 0..0;
+        for (part, range) in vec![header_before, job_lines, header_after]
+            .into_iter()
+            .enumerate()
+        {
+            let start = lines.len();
+            for i in range {
+                text.push_str(self.lines[i]);
+                text.push('\n');
+                lines.push(i + 1);
+            }
+            if part == 1 {
+                jobs = start..lines.len();
+            }
+        }
+        Some(Segment {
+            text,
+            lines,
+            jobs,
+            first,
+        })
+    }
+}
+
+impl Segment {
+    /// Where `location` in the segment is in the file, if it's a finding
+    /// this segment keeps.
+    fn location(&self, location: Location) -> Option<Location> {
+        let index = location.line.checked_sub(1)?;
+        if !self.first && !self.jobs.contains(&index) {
+            return None;
+        }
+        // Past the end is the end of the file
+        let line = match self.lines.get(index) {
+            Some(&line) => line,
+            None if self.first => self.lines.last().map_or(1, |&last| last + 1),
+            None => return None,
+        };
+        Some(Location {
+            line,
+            column: location.column,
+        })
+    }
+
+    /// `diagnostics` about the segment, as findings about the file. Those
+    /// about the header, or without a place, are kept from the first segment
+    /// only, and fixes and spans are dropped, as they're for the segment's
+    /// text.
+    pub fn unwrap(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                match diagnostic.location {
+                    Some(location) => diagnostic.location = Some(self.location(location)?),
+                    None if !self.first => return None,
+                    None => {}
+                }
+                diagnostic.fix = None;
+                diagnostic.span = None;
+                diagnostic.related = self.unwrap(std::mem::take(&mut diagnostic.related));
+                Some(diagnostic)
+            })
+            .collect()
+    }
+}
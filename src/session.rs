@@ -0,0 +1,183 @@
+//! Linting one workflow from a library, without setting up a [`Context`]
+//! by hand. [`LintSession::run`] blocks, as the binary does; with the
+//! `async` feature, [`LintSession::run_async`] runs it on a thread of its
+//! own and returns a future for the outcome, so embedders with an async
+//! runtime can lint several files at once without blocking it. The future
+//! doesn't need any particular runtime.
+//!
+//! It's the same lint either way: the rules don't call the GitHub API, and
+//! the checks that do, like `pins` and `attestations`, go through the
+//! blocking [`Scheduler`](crate::github::Scheduler) and aren't part of a
+//! session. Running sessions at once overlaps whole lints, not API
+//! requests.
+
+use std::path::PathBuf;
+
+use crate::budget::Budget;
+use crate::diagnostics::Diagnostic;
+use crate::eval::Stubs;
+use crate::lint::{self, Context, RuleOptions, Skipped};
+use crate::repo;
+use crate::spans::SpanMap;
+use crate::workflow::Workflow;
+
+/// A workflow to lint, and how.
+#[derive(Debug, Clone, Default)]
+pub struct LintSession {
+    /// Where the workflow is, which findings carry and the checkout is
+    /// found from.
+    pub path: Option<PathBuf>,
+    pub source: String,
+    pub options: RuleOptions,
+    pub stubs: Stubs,
+    pub budget: Budget,
+}
+
+/// What a run found.
+#[derive(Debug, Clone, Default)]
+pub struct Outcome {
+    pub diagnostics: Vec<Diagnostic>,
+    /// The rules that weren't run, or whose findings were dropped.
+    pub skipped: Vec<Skipped>,
+}
+
+impl LintSession {
+    pub fn new(source: impl Into<String>) -> Self {
+        LintSession {
+            source: source.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn with_options(mut self, options: RuleOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_stubs(mut self, stubs: Stubs) -> Self {
+        self.stubs = stubs;
+        self
+    }
+
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Parse the workflow and run the rules that are on by default, set up
+    /// with the session's options.
+    pub fn run(&self) -> Result<Outcome, serde_yaml::Error> {
+        let mut workflow = Workflow::parse_str(&self.source)?;
+        let document = serde_yaml::from_str(&self.source)?;
+        let spans = SpanMap::parse(&self.source);
+        workflow.locate(&document, &spans);
+        let repo_root = self.path.as_deref().and_then(repo::find_root);
+        let cx = Context {
+            path: self.path.as_deref(),
+            source: &self.source,
+            workflow: &workflow,
+            document: &document,
+            spans: &spans,
+            repo_root: repo_root.as_deref(),
+            stubs: &self.stubs,
+            analyses: Default::default(),
+            budget: self.budget.clone(),
+        };
+        let rules: Vec<_> = lint::configured_rules(&self.options)
+            .into_iter()
+            .filter(|rule| rule.enabled_by_default())
+            .collect();
+        let (diagnostics, skipped) = lint::lint_reporting_skipped(&cx, &rules);
+        Ok(Outcome {
+            diagnostics,
+            skipped,
+        })
+    }
+
+    /// Run the session on a thread of its own, returning a future for the
+    /// outcome. Dropping the future before it's done cancels the run, with
+    /// the budget's token, and it stops at the next rule.
+    #[cfg(feature = "async")]
+    pub fn run_async(self) -> asynchronous::Running {
+        asynchronous::Running::spawn(self)
+    }
+}
+
+#[cfg(feature = "async")]
+pub mod asynchronous {
+    use std::future::Future;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+
+    use super::{LintSession, Outcome};
+    use crate::budget::CancelToken;
+
+    type Finished = thread::Result<Result<Outcome, serde_yaml::Error>>;
+
+    #[derive(Default)]
+    struct Shared {
+        finished: Option<Finished>,
+        /// Whether the thread is done, even once the outcome's been taken.
+        done: bool,
+        waker: Option<Waker>,
+    }
+
+    /// A [`LintSession`] running on its own thread.
+    pub struct Running {
+        shared: Arc<Mutex<Shared>>,
+        cancel: CancelToken,
+    }
+
+    impl Running {
+        pub(super) fn spawn(session: LintSession) -> Self {
+            let shared = Arc::new(Mutex::new(Shared::default()));
+            let cancel = session.budget.cancel.clone();
+            let done = Arc::clone(&shared);
+            thread::spawn(move || {
+                let finished = panic::catch_unwind(AssertUnwindSafe(|| session.run()));
+                let mut shared = done.lock().unwrap_or_else(|e| e.into_inner());
+                shared.finished = Some(finished);
+                shared.done = true;
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+            });
+            Running { shared, cancel }
+        }
+    }
+
+    impl Future for Running {
+        type Output = Result<Outcome, serde_yaml::Error>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+            match shared.finished.take() {
+                Some(Ok(outcome)) => Poll::Ready(outcome),
+                // The run panicked, which the caller gets as if it had run
+                // on their own thread
+                Some(Err(payload)) => panic::resume_unwind(payload),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    impl Drop for Running {
+        fn drop(&mut self) {
+            let shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+            if !shared.done {
+                self.cancel.cancel();
+            }
+        }
+    }
+}
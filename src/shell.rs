@@ -0,0 +1,345 @@
+//! The scripts of `run` steps as the shell sees them: which shell runs
+//! them, going by the step's `shell` or the job's and workflow's
+//! `defaults`, and what's wrong with them, from `shellcheck` when it's
+//! there to ask or from a few checks of our own when it isn't.
+
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crate::budget::Budget;
+use crate::diagnostics::Severity;
+use crate::expr;
+use crate::runner::{self, Os};
+use crate::workflow::{Job, Step, Workflow};
+
+/// How often to check whether `shellcheck` is done.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A shell `run` scripts can be written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Sh,
+    /// PowerShell, Python, `cmd` or anything else, which are left alone.
+    Other,
+}
+
+impl Shell {
+    /// The name `shellcheck --shell` takes.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Sh => "sh",
+            Shell::Other => "other",
+        }
+    }
+}
+
+/// How a step's script is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Effective {
+    pub shell: Shell,
+    /// The command line GitHub runs the script with, like `bash -e {0}`.
+    pub command: String,
+    /// Whether the command line stops the script at the first failing
+    /// command, as `-e` does.
+    pub errexit: bool,
+}
+
+/// Whether the job runs on Windows, where a step without a `shell` runs in
+/// `pwsh`.
+fn on_windows(job: &Job) -> bool {
+    job.labels().iter().any(|label| {
+        label.eq_ignore_ascii_case("windows")
+            || runner::image_for(label).is_some_and(|image| image.os == Os::Windows)
+    })
+}
+
+/// Whether the arguments of `command` turn on `errexit`, with `-e` or
+/// `-o errexit`.
+fn sets_errexit(command: &str) -> bool {
+    let words: Vec<&str> = command.split_whitespace().skip(1).collect();
+    words.iter().enumerate().any(|(i, word)| {
+        (word.starts_with('-') && !word.starts_with("--") && word.contains('e'))
+            || (*word == "-o" && words.get(i + 1) == Some(&"errexit"))
+    })
+}
+
+/// The shell the step's script runs in, and how.
+pub fn effective(workflow: &Workflow, job: &Job, step: &Step) -> Effective {
+    let shell = workflow.run_settings(job, step).shell;
+    let command = match shell {
+        None if on_windows(job) => "pwsh -command \". '{0}'\"".to_string(),
+        None => "bash -e {0}".to_string(),
+        Some("bash") => "bash --noprofile --norc -eo pipefail {0}".to_string(),
+        Some("sh") => "sh -e {0}".to_string(),
+        Some(custom) => custom.to_string(),
+    };
+    let program = command.split_whitespace().next().unwrap_or_default();
+    let shell = match program.rsplit('/').next().unwrap_or_default() {
+        "bash" => Shell::Bash,
+        "sh" | "dash" => Shell::Sh,
+        _ => Shell::Other,
+    };
+    Effective {
+        shell,
+        errexit: sets_errexit(&command),
+        command,
+    }
+}
+
+/// Something wrong with a script, at a byte offset in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub offset: usize,
+    pub severity: Severity,
+    /// What kind of problem it is, like `unquoted` or shellcheck's `SC2086`.
+    pub code: String,
+    pub message: String,
+}
+
+/// `script` with each `${{ }}` replaced by as many `_`, so that offsets
+/// in it are offsets in the script and the shell sees a plain word where
+/// GitHub pastes a value in.
+pub fn mask_expressions(script: &str) -> String {
+    let mut masked = script.to_string();
+    for embedded in expr::find_expressions(script) {
+        let end = (embedded.body_offset + embedded.body.len() + 2).min(script.len());
+        masked.replace_range(embedded.offset..end, &"_".repeat(end - embedded.offset));
+    }
+    masked
+}
+
+/// Whether the script turns on `errexit` itself, like `set -e` or
+/// `set -euo pipefail`.
+pub fn script_sets_errexit(script: &str) -> bool {
+    script.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("set") && sets_errexit(line)
+    })
+}
+
+/// Variables the runner sets to paths and names without spaces in them,
+/// which scripts rarely quote and don't need to.
+fn is_runner_variable(name: &str) -> bool {
+    name.starts_with("GITHUB_") || name.starts_with("RUNNER_") || name == "HOME" || name == "CI"
+}
+
+/// The name of the variable expanded at the `$` at `i`, if it's a plain
+/// `$NAME` or `${NAME}`.
+fn variable_at(script: &str, i: usize) -> Option<&str> {
+    let rest = &script[i + 1..];
+    let (start, braced) = match rest.strip_prefix('{') {
+        Some(_) => (i + 2, true),
+        None => (i + 1, false),
+    };
+    let name_len = script[start..]
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(script.len() - start);
+    let name = &script[start..start + name_len];
+    let closed = !braced || script[start + name_len..].starts_with('}');
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+    (valid && closed).then_some(name)
+}
+
+/// Whether `word`, the part of a word before a `$`, makes it the value of
+/// an assignment, like `FOO=$BAR`, which the shell doesn't split.
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            let name = name.strip_suffix('+').unwrap_or(name);
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// The checks done without `shellcheck`: variables expanded outside
+/// quotes, where the shell splits their value on spaces and expands globs
+/// in it. Each variable is reported once, where it's first unquoted.
+/// Heredocs, `[[ ]]` tests, arithmetic and `case` words aren't split, so
+/// they're skipped, as are the runner's own variables.
+pub fn builtin_checks(script: &str) -> Vec<Finding> {
+    let masked = mask_expressions(script);
+    let bytes = masked.as_bytes();
+    let mut findings: Vec<Finding> = Vec::new();
+    let mut reported: Vec<&str> = Vec::new();
+    let (mut single, mut double, mut test, mut arithmetic) = (false, false, 0, 0);
+    let mut heredoc: Option<String> = None;
+    let mut word_start = 0;
+    let mut previous_word = "";
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if let Some(terminator) = &heredoc {
+            let line_end = masked[i..].find('\n').map_or(masked.len(), |n| i + n);
+            if masked[i..line_end].trim() == terminator {
+                heredoc = None;
+            }
+            i = line_end + 1;
+            word_start = i;
+            continue;
+        }
+        if single {
+            single = c != b'\'';
+            i += 1;
+            continue;
+        }
+        match c {
+            b'\\' => i += 1,
+            b'\'' if !double => single = true,
+            b'"' => double = !double,
+            b'#' if !double && (i == word_start) => {
+                i = masked[i..].find('\n').map_or(masked.len(), |n| i + n);
+                continue;
+            }
+            b'[' if !double && masked[i..].starts_with("[[") => test += 1,
+            b']' if !double && test > 0 && masked[i..].starts_with("]]") => test -= 1,
+            b'(' if !double && masked[i..].starts_with("((") => arithmetic += 1,
+            b')' if !double && arithmetic > 0 && masked[i..].starts_with("))") => arithmetic -= 1,
+            b'<' if !double && masked[i..].starts_with("<<<") => i += 2,
+            b'<' if !double && masked[i..].starts_with("<<") => {
+                let rest = masked[i + 2..].trim_start_matches(['-', '~']);
+                let rest = rest.trim_start();
+                let terminator: String = rest
+                    .trim_start_matches(['\'', '"'])
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                    .collect();
+                if !terminator.is_empty() {
+                    let line_end = masked[i..].find('\n').map_or(masked.len(), |n| i + n);
+                    heredoc = Some(terminator);
+                    i = line_end + 1;
+                    word_start = i;
+                    continue;
+                }
+            }
+            b' ' | b'\t' | b'\n' | b';' | b'|' | b'&' if !double => {
+                previous_word = masked[word_start..i].trim();
+                word_start = i + 1;
+            }
+            b'$' if !double && test == 0 && arithmetic == 0 => {
+                let word = &masked[word_start..i];
+                let name = variable_at(&masked, i);
+                if let Some(name) = name.filter(|&name| {
+                    !is_runner_variable(name)
+                        && !is_assignment(word)
+                        && previous_word != "case"
+                        && !reported.contains(&name)
+                }) {
+                    reported.push(name);
+                    findings.push(Finding {
+                        offset: i,
+                        severity: Severity::Warning,
+                        code: "unquoted".to_string(),
+                        message: format!(
+                            "`${}` isn't quoted, so the shell splits its value on spaces and \
+                             expands any `*` in it; write `\"${}\"`",
+                            name, name
+                        ),
+                    });
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    findings
+}
+
+/// The byte offset in `script` of a 1-based line and column.
+fn offset_of(script: &str, line: u64, column: u64) -> usize {
+    let start: usize = script
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    let text = script[start..].lines().next().unwrap_or_default();
+    let column = text
+        .char_indices()
+        .nth(column.saturating_sub(1) as usize)
+        .map_or(text.len(), |(i, _)| i);
+    (start + column).min(script.len())
+}
+
+/// What `program`, a `shellcheck`, finds in `script` run by `shell`. It's
+/// killed if the run is cancelled or `budget` runs out before it's done.
+pub fn shellcheck(
+    program: &str,
+    script: &str,
+    shell: Shell,
+    budget: &Budget,
+) -> io::Result<Vec<Finding>> {
+    let masked = mask_expressions(script);
+    let mut child = Command::new(program)
+        .args(["--format=json1", "--shell", shell.as_str(), "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let (mut stdin, mut stdout) = match (child.stdin.take(), child.stdout.take()) {
+        (Some(stdin), Some(stdout)) => (stdin, stdout),
+        _ => return Err(io::Error::other("its stdin and stdout weren't piped")),
+    };
+    // Written and read on threads of their own, so that neither a full
+    // pipe nor a `shellcheck` that stops reading holds up the deadline
+    let writer = thread::spawn(move || {
+        let written = stdin.write_all(masked.as_bytes());
+        drop(stdin);
+        written
+    });
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+    while child.try_wait()?.is_none() {
+        if budget.interrupted() {
+            child.kill()?;
+            child.wait()?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "it didn't finish in time",
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let panicked = || io::Error::other("the thread talking to it panicked");
+    writer.join().map_err(|_| panicked())??;
+    let stdout = reader.join().map_err(|_| panicked())??;
+    let report: serde_json::Value = serde_json::from_slice(&stdout)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let comments = report
+        .get("comments")
+        .and_then(serde_json::Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let findings = comments
+        .iter()
+        .map(|comment| {
+            let number = |key: &str| comment.get(key).and_then(serde_json::Value::as_u64);
+            let code = format!("SC{}", number("code").unwrap_or_default());
+            let message = comment
+                .get("message")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default();
+            let severity = match comment.get("level").and_then(serde_json::Value::as_str) {
+                Some("error") => Severity::Error,
+                Some("warning") => Severity::Warning,
+                _ => Severity::Info,
+            };
+            Finding {
+                offset: offset_of(
+                    script,
+                    number("line").unwrap_or(1),
+                    number("column").unwrap_or(1),
+                ),
+                severity,
+                code: code.clone(),
+                message: format!("{} ({})", message, code),
+            }
+        })
+        .collect();
+    Ok(findings)
+}
@@ -0,0 +1,143 @@
+//! Reading workflow files that aren't quite plain UTF-8 with `\n` line
+//! endings.
+//!
+//! Files edited on Windows often have CRLF line endings or a byte order
+//! mark, and now and then a stray Latin-1 byte. None of these should stop
+//! the file being linted, so they're smoothed over before parsing, with a
+//! warning. Line endings are normalized to `\n`, which leaves line and
+//! column numbers as they are in the original, and put back when fixes are
+//! written out.
+
+use std::path::Path;
+
+use crate::diagnostics::{Diagnostic, Severity};
+
+const BOM: &str = "\u{feff}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEndings {
+    Lf,
+    Crlf,
+    /// Some of each.
+    Mixed,
+}
+
+/// A file's text, ready to parse, and what was done to get it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// The text with the byte order mark taken off and `\n` line endings.
+    pub text: String,
+    pub bom: bool,
+    pub line_endings: LineEndings,
+    /// The 1-based line and column of the first byte that isn't UTF-8,
+    /// which is replaced with U+FFFD in `text`.
+    pub invalid_utf8: Option<(usize, usize)>,
+}
+
+/// Where the first invalid byte of `bytes` is, as a line and column.
+fn position_of(bytes: &[u8], offset: usize) -> (usize, usize) {
+    let before = String::from_utf8_lossy(&bytes[..offset]);
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+impl Decoded {
+    pub fn decode(bytes: &[u8]) -> Decoded {
+        let (text, invalid_utf8) = match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), None),
+            Err(e) => (
+                String::from_utf8_lossy(bytes).into_owned(),
+                Some(position_of(bytes, e.valid_up_to())),
+            ),
+        };
+        let (text, bom) = match text.strip_prefix(BOM) {
+            Some(rest) => (rest.to_string(), true),
+            None => (text, false),
+        };
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        let line_endings = match (crlf, lf) {
+            (0, _) => LineEndings::Lf,
+            (_, 0) => LineEndings::Crlf,
+            _ => LineEndings::Mixed,
+        };
+        let text = match line_endings {
+            LineEndings::Lf => text,
+            _ => text.replace("\r\n", "\n"),
+        };
+        Decoded {
+            text,
+            bom,
+            line_endings,
+            invalid_utf8,
+        }
+    }
+
+    /// Warnings about anything that had to be smoothed over.
+    pub fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut warn = |message: &str, line: usize, column: usize| {
+            diagnostics.push(
+                Diagnostic::new("encoding", Severity::Warning, message)
+                    .with_path(path)
+                    .at(line, column),
+            );
+        };
+        if let Some((line, column)) = self.invalid_utf8 {
+            warn(
+                "the file isn't valid UTF-8, which GitHub requires; invalid bytes were \
+                 read as U+FFFD and fixes won't be written",
+                line,
+                column,
+            );
+        }
+        if self.bom {
+            warn(
+                "the file starts with a byte order mark, which some YAML tools trip over; \
+                 save it as UTF-8 without one",
+                1,
+                1,
+            );
+        }
+        match self.line_endings {
+            LineEndings::Lf => {}
+            LineEndings::Crlf => warn(
+                "the file has CRLF line endings; use LF, or have git convert them with \
+                 `* text=auto` in .gitattributes",
+                1,
+                1,
+            ),
+            LineEndings::Mixed => warn(
+                "the file mixes CRLF and LF line endings; fixes are written with LF",
+                1,
+                1,
+            ),
+        }
+        diagnostics
+    }
+
+    /// Turn text derived from `self.text`, like the result of applying
+    /// fixes, back into a file like the original. `None` if the original
+    /// can't be written back without losing bytes.
+    pub fn encode(&self, text: &str) -> Option<String> {
+        if self.invalid_utf8.is_some() {
+            return None;
+        }
+        let text = match self.line_endings {
+            LineEndings::Crlf => text.replace('\n', "\r\n"),
+            _ => text.to_string(),
+        };
+        Some(if self.bom {
+            format!("{}{}", BOM, text)
+        } else {
+            text
+        })
+    }
+}
@@ -0,0 +1,502 @@
+//! Where things are in a workflow file.
+//!
+//! serde doesn't keep track of positions, so the file is read a second time
+//! with the YAML event parser and every node is recorded under its path from
+//! the root, e.g. `jobs.build.steps[2].uses`.
+
+use std::collections::HashMap;
+
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle};
+
+use crate::diagnostics::{Location, Span};
+
+/// A point in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Mark {
+    /// Byte offset from the start of the file.
+    pub offset: usize,
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+impl Mark {
+    pub fn location(self) -> Location {
+        Location {
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+/// Where a node is. For mapping values the key is recorded too, since
+/// that's usually the more useful thing to point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeSpan {
+    pub key: Option<Mark>,
+    pub value: Mark,
+    /// Just past the end of the value, leaving out the comments and blank
+    /// lines after it.
+    pub end: Mark,
+}
+
+impl NodeSpan {
+    /// The key if there is one, otherwise the value.
+    pub fn start(&self) -> Mark {
+        self.key.unwrap_or(self.value)
+    }
+
+    /// The node from its key, if it has one, to the end of its value.
+    pub fn span(&self) -> Span {
+        let start = self.start();
+        Span {
+            offset: start.offset,
+            len: self.end.offset.saturating_sub(start.offset),
+            end: self.end.location(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpanMap {
+    nodes: HashMap<String, NodeSpan>,
+    /// The text of each unquoted scalar value, before YAML turns it into a
+    /// number or boolean.
+    plain: HashMap<String, String>,
+    /// How each scalar is written: plain, quoted or a block.
+    styles: HashMap<String, TScalarStyle>,
+}
+
+impl SpanMap {
+    /// Record where every node in `source` is. Files that aren't valid YAML
+    /// just get an empty (or partial) map; the parse error is reported
+    /// elsewhere.
+    pub fn parse(source: &str) -> Self {
+        let mut builder = Builder {
+            source,
+            offsets: source.char_indices().map(|(i, _)| i).collect(),
+            line_starts: std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            nodes: HashMap::new(),
+            plain: HashMap::new(),
+            styles: HashMap::new(),
+            stack: Vec::new(),
+            open: None,
+        };
+        let _ = Parser::new(source.chars()).load(&mut builder, false);
+        SpanMap {
+            nodes: builder.nodes,
+            plain: builder.plain,
+            styles: builder.styles,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<NodeSpan> {
+        self.nodes.get(path).copied()
+    }
+
+    /// Every unquoted scalar value as written, with its path and where it
+    /// starts, in file order.
+    pub fn plain_scalars(&self) -> Vec<(&str, &str, Mark)> {
+        let mut scalars: Vec<_> = self
+            .plain
+            .iter()
+            .filter_map(|(path, text)| Some((path.as_str(), text.as_str(), self.get(path)?.value)))
+            .collect();
+        scalars.sort_by_key(|&(_, _, mark)| mark);
+        scalars
+    }
+
+    /// The location of `path`, falling back to its closest recorded
+    /// ancestor so that missing keys can still point at their parent.
+    pub fn locate(&self, path: &str) -> Option<Location> {
+        let mut path = path;
+        loop {
+            if let Some(span) = self.nodes.get(path) {
+                return Some(span.start().location());
+            }
+            path = parent(path)?;
+        }
+    }
+
+    /// What `path` covers, or its closest recorded ancestor, like
+    /// [`locate`](Self::locate).
+    pub fn span(&self, path: &str) -> Option<Span> {
+        let mut path = path;
+        loop {
+            if let Some(span) = self.nodes.get(path) {
+                return Some(span.span());
+            }
+            path = parent(path)?;
+        }
+    }
+
+    /// Whether the string at `path` is a literal block (`|`), whose lines
+    /// are the lines of the source from its start.
+    pub fn is_literal(&self, path: &str) -> bool {
+        self.styles.get(path) == Some(&TScalarStyle::Literal)
+    }
+
+    /// Where byte `offset` of the string at `path`, as YAML reads it, is in
+    /// `source`, following the scalar's quotes, escapes, indentation and
+    /// folded lines. `None` if `path` isn't a scalar.
+    pub fn locate_in(&self, source: &str, path: &str, offset: usize) -> Option<Location> {
+        let style = *self.styles.get(path)?;
+        let start = self.get(path)?.value;
+        Some(scalar_location(source, start, style, offset))
+    }
+}
+
+/// How many bytes of the value the escape starting at `escape`, just after
+/// a `\`, becomes, and how many characters of source it takes up.
+fn escape(escape: &str) -> (usize, usize) {
+    let digits = match escape.chars().next() {
+        Some('x') => 2,
+        Some('u') => 4,
+        Some('U') => 8,
+        // Next line, non-breaking space, line and paragraph separators
+        Some('N') | Some('_') => return (2, 1),
+        Some('L') | Some('P') => return (3, 1),
+        // A line break, which is left out
+        None => return (0, 0),
+        _ => return (1, 1),
+    };
+    let code = escape
+        .get(1..1 + digits)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .and_then(char::from_u32);
+    match code {
+        Some(c) => (c.len_utf8(), 1 + digits),
+        None => (1, 1),
+    }
+}
+
+/// Where byte `offset` of the scalar starting at `start` is. Block scalars
+/// start at their first line of content, quoted ones at their quote.
+fn scalar_location(source: &str, start: Mark, style: TScalarStyle, offset: usize) -> Location {
+    let block = matches!(style, TScalarStyle::Literal | TScalarStyle::Foled);
+    let indent = start.column - 1;
+    let lines: Vec<&str> = source[start.offset..]
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    let blank = |line: &str| line.trim().is_empty();
+    // Lines indented more than the rest of a folded block keep their breaks
+    let more_indented = |line: &str| {
+        style == TScalarStyle::Foled
+            && line.len() > indent
+            && line[indent.min(line.len())..].starts_with([' ', '\t'])
+    };
+    let mut read = 0;
+    let mut location = start.location();
+    for (i, line) in lines.iter().enumerate() {
+        let skipped = if i == 0 {
+            usize::from(matches!(
+                style,
+                TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted
+            ))
+        } else if block {
+            (line.len() - line.trim_start_matches(' ').len()).min(indent)
+        } else {
+            line.len() - line.trim_start().len()
+        };
+        let content = &line[skipped.min(line.len())..];
+        // Folding takes off the spaces a line of a flow scalar ends with
+        let content = if block { content } else { content.trim_end() };
+        let first_column = if i == 0 { start.column } else { 1 };
+        location = Location {
+            line: start.line + i,
+            column: first_column + line[..skipped.min(line.len())].chars().count(),
+        };
+
+        let mut escaped_break = false;
+        let mut chars = content.char_indices();
+        while let Some((at, c)) = chars.next() {
+            if read >= offset {
+                return location;
+            }
+            let (bytes, width) = match (style, c) {
+                (TScalarStyle::DoubleQuoted, '\\') => {
+                    let (bytes, width) = escape(&content[at + 1..]);
+                    escaped_break = width == 0;
+                    (bytes, width + 1)
+                }
+                (TScalarStyle::SingleQuoted, '\'') if content[at + 1..].starts_with('\'') => (1, 2),
+                _ => (c.len_utf8(), 1),
+            };
+            for _ in 1..width {
+                chars.next();
+            }
+            read += bytes;
+            location.column += width;
+        }
+        if read >= offset {
+            return location;
+        }
+
+        // The line break: kept in a literal block, and folded into a space
+        // elsewhere, unless blank lines follow, which are breaks themselves
+        let next = match lines.get(i + 1) {
+            Some(next) => next,
+            None => break,
+        };
+        let folded_away = !blank(line) && blank(next) && !more_indented(line);
+        if style != TScalarStyle::Literal && (folded_away || escaped_break) {
+            continue;
+        }
+        read += 1;
+    }
+    location
+}
+
+/// Join a mapping key onto a path.
+pub fn key_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// Join a sequence index onto a path.
+pub fn index_path(parent: &str, index: usize) -> String {
+    format!("{}[{}]", parent, index)
+}
+
+fn parent(path: &str) -> Option<&str> {
+    if path.is_empty() {
+        return None;
+    }
+    match path.rfind(['.', '[']) {
+        Some(i) => Some(&path[..i]),
+        None => Some(""),
+    }
+}
+
+enum Frame {
+    Mapping {
+        path: String,
+        /// The key waiting for its value, if any.
+        key: Option<(String, Mark)>,
+        /// Where the last value so far ends.
+        end: usize,
+    },
+    Sequence {
+        path: String,
+        index: usize,
+        end: usize,
+    },
+}
+
+impl Frame {
+    fn end(&mut self) -> &mut usize {
+        match self {
+            Frame::Mapping { end, .. } | Frame::Sequence { end, .. } => end,
+        }
+    }
+}
+
+struct Builder<'s> {
+    source: &'s str,
+    /// Byte offset of each character, since the parser counts characters.
+    offsets: Vec<usize>,
+    /// Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+    nodes: HashMap<String, NodeSpan>,
+    plain: HashMap<String, String>,
+    styles: HashMap<String, TScalarStyle>,
+    stack: Vec<Frame>,
+    /// A node whose end is only known once the next event starts.
+    open: Option<String>,
+}
+
+/// Where the quoted scalar at the start of `text` ends, after its closing
+/// quote.
+fn quoted_end(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            c if c == quote => {
+                if quote == '\'' && text[i + 1..].starts_with('\'') {
+                    chars.next();
+                } else {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+impl<'s> Builder<'s> {
+    fn mark(&self, marker: Marker) -> Mark {
+        Mark {
+            offset: self
+                .offsets
+                .get(marker.index())
+                .copied()
+                .unwrap_or(self.source.len()),
+            line: marker.line(),
+            column: marker.col() + 1,
+        }
+    }
+
+    /// The mark at byte `offset`.
+    fn mark_at(&self, offset: usize) -> Mark {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let start = self.line_starts[line - 1];
+        Mark {
+            offset,
+            line,
+            column: self.source[start..offset].chars().count() + 1,
+        }
+    }
+
+    /// Where a node starting at `start` ends, when the next one starts at
+    /// `next`: before the blank lines and comment lines in between.
+    fn end_before(&self, next: usize, start: usize) -> usize {
+        let mut end = next;
+        loop {
+            end = start.max(self.source[..end].trim_end().len());
+            let line = self.source[..end].rfind('\n').map_or(0, |i| i + 1);
+            if line <= start || !self.source[line..end].trim_start().starts_with('#') {
+                return end;
+            }
+            end = line;
+        }
+    }
+
+    /// Where the scalar `value` starting at `start` ends, if that can be
+    /// told without what comes next.
+    fn scalar_end(&self, start: usize, value: &str, style: TScalarStyle) -> Option<usize> {
+        let text = &self.source[start..];
+        match style {
+            TScalarStyle::Plain if text.starts_with(value) => Some(start + value.len()),
+            TScalarStyle::SingleQuoted => quoted_end(text, '\'').map(|end| start + end),
+            TScalarStyle::DoubleQuoted => quoted_end(text, '"').map(|end| start + end),
+            _ => None,
+        }
+    }
+
+    /// Record that the node at `path` ends at byte `end`, which is also as
+    /// far as its parent goes so far.
+    fn close(&mut self, path: &str, end: usize) {
+        let mark = self.mark_at(end);
+        if let Some(span) = self.nodes.get_mut(path) {
+            span.end = mark.max(span.value);
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            let last = frame.end();
+            *last = (*last).max(end);
+        }
+    }
+
+    /// Work out the path of a node starting at `mark`, or return `None` if
+    /// the node is a mapping key.
+    fn start_node(&mut self, mark: Mark, scalar: Option<&str>) -> Option<String> {
+        let (path, key) = match self.stack.last_mut() {
+            None => (String::new(), None),
+            Some(Frame::Sequence { path, index, .. }) => {
+                let path = index_path(path, *index);
+                *index += 1;
+                (path, None)
+            }
+            Some(Frame::Mapping { path, key, .. }) => match key.take() {
+                Some((name, key_mark)) => (key_path(path, &name), Some(key_mark)),
+                None => {
+                    // Complex keys are rare enough to not bother naming
+                    *key = Some((scalar.unwrap_or("?").to_string(), mark));
+                    // Block mappings are only reported as started once the
+                    // parser is past their first key
+                    if let Some(span) = self.nodes.get_mut(path.as_str()) {
+                        if span.value.offset > mark.offset {
+                            span.value = mark;
+                        }
+                    }
+                    return None;
+                }
+            },
+        };
+        self.nodes.insert(
+            path.clone(),
+            NodeSpan {
+                key,
+                value: mark,
+                end: mark,
+            },
+        );
+        Some(path)
+    }
+}
+
+impl<'s> MarkedEventReceiver for Builder<'s> {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let mark = self.mark(marker);
+        if let Some(path) = self.open.take() {
+            let start = self
+                .nodes
+                .get(&path)
+                .map_or(mark.offset, |span| span.value.offset);
+            let end = self.end_before(mark.offset, start);
+            self.close(&path, end);
+        }
+        match event {
+            Event::Scalar(value, style, ..) => {
+                let path = match self.start_node(mark, Some(&value)) {
+                    Some(path) => path,
+                    None => return,
+                };
+                match self.scalar_end(mark.offset, &value, style) {
+                    Some(end) => self.close(&path, end),
+                    None => self.open = Some(path.clone()),
+                }
+                self.styles.insert(path.clone(), style);
+                if style == TScalarStyle::Plain {
+                    self.plain.insert(path, value);
+                }
+            }
+            Event::Alias(_) => {
+                self.open = self.start_node(mark, None);
+            }
+            Event::MappingStart(_) => {
+                let path = self.start_node(mark, None).unwrap_or_default();
+                let end = mark.offset;
+                self.stack.push(Frame::Mapping {
+                    path,
+                    key: None,
+                    end,
+                });
+            }
+            Event::SequenceStart(_) => {
+                let path = self.start_node(mark, None).unwrap_or_default();
+                let end = mark.offset;
+                self.stack.push(Frame::Sequence {
+                    path,
+                    index: 0,
+                    end,
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                if let Some(mut frame) = self.stack.pop() {
+                    // Flow collections end at their bracket
+                    let end = match self.source[mark.offset..].chars().next() {
+                        Some('}') | Some(']') => mark.offset + 1,
+                        _ => *frame.end(),
+                    };
+                    let path = match frame {
+                        Frame::Mapping { path, .. } | Frame::Sequence { path, .. } => path,
+                    };
+                    self.close(&path, end);
+                }
+            }
+            _ => {}
+        }
+    }
+}
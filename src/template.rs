@@ -0,0 +1,244 @@
+//! Workflow templates, the starter workflows an organization publishes from
+//! `.github/workflow-templates/` in its `.github` repository. Each template
+//! needs a `<name>.properties.json` next to it for GitHub to offer it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::diagnostics::{Diagnostic, Location, Severity};
+
+pub const TEMPLATES_DIR: &str = ".github/workflow-templates";
+
+const RULE: &str = "template-properties";
+
+/// The templates in `dir`, in name order.
+pub fn templates(dir: &Path) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|ext| ext == "yml" || ext == "yaml")
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    found.sort();
+    found
+}
+
+/// Where the properties file for `template` lives.
+pub fn properties_path(template: &Path) -> PathBuf {
+    let stem = template
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    template.with_file_name(format!("{}.properties.json", stem))
+}
+
+/// Properties files in `dir` with no template next to them.
+pub fn orphaned_properties(dir: &Path) -> Vec<Diagnostic> {
+    let mut orphans: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.to_string_lossy().ends_with(".properties.json")
+                    && !templates(dir).iter().any(|t| properties_path(t) == *path)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    orphans.sort();
+    orphans
+        .into_iter()
+        .map(|path| {
+            Diagnostic::new(
+                RULE,
+                Severity::Warning,
+                "there's no workflow template with this name next to it",
+            )
+            .with_path(path)
+        })
+        .collect()
+}
+
+/// A template with no properties file, which GitHub won't list.
+pub fn missing_properties(template: &Path) -> Option<Diagnostic> {
+    let properties = properties_path(template);
+    if properties.is_file() {
+        return None;
+    }
+    let name = properties
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Some(
+        Diagnostic::new(
+            RULE,
+            Severity::Warning,
+            format!("template has no `{}`, so GitHub won't offer it", name),
+        )
+        .with_path(template)
+        .at(1, 1),
+    )
+}
+
+/// The line and column of the first `"needle"` string in `source`.
+fn locate(source: &str, needle: &str) -> Option<Location> {
+    let quoted = serde_json::to_string(needle).ok()?;
+    let offset = source.find(&quoted)?;
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    Some(Location { line, column })
+}
+
+/// What's wrong with a `filePatterns` entry, which is a regular expression
+/// matched against the names of files in the repository root.
+fn check_pattern(pattern: &str) -> Option<String> {
+    if pattern.is_empty() {
+        return Some("pattern is empty, so it matches every repository".to_string());
+    }
+    if let Some(glob) = pattern.strip_prefix('*') {
+        return Some(format!(
+            "`{}` looks like a glob, but file patterns are regular expressions; \
+             try `.*{}$`",
+            pattern,
+            glob.replace('.', "\\.")
+        ));
+    }
+    if pattern.starts_with(['+', '?']) {
+        return Some(format!("`{}` starts with a repetition of nothing", pattern));
+    }
+
+    let mut depth = 0i32;
+    let mut chars = pattern.chars();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next().is_none() => {
+                return Some(format!("`{}` ends with a lone `\\`", pattern));
+            }
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => {
+                depth -= 1;
+                if depth < 0 {
+                    return Some(format!("`{}` has an unopened `)`", pattern));
+                }
+            }
+            _ => {}
+        }
+    }
+    if in_class {
+        Some(format!("`{}` has an unclosed `[`", pattern))
+    } else if depth > 0 {
+        Some(format!("`{}` has an unclosed `(`", pattern))
+    } else {
+        None
+    }
+}
+
+/// Check a template's properties file: valid JSON with a `name` and
+/// `description`, string lists where lists are expected, an icon that
+/// exists, and `filePatterns` that are usable regular expressions.
+pub fn check_properties(path: &Path, source: &str) -> Vec<Diagnostic> {
+    let error = |severity, message: String, key: &str| {
+        Diagnostic::new(RULE, severity, message)
+            .with_path(path)
+            .with_location(locate(source, key))
+    };
+
+    let value: Value = match serde_json::from_str(source) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![
+                Diagnostic::new(RULE, Severity::Error, format!("invalid JSON: {}", e))
+                    .with_path(path)
+                    .at(e.line(), e.column()),
+            ]
+        }
+    };
+    let properties = match value.as_object() {
+        Some(properties) => properties,
+        None => {
+            return vec![Diagnostic::new(
+                RULE,
+                Severity::Error,
+                "properties should be a JSON object",
+            )
+            .with_path(path)
+            .at(1, 1)]
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    for key in &["name", "description"] {
+        match properties.get(*key) {
+            Some(Value::String(s)) if !s.trim().is_empty() => {}
+            Some(Value::String(_)) => {
+                diagnostics.push(error(Severity::Error, format!("`{}` is empty", key), key))
+            }
+            Some(_) => diagnostics.push(error(
+                Severity::Error,
+                format!("`{}` should be a string", key),
+                key,
+            )),
+            None => diagnostics.push(
+                Diagnostic::new(RULE, Severity::Error, format!("`{}` is required", key))
+                    .with_path(path)
+                    .at(1, 1),
+            ),
+        }
+    }
+
+    for key in &["categories", "filePatterns"] {
+        match properties.get(*key) {
+            None => {}
+            Some(Value::Array(items)) if items.iter().all(Value::is_string) => {}
+            Some(_) => diagnostics.push(error(
+                Severity::Error,
+                format!("`{}` should be a list of strings", key),
+                key,
+            )),
+        }
+    }
+
+    match properties.get("iconName") {
+        None => {}
+        Some(Value::String(icon)) => {
+            let svg = path.with_file_name(format!("{}.svg", icon));
+            let builtin = icon.starts_with("octicon ");
+            if !builtin && !svg.is_file() {
+                diagnostics.push(error(
+                    Severity::Warning,
+                    format!(
+                        "icon `{}` needs a `{}.svg` next to the template, \
+                         or use `octicon <name>` for a built-in icon",
+                        icon, icon
+                    ),
+                    "iconName",
+                ));
+            }
+        }
+        Some(_) => diagnostics.push(error(
+            Severity::Error,
+            "`iconName` should be a string".to_string(),
+            "iconName",
+        )),
+    }
+
+    if let Some(Value::Array(patterns)) = properties.get("filePatterns") {
+        for pattern in patterns.iter().filter_map(Value::as_str) {
+            if let Some(message) = check_pattern(pattern) {
+                diagnostics.push(error(Severity::Warning, message, pattern));
+            }
+        }
+    }
+    diagnostics
+}
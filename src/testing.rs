@@ -0,0 +1,448 @@
+//! Random but repeatable workflows, for fuzzing what's built on the model:
+//! that a workflow survives a round trip, that a fix leaves nothing for a
+//! second run to fix, or that a rule doesn't panic.
+//!
+//! Every workflow comes from a seed, and the same seed always gives the same
+//! workflow, so a failure found by [`check`] can be replayed from the seed
+//! it reports. The workflows are ones the model parses and GitHub would
+//! accept: jobs only need jobs before them, steps either use an action or
+//! run a script, matrices have values, and activity types are ones their
+//! events have.
+
+use std::fmt;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::workflow::Workflow;
+
+/// How big generated workflows can get.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sizes {
+    pub max_jobs: usize,
+    pub max_steps: usize,
+    /// Environment variables in each `env`.
+    pub max_env: usize,
+    pub max_matrix_axes: usize,
+    pub max_matrix_values: usize,
+}
+
+impl Default for Sizes {
+    fn default() -> Self {
+        Sizes {
+            max_jobs: 6,
+            max_steps: 5,
+            max_env: 3,
+            max_matrix_axes: 3,
+            max_matrix_values: 3,
+        }
+    }
+}
+
+const EVENTS: &[&str] = &[
+    "push",
+    "pull_request",
+    "pull_request_target",
+    "workflow_dispatch",
+    "release",
+    "issue_comment",
+    "gollum",
+    "page_build",
+    "status",
+    "watch",
+];
+const BRANCHES: &[&str] = &["main", "develop", "release/**", "!release/old"];
+const PULL_REQUEST_TYPES: &[&str] = &["opened", "synchronize", "reopened", "labeled", "closed"];
+const PROJECT_CARD_TYPES: &[&str] = &["created", "moved", "converted", "edited", "deleted"];
+const REGISTRY_PACKAGE_TYPES: &[&str] = &["published", "updated"];
+const CRONS: &[&str] = &["0 0 * * *", "*/15 * * * *", "30 5 * * 1-5"];
+const LABELS: &[&str] = &[
+    "ubuntu-latest",
+    "ubuntu-22.04",
+    "windows-latest",
+    "macos-latest",
+    "self-hosted",
+];
+const CALLED_WORKFLOWS: &[&str] = &[
+    "./.github/workflows/build.yml",
+    "octo-org/ci/.github/workflows/release.yml@v1",
+];
+const ACTIONS: &[(&str, &[&str])] = &[
+    ("actions/checkout@v4", &["fetch-depth", "ref"]),
+    ("actions/setup-node@v4", &["node-version", "cache"]),
+    ("actions/cache@v4", &["path", "key"]),
+    ("actions/upload-artifact@v4", &["name", "path"]),
+    ("./.github/actions/build", &["target"]),
+];
+const SCRIPTS: &[&str] = &[
+    "npm ci",
+    "cargo test --workspace",
+    "echo \"$GITHUB_SHA\"",
+    "make build\nmake check\n",
+];
+const SHELLS: &[&str] = &["bash", "pwsh", "python"];
+const AXES: &[&str] = &["os", "node", "rust", "target", "shard"];
+const CONDITIONS: &[&str] = &[
+    "github.event_name == 'push'",
+    "${{ github.ref == 'refs/heads/main' }}",
+    "always()",
+    "success() && !cancelled()",
+];
+
+/// SplitMix64, which is small and good enough to vary workflows with.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to, but not including, `n`.
+    fn below(&mut self, n: usize) -> usize {
+        match n {
+            0 => 0,
+            n => (self.next() % n as u64) as usize,
+        }
+    }
+
+    /// A number from `low` up to and including `high`.
+    fn between(&mut self, low: usize, high: usize) -> usize {
+        low + self.below(high.saturating_sub(low) + 1)
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// Some of `items`, in their order, at least one of them.
+    fn some<'a>(&mut self, items: &[&'a str]) -> Vec<&'a str> {
+        let picked: Vec<&str> = items.iter().copied().filter(|_| self.chance(50)).collect();
+        match picked.is_empty() {
+            true => vec![self.pick(items)],
+            false => picked,
+        }
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+fn strings(items: &[&str]) -> Value {
+    Value::Sequence(items.iter().map(|s| string(s)).collect())
+}
+
+fn mapping(entries: Vec<(&str, Value)>) -> Value {
+    let mut map = Mapping::new();
+    for (key, value) in entries {
+        map.insert(string(key), value);
+    }
+    Value::Mapping(map)
+}
+
+/// Makes workflows from a seed.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    rng: Rng,
+    sizes: Sizes,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            rng: Rng(seed),
+            sizes: Sizes::default(),
+        }
+    }
+
+    pub fn with_sizes(mut self, sizes: Sizes) -> Generator {
+        self.sizes = sizes;
+        self
+    }
+
+    fn env(&mut self, prefix: &str) -> Value {
+        let count = self.rng.between(1, self.sizes.max_env.max(1));
+        let mut env = Mapping::new();
+        for i in 0..count {
+            let value = match self.rng.below(3) {
+                0 => format!("value-{}", self.rng.below(100)),
+                1 => "${{ github.sha }}".to_string(),
+                _ => self.rng.below(10).to_string(),
+            };
+            env.insert(string(&format!("{}_{}", prefix, i)), Value::String(value));
+        }
+        Value::Mapping(env)
+    }
+
+    fn event(&mut self, event: &str) -> Value {
+        match event {
+            "push" => mapping(vec![("branches", strings(&self.rng.some(BRANCHES)))]),
+            "pull_request" | "pull_request_target" => {
+                mapping(vec![("types", strings(&self.rng.some(PULL_REQUEST_TYPES)))])
+            }
+            "watch" => mapping(vec![("types", strings(&["started"]))]),
+            "project_card" => mapping(vec![("types", strings(&self.rng.some(PROJECT_CARD_TYPES)))]),
+            "registry_package" => mapping(vec![(
+                "types",
+                strings(&self.rng.some(REGISTRY_PACKAGE_TYPES)),
+            )]),
+            _ => Value::Null,
+        }
+    }
+
+    fn on(&mut self) -> Value {
+        match self.rng.below(4) {
+            0 => string(self.rng.pick(EVENTS)),
+            1 => strings(&self.rng.some(EVENTS)),
+            _ => {
+                let mut events = Mapping::new();
+                let mut names = self.rng.some(EVENTS);
+                if self.rng.chance(30) {
+                    names.push("project_card");
+                }
+                if self.rng.chance(30) {
+                    names.push("registry_package");
+                }
+                for name in names {
+                    let settings = self.event(name);
+                    events.insert(string(name), settings);
+                }
+                if self.rng.chance(30) {
+                    let cron = mapping(vec![("cron", string(self.rng.pick(CRONS)))]);
+                    events.insert(string("schedule"), Value::Sequence(vec![cron]));
+                }
+                Value::Mapping(events)
+            }
+        }
+    }
+
+    fn step(&mut self, index: usize) -> Value {
+        // A step runs an action or a script, never both
+        let (mut entries, inputs) = match self.rng.chance(40) {
+            true => {
+                let mut entries = vec![("run", string(self.rng.pick(SCRIPTS)))];
+                if self.rng.chance(20) {
+                    entries.push(("shell", string(self.rng.pick(SHELLS))));
+                }
+                (entries, &[][..])
+            }
+            false => {
+                let (uses, inputs) = ACTIONS[self.rng.below(ACTIONS.len())];
+                (vec![("uses", string(uses))], inputs)
+            }
+        };
+        if self.rng.chance(50) {
+            entries.push(("name", Value::String(format!("step {}", index))));
+        }
+        if self.rng.chance(30) {
+            entries.push(("id", Value::String(format!("step{}", index))));
+        }
+        if self.rng.chance(20) {
+            entries.push(("if", string(self.rng.pick(CONDITIONS))));
+        }
+        if !inputs.is_empty() && self.rng.chance(60) {
+            let mut with = Mapping::new();
+            for input in self.rng.some(inputs) {
+                with.insert(string(input), Value::String(format!("{}-{}", input, index)));
+            }
+            entries.push(("with", Value::Mapping(with)));
+        }
+        if self.rng.chance(20) {
+            entries.push(("env", self.env("STEP")));
+        }
+        if self.rng.chance(10) {
+            entries.push(("continue-on-error", Value::Bool(true)));
+        }
+        mapping(entries)
+    }
+
+    fn strategy(&mut self) -> Value {
+        let mut matrix = Mapping::new();
+        let axes = self.rng.between(1, self.sizes.max_matrix_axes.max(1));
+        for axis in AXES.iter().take(axes) {
+            let count = self.rng.between(1, self.sizes.max_matrix_values.max(1));
+            let values = (0..count)
+                .map(|i| Value::String(format!("{}-{}", axis, i)))
+                .collect();
+            matrix.insert(string(axis), Value::Sequence(values));
+        }
+        let mut entries = vec![("matrix", Value::Mapping(matrix))];
+        if self.rng.chance(50) {
+            entries.push(("fail-fast", Value::Bool(self.rng.chance(50))));
+        }
+        mapping(entries)
+    }
+
+    /// A job that calls a reusable workflow, with inputs and secrets.
+    fn call(&mut self) -> Vec<(&'static str, Value)> {
+        let mut entries = vec![("uses", string(self.rng.pick(CALLED_WORKFLOWS)))];
+        if self.rng.chance(60) {
+            entries.push((
+                "with",
+                mapping(vec![
+                    ("version", string("1.2.3")),
+                    ("debug", Value::Bool(self.rng.chance(50))),
+                ]),
+            ));
+        }
+        if self.rng.chance(50) {
+            let secrets = match self.rng.chance(50) {
+                true => string("inherit"),
+                false => mapping(vec![("token", string("${{ secrets.TOKEN }}"))]),
+            };
+            entries.push(("secrets", secrets));
+        }
+        entries
+    }
+
+    fn job(&mut self, index: usize) -> Value {
+        let call = self.rng.chance(10);
+        let mut entries = match call {
+            true => self.call(),
+            false => {
+                let runs_on = match self.rng.below(10) {
+                    0..=6 => string(self.rng.pick(LABELS)),
+                    7 | 8 => strings(&self.rng.some(LABELS)),
+                    _ => mapping(vec![
+                        ("group", string("large-runners")),
+                        ("labels", strings(&self.rng.some(LABELS))),
+                    ]),
+                };
+                vec![("runs-on", runs_on)]
+            }
+        };
+        if self.rng.chance(50) {
+            entries.push(("name", Value::String(format!("Job {}", index))));
+        }
+        let needs: Vec<Value> = (0..index)
+            .filter(|_| self.rng.chance(30))
+            .map(|i| Value::String(format!("job{}", i)))
+            .collect();
+        if !needs.is_empty() {
+            entries.push(("needs", Value::Sequence(needs)));
+        }
+        if self.rng.chance(20) {
+            entries.push(("if", string(self.rng.pick(CONDITIONS))));
+        }
+        if self.rng.chance(30) {
+            entries.push(("strategy", self.strategy()));
+        }
+        if call {
+            return mapping(entries);
+        }
+        if self.rng.chance(30) {
+            let minutes = self.rng.between(5, 120) as u64;
+            entries.push(("timeout-minutes", Value::Number(minutes.into())));
+        }
+        if self.rng.chance(30) {
+            entries.push(("env", self.env("JOB")));
+        }
+        let steps = self.rng.between(1, self.sizes.max_steps.max(1));
+        entries.push((
+            "steps",
+            Value::Sequence((0..steps).map(|i| self.step(i)).collect()),
+        ));
+        mapping(entries)
+    }
+
+    /// The next workflow, as plain YAML.
+    pub fn document(&mut self) -> Value {
+        let mut entries = Vec::new();
+        if self.rng.chance(70) {
+            entries.push(("name", string("generated")));
+        }
+        entries.push(("on", self.on()));
+        if self.rng.chance(40) {
+            let permissions = match self.rng.below(3) {
+                0 => string("read-all"),
+                1 => Value::Mapping(Mapping::new()),
+                _ => mapping(vec![("contents", string("read"))]),
+            };
+            entries.push(("permissions", permissions));
+        }
+        if self.rng.chance(30) {
+            let concurrency = match self.rng.below(2) {
+                0 => string("ci"),
+                _ => mapping(vec![
+                    ("group", string("${{ github.workflow }}-${{ github.ref }}")),
+                    ("cancel-in-progress", Value::Bool(true)),
+                ]),
+            };
+            entries.push(("concurrency", concurrency));
+        }
+        if self.rng.chance(30) {
+            entries.push(("env", self.env("WORKFLOW")));
+        }
+        let count = self.rng.between(1, self.sizes.max_jobs.max(1));
+        let mut jobs = Mapping::new();
+        for i in 0..count {
+            let job = self.job(i);
+            jobs.insert(Value::String(format!("job{}", i)), job);
+        }
+        entries.push(("jobs", Value::Mapping(jobs)));
+        mapping(entries)
+    }
+
+    /// The next workflow, as the text of a workflow file.
+    pub fn source(&mut self) -> String {
+        serde_yaml::to_string(&self.document()).expect("generated YAML to serialize")
+    }
+
+    /// The next workflow, parsed.
+    pub fn workflow(&mut self) -> Workflow {
+        Workflow::parse_located(&self.source()).expect("generated workflows to parse")
+    }
+}
+
+/// A workflow a property didn't hold for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// The seed that makes the workflow again, with `Generator::new` and
+    /// the same sizes.
+    pub seed: u64,
+    pub source: String,
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (seed {}), for this workflow:\n{}",
+            self.message, self.seed, self.source
+        )
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Check `property` against `cases` workflows made from `seed`, stopping at
+/// the first it fails for. The property gets each workflow's text and the
+/// workflow parsed from it.
+pub fn check<F>(cases: usize, seed: u64, sizes: Sizes, mut property: F) -> Result<(), Failure>
+where
+    F: FnMut(&str, &Workflow) -> Result<(), String>,
+{
+    let mut seeds = Rng(seed);
+    for _ in 0..cases {
+        let seed = seeds.next();
+        let source = Generator::new(seed).with_sizes(sizes).source();
+        let workflow = Workflow::parse_located(&source).expect("generated workflows to parse");
+        if let Err(message) = property(&source, &workflow) {
+            return Err(Failure {
+                seed,
+                source,
+                message,
+            });
+        }
+    }
+    Ok(())
+}
@@ -1,33 +1,55 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_yaml::{Error, Value};
 use void::Void;
 
-use crate::custom_types::OneOrMany;
+use crate::action_ref::{ActionRef, ActionRefError};
+use crate::cron::{Cron, CronError};
+use crate::custom_types::{
+    one_or_many, one_or_many_checked, optional_scalar_map, optional_string_or_struct, scalar_list,
+    scalar_map, OneOrMany,
+};
+use crate::glob::Glob;
+use crate::runner::Runner;
+use crate::spans::{index_path, key_path, NodeSpan, SpanMap};
 
 /// You can schedule a workflow to run at specific UTC times using POSIX cron
 /// syntax. Scheduled workflows run on the latest commit on the default or base
 /// branch. The shortest interval you can run scheduled workflows is once every 5
 /// minutes.
-type Schedule = Vec<CronSchedule>;
+pub type Schedule = Vec<CronSchedule>;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct CronSchedule {
-    // TODO: validate cron string
-    cron: String,
+    /// When to run, in POSIX cron syntax; see [`Cron`].
+    pub cron: String,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-// TODO: enumerate these, starting with the common ones
-/// Event types
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged, rename_all = "kebab-case")]
-enum Event {
-    Push(Value),
-    PullRequest(Value),
-    WorkflowDispatch(Value),
+impl CronSchedule {
+    /// Parse `cron` into the times it fires at.
+    pub fn parse(&self) -> Result<Cron, CronError> {
+        self.cron.parse()
+    }
+}
+
+/// The settings of an event in `on`, by which event it is. Events only
+/// named, like `on: push`, have none.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Event {
+    Push(Option<Box<PushSettings>>),
+    PullRequest(Option<Box<PullRequestSettings>>),
+    WorkflowDispatch(Option<Box<WorkflowDispatchSettings>>),
+    WorkflowCall(Option<Box<WorkflowCallSettings>>),
     RepositoryDispatch(Value),
     CheckRun(Value),
     CheckSuite(Value),
@@ -36,40 +58,559 @@ enum Event {
     Deployment(Value),
     DeploymentStatus(Value),
     Fork(Value),
-    Gollum(Value),
+    Gollum(Option<NoSettings>),
     IssueComment(Value),
     Issues(Value),
     Label(Value),
     Milestone(Value),
-    PageBuild(Value),
+    PageBuild(Option<NoSettings>),
     Project(Value),
-    ProjectCard(Value),
+    ProjectCard(Option<Activities<ProjectCardActivity>>),
     ProjectColumn(Value),
     Public(Value),
     PullRequestReview(Value),
     PullRequestReviewComment(Value),
-    PullRequestTarget(Value),
-    RegistryPackage(Value),
+    PullRequestTarget(Option<Box<PullRequestSettings>>),
+    RegistryPackage(Option<Activities<RegistryPackageActivity>>),
     Release(Value),
-    Status(Value),
-    Watch(Value),
+    Status(Option<NoSettings>),
+    Watch(Option<Activities<WatchActivity>>),
     WorkflowRun(Value),
+    Schedule(Schedule),
+    /// An event GitHub doesn't have.
+    Other(Value),
+    /// Settings that don't parse as the event's.
+    Invalid(InvalidSettings),
+}
+
+/// Settings that don't parse as their event's, and why.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct InvalidSettings {
+    pub settings: Value,
+    #[serde(skip)]
+    pub error: String,
+}
+
+/// The events in `on`, by name, in the order they're written.
+pub type Events = IndexMap<String, Event>;
+
+/// Read `on` as a name, a list of names or a map of names to settings,
+/// parsing each event's settings as that event's.
+fn events<'de, D>(deserializer: D) -> Result<Events, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error as _;
+    let named: Vec<(String, Value)> = match Value::deserialize(deserializer)? {
+        Value::String(name) => vec![(name, Value::Null)],
+        Value::Sequence(names) => names
+            .into_iter()
+            .map(|name| match name {
+                Value::String(name) => Ok((name, Value::Null)),
+                _ => Err(D::Error::custom("expected the name of an event")),
+            })
+            .collect::<Result<_, _>>()?,
+        Value::Mapping(events) => events
+            .into_iter()
+            .map(|(name, settings)| match name {
+                Value::String(name) => Ok((name, settings)),
+                _ => Err(D::Error::custom("expected the name of an event")),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => {
+            return Err(D::Error::custom(
+                "expected an event, a list of events or a map of events to their settings",
+            ))
+        }
+    };
+    Ok(named
+        .into_iter()
+        .map(|(name, settings)| {
+            let event = match Event::from_settings(&name, &settings) {
+                Some(Ok(event)) => event,
+                Some(Err(error)) => Event::Invalid(InvalidSettings {
+                    settings,
+                    error: error.to_string(),
+                }),
+                None => Event::Other(settings),
+            };
+            (name, event)
+        })
+        .collect())
+}
+
+impl Event {
+    /// Parse `settings`, what `on.<name>` is set to, as the event `name`;
+    /// `None` for events GitHub doesn't have.
+    pub fn from_settings(name: &str, settings: &Value) -> Option<Result<Event, Error>> {
+        fn parse<T: DeserializeOwned>(settings: &Value) -> Result<Option<T>, Error> {
+            serde_yaml::from_value(settings.clone())
+        }
+        let raw = |event: fn(Value) -> Event| Ok(event(settings.clone()));
+        Some(match name {
+            "push" => parse(settings).map(Event::Push),
+            "pull_request" => parse(settings).map(Event::PullRequest),
+            "pull_request_target" => parse(settings).map(Event::PullRequestTarget),
+            "workflow_dispatch" => parse(settings).map(Event::WorkflowDispatch),
+            "workflow_call" => parse(settings).map(Event::WorkflowCall),
+            "gollum" => parse(settings).map(Event::Gollum),
+            "page_build" => parse(settings).map(Event::PageBuild),
+            "project_card" => parse(settings).map(Event::ProjectCard),
+            "registry_package" => parse(settings).map(Event::RegistryPackage),
+            "status" => parse(settings).map(Event::Status),
+            "watch" => parse(settings).map(Event::Watch),
+            "schedule" => parse(settings).map(|s| Event::Schedule(s.unwrap_or_default())),
+            "repository_dispatch" => raw(Event::RepositoryDispatch),
+            "check_run" => raw(Event::CheckRun),
+            "check_suite" => raw(Event::CheckSuite),
+            "create" => raw(Event::Create),
+            "delete" => raw(Event::Delete),
+            "deployment" => raw(Event::Deployment),
+            "deployment_status" => raw(Event::DeploymentStatus),
+            "fork" => raw(Event::Fork),
+            "issue_comment" => raw(Event::IssueComment),
+            "issues" => raw(Event::Issues),
+            "label" => raw(Event::Label),
+            "milestone" => raw(Event::Milestone),
+            "project" => raw(Event::Project),
+            "project_column" => raw(Event::ProjectColumn),
+            "public" => raw(Event::Public),
+            "pull_request_review" => raw(Event::PullRequestReview),
+            "pull_request_review_comment" => raw(Event::PullRequestReviewComment),
+            "release" => raw(Event::Release),
+            "workflow_run" => raw(Event::WorkflowRun),
+            _ => return None,
+        })
+    }
+
+    /// The keys of a modeled event's settings that aren't part of them,
+    /// and the keys that are.
+    pub fn extra(&self) -> Option<(&Extra, &'static [&'static str])> {
+        const NONE: &[&str] = &[];
+        const TYPES: &[&str] = &["types"];
+        const PUSH: &[&str] = &[
+            "branches",
+            "branches-ignore",
+            "tags",
+            "tags-ignore",
+            "paths",
+            "paths-ignore",
+        ];
+        const PULL_REQUEST: &[&str] = &[
+            "types",
+            "branches",
+            "branches-ignore",
+            "paths",
+            "paths-ignore",
+        ];
+        const DISPATCH: &[&str] = &["inputs"];
+        const CALL: &[&str] = &["inputs", "secrets", "outputs"];
+        match self {
+            Event::Push(Some(settings)) => Some((&settings.extra, PUSH)),
+            Event::PullRequest(Some(settings)) | Event::PullRequestTarget(Some(settings)) => {
+                Some((&settings.extra, PULL_REQUEST))
+            }
+            Event::WorkflowDispatch(Some(settings)) => Some((&settings.extra, DISPATCH)),
+            Event::WorkflowCall(Some(settings)) => Some((&settings.extra, CALL)),
+            Event::Gollum(Some(settings))
+            | Event::PageBuild(Some(settings))
+            | Event::Status(Some(settings)) => Some((&settings.extra, NONE)),
+            Event::ProjectCard(Some(settings)) => Some((&settings.extra, TYPES)),
+            Event::RegistryPackage(Some(settings)) => Some((&settings.extra, TYPES)),
+            Event::Watch(Some(settings)) => Some((&settings.extra, TYPES)),
+            _ => None,
+        }
+    }
+
+    /// What's wrong with settings that parse but that GitHub still won't
+    /// accept, like both `branches` and `branches-ignore`, or a filter
+    /// that isn't a valid glob.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match self {
+            Event::Invalid(invalid) => problems.push(invalid.error.clone()),
+            Event::Push(Some(settings)) => {
+                let filters = [
+                    ("branches", &settings.branches, &settings.branches_ignore),
+                    ("tags", &settings.tags, &settings.tags_ignore),
+                    ("paths", &settings.paths, &settings.paths_ignore),
+                ];
+                filter_problems(&filters, &mut problems);
+            }
+            Event::PullRequest(Some(settings)) | Event::PullRequestTarget(Some(settings)) => {
+                let filters = [
+                    ("branches", &settings.branches, &settings.branches_ignore),
+                    ("paths", &settings.paths, &settings.paths_ignore),
+                ];
+                filter_problems(&filters, &mut problems);
+            }
+            Event::WorkflowDispatch(Some(settings)) => {
+                for (name, input) in &settings.inputs {
+                    if input.input_type != Some(DispatchInputType::Choice) {
+                        continue;
+                    }
+                    if input.options.is_empty() {
+                        problems.push(format!("the choice input `{}` has no `options`", name));
+                    }
+                    if let Some(default) = input.default.as_ref().and_then(Value::as_str) {
+                        if !input.options.iter().any(|option| option == default) {
+                            problems.push(format!(
+                                "the default of the choice input `{}`, `{}`, isn't one of its \
+                                 `options`",
+                                name, default
+                            ));
+                        }
+                    }
+                }
+            }
+            Event::WorkflowCall(Some(settings)) => {
+                for (name, input) in &settings.inputs {
+                    if let Some(default) = &input.default {
+                        if !input.input_type.accepts(default) {
+                            problems.push(format!(
+                                "the default of the input `{}` isn't a {}",
+                                name,
+                                input.input_type.as_str()
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        problems
+    }
 }
 
-/// Trigger types for a workflow.
+/// Check each of an event's filters, named with the list it includes by
+/// and the list it excludes by.
+fn filter_problems(filters: &[(&str, &Vec<String>, &Vec<String>)], problems: &mut Vec<String>) {
+    for &(name, include, exclude) in filters {
+        if !include.is_empty() && !exclude.is_empty() {
+            problems.push(format!(
+                "`{0}` and `{0}-ignore` can't both be used; use `!` patterns in `{0}` to \
+                 leave some out",
+                name
+            ));
+        }
+        for pattern in include.iter().chain(exclude) {
+            if let Err(error) = Glob::parse(pattern) {
+                problems.push(format!(
+                    "`{}` in `{}` isn't a valid pattern: {}",
+                    pattern, name, error
+                ));
+            }
+        }
+    }
+}
+
+/// The settings of an event that has none, like `gollum`, which runs the
+/// workflow for every activity of its kind.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NoSettings {
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The settings of an event that can be limited to some of its activities.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: DeserializeOwned"))]
+pub struct Activities<T> {
+    /// The activities to run the workflow for; all of them if empty.
+    #[serde(default, deserialize_with = "one_or_many_checked")]
+    pub types: Vec<T>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The activities of the `project_card` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectCardActivity {
+    Created,
+    Moved,
+    Converted,
+    Edited,
+    Deleted,
+}
+
+/// The activities of the `registry_package` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryPackageActivity {
+    Published,
+    Updated,
+}
+
+/// The activities of the `watch` event, which only has starring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchActivity {
+    Started,
+}
+
+/// The settings of `push`, which filter the branches, tags and files a push
+/// has to touch to run the workflow. Each filter can be given as the
+/// patterns to run for or, with `-ignore`, the ones not to, but not both.
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-#[serde(untagged)]
-enum Trigger {
-    Events(OneOrMany<Event>),
-    Schedule(Schedule),
+pub struct PushSettings {
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub branches: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub branches_ignore: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags_ignore: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub paths: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub paths_ignore: Vec<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The settings of `pull_request` and `pull_request_target`: the activities
+/// to run for, and filters on the base branch and the files changed, like
+/// [`PushSettings`]'.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PullRequestSettings {
+    /// The activities to run the workflow for; `opened`, `synchronize` and
+    /// `reopened` if empty.
+    #[serde(
+        default,
+        deserialize_with = "one_or_many_checked",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub types: Vec<PullRequestActivity>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub branches: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub branches_ignore: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub paths: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub paths_ignore: Vec<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The activities of the `pull_request` and `pull_request_target` events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullRequestActivity {
+    Assigned,
+    Unassigned,
+    Labeled,
+    Unlabeled,
+    Opened,
+    Edited,
+    Closed,
+    Reopened,
+    Synchronize,
+    ConvertedToDraft,
+    ReadyForReview,
+    Locked,
+    Unlocked,
+    ReviewRequested,
+    ReviewRequestRemoved,
+    AutoMergeEnabled,
+    AutoMergeDisabled,
+    Milestoned,
+    Demilestoned,
+    Enqueued,
+    Dequeued,
+}
+
+/// The settings of `workflow_dispatch`: the inputs asked for when the
+/// workflow is run by hand.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkflowDispatchSettings {
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub inputs: IndexMap<String, DispatchInput>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// An input of `workflow_dispatch`, available as `inputs.<name>`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DispatchInput {
+    pub description: Option<String>,
+    pub required: Option<bool>,
+    pub default: Option<Value>,
+
+    /// `string` when left out.
+    #[serde(rename = "type")]
+    pub input_type: Option<DispatchInputType>,
+
+    /// The values a `choice` input can be.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchInputType {
+    String,
+    Boolean,
+    Choice,
+    Number,
+    Environment,
+}
+
+/// The settings of `workflow_call`, which make the workflow reusable: what
+/// callers pass in with `with` and `secrets`, and what they get back.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkflowCallSettings {
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub inputs: IndexMap<String, CallInput>,
+
+    /// The secrets callers can pass, each with no settings or some.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub secrets: IndexMap<String, Option<CallSecret>>,
+
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub outputs: IndexMap<String, CallOutput>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
+/// An input of `workflow_call`, passed with `with` and available as
+/// `inputs.<name>`.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct CallInput {
+    pub description: Option<String>,
+    pub required: Option<bool>,
+    pub default: Option<Value>,
+
+    /// Unlike `workflow_dispatch`'s, required.
+    #[serde(rename = "type")]
+    pub input_type: CallInputType,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallInputType {
+    String,
+    Boolean,
+    Number,
+}
+
+impl CallInputType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CallInputType::String => "string",
+            CallInputType::Boolean => "boolean",
+            CallInputType::Number => "number",
+        }
+    }
+
+    /// Whether `default` can be the default of an input of this type. An
+    /// expression can be any of them.
+    pub fn accepts(self, default: &Value) -> bool {
+        match (self, default) {
+            (_, Value::String(s)) if s.contains("${{") => true,
+            (CallInputType::String, Value::String(_)) => true,
+            (CallInputType::Boolean, Value::Bool(_)) => true,
+            (CallInputType::Number, Value::Number(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A secret of `workflow_call`, available as `secrets.<name>`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CallSecret {
+    pub description: Option<String>,
+    pub required: Option<bool>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// An output of `workflow_call`, usually a job's output passed on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CallOutput {
+    pub description: Option<String>,
+    pub value: String,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DefaultSettings {
-    shell: Option<String>,
-    working_directory: Option<String>,
+    pub shell: Option<String>,
+    pub working_directory: Option<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// Provide default shell and working-directory to all run steps in the job.
@@ -77,7 +618,120 @@ pub struct DefaultSettings {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Defaults {
-    run: DefaultSettings,
+    #[serde(default)]
+    pub run: DefaultSettings,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The access `permissions` gives the `GITHUB_TOKEN`: read or write to
+/// everything, or to each scope listed, with the scopes left out getting
+/// none.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Permissions {
+    All(AllAccess),
+    Scopes(IndexMap<String, Access>),
+    /// Anything else, kept so it can be reported rather than failing the
+    /// parse.
+    Invalid(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AllAccess {
+    #[serde(rename = "read-all")]
+    ReadAll,
+    #[serde(rename = "write-all")]
+    WriteAll,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    None,
+    Read,
+    Write,
+}
+
+impl Access {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Access::None => "none",
+            Access::Read => "read",
+            Access::Write => "write",
+        }
+    }
+}
+
+impl Permissions {
+    /// The access the token has to `scope`, or `None` if the permissions
+    /// are invalid.
+    pub fn access(&self, scope: &str) -> Option<Access> {
+        match self {
+            Permissions::All(AllAccess::ReadAll) => Some(Access::Read),
+            Permissions::All(AllAccess::WriteAll) => Some(Access::Write),
+            Permissions::Scopes(scopes) => Some(scopes.get(scope).copied().unwrap_or(Access::None)),
+            Permissions::Invalid(_) => None,
+        }
+    }
+
+    /// The scopes listed with write access, in the order they're written.
+    pub fn writes(&self) -> Vec<&str> {
+        match self {
+            Permissions::Scopes(scopes) => scopes
+                .iter()
+                .filter(|&(_, &access)| access == Access::Write)
+                .map(|(scope, _)| scope.as_str())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A concurrency group: at most one run or job in the group runs at a time,
+/// with one more waiting, which a newer one replaces. `concurrency: ci` is
+/// short for the group alone.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Concurrency {
+    pub group: String,
+
+    /// Whether a run joining the group cancels the one running, rather than
+    /// waiting for it.
+    pub cancel_in_progress: Option<BoolOrExpression>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl FromStr for Concurrency {
+    type Err = Void;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Concurrency {
+            group: s.to_string(),
+            cancel_in_progress: None,
+            extra: Extra::new(),
+        })
+    }
+}
+
+impl Concurrency {
+    /// Whether it always cancels the run in progress.
+    pub fn cancels(&self) -> bool {
+        matches!(self.cancel_in_progress, Some(BoolOrExpression::Bool(true)))
+    }
+}
+
+/// A setting that's `true`, `false`, or an expression for either.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BoolOrExpression {
+    Bool(bool),
+    Expression(String),
 }
 
 /// The environment that the job references. All environment protection rules
@@ -85,25 +739,142 @@ pub struct Defaults {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Environment {
-    name: String,
-    url: Option<String>,
+    pub name: String,
+    pub url: Option<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-// TODO
-type Matrix = Value;
+/// A job's `strategy.matrix`: axes, each a list of values a leg takes one
+/// of, with combinations taken out by `exclude` and extended or added by
+/// `include`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Matrix {
+    Axes(MatrixAxes),
+    /// A string, which GitHub only accepts as an expression, like
+    /// `${{ fromJSON(needs.setup.outputs.matrix) }}`.
+    Expression(String),
+    /// Anything else, kept so the matrix can be reported rather than
+    /// failing the parse.
+    Invalid(Value),
+}
+
+impl Matrix {
+    /// The axes, unless the whole matrix is an expression or invalid.
+    pub fn axes(&self) -> Option<&MatrixAxes> {
+        match self {
+            Matrix::Axes(axes) => Some(axes),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatrixAxes {
+    /// The axes in the order they're written, which is the order legs run
+    /// in.
+    #[serde(flatten)]
+    pub axes: IndexMap<String, Axis>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<MatrixEntries>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<MatrixEntries>,
+}
+
+impl MatrixAxes {
+    /// The `include` entries, or none if there aren't any or they're an
+    /// expression.
+    pub fn includes(&self) -> &[IndexMap<String, Value>] {
+        self.include.as_ref().map_or(&[], MatrixEntries::entries)
+    }
+
+    /// The `exclude` entries, likewise.
+    pub fn excludes(&self) -> &[IndexMap<String, Value>] {
+        self.exclude.as_ref().map_or(&[], MatrixEntries::entries)
+    }
+
+    /// Every value `key` takes: the axis's own, then those `include` sets.
+    pub fn values(&self, key: &str) -> Vec<&Value> {
+        let mut values: Vec<&Value> = match self.axes.get(key) {
+            Some(Axis::Values(values)) => values.iter().collect(),
+            _ => Vec::new(),
+        };
+        values.extend(
+            self.includes()
+                .iter()
+                .filter_map(|include| include.get(key)),
+        );
+        values
+    }
+
+    /// Whether `key` is in the `matrix` context of some leg, as an axis or
+    /// a key `include` adds, ignoring case as expressions do.
+    pub fn has_key(&self, key: &str) -> bool {
+        let is_key = |k: &String| k.eq_ignore_ascii_case(key);
+        self.axes.keys().any(is_key) || self.includes().iter().any(|i| i.keys().any(is_key))
+    }
+
+    /// Whether some of the matrix is an expression, so which keys it has is
+    /// only known during the run.
+    pub fn has_expression(&self) -> bool {
+        let entries = |e: &Option<MatrixEntries>| matches!(e, Some(MatrixEntries::Expression(_)));
+        self.axes.values().any(|a| matches!(a, Axis::Expression(_)))
+            || entries(&self.include)
+            || entries(&self.exclude)
+    }
+}
+
+/// The values of one axis of a matrix.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Axis {
+    Values(Vec<Value>),
+    /// A string, which GitHub only accepts as an expression.
+    Expression(String),
+    Invalid(Value),
+}
+
+/// A matrix's `include` or `exclude`: maps of keys to values.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MatrixEntries {
+    Entries(Vec<IndexMap<String, Value>>),
+    /// A string, which GitHub only accepts as an expression.
+    Expression(String),
+    Invalid(Value),
+}
+
+impl MatrixEntries {
+    /// The entries, or none if they're an expression or invalid.
+    pub fn entries(&self) -> &[IndexMap<String, Value>] {
+        match self {
+            MatrixEntries::Entries(entries) => entries,
+            _ => &[],
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Strategy {
-    matrix: Option<Matrix>,
-    fail_fast: Option<bool>,
-    max_parallel: Option<i32>,
+    pub matrix: Option<Matrix>,
+    pub fail_fast: Option<bool>,
+    pub max_parallel: Option<i32>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// Runs command-line programs using the operating system's shell. If you do not
 /// provide a name, the step name will default to the text specified in the run
 /// command.
-type ShellCommand = String;
+pub type ShellCommand = String;
 
 /// Steps can run commands, run setup tasks, or run an action in your
 /// repository, a public repository, or an action published in a Docker registry.
@@ -114,24 +885,33 @@ type ShellCommand = String;
 #[serde(rename_all = "kebab-case")]
 pub struct Step {
     /// A name for your step to display on GitHub.
-    name: Option<String>,
+    pub name: Option<String>,
 
     /// A unique identifier for the step. You can use the id to reference the
     /// step in contexts.
-    id: Option<String>,
+    pub id: Option<String>,
 
     /// You can use the if conditional to prevent a step from running unless a
     /// condition is met. You can use any supported context and expression to
     /// create a conditional.
     #[serde(rename = "if")]
-    run_if: Option<String>,
+    pub run_if: Option<String>,
 
     /// Selects an action to run as part of a step in your job. An action is a reusable
     /// unit of code. You can use an action defined in the same repository as the
     /// workflow, a public repository, or in a published Docker container image.
-    uses: String,
+    /// A step has either this or `run`, but not both.
+    pub uses: Option<String>,
+
+    /// Runs command-line programs using the operating system's shell. A step has
+    /// either this or `uses`, but not both.
+    pub run: Option<ShellCommand>,
 
-    run: Option<ShellCommand>,
+    /// The shell to run `run` with, overriding the job and workflow defaults.
+    pub shell: Option<String>,
+
+    /// The directory to run `run` in, overriding the job and workflow defaults.
+    pub working_directory: Option<String>,
 
     /// A map of the input parameters defined by the action. Each input parameter is a
     /// key/value pair. Input parameters are set as environment variables. The variable
@@ -139,35 +919,75 @@ pub struct Step {
     ///
     /// Both `entrypoint` and `args` are supported and override a docker image's default
     /// values for those variables.
-    #[serde(default)]
-    with: HashMap<String, String>,
+    #[serde(default, deserialize_with = "scalar_map")]
+    pub with: HashMap<String, String>,
 
     /// Sets environment variables for steps to use in the runner environment. You can
     /// also set environment variables for the entire workflow or a job.
-    #[serde(default)]
-    env: Env,
+    #[serde(default, deserialize_with = "scalar_map")]
+    pub env: Env,
 
     /// Prevents a job from failing when a step fails. Set to true to allow a job to
     /// pass when this step fails.
-    continue_on_error: Option<bool>,
+    pub continue_on_error: Option<bool>,
 
     /// The maximum number of minutes to run the step before killing the process.
-    timeout_minutes: Option<i32>,
+    pub timeout_minutes: Option<i32>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+
+    /// Where the step is in the file, once the workflow is
+    /// [located](Workflow::locate).
+    #[serde(skip)]
+    pub span: Option<NodeSpan>,
+}
+
+impl Step {
+    /// Parse `uses` into the kind of action it refers to; `None` for steps
+    /// that don't use an action.
+    pub fn action(&self) -> Option<Result<ActionRef, ActionRefError>> {
+        self.uses.as_ref().map(|uses| uses.parse())
+    }
+
+    /// Whether the step uses the action `name`, like `actions/checkout` or
+    /// `github/codeql-action/init`, at any version.
+    pub fn uses_action(&self, name: &str) -> bool {
+        match self.action() {
+            Some(Ok(ActionRef::Repository {
+                owner, repo, path, ..
+            })) => {
+                let full = match path {
+                    Some(path) => format!("{}/{}/{}", owner, repo, path),
+                    None => format!("{}/{}", owner, repo),
+                };
+                full.eq_ignore_ascii_case(name)
+            }
+            _ => false,
+        }
+    }
 }
 
 /// A container to run any steps in a job that don't already specify a container.
 #[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", default)]
 pub struct Container {
-    name: String,
-    credentials: Option<HashMap<String, String>>,
-    env: Option<Env>,
-    #[serde(default)]
-    ports: Vec<i32>,
-    #[serde(default)]
-    volumes: Vec<i32>,
-    #[serde(default)]
-    options: Vec<String>,
+    /// The Docker image to run the steps in.
+    pub image: Option<String>,
+    pub credentials: Option<HashMap<String, String>>,
+    #[serde(deserialize_with = "optional_scalar_map")]
+    pub env: Option<Env>,
+    /// Ports to expose, like `80` or `8080:80`.
+    #[serde(deserialize_with = "scalar_list")]
+    pub ports: Vec<String>,
+    pub volumes: Vec<String>,
+    /// Extra `docker create` flags, like `--cpus 1`.
+    pub options: Option<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 impl FromStr for Container {
     // This implementation of `from_str` can never fail, so use the impossible
@@ -176,95 +996,305 @@ impl FromStr for Container {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Container {
-            name: s.to_string(),
+            image: Some(s.to_string()),
             ..Default::default()
         })
     }
 }
 
-// TODO
 /// Used to host service containers for a job in a workflow. Service containers
 /// are useful for creating databases or cache services like Redis.
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Service {
-    name: String,
-    #[serde(default)]
-    ports: Vec<String>,
+    /// The Docker image to run. An empty image, usually from an expression,
+    /// means the service isn't started.
+    pub image: Option<String>,
+    pub credentials: Option<HashMap<String, String>>,
+    #[serde(deserialize_with = "optional_scalar_map")]
+    pub env: Option<Env>,
+    /// Ports to expose, like `5432` or `5432:5432`.
+    #[serde(deserialize_with = "scalar_list")]
+    pub ports: Vec<String>,
+    pub volumes: Vec<String>,
+    /// Extra `docker create` flags, like `--health-cmd pg_isready`.
+    pub options: Option<String>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// How Docker checks that a service is ready. The runner waits for a
+/// service with one to be healthy before it runs the job's steps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub command: String,
+    pub interval: Option<String>,
+    pub timeout: Option<String>,
+    pub retries: Option<String>,
+    pub start_period: Option<String>,
+}
+
+/// Split `options` into words the way a shell would, keeping quoted
+/// strings together.
+fn shell_words(options: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in options.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+impl Service {
+    /// The `--flag value` and `--flag=value` pairs in `options`.
+    pub fn option_flags(&self) -> Vec<(String, Option<String>)> {
+        let words = shell_words(self.options.as_deref().unwrap_or_default());
+        let mut flags = Vec::new();
+        let mut words = words.into_iter().peekable();
+        while let Some(word) = words.next() {
+            if !word.starts_with("--") {
+                continue;
+            }
+            match word.split_once('=') {
+                Some((flag, value)) => flags.push((flag.to_string(), Some(value.to_string()))),
+                None => {
+                    let value = match words.peek() {
+                        Some(next) if !next.starts_with("--") => words.next(),
+                        _ => None,
+                    };
+                    flags.push((word, value));
+                }
+            }
+        }
+        flags
+    }
+
+    /// The health check set with `--health-cmd` in `options`, unless
+    /// `--no-healthcheck` turns checks off.
+    pub fn health_check(&self) -> Option<HealthCheck> {
+        let flags = self.option_flags();
+        let flag = |name: &str| {
+            flags
+                .iter()
+                .find(|(flag, _)| flag == name)
+                .and_then(|(_, value)| value.clone())
+        };
+        if flags.iter().any(|(flag, _)| flag == "--no-healthcheck") {
+            return None;
+        }
+        Some(HealthCheck {
+            command: flag("--health-cmd").filter(|c| c != "NONE" && c != "none")?,
+            interval: flag("--health-interval"),
+            timeout: flag("--health-timeout"),
+            retries: flag("--health-retries"),
+            start_period: flag("--health-start-period"),
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Job {
     /// The name of the job displayed on GitHub.
-    name: Option<String>,
+    pub name: Option<String>,
 
     /// Identifies any jobs that must complete successfully before this job
     /// will run. It can be a string or array of strings. If a job fails, all jobs that
     /// need it are skipped unless the jobs use a conditional expression that causes
     /// the job to continue.
-    #[serde(default)]
-    needs: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub needs: Vec<String>,
 
     /// The type of machine to run the job on. The machine can be either a GitHub-hosted
-    /// runner or a self-hosted runner.
-    runs_on: OneOrMany<String>,
+    /// runner or a self-hosted runner. Jobs that call a reusable workflow
+    /// don't have one.
+    pub runs_on: Option<RunsOn>,
+
+    /// The reusable workflow the job calls instead of running steps, like
+    /// `./.github/workflows/build.yml` in the same repository, or
+    /// `octo-org/ci/.github/workflows/build.yml@v1` from another.
+    pub uses: Option<String>,
+
+    /// The inputs passed to the reusable workflow in `uses`.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub with: IndexMap<String, Value>,
+
+    /// The secrets passed to the reusable workflow in `uses`.
+    pub secrets: Option<CallSecrets>,
+
+    /// What the job's `GITHUB_TOKEN` can do, instead of the workflow's
+    /// `permissions`.
+    pub permissions: Option<Permissions>,
+
+    /// The concurrency group the job joins, as well as the workflow's.
+    #[serde(default, deserialize_with = "optional_string_or_struct")]
+    pub concurrency: Option<Concurrency>,
 
     /// The environment that the job references. All environment protection rules must
     /// pass before a job referencing the environment is sent to a runner.
-    environment: Option<Environment>,
+    pub environment: Option<Environment>,
 
     /// A map of outputs for a job. Job outputs are available to all downstream jobs
     /// that depend on this job.
-    outputs: Option<HashMap<String, Output>>,
+    pub outputs: Option<HashMap<String, Output>>,
 
     /// A map of environment variables that are available to all steps in the job. You
     /// can also set environment variables for the entire workflow or an individual step.
-    #[serde(default)]
-    env: Env,
+    #[serde(default, deserialize_with = "scalar_map")]
+    pub env: Env,
 
     /// A map of default settings that will apply to all steps in the job. You can also
     /// set default settings for the entire workflow.
-    defaults: Option<Defaults>,
+    pub defaults: Option<Defaults>,
 
     /// You can use the if conditional to prevent a job from running unless a condition
     /// is met. You can use any supported context and expression to create a conditional.
     #[serde(rename = "if")]
-    run_if: Option<String>,
+    pub run_if: Option<String>,
 
     /// A job contains a sequence of tasks called steps. Because steps run in
     /// their own process, changes to environment variables are not preserved
     /// between steps. GitHub provides built-in steps to set up and complete a job.
     #[serde(default)]
-    steps: Vec<Step>,
+    pub steps: Vec<Step>,
 
     /// The maximum number of minutes to run the step before killing the process.
-    timeout_minutes: Option<i32>,
+    pub timeout_minutes: Option<i32>,
 
     /// A strategy creates a build matrix for your jobs. You can define different
     /// variations to run each job in.
-    strategy: Option<Strategy>,
+    pub strategy: Option<Strategy>,
 
     /// Prevents a job from failing when a step fails. Set to true to allow a job to
     /// pass when this step fails.
-    continue_on_error: Option<String>,
+    pub continue_on_error: Option<String>,
 
     /// If you have steps that use both script and container actions, the container
     /// actions will run as sibling containers on the same network with the same volume mounts.
-    container: Option<Container>,
+    #[serde(default, deserialize_with = "optional_string_or_struct")]
+    pub container: Option<Container>,
 
     /// The runner automatically creates a Docker network and manages the life
     /// cycle of the service containers.
     #[serde(default)]
-    services: Vec<Service>,
+    pub services: IndexMap<String, Service>,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+
+    /// Where the job is in the file, from its id to the end of its last
+    /// key, once the workflow is [located](Workflow::locate).
+    #[serde(skip)]
+    pub span: Option<NodeSpan>,
+}
+
+impl Job {
+    /// What the job does: run steps, or call a reusable workflow.
+    pub fn kind(&self) -> JobKind<'_> {
+        match &self.uses {
+            Some(uses) => JobKind::Call {
+                uses,
+                with: &self.with,
+                secrets: self.secrets.as_ref(),
+            },
+            None => JobKind::Steps {
+                runs_on: self.runs_on.as_ref(),
+                steps: &self.steps,
+            },
+        }
+    }
+
+    /// The `runs-on` labels, or none for a job calling a reusable workflow.
+    pub fn labels(&self) -> &[String] {
+        self.runs_on.as_ref().map_or(&[], RunsOn::labels)
+    }
+}
+
+/// The two kinds of job: ones that run steps on a runner, and ones that
+/// call a reusable workflow, which runs its own jobs.
+#[derive(Debug, Clone, Copy)]
+pub enum JobKind<'j> {
+    Steps {
+        /// `None` when it's missing, which GitHub rejects.
+        runs_on: Option<&'j RunsOn>,
+        steps: &'j [Step],
+    },
+    Call {
+        uses: &'j str,
+        with: &'j IndexMap<String, Value>,
+        secrets: Option<&'j CallSecrets>,
+    },
 }
 
-type Env = HashMap<String, String>;
+/// The secrets a job passes to the reusable workflow it calls.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CallSecrets {
+    /// `inherit`, passing on all of the caller's secrets. Other strings
+    /// are kept too, so the workflow still parses.
+    Keyword(String),
+    #[serde(deserialize_with = "scalar_map")]
+    Named(HashMap<String, String>),
+}
 
-type JobMap = HashMap<String, Job>;
+impl CallSecrets {
+    pub fn is_inherit(&self) -> bool {
+        matches!(self, CallSecrets::Keyword(keyword) if keyword == "inherit")
+    }
+}
+
+/// A job's `runs-on`: labels a runner needs all of, or a runner group with
+/// labels to pick runners in it by.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RunsOn {
+    Labels(OneOrMany<String>),
+    Group {
+        group: Option<String>,
+        #[serde(default, deserialize_with = "one_or_many")]
+        labels: Vec<String>,
+    },
+}
+
+impl RunsOn {
+    /// The labels, which for a group are only those in `labels`.
+    pub fn labels(&self) -> &[String] {
+        match self {
+            RunsOn::Labels(labels) => labels.as_slice(),
+            RunsOn::Group { labels, .. } => labels,
+        }
+    }
+
+    /// What the labels pick.
+    pub fn runner(&self) -> Runner<'_> {
+        Runner::new(self)
+    }
+}
+
+pub type Env = HashMap<String, String>;
+
+/// Keys of a workflow object that the structs here don't have fields for, in
+/// the order they were written.
+pub type Extra = IndexMap<String, Value>;
+
+pub type JobMap = HashMap<String, Job>;
 
 // TODO: determine if outputs _need_ to be an expression and validate
-type Output = String;
+pub type Output = String;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -272,31 +1302,173 @@ pub struct Workflow {
     /// The name of your workflow. GitHub displays the names of your workflows on your
     /// repository's actions page. If you omit name, GitHub sets it to the workflow
     /// file path relative to the root of the repository.
-    name: Option<String>,
+    pub name: Option<String>,
 
     /// The name of the GitHub event that triggers the workflow. You can provide a
     /// single event string, array of events, array of event types, or an event
     /// configuration map that schedules a workflow or restricts the execution of a
     /// workflow to specific files, tags, or branch changes.
-    on: Trigger,
+    #[serde(deserialize_with = "events")]
+    pub on: Events,
 
     /// A map of environment variables that are available to all jobs and steps
     /// in the workflow. You can also set environment variables that are only
     /// available to a job or step.
-    env: Option<Env>,
+    #[serde(default, deserialize_with = "optional_scalar_map")]
+    pub env: Option<Env>,
 
     /// A map of default settings that will apply to all jobs in the workflow. You can
     /// also set default settings that are only available to a job.
-    defaults: Option<Defaults>,
+    pub defaults: Option<Defaults>,
+
+    /// What the `GITHUB_TOKEN` of each job can do, unless the job sets its
+    /// own. Without either, it gets the repository's default, which can be
+    /// write access to most scopes.
+    pub permissions: Option<Permissions>,
+
+    /// The concurrency group each run of the workflow joins.
+    #[serde(default, deserialize_with = "optional_string_or_struct")]
+    pub concurrency: Option<Concurrency>,
 
     /// A workflow run is made up of one or more jobs. Jobs run in parallel by
     /// default. To run jobs sequentially, you can define dependencies on other jobs
     /// using the jobs.<job_id>.needs keyword.
-    jobs: JobMap,
+    pub jobs: JobMap,
+
+    /// Keys not modeled here, kept so that they survive a round trip.
+    #[serde(flatten)]
+    pub extra: Extra,
+
+    /// Where `on` is in the file, once the workflow is
+    /// [located](Workflow::locate).
+    #[serde(skip)]
+    pub on_span: Option<NodeSpan>,
+
+    /// Where each event in `on` is named, in the order they're written.
+    #[serde(skip)]
+    pub event_spans: IndexMap<String, NodeSpan>,
+}
+
+/// The shell and working directory a `run` step ends up with once the job
+/// and workflow `defaults` are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSettings<'a> {
+    /// `None` when left to GitHub, which uses `bash` (falling back to `sh`)
+    /// on Linux and macOS and `pwsh` on Windows.
+    pub shell: Option<&'a str>,
+    pub working_directory: Option<&'a str>,
+}
+
+impl<'a> RunSettings<'a> {
+    /// The program the shell runs, e.g. `bash` for `bash --noprofile {0}`.
+    pub fn shell_program(&self) -> Option<&'a str> {
+        self.shell.and_then(|shell| shell.split_whitespace().next())
+    }
 }
 
 impl Workflow {
     pub fn parse_str(input: &str) -> Result<Self, Error> {
-        serde_yaml::from_str(&input)
+        serde_yaml::from_str(input)
+    }
+
+    /// Parse just the `on` of a workflow, for when the rest of it doesn't
+    /// matter and may not parse.
+    pub fn parse_events(input: &str) -> Result<Events, Error> {
+        #[derive(Deserialize)]
+        struct On {
+            #[serde(deserialize_with = "events")]
+            on: Events,
+        }
+        Ok(serde_yaml::from_str::<On>(input)?.on)
+    }
+
+    /// The permissions `job`'s token gets: its own, or else the
+    /// workflow's. `None` means the repository's default.
+    pub fn permissions_for<'a>(&'a self, job: &'a Job) -> Option<&'a Permissions> {
+        job.permissions.as_ref().or(self.permissions.as_ref())
+    }
+
+    /// The concurrency group `job` runs in: its own, or else the
+    /// workflow's.
+    pub fn concurrency_for<'a>(&'a self, job: &'a Job) -> Option<&'a Concurrency> {
+        job.concurrency.as_ref().or(self.concurrency.as_ref())
+    }
+
+    /// Parse `input` and [locate](Self::locate) its jobs, steps and
+    /// triggers in it.
+    pub fn parse_located(input: &str) -> Result<Self, Error> {
+        let mut workflow = Self::parse_str(input)?;
+        let document: Value = serde_yaml::from_str(input)?;
+        workflow.locate(&document, &SpanMap::parse(input));
+        Ok(workflow)
+    }
+
+    /// Record where each job, step and trigger is, from the `spans` of the
+    /// file the workflow was parsed from, whose plain YAML is `document`.
+    /// serde can't keep positions, so they're filled in after parsing.
+    pub fn locate(&mut self, document: &Value, spans: &SpanMap) {
+        for (id, job) in self.jobs.iter_mut() {
+            let path = key_path("jobs", id);
+            let steps = key_path(&path, "steps");
+            job.span = spans.get(&path);
+            for (i, step) in job.steps.iter_mut().enumerate() {
+                step.span = spans.get(&index_path(&steps, i));
+            }
+        }
+        self.on_span = spans.get("on");
+        let events: Vec<(String, String)> = match document.get("on") {
+            Some(Value::String(event)) => vec![(event.clone(), "on".to_string())],
+            Some(Value::Sequence(events)) => events
+                .iter()
+                .enumerate()
+                .filter_map(|(i, e)| Some((e.as_str()?.to_string(), index_path("on", i))))
+                .collect(),
+            Some(Value::Mapping(events)) => events
+                .iter()
+                .filter_map(|(k, _)| {
+                    let event = k.as_str()?;
+                    Some((event.to_string(), key_path("on", event)))
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.event_spans = events
+            .into_iter()
+            .filter_map(|(event, path)| Some((event, spans.get(&path)?)))
+            .collect();
+    }
+
+    /// Resolve the settings for a `run` step, with each of shell and
+    /// working directory taken from the step, then the job's `defaults`,
+    /// then the workflow's.
+    pub fn run_settings<'a>(&'a self, job: &'a Job, step: &'a Step) -> RunSettings<'a> {
+        let defaults = [job.defaults.as_ref(), self.defaults.as_ref()];
+        let from_defaults = |get: fn(&DefaultSettings) -> Option<&String>| {
+            defaults
+                .iter()
+                .flatten()
+                .find_map(|d| get(&d.run))
+                .map(String::as_str)
+        };
+        RunSettings {
+            shell: step
+                .shell
+                .as_deref()
+                .or_else(|| from_defaults(|d| d.shell.as_ref())),
+            working_directory: step
+                .working_directory
+                .as_deref()
+                .or_else(|| from_defaults(|d| d.working_directory.as_ref())),
+        }
+    }
+
+    /// The value of the environment variable `name` for `step`, from the
+    /// step's `env`, then the job's, then the workflow's.
+    pub fn env_value<'a>(&'a self, job: &'a Job, step: &'a Step, name: &str) -> Option<&'a str> {
+        step.env
+            .get(name)
+            .or_else(|| job.env.get(name))
+            .or_else(|| self.env.as_ref().and_then(|env| env.get(name)))
+            .map(String::as_str)
     }
 }